@@ -0,0 +1,617 @@
+use anyhow::Result;
+use colored::*;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::analyzer::StateModificationAnalyzer;
+use crate::models::*;
+use crate::parser::SolidityParser;
+
+/// A source-level type resolved to its ABI representation
+struct AbiType {
+    abi: String,                        // JSON "type", e.g. "tuple[]"
+    canonical: String,                  // As hashed in signatures, e.g. "(uint256,address)[]"
+    internal_type: String,              // e.g. "struct Vault.Position[]"
+    components: Vec<(String, AbiType)>, // Struct members (name, type) for tuples
+}
+
+/// Resolves type names as seen from one contract (structs, enums, value types and contract types)
+pub(crate) struct TypeResolver<'a> {
+    contract: Option<&'a ContractInfo>, // None when resolving file-level definitions
+    all_contracts: &'a [ContractInfo],
+    source_units: Vec<&'a SourceUnit>, // File-level definitions visible from the contract
+}
+
+// Struct members may refer back to their own struct through arrays
+const MAX_TYPE_DEPTH: usize = 16;
+
+impl<'a> TypeResolver<'a> {
+    pub(crate) fn new(contract: &'a ContractInfo, all_contracts: &'a [ContractInfo], source_units: &'a [SourceUnit]) -> Self {
+        TypeResolver {
+            contract: Some(contract),
+            all_contracts,
+            source_units: StateModificationAnalyzer::visible_source_units(&contract.file_path, source_units),
+        }
+    }
+
+    fn for_file(file_path: &str, all_contracts: &'a [ContractInfo], source_units: &'a [SourceUnit]) -> Self {
+        TypeResolver {
+            contract: None,
+            all_contracts,
+            source_units: StateModificationAnalyzer::visible_source_units(file_path, source_units),
+        }
+    }
+
+    fn resolve(&self, ty: &str) -> AbiType {
+        self.resolve_at_depth(ty.trim(), 0)
+    }
+
+    fn resolve_at_depth(&self, ty: &str, depth: usize) -> AbiType {
+        // Arrays: resolve the element type and keep the dimension suffix
+        if let Some(open) = ty.strip_suffix(']').and_then(|t| t.rfind('[')) {
+            let dims = &ty[open..];
+            let element = self.resolve_at_depth(&ty[..open], depth);
+            return AbiType {
+                abi: format!("{}{}", element.abi, dims),
+                canonical: format!("{}{}", element.canonical, dims),
+                internal_type: format!("{}{}", element.internal_type, dims),
+                components: element.components,
+            };
+        }
+
+        if let Some(elementary) = Self::elementary(ty) {
+            return AbiType {
+                abi: elementary.clone(),
+                canonical: elementary,
+                internal_type: ty.to_string(),
+                components: Vec::new(),
+            };
+        }
+
+        let (qualifier, name) = match ty.rsplit_once('.') {
+            Some((q, n)) => (Some(q), n),
+            None => (None, ty),
+        };
+
+        if depth < MAX_TYPE_DEPTH {
+            if let Some((owner, def)) = self.find_struct(qualifier, name) {
+                let components: Vec<(String, AbiType)> = def.members.iter()
+                    .map(|m| (m.name.clone(), self.resolve_at_depth(&m.member_type, depth + 1)))
+                    .collect();
+                let canonical = format!("({})", components.iter()
+                    .map(|(_, t)| t.canonical.clone())
+                    .collect::<Vec<_>>()
+                    .join(","));
+                return AbiType {
+                    abi: "tuple".to_string(),
+                    canonical,
+                    internal_type: Self::qualified("struct", owner, name),
+                    components,
+                };
+            }
+        }
+
+        if let Some(owner) = self.find_enum(qualifier, name) {
+            return AbiType {
+                abi: "uint8".to_string(),
+                canonical: "uint8".to_string(),
+                internal_type: Self::qualified("enum", owner, name),
+                components: Vec::new(),
+            };
+        }
+
+        // User-defined value types are encoded as their underlying type
+        if qualifier.is_none() {
+            if let Some(udvt) = self.find_user_type(name) {
+                let underlying = self.resolve_at_depth(&udvt.underlying, depth);
+                return AbiType { internal_type: name.to_string(), ..underlying };
+            }
+        }
+
+        if qualifier.is_none() && self.all_contracts.iter().any(|c| c.name == name) {
+            return AbiType {
+                abi: "address".to_string(),
+                canonical: "address".to_string(),
+                internal_type: format!("contract {}", name),
+                components: Vec::new(),
+            };
+        }
+
+        // Unknown (declared outside the project): keep the name as written
+        AbiType {
+            abi: ty.to_string(),
+            canonical: ty.to_string(),
+            internal_type: ty.to_string(),
+            components: Vec::new(),
+        }
+    }
+
+    /// Structured form of a type as written (e.g. `mapping(address => Vault.Position[])`)
+    pub(crate) fn resolve_type(&self, ty: &str) -> SolType {
+        let ty = ty.trim();
+
+        if let Some(rest) = ty.strip_prefix("function") {
+            return Self::function_type(rest, |t| self.resolve_type(t));
+        }
+        // Arrays: the outermost dimension is the last one
+        if let Some(open) = ty.strip_suffix(']').and_then(|t| t.rfind('[')) {
+            let length = ty[open + 1..ty.len() - 1].trim();
+            return SolType::Array {
+                base: Box::new(self.resolve_type(&ty[..open])),
+                length: (!length.is_empty()).then(|| length.to_string()),
+            };
+        }
+        if let Some((key, value)) = Self::split_mapping(ty) {
+            return SolType::Mapping {
+                key: Box::new(self.resolve_type(key)),
+                value: Box::new(self.resolve_type(value)),
+            };
+        }
+        if ty == "address payable" {
+            return SolType::elementary(ty);
+        }
+        if let Some(name) = Self::elementary(ty) {
+            return SolType::Elementary { name };
+        }
+
+        let (qualifier, name) = match ty.rsplit_once('.') {
+            Some((q, n)) => (Some(q), n),
+            None => (None, ty),
+        };
+        if let Some((owner, _)) = self.find_struct(qualifier, name) {
+            return SolType::Struct { name: name.to_string(), owner };
+        }
+        if let Some(owner) = self.find_enum(qualifier, name) {
+            return SolType::Enum { name: name.to_string(), owner };
+        }
+        if qualifier.is_none() {
+            if let Some(udvt) = self.find_user_type(name) {
+                return SolType::UserDefined { name: name.to_string(), underlying: udvt.underlying.clone() };
+            }
+        }
+        if self.all_contracts.iter().any(|c| c.name == name) {
+            return SolType::Contract { name: name.to_string() };
+        }
+        SolType::Unresolved { name: ty.to_string() }
+    }
+
+    /// `(<params>) [attributes] [returns (<returns>)]`, the rest of a function type after `function`
+    fn function_type(rest: &str, resolve: impl Fn(&str) -> SolType) -> SolType {
+        let rest = rest.trim_start();
+        let close = Self::closing_paren(rest).unwrap_or(rest.len());
+        let params = rest.get(1..close).unwrap_or_default();
+        let tail = rest.get(close + 1..).unwrap_or_default();
+        let (attributes, returns) = match tail.split_once("returns") {
+            Some((attributes, returns)) => {
+                let returns = returns.trim();
+                let inner = Self::closing_paren(returns).and_then(|close| returns.get(1..close)).unwrap_or_default();
+                (attributes, inner)
+            }
+            None => (tail, ""),
+        };
+        let list = |types: &str| Self::split_top_level(types).into_iter().map(&resolve).collect();
+        SolType::Function {
+            params: list(params),
+            returns: list(returns),
+            external: attributes.split_whitespace().any(|a| a == "external" || a == "public"),
+        }
+    }
+
+    /// Index of the parenthesis closing the one `text` starts with
+    fn closing_paren(text: &str) -> Option<usize> {
+        let mut depth = 0usize;
+        for (i, c) in text.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth = depth.checked_sub(1)?;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Items of a comma-separated type list, ignoring commas inside parentheses
+    fn split_top_level(list: &str) -> Vec<&str> {
+        let mut items = Vec::new();
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (i, c) in list.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    items.push(list[start..i].trim());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        items.push(list[start..].trim());
+        items.retain(|item| !item.is_empty());
+        items
+    }
+
+    /// "mapping(K => V)" -> (K, V), splitting at the top-level arrow
+    pub(crate) fn split_mapping(ty: &str) -> Option<(&str, &str)> {
+        let inner = ty.strip_prefix("mapping(")?.strip_suffix(')')?;
+        let mut depth = 0usize;
+        for (i, c) in inner.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                '=' if depth == 0 && inner[i..].starts_with("=>") => {
+                    return Some((inner[..i].trim(), inner[i + 2..].trim()));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// ABI name of an elementary type, None for user-defined types
+    pub(crate) fn elementary(ty: &str) -> Option<String> {
+        let normalized = match ty {
+            "address payable" => "address",
+            "uint" => "uint256",
+            "int" => "int256",
+            "byte" => "bytes1",
+            other => other,
+        };
+        let is_sized = |prefix: &str| normalized.strip_prefix(prefix)
+            .is_some_and(|bits| !bits.is_empty() && bits.chars().all(|c| c.is_ascii_digit()));
+
+        if matches!(normalized, "address" | "bool" | "string" | "bytes")
+            || is_sized("uint") || is_sized("int") || is_sized("bytes") {
+            Some(normalized.to_string())
+        } else if normalized.starts_with("function") {
+            Some("function".to_string())
+        } else if normalized.starts_with("mapping(") {
+            // Not ABI-encodable; only reachable through storage parameters and getters
+            Some(normalized.to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Contracts to search for a user-defined type: the qualifier if given, otherwise
+    /// the inheritance chain from the most base contract (the declaring one) and then the project
+    fn search_order(&self, qualifier: Option<&str>) -> Vec<&'a ContractInfo> {
+        if let Some(q) = qualifier {
+            return self.all_contracts.iter().filter(|c| c.name == q).collect();
+        }
+
+        let Some(contract) = self.contract else {
+            return Vec::new();
+        };
+        let mut order: Vec<&ContractInfo> = contract.linearized_bases.iter()
+            .rev()
+            .filter_map(|b| self.all_contracts.iter().find(|c| &c.name == b))
+            .collect();
+        order.push(contract);
+        order
+    }
+
+    /// Contract members shadow file-level definitions, which shadow other contracts' members
+    /// The owner is None for file-level definitions.
+    pub(crate) fn find_struct(&self, qualifier: Option<&str>, name: &str) -> Option<(Option<String>, &'a StructDef)> {
+        let in_contract = |c: &'a ContractInfo| c.structs.iter()
+            .find(|s| s.name == name)
+            .map(|s| (Some(c.name.clone()), s));
+
+        if let Some(found) = self.search_order(qualifier).into_iter().find_map(in_contract) {
+            return Some(found);
+        }
+        if qualifier.is_some() {
+            return None;
+        }
+        self.source_units.iter()
+            .find_map(|u| u.structs.iter().find(|s| s.name == name))
+            .map(|s| (None, s))
+            .or_else(|| self.all_contracts.iter().find_map(in_contract))
+    }
+
+    pub(crate) fn find_enum(&self, qualifier: Option<&str>, name: &str) -> Option<Option<String>> {
+        let in_contract = |c: &'a ContractInfo| c.enums.iter()
+            .any(|e| e.name == name)
+            .then(|| Some(c.name.clone()));
+
+        if let Some(found) = self.search_order(qualifier).into_iter().find_map(in_contract) {
+            return Some(found);
+        }
+        if qualifier.is_some() {
+            return None;
+        }
+        if self.source_units.iter().any(|u| u.enums.iter().any(|e| e.name == name)) {
+            return Some(None);
+        }
+        self.all_contracts.iter().find_map(in_contract)
+    }
+
+    pub(crate) fn find_user_type(&self, name: &str) -> Option<&'a UserDefinedType> {
+        self.source_units.iter().find_map(|u| u.user_types.iter().find(|t| t.name == name))
+    }
+
+    /// Free functions declared in the visible files
+    pub(crate) fn free_functions(&self) -> impl Iterator<Item = &'a FunctionDef> + '_ {
+        self.source_units.iter().flat_map(|u| u.functions.iter())
+    }
+
+    /// solc's internalType: "struct Vault.Position", or "struct Position" at file level
+    pub(crate) fn qualified(kind: &str, owner: Option<String>, name: &str) -> String {
+        match owner {
+            Some(owner) => format!("{} {}.{}", kind, owner, name),
+            None => format!("{} {}", kind, name),
+        }
+    }
+
+    fn signature(&self, name: &str, types: &[String]) -> String {
+        let canonical: Vec<String> = types.iter().map(|t| self.resolve(t).canonical).collect();
+        format!("{}({})", name, canonical.join(","))
+    }
+}
+
+pub struct AbiGenerator;
+
+impl AbiGenerator {
+    pub fn keccak256(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Keccak::v256();
+        let mut output = [0u8; 32];
+        hasher.update(data);
+        hasher.finalize(&mut output);
+        output
+    }
+
+    /// 4-byte selector of a function or error signature, e.g. "0xa9059cbb"
+    pub fn selector(signature: &str) -> String {
+        format!("0x{}", Self::to_hex(&Self::keccak256(signature.as_bytes())[..4]))
+    }
+
+    /// Event topic0 (full keccak-256 hash of the signature)
+    pub fn topic(signature: &str) -> String {
+        format!("0x{}", Self::to_hex(&Self::keccak256(signature.as_bytes())))
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Compute ABI signatures, selectors and event topics for every contract
+    /// Needs inheritance to be resolved so inherited structs and enums are visible, and
+    /// the analysis to have run so errors declared at file level carry their parameters.
+    pub fn assign_selectors(contracts: &mut [ContractInfo], source_units: &[SourceUnit]) {
+        let snapshot = contracts.to_vec();
+
+        for (i, contract) in contracts.iter_mut().enumerate() {
+            let resolver = TypeResolver::new(&snapshot[i], &snapshot, source_units);
+
+            for func in &mut contract.functions {
+                if !Self::has_selector(func) {
+                    continue;
+                }
+                let types = SolidityParser::signature_param_types(&func.signature);
+                let signature = resolver.signature(&func.name, &types);
+                func.selector = Some(Self::selector(&signature));
+                func.abi_signature = Some(signature);
+            }
+
+            for event in &mut contract.events {
+                let types: Vec<String> = event.parameters.iter().map(|p| p.param_type.clone()).collect();
+                let signature = resolver.signature(&event.name, &types);
+                event.topic0 = if event.is_anonymous { None } else { Some(Self::topic(&signature)) };
+                event.signature = Some(signature);
+            }
+
+            for error in &mut contract.errors {
+                // Errors only known by name (declared outside the project) can't be hashed
+                if error.is_inherited && error.declared_at.is_none() {
+                    continue;
+                }
+                let types: Vec<String> = error.parameters.iter().map(|p| p.param_type.clone()).collect();
+                let signature = resolver.signature(Self::bare_name(&error.name), &types);
+                error.selector = Some(Self::selector(&signature));
+                error.signature = Some(signature);
+            }
+        }
+    }
+
+    /// Error selectors and event topics for definitions at file level
+    pub fn assign_file_level_selectors(source_units: &mut [SourceUnit], contracts: &[ContractInfo]) {
+        let snapshot = source_units.to_vec();
+
+        for unit in source_units.iter_mut() {
+            let resolver = TypeResolver::for_file(&unit.file_path, contracts, &snapshot);
+
+            for event in &mut unit.events {
+                let types: Vec<String> = event.parameters.iter().map(|p| p.param_type.clone()).collect();
+                let signature = resolver.signature(&event.name, &types);
+                event.topic0 = if event.is_anonymous { None } else { Some(Self::topic(&signature)) };
+                event.signature = Some(signature);
+            }
+
+            for error in &mut unit.errors {
+                let types: Vec<String> = error.parameters.iter().map(|p| p.param_type.clone()).collect();
+                let signature = resolver.signature(&error.name, &types);
+                error.selector = Some(Self::selector(&signature));
+                error.signature = Some(signature);
+            }
+        }
+    }
+
+    /// Errors thrown as `Library.Error` are hashed under their own name
+    fn bare_name(name: &str) -> &str {
+        name.rsplit('.').next().unwrap_or(name)
+    }
+
+    /// Only externally callable functions have a selector
+    fn has_selector(func: &FunctionDef) -> bool {
+        matches!(func.visibility.as_str(), "public" | "external")
+            && !func.name.contains('.')
+            && !matches!(func.name.as_str(), "constructor" | "fallback" | "receive")
+    }
+
+    /// Build the contract ABI in solc's JSON format
+    pub fn generate_abi(contract: &ContractInfo, all_contracts: &[ContractInfo], source_units: &[SourceUnit]) -> Value {
+        let resolver = TypeResolver::new(contract, all_contracts, source_units);
+        let mut entries: Vec<Value> = Vec::new();
+
+        for func in &contract.functions {
+            match func.name.as_str() {
+                "constructor" if func.inherited_from.is_none() => {
+                    entries.push(json!({
+                        "type": "constructor",
+                        "inputs": Self::params_json(&resolver, &func.parameters),
+                        "stateMutability": Self::abi_mutability(func),
+                    }));
+                }
+                "fallback" | "receive" if func.visibility == "external" => {
+                    entries.push(json!({
+                        "type": func.name,
+                        "stateMutability": Self::abi_mutability(func),
+                    }));
+                }
+                _ if Self::has_selector(func) => {
+                    entries.push(json!({
+                        "type": "function",
+                        "name": func.name,
+                        "inputs": Self::params_json(&resolver, &func.parameters),
+                        "outputs": Self::params_json(&resolver, &func.returns),
+                        "stateMutability": Self::abi_mutability(func),
+                    }));
+                }
+                _ => {}
+            }
+        }
+
+        for event in &contract.events {
+            let inputs: Vec<Value> = event.parameters.iter()
+                .map(|p| {
+                    let mut input = Self::param_json(&p.name, &resolver.resolve(&p.param_type));
+                    input["indexed"] = json!(p.indexed);
+                    input
+                })
+                .collect();
+            entries.push(json!({
+                "type": "event",
+                "name": event.name,
+                "inputs": inputs,
+                "anonymous": event.is_anonymous,
+            }));
+        }
+
+        for error in contract.errors.iter().filter(|e| e.signature.is_some()) {
+            let inputs: Vec<Value> = error.parameters.iter()
+                .map(|p| Self::param_json(&p.name, &resolver.resolve(&p.param_type)))
+                .collect();
+            entries.push(json!({
+                "type": "error",
+                "name": Self::bare_name(&error.name),
+                "inputs": inputs,
+            }));
+        }
+
+        // solc orders entries by type, then by name
+        entries.sort_by(|a, b| {
+            let key = |v: &Value| (v["type"].as_str().unwrap_or("").to_string(), v["name"].as_str().unwrap_or("").to_string());
+            key(a).cmp(&key(b))
+        });
+
+        Value::Array(entries)
+    }
+
+    /// Write `<Contract>.abi.json` next to the markdown reports (context-only dependencies are skipped)
+    pub fn save_abi_files(contracts: &[ContractInfo], source_units: &[SourceUnit], output_dir: &Path) -> Result<()> {
+        fs::create_dir_all(output_dir)?;
+
+        println!();
+        println!("{}", "🧾 Saving ABI files...".bold().green());
+
+        for contract in contracts.iter().filter(|c| !c.is_context_only) {
+            let filename = format!("{}.abi.json", contract.name);
+            let abi = Self::generate_abi(contract, contracts, source_units);
+            fs::write(output_dir.join(&filename), serde_json::to_string_pretty(&abi)?)?;
+            println!("  {} {}", "✓".green(), filename);
+        }
+
+        Ok(())
+    }
+
+    fn abi_mutability(func: &FunctionDef) -> &str {
+        match func.state_mutability.as_str() {
+            "pure" | "view" | "payable" => func.state_mutability.as_str(),
+            _ => "nonpayable",
+        }
+    }
+
+    fn params_json(resolver: &TypeResolver, params: &[String]) -> Vec<Value> {
+        params.iter()
+            .map(|p| {
+                let (ty, name) = Self::split_param(p);
+                Self::param_json(&name, &resolver.resolve(&ty))
+            })
+            .collect()
+    }
+
+    fn param_json(name: &str, ty: &AbiType) -> Value {
+        let mut param = json!({
+            "name": name,
+            "type": ty.abi,
+            "internalType": ty.internal_type,
+        });
+        if !ty.components.is_empty() {
+            param["components"] = Value::Array(ty.components.iter()
+                .map(|(member, member_ty)| Self::param_json(member, member_ty))
+                .collect());
+        }
+        param
+    }
+
+    /// Split a "type name" parameter string (the name is optional)
+    pub(crate) fn split_param(param: &str) -> (String, String) {
+        // Function types end in their attributes when unnamed
+        let keywords = ["payable", "external", "internal", "public", "private", "view", "pure"];
+        match param.rsplit_once(' ') {
+            Some((ty, name)) if !keywords.contains(&name) && !name.contains(')') => {
+                (ty.to_string(), name.to_string())
+            }
+            _ => (param.to_string(), String::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keccak256_of_empty_input() {
+        assert_eq!(AbiGenerator::to_hex(&AbiGenerator::keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+    }
+
+    #[test]
+    fn function_selectors() {
+        assert_eq!(AbiGenerator::selector("transfer(address,uint256)"), "0xa9059cbb");
+        assert_eq!(AbiGenerator::selector("balanceOf(address)"), "0x70a08231");
+        assert_eq!(AbiGenerator::selector("Error(string)"), "0x08c379a0");
+    }
+
+    #[test]
+    fn event_topics() {
+        assert_eq!(AbiGenerator::topic("Transfer(address,address,uint256)"),
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+    }
+
+    #[test]
+    fn signatures_use_canonical_types() {
+        let resolver = TypeResolver::for_file("A.sol", &[], &[]);
+        assert_eq!(resolver.signature("f", &["uint".to_string(), "int[2]".to_string(), "bytes32".to_string()]),
+            "f(uint256,int256[2],bytes32)");
+    }
+}
@@ -0,0 +1,762 @@
+use crate::analyzer::StateModificationAnalyzer;
+use crate::models::*;
+use crate::parser::SolidityParser;
+use anyhow::Result;
+use colored::*;
+use solang_parser::pt;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+// Modifiers of upgradeable contracts that only let the deployer's first call through
+pub(crate) const INITIALIZER_MODIFIERS: &[&str] = &["initializer", "reinitializer", "onlyInitializing"];
+
+// Prefixes of internal helpers that revert for unauthorized callers, e.g. `_checkOwner()`,
+// and what such a helper must name to be about the caller rather than, say, pausing
+const CHECK_PREFIXES: &[&str] = &["_check", "_only", "_require", "_authorize", "_enforce"];
+const CHECK_SUBJECTS: &[&str] = &["owner", "role", "admin", "auth", "sender", "caller", "governance", "guardian", "operator"];
+
+// Internal calls followed when looking for checks
+const MAX_CHECK_DEPTH: usize = 4;
+
+// Name fragments (lowercased) of state that decides who controls the contract
+const OWNERSHIP_NAMES: &[&str] = &[
+    "owner", "admin", "governance", "governor", "guardian", "operator", "role", "authority", "minter", "keeper",
+];
+const PAUSE_NAMES: &[&str] = &["paused", "pause", "emergency", "frozen", "halted", "shutdown"];
+const FEE_NAMES: &[&str] = &["fee", "rate", "bps", "ratio", "percent", "share", "tax", "commission"];
+
+/// Classifies who may call each state-changing entry point and flags sensitive state anyone can write
+pub struct AccessControlAnalyzer;
+
+// The body of an `if` only callers passing a sender check reach
+struct GuardedBranch<'s> {
+    role: String,
+    cond: &'s pt::Expression,
+    loc: pt::Loc,
+    body: &'s pt::Statement,
+}
+
+struct GuardScanner<'a> {
+    contract: &'a ContractInfo,
+    all_contracts: &'a [ContractInfo],
+    ast: &'a pt::ContractDefinition,
+    source_units: &'a [SourceUnit],
+}
+
+impl AccessControlAnalyzer {
+    pub fn analyze(contracts: &mut [ContractInfo], contract_asts: &[pt::ContractDefinition], source_units: &[SourceUnit]) {
+        let snapshot = contracts.to_vec();
+        for ((contract, declared), ast) in contracts.iter_mut().zip(&snapshot).zip(contract_asts) {
+            if contract.kind != ContractKind::Contract {
+                continue;
+            }
+            let scanner = GuardScanner { contract: declared, all_contracts: &snapshot, ast, source_units };
+            let writes = Self::entry_point_writes(declared);
+
+            // Writes made only inside `if (msg.sender == ...) { ... }` are guarded even when the
+            // function itself admits anyone
+            let mut branch_guarded: HashMap<&str, Vec<String>> = HashMap::new();
+            contract.access_control = declared.functions.iter()
+                .filter(|f| Self::is_entry_point(f))
+                .map(|f| {
+                    let written = writes.get(f.signature.as_str()).cloned().unwrap_or_default();
+                    let (branch_guards, guarded) = scanner.branch_guards(f, &writes);
+                    let mut guards = scanner.guards(f);
+                    if guards.is_empty() && !written.is_empty() && written.iter().all(|w| guarded.contains(w)) {
+                        guards = branch_guards;
+                    }
+                    branch_guarded.insert(f.signature.as_str(), guarded);
+                    EntryPointAccess { function: f.signature.clone(), guards, writes: written }
+                })
+                .collect();
+
+            contract.unprotected_writes = contract.access_control.iter()
+                .filter(|access| access.guards.is_empty())
+                .flat_map(|access| access.writes.iter().map(move |var| (access, var)))
+                .filter(|(access, name)| !branch_guarded.get(access.function.as_str()).is_some_and(|g| g.contains(name)))
+                .filter_map(|(access, name)| {
+                    let var = declared.state_variables.iter().find(|v| &v.name == name)?;
+                    let func = declared.functions.iter().find(|f| f.signature == access.function)?;
+                    Some(UnprotectedWrite {
+                        function: access.function.clone(),
+                        variable: name.clone(),
+                        category: Self::sensitivity(var)?,
+                        line_number: func.line_number,
+                    })
+                })
+                .collect();
+        }
+    }
+
+    fn is_entry_point(func: &FunctionDef) -> bool {
+        (matches!(func.visibility.as_str(), "public" | "external") || matches!(func.name.as_str(), "fallback" | "receive"))
+            && !matches!(func.state_mutability.as_str(), "view" | "pure")
+            && func.name != "constructor"
+            && !func.name.contains('.')
+    }
+
+    /// State each entry point writes, directly or through the functions and modifiers it runs
+    fn entry_point_writes(contract: &ContractInfo) -> HashMap<&str, Vec<String>> {
+        let mut writes: HashMap<&str, Vec<String>> = HashMap::new();
+        for var in &contract.state_variables {
+            for chain in &var.modification_chains {
+                let reached = std::iter::once(chain.direct_modifier.as_str())
+                    .chain(chain.call_chain.iter().map(|c| c.function_name.as_str()));
+                for signature in reached {
+                    let vars = writes.entry(signature).or_default();
+                    if !vars.contains(&var.name) {
+                        vars.push(var.name.clone());
+                    }
+                }
+            }
+        }
+        writes
+    }
+
+    /// Kind of control a state variable holds, if it is sensitive
+    pub fn sensitivity(var: &StateVariable) -> Option<SensitiveState> {
+        if var.is_constant || var.is_immutable {
+            return None;
+        }
+        let name = var.name.to_lowercase();
+        let matches = |fragments: &[&str]| fragments.iter().any(|f| name.contains(f));
+        let resolved = var.resolved_type.as_ref();
+        let is_collection = resolved.is_some_and(|t| matches!(t, SolType::Mapping { .. } | SolType::Array { .. }));
+
+        // Collections count only as role membership (`isAdmin[a]`), not as per-item owners
+        let is_membership = resolved.and_then(|t| t.element()).is_some_and(|t| t == SolType::elementary("bool"));
+
+        if matches(OWNERSHIP_NAMES) && (!is_collection || is_membership) {
+            Some(SensitiveState::Ownership)
+        } else if matches(PAUSE_NAMES) && !is_collection {
+            Some(SensitiveState::Pause)
+        } else if matches(FEE_NAMES) && !is_collection {
+            Some(SensitiveState::Fee)
+        } else if resolved.is_some_and(|t| t.is_address() || matches!(t, SolType::Contract { .. } | SolType::Unresolved { .. })) {
+            Some(SensitiveState::Address)
+        } else {
+            None
+        }
+    }
+
+    pub fn print_summary(contracts: &[ContractInfo]) {
+        let analyzed: Vec<&ContractInfo> = contracts.iter().filter(|c| !c.access_control.is_empty()).collect();
+        if analyzed.is_empty() {
+            return;
+        }
+
+        println!();
+        println!("{}", "🔐 Access Control".bold());
+        for contract in analyzed {
+            let open = contract.access_control.iter().filter(|a| a.guards.is_empty()).count();
+            let line = format!("{}: {} state-changing entry point(s), {} callable by anyone",
+                contract.name, contract.access_control.len(), open);
+            if contract.unprotected_writes.is_empty() {
+                println!("  {} {}", "✓".green(), line);
+            } else {
+                println!("  {} {}, {} unprotected sensitive write(s)", "⚠️".yellow(), line, contract.unprotected_writes.len());
+            }
+        }
+    }
+
+    pub fn save_report(contracts: &[ContractInfo], output_dir: &Path) -> Result<()> {
+        fs::create_dir_all(output_dir)?;
+        let code = |items: &[String]| if items.is_empty() {
+            "-".to_string()
+        } else {
+            items.iter().map(|i| format!("`{}`", i)).collect::<Vec<_>>().join(", ")
+        };
+
+        let mut md = String::new();
+        md.push_str("# Access Control\n\n");
+        let unprotected: usize = contracts.iter().map(|c| c.unprotected_writes.len()).sum();
+        md.push_str(&format!("**Unprotected sensitive writes:** {}\n\n", unprotected));
+
+        for contract in contracts.iter().filter(|c| !c.access_control.is_empty()) {
+            md.push_str(&format!("## `{}`\n\n", contract.name));
+            md.push_str(&format!("*{}*\n\n", contract.file_path));
+
+            md.push_str("| Function | Callers | Guards | Writes |\n");
+            md.push_str("|----------|---------|--------|--------|\n");
+            for access in &contract.access_control {
+                let roles = Self::roles(access);
+                let guards: Vec<String> = access.guards.iter().map(|g| g.source.clone()).collect();
+                md.push_str(&format!("| `{}` | {} | {} | {} |\n",
+                    access.function,
+                    if roles.is_empty() { "anyone".to_string() } else { code(&roles) },
+                    code(&guards),
+                    code(&access.writes),
+                ));
+            }
+
+            // Role × function × state
+            let mut by_role: BTreeMap<String, Vec<&EntryPointAccess>> = BTreeMap::new();
+            for access in &contract.access_control {
+                let roles = Self::roles(access);
+                if roles.is_empty() {
+                    by_role.entry("anyone".to_string()).or_default().push(access);
+                }
+                for role in roles {
+                    by_role.entry(role).or_default().push(access);
+                }
+            }
+            md.push_str("\n### Privileges by role\n\n");
+            md.push_str("| Role | Function | Writes |\n");
+            md.push_str("|------|----------|--------|\n");
+            for (role, entries) in &by_role {
+                for access in entries {
+                    md.push_str(&format!("| `{}` | `{}` | {} |\n", role, access.function, code(&access.writes)));
+                }
+            }
+
+            if !contract.unprotected_writes.is_empty() {
+                md.push_str("\n### ⚠️ Unprotected sensitive writes\n\n");
+                for write in &contract.unprotected_writes {
+                    md.push_str(&format!("- `{}` (line {}) lets anyone change {} `{}`\n",
+                        write.function, write.line_number, write.category.as_str(), write.variable));
+                }
+            }
+            md.push('\n');
+        }
+
+        fs::write(output_dir.join("access_control.md"), md)?;
+        println!("  {} access_control.md", "✓".green());
+        Ok(())
+    }
+
+    fn roles(access: &EntryPointAccess) -> Vec<String> {
+        let mut roles: Vec<String> = Vec::new();
+        for guard in &access.guards {
+            if !roles.contains(&guard.role) {
+                roles.push(guard.role.clone());
+            }
+        }
+        roles
+    }
+}
+
+impl<'a> GuardScanner<'a> {
+    fn guards(&self, func: &FunctionDef) -> Vec<AccessGuard> {
+        let Some(part) = self.function_part(&func.signature) else {
+            return Vec::new();
+        };
+        let mut visited = HashSet::new();
+        let mut guards = self.function_guards(part, func.inherited_from.as_deref(), 0, &mut visited);
+        for guard in &mut guards {
+            guard.role = Self::normalize_role(&guard.role);
+        }
+        let mut seen = HashSet::new();
+        guards.retain(|g| seen.insert((g.role.clone(), g.source.clone())));
+        guards
+    }
+
+    /// Checks of `if` statements whose branch only a role reaches, e.g. `if (msg.sender == owner) { ... }`,
+    /// and the state the function writes only inside such branches
+    fn branch_guards(&self, func: &FunctionDef, writes: &HashMap<&str, Vec<String>>) -> (Vec<AccessGuard>, Vec<String>) {
+        let Some(body) = self.function_part(&func.signature).and_then(|part| part.body.as_ref()) else {
+            return (Vec::new(), Vec::new());
+        };
+        let source = self.source(func.inherited_from.as_deref());
+        let mut branches = Vec::new();
+        Self::guarded_branches(body, &mut branches);
+
+        let mut guards = Vec::new();
+        let mut inside = HashMap::new();
+        for branch in branches {
+            guards.push(AccessGuard {
+                role: Self::normalize_role(&branch.role),
+                kind: AccessGuardKind::InlineCheck,
+                source: format!("if ({}) {{ ... }}", branch.cond),
+                line_number: Self::line(source, &branch.loc),
+            });
+            self.count_writes(branch.body, writes, &mut inside);
+        }
+
+        // Guarded when no write of the variable happens outside the branches
+        let mut all = HashMap::new();
+        self.count_writes(body, writes, &mut all);
+        let mut guarded: Vec<String> = inside.into_iter()
+            .filter(|(var, count)| all.get(var) == Some(count))
+            .map(|(var, _)| var)
+            .collect();
+        guarded.sort();
+        (guards, guarded)
+    }
+
+    /// Branches of `if` statements taken only by callers that pass a sender check
+    fn guarded_branches<'s>(stmt: &'s pt::Statement, branches: &mut Vec<GuardedBranch<'s>>) {
+        match stmt {
+            pt::Statement::Block { statements, .. } => {
+                statements.iter().for_each(|s| Self::guarded_branches(s, branches));
+            }
+            pt::Statement::If(loc, cond, then_branch, else_branch) => {
+                Self::guarded_branch(cond, true, then_branch, loc, branches);
+                if let Some(else_branch) = else_branch {
+                    Self::guarded_branch(cond, false, else_branch, loc, branches);
+                }
+            }
+            pt::Statement::While(_, _, body)
+            | pt::Statement::DoWhile(_, body, _)
+            | pt::Statement::For(_, _, _, _, Some(body)) => Self::guarded_branches(body, branches),
+            _ => {}
+        }
+    }
+
+    /// The branch taken when `cond` is `taken`; branches that revert are `if (...) revert` checks
+    fn guarded_branch<'s>(
+        cond: &'s pt::Expression,
+        taken: bool,
+        body: &'s pt::Statement,
+        loc: &pt::Loc,
+        branches: &mut Vec<GuardedBranch<'s>>,
+    ) {
+        match Self::sender_check(cond, taken) {
+            Some(role) if !Self::reverts(body) => branches.push(GuardedBranch { role, cond, loc: *loc, body }),
+            Some(_) => {}
+            None => Self::guarded_branches(body, branches),
+        }
+    }
+
+    /// How many times each state variable is written in a statement, directly or by the internal
+    /// functions it calls
+    fn count_writes(&self, stmt: &pt::Statement, writes: &HashMap<&str, Vec<String>>, counts: &mut HashMap<String, usize>) {
+        StateModificationAnalyzer::visit_statement_expressions(stmt, &mut |expr| {
+            let written: Vec<String> = match expr {
+                pt::Expression::Assign(_, target, _)
+                | pt::Expression::AssignAdd(_, target, _)
+                | pt::Expression::AssignSubtract(_, target, _)
+                | pt::Expression::AssignMultiply(_, target, _)
+                | pt::Expression::AssignDivide(_, target, _)
+                | pt::Expression::AssignModulo(_, target, _)
+                | pt::Expression::AssignOr(_, target, _)
+                | pt::Expression::AssignAnd(_, target, _)
+                | pt::Expression::AssignXor(_, target, _)
+                | pt::Expression::AssignShiftLeft(_, target, _)
+                | pt::Expression::AssignShiftRight(_, target, _)
+                | pt::Expression::PreIncrement(_, target)
+                | pt::Expression::PreDecrement(_, target)
+                | pt::Expression::PostIncrement(_, target)
+                | pt::Expression::PostDecrement(_, target)
+                | pt::Expression::Delete(_, target) => StateModificationAnalyzer::extract_base_variable(target)
+                    .filter(|name| self.contract.state_variables.iter().any(|v| &v.name == name))
+                    .into_iter()
+                    .collect(),
+                pt::Expression::FunctionCall(_, callee, args) => match callee.as_ref() {
+                    pt::Expression::Variable(id) => self.contract.functions.iter()
+                        .filter(|f| f.name == id.name && f.parameters.len() == args.len())
+                        .flat_map(|f| writes.get(f.signature.as_str()).into_iter().flatten().cloned())
+                        .collect(),
+                    _ => Vec::new(),
+                },
+                _ => Vec::new(),
+            };
+            for var in written {
+                *counts.entry(var).or_default() += 1;
+            }
+        });
+    }
+
+    fn function_part(&self, signature: &str) -> Option<&'a pt::FunctionDefinition> {
+        self.ast.parts.iter().find_map(|part| match part {
+            pt::ContractPart::FunctionDefinition(f)
+                if !matches!(f.ty, pt::FunctionTy::Modifier) && SolidityParser::function_signature(f) == signature => Some(f.as_ref()),
+            _ => None,
+        })
+    }
+
+    fn internal_part(&self, name: &str, arity: usize) -> Option<&'a pt::FunctionDefinition> {
+        self.ast.parts.iter().find_map(|part| match part {
+            pt::ContractPart::FunctionDefinition(f)
+                if matches!(f.ty, pt::FunctionTy::Function) && f.params.len() == arity
+                    && f.name.as_ref().is_some_and(|n| n.name == name) => Some(f.as_ref()),
+            _ => None,
+        })
+    }
+
+    fn modifier_part(&self, name: &str) -> Option<&'a pt::FunctionDefinition> {
+        self.ast.parts.iter().find_map(|part| match part {
+            pt::ContractPart::FunctionDefinition(f)
+                if matches!(f.ty, pt::FunctionTy::Modifier) && f.name.as_ref().is_some_and(|n| n.name == name) => Some(f.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// File that declares a member, given the base it is inherited from
+    fn source(&self, inherited_from: Option<&str>) -> Option<&'a SourceUnit> {
+        let file_path = inherited_from
+            .and_then(|base| self.all_contracts.iter().find(|c| c.name == base))
+            .map_or(self.contract.file_path.as_str(), |c| c.file_path.as_str());
+        self.source_units.iter().find(|u| u.file_path == file_path)
+    }
+
+    fn line(source: Option<&SourceUnit>, loc: &pt::Loc) -> usize {
+        source.map(|u| u.line_at(loc.start())).unwrap_or_default()
+    }
+
+    /// Modifier guards, then checks in the body and in the internal functions it calls
+    fn function_guards(
+        &self,
+        part: &'a pt::FunctionDefinition,
+        inherited_from: Option<&str>,
+        depth: usize,
+        visited: &mut HashSet<*const pt::FunctionDefinition>,
+    ) -> Vec<AccessGuard> {
+        if depth > MAX_CHECK_DEPTH || !visited.insert(part as *const _) {
+            return Vec::new();
+        }
+        let source = self.source(inherited_from);
+        let mut guards = Vec::new();
+
+        for attr in &part.attributes {
+            let pt::FunctionAttribute::BaseOrModifier(loc, base) = attr else {
+                continue;
+            };
+            let name = base.name.identifiers.iter().map(|id| id.name.clone()).collect::<Vec<_>>().join(".");
+            let args = base.args.as_deref().unwrap_or_default();
+            let role = if INITIALIZER_MODIFIERS.contains(&name.as_str()) {
+                Some("initializer".to_string())
+            } else if let Some(rest) = name.strip_prefix("only") {
+                Some(args.first().map_or_else(|| Self::role_from_name(rest), |arg| arg.to_string()))
+            } else {
+                // A modifier of the project that checks the sender itself, e.g. `auth`
+                self.modifier_part(&name)
+                    .and_then(|m| m.body.as_ref())
+                    .and_then(|body| self.checks(body, None).into_iter().next())
+                    .map(|check| check.role)
+            };
+            if let Some(role) = role {
+                let source_text = match args.is_empty() {
+                    true => name.clone(),
+                    false => format!("{}({})", name, args.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")),
+                };
+                guards.push(AccessGuard { role, kind: AccessGuardKind::Modifier, source: source_text, line_number: Self::line(source, loc) });
+            }
+        }
+
+        let Some(body) = &part.body else {
+            return guards;
+        };
+        guards.extend(self.checks(body, source));
+
+        // Internal calls: follow project functions, trust the names of ones from outside the project
+        let mut calls: Vec<(String, Vec<String>, pt::Loc)> = Vec::new();
+        StateModificationAnalyzer::visit_statement_expressions(body, &mut |expr| {
+            if let pt::Expression::FunctionCall(loc, callee, args) = expr {
+                if let pt::Expression::Variable(id) = callee.as_ref() {
+                    calls.push((id.name.clone(), args.iter().map(|a| a.to_string()).collect(), *loc));
+                }
+            }
+        });
+        for (name, args, loc) in calls {
+            let text = format!("{}({})", name, args.join(", "));
+            let line_number = Self::line(source, &loc);
+            match self.internal_part(&name, args.len()) {
+                Some(callee) => {
+                    let declared = self.contract.functions.iter()
+                        .find(|f| f.signature == SolidityParser::function_signature(callee))
+                        .and_then(|f| f.inherited_from.clone());
+                    for inner in self.function_guards(callee, declared.as_deref(), depth + 1, visited) {
+                        guards.push(AccessGuard { role: inner.role, kind: AccessGuardKind::InternalCheck, source: text.clone(), line_number });
+                    }
+                }
+                None if CHECK_PREFIXES.iter().any(|p| name.starts_with(p))
+                    && CHECK_SUBJECTS.iter().any(|s| name.to_lowercase().contains(s)) =>
+                {
+                    let rest = CHECK_PREFIXES.iter().find_map(|p| name.strip_prefix(p)).unwrap_or_default();
+                    let role = args.first().cloned().unwrap_or_else(|| Self::role_from_name(rest));
+                    guards.push(AccessGuard { role, kind: AccessGuardKind::InternalCheck, source: text, line_number });
+                }
+                None => {}
+            }
+        }
+        guards
+    }
+
+    /// `require`/`assert` on the sender, and `if (...) revert` on it
+    fn checks(&self, body: &pt::Statement, source: Option<&SourceUnit>) -> Vec<AccessGuard> {
+        let mut guards = Vec::new();
+        StateModificationAnalyzer::visit_statement_expressions(body, &mut |expr| {
+            if let pt::Expression::FunctionCall(loc, callee, args) = expr {
+                let is_assertion = matches!(callee.as_ref(), pt::Expression::Variable(id) if id.name == "require" || id.name == "assert");
+                if let (true, Some(cond)) = (is_assertion, args.first()) {
+                    if let Some(role) = Self::sender_check(cond, true) {
+                        guards.push(AccessGuard {
+                            role,
+                            kind: AccessGuardKind::InlineCheck,
+                            source: format!("require({})", cond),
+                            line_number: Self::line(source, loc),
+                        });
+                    }
+                }
+            }
+        });
+        Self::revert_checks(body, source, &mut guards);
+        guards
+    }
+
+    fn revert_checks(stmt: &pt::Statement, source: Option<&SourceUnit>, guards: &mut Vec<AccessGuard>) {
+        match stmt {
+            pt::Statement::Block { statements, .. } => {
+                statements.iter().for_each(|s| Self::revert_checks(s, source, guards));
+            }
+            pt::Statement::If(loc, cond, then_branch, else_branch) => {
+                if let (true, Some(role)) = (Self::reverts(then_branch), Self::sender_check(cond, false)) {
+                    guards.push(AccessGuard {
+                        role,
+                        kind: AccessGuardKind::InlineCheck,
+                        source: format!("if ({}) revert", cond),
+                        line_number: Self::line(source, loc),
+                    });
+                }
+                Self::revert_checks(then_branch, source, guards);
+                if let Some(else_branch) = else_branch {
+                    Self::revert_checks(else_branch, source, guards);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn reverts(stmt: &pt::Statement) -> bool {
+        match stmt {
+            pt::Statement::Revert(..) | pt::Statement::RevertNamedArgs(..) => true,
+            pt::Statement::Block { statements, .. } => statements.first().is_some_and(Self::reverts),
+            pt::Statement::Expression(_, pt::Expression::FunctionCall(_, callee, _)) => {
+                matches!(callee.as_ref(), pt::Expression::Variable(id) if id.name == "revert")
+            }
+            _ => false,
+        }
+    }
+
+    /// Who a condition on the sender admits, e.g. "owner" for `msg.sender == owner()`. The caller
+    /// gets through when it holds if `passes` (`require`), otherwise when it fails (`if (...) revert`);
+    /// a condition that only rejects some callers, like a blacklist, admits anyone
+    fn sender_check(cond: &pt::Expression, passes: bool) -> Option<String> {
+        // Every side must restrict the caller when passing either one is enough
+        let all = |left: &pt::Expression, right: &pt::Expression| {
+            match (Self::sender_check(left, passes), Self::sender_check(right, passes)) {
+                (Some(left), Some(right)) => Some(format!("{} or {}", left, right)),
+                _ => None,
+            }
+        };
+        let any = |left: &pt::Expression, right: &pt::Expression| {
+            Self::sender_check(left, passes).or_else(|| Self::sender_check(right, passes))
+        };
+        match cond {
+            pt::Expression::Parenthesis(_, inner) => Self::sender_check(inner, passes),
+            pt::Expression::Not(_, inner) => Self::sender_check(inner, !passes),
+            pt::Expression::And(_, left, right) if passes => any(left, right),
+            pt::Expression::And(_, left, right) => all(left, right),
+            pt::Expression::Or(_, left, right) if passes => all(left, right),
+            pt::Expression::Or(_, left, right) => any(left, right),
+            pt::Expression::Equal(_, left, right) if passes => Self::compared_role(left, right),
+            pt::Expression::NotEqual(_, left, right) if !passes => Self::compared_role(left, right),
+            // `hasRole(ROLE, msg.sender)`, `isKeeper(msg.sender)`
+            pt::Expression::FunctionCall(_, callee, args) if passes && args.iter().any(Self::is_sender) => {
+                let name = match callee.as_ref() {
+                    pt::Expression::Variable(id) => id.name.clone(),
+                    other => other.to_string(),
+                };
+                match (name.ends_with("hasRole") || name.ends_with("hasAnyRole"), args.first()) {
+                    (true, Some(role)) if !Self::is_sender(role) => Some(role.to_string()),
+                    _ => Some(name),
+                }
+            }
+            // `whitelist[msg.sender]`, `users[msg.sender].isAdmin`
+            pt::Expression::ArraySubscript(_, base, Some(index)) if passes && Self::is_sender(index) => Some(base.to_string()),
+            pt::Expression::MemberAccess(_, base, _) => Self::sender_check(base, passes).map(|_| cond.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The side `msg.sender` is compared with
+    fn compared_role(left: &pt::Expression, right: &pt::Expression) -> Option<String> {
+        if Self::is_sender(left) {
+            Some(Self::role_of(right))
+        } else if Self::is_sender(right) {
+            Some(Self::role_of(left))
+        } else {
+            None
+        }
+    }
+
+    fn is_sender(expr: &pt::Expression) -> bool {
+        match expr {
+            pt::Expression::MemberAccess(_, base, member) => {
+                member.name == "sender" && matches!(base.as_ref(), pt::Expression::Variable(id) if id.name == "msg")
+            }
+            pt::Expression::FunctionCall(_, callee, args) => match callee.as_ref() {
+                pt::Expression::Variable(id) if id.name == "_msgSender" => args.is_empty(),
+                pt::Expression::Type(..) => args.len() == 1 && Self::is_sender(&args[0]),
+                _ => false,
+            },
+            pt::Expression::Parenthesis(_, inner) => Self::is_sender(inner),
+            _ => false,
+        }
+    }
+
+    /// `owner()` and `address(pool)` name their role without the call syntax
+    fn role_of(expr: &pt::Expression) -> String {
+        match expr {
+            pt::Expression::FunctionCall(_, callee, args) if args.is_empty() => callee.to_string(),
+            pt::Expression::FunctionCall(_, callee, args) if args.len() == 1 && matches!(callee.as_ref(), pt::Expression::Type(..)) => {
+                Self::role_of(&args[0])
+            }
+            pt::Expression::Parenthesis(_, inner) => Self::role_of(inner),
+            other => other.to_string(),
+        }
+    }
+
+    /// `_owner` and `_checkOwner` name the role `onlyOwner` does: "owner"
+    fn normalize_role(role: &str) -> String {
+        role.split(" or ")
+            .map(|part| {
+                let name = part.trim_start_matches('_');
+                if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return part.to_string();
+                }
+                let name = CHECK_PREFIXES.iter()
+                    .filter_map(|prefix| name.strip_prefix(prefix.trim_start_matches('_')))
+                    .find(|rest| rest.starts_with(char::is_uppercase))
+                    .unwrap_or(name);
+                Self::role_from_name(name)
+            })
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+
+    /// "Owner" → "owner", "LPManager" stays as written
+    fn role_from_name(name: &str) -> String {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(first), Some(second)) if second.is_lowercase() => format!("{}{}", first.to_lowercase(), &name[first.len_utf8()..]),
+            (Some(_), _) => name.to_string(),
+            (None, _) => "unknown".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+
+    const VAULT: &str = r#"
+        contract Vault {
+            address public owner;
+            uint256 public feeBps;
+            mapping(address => bool) public blacklist;
+            bytes32 public constant ADMIN = keccak256("ADMIN");
+
+            function hasRole(bytes32 role, address account) public view returns (bool) {}
+
+            function transferOwnership(address newOwner) external {
+                require(newOwner != msg.sender);
+                owner = newOwner;
+            }
+
+            function setFeeUnlessBlacklisted(uint256 fee) external {
+                require(!blacklist[msg.sender]);
+                feeBps = fee;
+            }
+
+            function setFeeUnlessOwner(uint256 fee) external {
+                if (msg.sender == owner) revert();
+                feeBps = fee;
+            }
+
+            function setOwner(address newOwner) external {
+                require(msg.sender == owner, "not owner");
+                owner = newOwner;
+            }
+
+            function setFee(uint256 fee) external {
+                if (msg.sender != owner) revert();
+                feeBps = fee;
+            }
+
+            function setFeeAsAdmin(uint256 fee) external {
+                if (!hasRole(ADMIN, msg.sender)) revert();
+                feeBps = fee;
+            }
+
+            function claimOwnership(address newOwner) external {
+                if (msg.sender == owner) {
+                    owner = newOwner;
+                }
+            }
+
+            function setOwnerAndFee(address newOwner, uint256 fee) external {
+                if (msg.sender != owner) {
+                    feeBps = fee;
+                } else {
+                    owner = newOwner;
+                }
+            }
+        }
+    "#;
+
+    fn guards<'a>(contract: &'a ContractInfo, function: &str) -> &'a [AccessGuard] {
+        &contract.access_control.iter().find(|a| a.function == function).unwrap().guards
+    }
+
+    #[test]
+    fn checks_that_reject_some_callers_admit_anyone() {
+        let project = analyze(VAULT);
+        let vault = contract(&project, "Vault");
+        for function in ["transferOwnership(address)", "setFeeUnlessBlacklisted(uint256)", "setFeeUnlessOwner(uint256)"] {
+            assert!(guards(vault, function).is_empty(), "{}: {:?}", function, guards(vault, function));
+            assert!(vault.unprotected_writes.iter().any(|w| w.function == function), "{}", function);
+        }
+    }
+
+    #[test]
+    fn checks_that_only_let_a_role_through_are_guards() {
+        let project = analyze(VAULT);
+        let vault = contract(&project, "Vault");
+        assert_eq!(guards(vault, "setOwner(address)")[0].role, "owner");
+        assert_eq!(guards(vault, "setFee(uint256)")[0].role, "owner");
+        assert_eq!(guards(vault, "setFeeAsAdmin(uint256)")[0].role, "ADMIN");
+        assert!(!vault.unprotected_writes.iter().any(|w| w.function.starts_with("setFee(")));
+    }
+
+    #[test]
+    fn writes_inside_a_branch_only_the_role_takes_are_guarded() {
+        let project = analyze(VAULT);
+        let vault = contract(&project, "Vault");
+        let claim = guards(vault, "claimOwnership(address)");
+        assert_eq!((claim[0].role.as_str(), claim[0].source.as_str()), ("owner", "if (msg.sender == owner) { ... }"));
+        assert!(!vault.unprotected_writes.iter().any(|w| w.function == "claimOwnership(address)"));
+
+        // Anyone reaches the fee update; only the owner reaches the ownership change
+        assert!(guards(vault, "setOwnerAndFee(address,uint256)").is_empty());
+        let unprotected: Vec<&str> = vault.unprotected_writes.iter()
+            .filter(|w| w.function == "setOwnerAndFee(address,uint256)")
+            .map(|w| w.variable.as_str())
+            .collect();
+        assert_eq!(unprotected, ["feeBps"]);
+    }
+
+    #[test]
+    fn roles_are_named_alike_across_modifiers_and_checks() {
+        let project = analyze(r#"
+            contract Owned {
+                address private _owner;
+                uint256 public feeBps;
+
+                modifier onlyOwner() { _checkOwner(); _; }
+
+                function _checkOwner() internal view { require(msg.sender == _owner); }
+
+                function setFee(uint256 fee) external onlyOwner { feeBps = fee; }
+                function resetFee() external { _checkOwner(); feeBps = 0; }
+            }
+        "#);
+        let owned = contract(&project, "Owned");
+        for function in ["setFee(uint256)", "resetFee()"] {
+            let roles: Vec<&str> = guards(owned, function).iter().map(|g| g.role.as_str()).collect();
+            assert_eq!(roles, ["owner"], "{}", function);
+        }
+    }
+}
@@ -1,40 +1,239 @@
 use solang_parser::pt;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
 use crate::models::*;
+use crate::constants::ConstantFolder;
 use crate::dataflow::DataFlowAnalyzer;
+use crate::parser::SolidityParser;
+use crate::pragma::PragmaChecker;
+use crate::types::ExpressionTyper;
+use crate::yul::YulAnalyzer;
+
+/// A call expression found in a function body, before overload resolution
+struct CallSite {
+    name: String,                   // Callee as written: "fn", "super.fn", "Base.fn" or "x.fn"
+    arg_types: Vec<Option<String>>, // Best-effort argument types (None when unknown)
+    receiver_type: Option<String>,  // Type of `x` in `x.fn(...)`, for calls bound with `using for`
+}
 
 /// Context for scanning external calls
 struct ExternalCallContext<'a> {
     contract_name: &'a str,
     function_name: &'a str,
-    var_types: &'a HashMap<String, String>,
-    type_to_contract: &'a HashMap<String, String>,
-    line_number: usize,
+    var_types: HashMap<String, String>,            // Names in scope -> declared type
+    receiver_kinds: HashMap<String, ReceiverKind>, // Names in scope -> how they are declared
+    types: &'a ReceiverTypes<'a>,
+    source: Option<&'a SourceUnit>, // File declaring the scanned function, for line numbers
+    line_number: usize,             // Fallback when the file is not loaded
     external_calls: &'a mut Vec<ExternalCall>,
     all_contracts: &'a [ContractInfo],
     function_external_calls: &'a mut HashMap<String, Vec<ExternalCall>>,
+    library_scope: &'a LibraryScope<'a>,
+    typer: &'a ExpressionTyper<'a>, // Scoped to the scanned function
+}
+
+impl ExternalCallContext<'_> {
+    fn line_of(&self, loc: &pt::Loc) -> usize {
+        match (loc, self.source) {
+            (pt::Loc::File(_, start, _), Some(unit)) => unit.line_at(*start),
+            _ => self.line_number,
+        }
+    }
+
+    fn record(&mut self, ext_call: ExternalCall) {
+        self.external_calls.push(ext_call.clone());
+        self.function_external_calls
+            .entry(self.function_name.to_string())
+            .or_default()
+            .push(ext_call);
+    }
+}
+
+/// Types known to the contract being scanned for external calls
+struct ReceiverTypes<'a> {
+    state_var_types: &'a HashMap<String, String>,
+    type_to_contract: &'a HashMap<String, Vec<String>>,
+    value_types: &'a HashSet<String>,    // Structs, enums and user-defined value types
+    function_names: &'a HashSet<String>, // Callable names that are not conversions
+}
+
+/// The expression a member call is made on
+struct Receiver {
+    name: String, // As written, e.g. "pools[id]" or "IERC20(token)"
+    ty: String,
+    kind: ReceiverKind,
+}
+
+/// Libraries and `using ... for` directives visible from one contract
+pub(crate) struct LibraryScope<'a> {
+    libraries: Vec<&'a ContractInfo>,
+    directives: Vec<&'a UsingDirective>,
+    units: Vec<&'a SourceUnit>,
+}
+
+/// A library function (or bound free function) a call resolves to
+pub(crate) struct LibraryCallee<'a> {
+    pub(crate) library: Option<&'a str>, // None for free functions attached with `using {f} for T`
+    pub(crate) function: &'a FunctionDef,
+}
+
+impl<'a> LibraryScope<'a> {
+    /// Directives of the contract and its bases, of its file, and `global` ones of imported files
+    pub(crate) fn new(
+        contract_name: &str,
+        file_path: &str,
+        bases: &[String],
+        all_contracts: &'a [ContractInfo],
+        source_units: &'a [SourceUnit],
+    ) -> LibraryScope<'a> {
+        let units = StateModificationAnalyzer::visible_source_units(file_path, source_units);
+
+        let mut directives: Vec<&UsingDirective> = all_contracts.iter()
+            .filter(|c| (c.name == contract_name && c.file_path == file_path) || bases.contains(&c.name))
+            .flat_map(|c| c.using_directives.iter())
+            .collect();
+        for unit in &units {
+            directives.extend(unit.using_directives.iter().filter(|d| unit.file_path == file_path || d.is_global));
+        }
+
+        LibraryScope {
+            libraries: all_contracts.iter().filter(|c| c.kind == ContractKind::Library).collect(),
+            directives,
+            units,
+        }
+    }
+
+    fn library(&self, name: &str) -> Option<&'a ContractInfo> {
+        self.libraries.iter().copied().find(|l| l.name == name)
+    }
+
+    /// `Library.fn(args)`
+    pub(crate) fn resolve_direct(&self, library: &str, member: &str, arg_types: &[Option<String>]) -> Vec<LibraryCallee<'a>> {
+        let Some(library) = self.library(library) else {
+            return Vec::new();
+        };
+        let candidates = library.functions.iter().filter(|f| f.name == member).collect();
+        StateModificationAnalyzer::select_overloads(candidates, arg_types)
+            .into_iter()
+            .map(|function| LibraryCallee { library: Some(library.name.as_str()), function })
+            .collect()
+    }
+
+    /// `x.fn(args)` where `fn` is attached to the type of `x`; `x` is passed as the first argument
+    pub(crate) fn resolve_bound(&self, receiver_type: Option<&str>, member: &str, arg_types: &[Option<String>]) -> Vec<LibraryCallee<'a>> {
+        let mut candidates: Vec<LibraryCallee> = Vec::new();
+        let add_library_functions = |candidates: &mut Vec<LibraryCallee<'a>>, library: &'a ContractInfo| {
+            for function in library.functions.iter().filter(|f| f.name == member) {
+                let known = candidates.iter().any(|c| c.library == Some(library.name.as_str())
+                    && c.function.signature == function.signature);
+                if !known {
+                    candidates.push(LibraryCallee { library: Some(library.name.as_str()), function });
+                }
+            }
+        };
+
+        for directive in self.directives.iter().filter(|d| Self::binds(d, receiver_type)) {
+            if let Some(library) = directive.library.as_deref().and_then(|l| self.library(l)) {
+                add_library_functions(&mut candidates, library);
+            }
+            for listed in &directive.functions {
+                match listed.rsplit_once('.') {
+                    Some((library, name)) if name == member => {
+                        if let Some(library) = self.library(library) {
+                            add_library_functions(&mut candidates, library);
+                        }
+                    }
+                    None if listed == member => {
+                        let free = self.units.iter()
+                            .flat_map(|u| u.functions.iter())
+                            .filter(|f| f.name == member && !candidates.iter().any(|c| c.library.is_none() && c.function.signature == f.signature))
+                            .collect::<Vec<_>>();
+                        candidates.extend(free.into_iter().map(|function| LibraryCallee { library: None, function }));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut all_arg_types = vec![receiver_type.map(str::to_string)];
+        all_arg_types.extend(arg_types.iter().cloned());
+        let selected = StateModificationAnalyzer::select_overloads(
+            candidates.iter().map(|c| c.function).collect(),
+            &all_arg_types,
+        );
+        let resolved: Vec<LibraryCallee> = candidates.into_iter()
+            .filter(|c| selected.iter().any(|f| std::ptr::eq(*f, c.function)))
+            .collect();
+
+        // Without the receiver type only an unambiguous match is trusted
+        if receiver_type.is_none() && resolved.len() > 1 {
+            return Vec::new();
+        }
+        resolved
+    }
+
+    /// Whether a directive attaches functions to values of `receiver_type` (None = unknown)
+    fn binds(directive: &UsingDirective, receiver_type: Option<&str>) -> bool {
+        match (directive.target_type.as_deref(), receiver_type) {
+            (None, _) | (_, None) => true,
+            // `Tracker` and `TicketComboTracker.Tracker` name the same struct
+            (Some(target), Some(receiver)) => target == receiver
+                || target.rsplit('.').next() == receiver.rsplit('.').next(),
+        }
+    }
+
+    /// `x.fn()` where the contract type of `x` declares no `fn`, but a `using` directive
+    /// attaches a library that was not loaded: a bound call whose target is unknown
+    pub(crate) fn is_unresolved_bound_call(&self, receiver_type: &str, member: &str, all_contracts: &[ContractInfo]) -> bool {
+        let declares_member = all_contracts.iter()
+            .find(|c| c.name == receiver_type)
+            .map(|c| c.functions.iter().any(|f| f.name == member));
+        declares_member == Some(false) && self.directives.iter()
+            .filter(|d| Self::binds(d, Some(receiver_type)))
+            .any(|d| d.library.as_deref().is_some_and(|l| self.library(l).is_none()))
+    }
+
+    /// Resolve a call site to library functions: `Library.fn()` first, then bound `x.fn()`
+    fn resolve(&self, call: &CallSite) -> Vec<LibraryCallee<'a>> {
+        let Some((receiver, member)) = call.name.split_once('.') else {
+            return Vec::new();
+        };
+        if receiver == "super" || receiver == "this" {
+            return Vec::new();
+        }
+        if self.library(receiver).is_some() {
+            return self.resolve_direct(receiver, member, &call.arg_types);
+        }
+        self.resolve_bound(call.receiver_type.as_deref(), member, &call.arg_types)
+    }
 }
 
 pub struct StateModificationAnalyzer;
 
 impl StateModificationAnalyzer {
     /// Analyze which functions modify which state variables and build call chains
-    pub fn analyze(contract_info: &mut ContractInfo, ast: &pt::ContractDefinition) {
+    /// File-level definitions (free functions, constants, errors) are looked up in `source_units`,
+    /// library functions (for `Library.fn()` and `using ... for` calls) in `all_contracts`.
+    pub fn analyze(
+        contract_info: &mut ContractInfo,
+        ast: &pt::ContractDefinition,
+        source_units: &[SourceUnit],
+        all_contracts: &[ContractInfo],
+    ) {
         // Step 1: Extract function and modifier bodies
         let mut function_bodies: HashMap<String, &pt::FunctionDefinition> = HashMap::new();
         let mut modifier_bodies: HashMap<String, &pt::FunctionDefinition> = HashMap::new();
 
         for part in &ast.parts {
             if let pt::ContractPart::FunctionDefinition(f) = part {
-                let func_name = f.name.as_ref()
-                    .map(|n| n.name.clone())
-                    .unwrap_or_else(|| "constructor".to_string());
-
                 if matches!(f.ty, pt::FunctionTy::Modifier) {
-                    modifier_bodies.insert(func_name, f);
+                    let modifier_name = f.name.as_ref()
+                        .map(|n| n.name.clone())
+                        .unwrap_or_default();
+                    modifier_bodies.insert(modifier_name, f);
                 } else {
-                    function_bodies.insert(func_name, f);
+                    // Keyed by signature so that overloads don't overwrite each other
+                    function_bodies.insert(SolidityParser::function_signature(f), f);
                 }
             }
         }
@@ -61,8 +260,59 @@ impl StateModificationAnalyzer {
             .map(|f| f.name.clone())
             .collect();
 
+        let mut linearization = vec![contract_info.name.clone()];
+        linearization.extend(contract_info.linearized_bases.iter().cloned());
+
+        // Snapshot used to resolve call sites to overloads while functions are being filled in
+        let function_index = contract_info.functions.clone();
+        let state_var_types: HashMap<String, String> = contract_info.state_variables
+            .iter()
+            .map(|v| (v.name.clone(), v.var_type.clone()))
+            .collect();
+
+        // File-level definitions visible from this contract; state variables shadow constants
+        let visible_units = Self::visible_source_units(&contract_info.file_path, source_units);
+        let file_constant_names: HashSet<String> = visible_units.iter()
+            .flat_map(|u| u.constants.iter().map(|c| c.name.clone()))
+            .filter(|name| !state_var_names.contains(name))
+            .collect();
+        let library_scope = LibraryScope::new(
+            &contract_info.name,
+            &contract_info.file_path,
+            &contract_info.linearized_bases,
+            all_contracts,
+            source_units,
+        );
+
+        // Slots assembly can name: `x.slot` of a state variable, or the namespaced storage location
+        let mut slot_names: HashMap<String, String> = state_var_names.iter()
+            .map(|v| (format!("{}.slot", v), v.clone()))
+            .collect();
+        for storage in &contract_info.upgradeable_storage {
+            slot_names.insert(storage.storage_location_constant.clone(), storage.storage_struct.clone());
+        }
+
+        let checked_arithmetic = PragmaChecker::checked_arithmetic(&contract_info.compiler);
+
+        // Line numbers come from the file that declares the function
+        let declared = contract_info.clone();
+        let mut typer = ExpressionTyper::new(&declared, all_contracts, source_units);
+        let source_of = |inherited_from: &Option<String>| {
+            let file_path = inherited_from.as_ref()
+                .and_then(|base| all_contracts.iter().find(|c| &c.name == base))
+                .map_or(declared.file_path.as_str(), |c| c.file_path.as_str());
+            source_units.iter().find(|u| u.file_path == file_path)
+        };
+        // Folded constants, so that casts of values known to fit aren't reported
+        let constants: HashMap<&str, &ConstantValue> = visible_units.iter()
+            .flat_map(|u| &u.constants)
+            .chain(&declared.state_variables)
+            .filter(|v| v.is_constant)
+            .filter_map(|v| Some((v.name.as_str(), v.value.as_ref()?)))
+            .collect();
+
         for func in &mut contract_info.functions {
-            if let Some(body) = function_bodies.get(&func.name) {
+            if let Some(body) = function_bodies.get(&func.signature) {
                 // Extract storage reference parameters
                 func.storage_params = Self::extract_storage_params(body);
 
@@ -88,7 +338,21 @@ impl StateModificationAnalyzer {
                 );
 
                 // Find function calls
-                func.calls_functions = Self::find_function_calls(body);
+                let defining_contract = func.inherited_from.clone()
+                    .unwrap_or_else(|| linearization[0].clone());
+                let mut var_types = state_var_types.clone();
+                Self::collect_local_types(body, &mut var_types);
+                let (calls_functions, calls_free_functions) = Self::resolve_call_sites(
+                    Self::find_function_calls(body, &var_types),
+                    &defining_contract,
+                    &linearization,
+                    &function_index,
+                    &visible_units,
+                    &library_scope,
+                );
+                func.calls_functions = calls_functions;
+                func.calls_free_functions = calls_free_functions;
+                func.reads_file_constants = Self::find_state_reads(body, &file_constant_names, &[]);
 
                 // Find event emissions
                 func.emits_events = Self::find_event_emissions(body, &event_names);
@@ -96,8 +360,15 @@ impl StateModificationAnalyzer {
                 // Find error usage
                 func.uses_errors = Self::find_error_usage(body, &error_names);
 
-                // Check for unchecked blocks
-                func.has_unchecked = Self::has_unchecked_blocks(body);
+                // Arithmetic wraps in unchecked blocks, and everywhere before 0.8.0
+                func.has_unchecked = Self::has_unchecked_blocks(body)
+                    || (!checked_arithmetic && Self::has_overflowing_arithmetic(body));
+
+                // Inline assembly: slot accesses, low-level calls and memory tricks
+                func.assembly = YulAnalyzer::analyze(body, &slot_names);
+
+                typer.enter_function(body);
+                func.narrowing_casts = Self::find_narrowing_casts(body, &typer, &constants, source_of(&func.inherited_from));
 
                 // Analyze return value usage
                 let (return_usage, ignored_returns) = Self::analyze_return_value_usage(body, &function_names);
@@ -115,11 +386,89 @@ impl StateModificationAnalyzer {
         }
 
         // Step 2c: Propagate modifications through storage parameters (inter-procedural analysis)
+        Self::propagate_storage_param_effects(&mut contract_info.functions, &function_bodies);
         Self::propagate_storage_param_modifications(
             &mut contract_info.functions,
             &function_bodies,
             &state_var_names
         );
+        Self::propagate_library_effects(
+            &mut contract_info.functions,
+            &function_bodies,
+            &state_var_names,
+            &library_scope,
+            &state_var_types,
+        );
+
+        // Step 2d: Analyze modifier bodies on each side of `_`
+        let mut modifier_effects: HashMap<String, Vec<ModifierEffect>> = HashMap::new();
+        for (modifier_name, modifier_body) in &modifier_bodies {
+            let defining_contract = contract_info.modifiers.iter()
+                .find(|m| &m.name == modifier_name)
+                .and_then(|m| m.inherited_from.clone())
+                .unwrap_or_else(|| linearization[0].clone());
+
+            let effects = Self::split_at_placeholder(modifier_body)
+                .into_iter()
+                .map(|(phase, part)| {
+                    let mut var_types = state_var_types.clone();
+                    Self::collect_local_types(&part, &mut var_types);
+                    let (calls_functions, _) = Self::resolve_call_sites(
+                        Self::find_function_calls(&part, &var_types),
+                        &defining_contract,
+                        &linearization,
+                        &function_index,
+                        &visible_units,
+                        &library_scope,
+                    );
+                    ModifierEffect {
+                        modifier: modifier_name.clone(),
+                        phase,
+                        modifies_states: Self::find_state_modifications(&part, &state_var_names, &[]),
+                        reads_states: Self::find_state_reads(&part, &state_var_names, &[]),
+                        calls_functions,
+                        external_calls: Vec::new(), // Will be filled by detect_external_calls
+                    }
+                })
+                .collect();
+            modifier_effects.insert(modifier_name.clone(), effects);
+        }
+
+        for modifier in &mut contract_info.modifiers {
+            modifier.effects = modifier_effects.get(&modifier.name).cloned().unwrap_or_default();
+        }
+
+        // Step 2e: Modifier effects become part of each function using the modifier;
+        // remember the accesses that only happen inside a modifier
+        let mut writes_via_modifier: HashMap<(String, String), String> = HashMap::new();
+        let mut reads_via_modifier: HashMap<(String, String), String> = HashMap::new();
+        for func in &mut contract_info.functions {
+            func.modifier_effects = func.modifier_order.iter()
+                .filter_map(|m| modifier_effects.get(m))
+                .flatten()
+                .cloned()
+                .collect();
+
+            for effect in &func.modifier_effects {
+                for var in &effect.modifies_states {
+                    if !func.modifies_states.contains(var) {
+                        func.modifies_states.push(var.clone());
+                        writes_via_modifier.insert((func.signature.clone(), var.clone()), effect.modifier.clone());
+                    }
+                }
+                for var in &effect.reads_states {
+                    if !func.reads_states.contains(var) {
+                        func.reads_states.push(var.clone());
+                        reads_via_modifier.insert((func.signature.clone(), var.clone()), effect.modifier.clone());
+                    }
+                }
+                for callee in &effect.calls_functions {
+                    if !func.calls_functions.contains(callee) {
+                        func.calls_functions.push(callee.clone());
+                    }
+                }
+            }
+        }
 
         // Step 3: Build call graph
         let call_graph = Self::build_call_graph(&contract_info.functions);
@@ -130,6 +479,7 @@ impl StateModificationAnalyzer {
                 &state_var.name,
                 &contract_info.functions,
                 &call_graph,
+                &writes_via_modifier,
             );
 
             // Also build read chains
@@ -137,6 +487,7 @@ impl StateModificationAnalyzer {
                 &state_var.name,
                 &contract_info.functions,
                 &call_graph,
+                &reads_via_modifier,
             );
         }
 
@@ -145,19 +496,19 @@ impl StateModificationAnalyzer {
             event.emitted_in = contract_info.functions
                 .iter()
                 .filter(|f| f.emits_events.contains(&event.name))
-                .map(|f| f.name.clone())
+                .map(|f| f.signature.clone())
                 .collect();
         }
 
         // Step 6: Build reverse lookups for errors (which functions use them)
         // Include both direct usage in functions and indirect usage through modifiers
         for error in &mut contract_info.errors {
-            let mut used_in = HashSet::new();
+            let mut used_in = BTreeSet::new();
 
             // Direct usage in functions
             for func in &contract_info.functions {
                 if func.uses_errors.contains(&error.name) {
-                    used_in.insert(func.name.clone());
+                    used_in.insert(func.signature.clone());
                 }
             }
 
@@ -182,7 +533,7 @@ impl StateModificationAnalyzer {
             .collect();
 
         // Collect all used errors from functions and modifiers
-        let mut all_used_errors: HashSet<String> = HashSet::new();
+        let mut all_used_errors: BTreeSet<String> = BTreeSet::new();
         for func in &contract_info.functions {
             all_used_errors.extend(func.uses_errors.clone());
         }
@@ -194,12 +545,12 @@ impl StateModificationAnalyzer {
         for error_name in all_used_errors {
             if !defined_error_names.contains(&error_name) {
                 // This error is used but not defined - it's inherited or imported
-                let mut used_in = HashSet::new();
+                let mut used_in = BTreeSet::new();
 
                 // Find which functions use this error
                 for func in &contract_info.functions {
                     if func.uses_errors.contains(&error_name) {
-                        used_in.insert(func.name.clone());
+                        used_in.insert(func.signature.clone());
                     }
                 }
 
@@ -210,13 +561,25 @@ impl StateModificationAnalyzer {
                     }
                 }
 
+                // File-level errors carry their parameters; others are known by name only
+                let file_level = visible_units.iter().find_map(|unit| {
+                    unit.errors.iter()
+                        .find(|e| e.name == error_name)
+                        .map(|e| (unit.file_path.as_str(), e))
+                });
+
                 // Create an ErrorDef for this inherited error
                 let inherited_error = ErrorDef {
                     name: error_name.clone(),
-                    parameters: Vec::new(), // We don't know the parameters
+                    parameters: file_level.map(|(_, e)| e.parameters.clone()).unwrap_or_default(),
                     line_number: 0,         // Not defined in this contract
                     used_in: used_in.into_iter().collect(),
                     is_inherited: true,
+                    inherited_from: None,   // Not declared by a base contract
+                    declared_at: file_level.map(|(file, e)| format!("{}:{}", file, e.line_number)),
+                    signature: None,        // Will be filled by ABI generator if the declaration is known
+                    selector: None,
+                    natspec: file_level.and_then(|(_, e)| e.natspec.clone()),
                 };
 
                 contract_info.errors.push(inherited_error);
@@ -228,7 +591,7 @@ impl StateModificationAnalyzer {
             modifier.used_in = contract_info.functions
                 .iter()
                 .filter(|f| f.uses_modifiers.contains(&modifier.name))
-                .map(|f| f.name.clone())
+                .map(|f| f.signature.clone())
                 .collect();
         }
 
@@ -243,6 +606,167 @@ impl StateModificationAnalyzer {
         contract_info.dataflow_analysis = Some(dataflow_analysis);
     }
 
+    /// Source units whose file-level definitions a contract in `file_path` can see:
+    /// its own file first, then everything reachable through imports (breadth-first).
+    /// Symbol lists of `import {A} from` are not applied, so this may over-approximate.
+    pub fn visible_source_units<'a>(file_path: &str, units: &'a [SourceUnit]) -> Vec<&'a SourceUnit> {
+        let mut visible: Vec<&SourceUnit> = Vec::new();
+        let mut queue: VecDeque<&str> = VecDeque::from([file_path]);
+        let mut seen: HashSet<&str> = HashSet::from([file_path]);
+
+        while let Some(current) = queue.pop_front() {
+            let Some(unit) = units.iter().find(|u| u.file_path == current) else {
+                continue;
+            };
+            visible.push(unit);
+            for import in &unit.imports {
+                if let Some(resolved) = import.resolved.as_deref() {
+                    if seen.insert(resolved) {
+                        queue.push_back(resolved);
+                    }
+                }
+            }
+        }
+        visible
+    }
+
+    /// Analyze free function bodies: free functions called, constants read, errors thrown
+    /// `asts` holds the definitions of `unit.functions` in the same order.
+    pub fn analyze_source_unit(unit: &mut SourceUnit, asts: &[pt::FunctionDefinition], all_units: &[SourceUnit]) {
+        let visible_units = Self::visible_source_units(&unit.file_path, all_units);
+        let constant_names: HashSet<String> = visible_units.iter()
+            .flat_map(|u| u.constants.iter().map(|c| c.name.clone()))
+            .collect();
+        let event_names: HashSet<String> = visible_units.iter()
+            .flat_map(|u| u.events.iter().map(|e| e.name.clone()))
+            .collect();
+        let checked_arithmetic = PragmaChecker::checked_arithmetic(&unit.compiler);
+
+        for (func, body) in unit.functions.iter_mut().zip(asts) {
+            let mut var_types = HashMap::new();
+            Self::collect_local_types(body, &mut var_types);
+
+            let mut calls_free_functions: Vec<String> = Vec::new();
+            for call in Self::find_function_calls(body, &var_types) {
+                for callee in Self::resolve_free_call(&call, &visible_units) {
+                    if !calls_free_functions.contains(&callee) {
+                        calls_free_functions.push(callee);
+                    }
+                }
+            }
+            func.calls_free_functions = calls_free_functions;
+            func.storage_params = Self::extract_storage_params(body);
+            func.reads_file_constants = Self::find_state_reads(body, &constant_names, &[]);
+            func.emits_events = Self::find_event_emissions(body, &event_names);
+            func.uses_errors = Self::find_error_usage(body, &HashSet::new());
+            func.has_unchecked = Self::has_unchecked_blocks(body)
+                || (!checked_arithmetic && Self::has_overflowing_arithmetic(body));
+            func.assembly = YulAnalyzer::analyze(body, &HashMap::new());
+        }
+    }
+
+    /// Fill in errors thrown as `Library.Error` from the library's declaration
+    pub fn resolve_library_errors(contracts: &mut [ContractInfo]) {
+        let snapshot = contracts.to_vec();
+
+        for error in contracts.iter_mut().flat_map(|c| c.errors.iter_mut()) {
+            if !error.is_inherited || error.declared_at.is_some() {
+                continue;
+            }
+            let Some((library, name)) = error.name.rsplit_once('.') else {
+                continue;
+            };
+            let declaration = snapshot.iter()
+                .filter(|c| c.name == library)
+                .find_map(|c| c.errors.iter()
+                    .find(|e| e.name == name && !e.is_inherited)
+                    .map(|e| (c, e)));
+            if let Some((declaring, declared)) = declaration {
+                error.parameters = declared.parameters.clone();
+                error.declared_at = Some(format!("{}:{}", declaring.file_path, declared.line_number));
+            }
+        }
+    }
+
+    /// Record which contract functions (and free functions) use each file-level definition
+    /// Inherited copies are skipped: the usage is attributed to the declaring contract.
+    pub fn record_source_unit_usages(units: &mut [SourceUnit], contracts: &[ContractInfo]) {
+        // (declaring file, item) -> users
+        let mut usages: HashMap<(String, String), Vec<String>> = HashMap::new();
+
+        let snapshot = units.to_vec();
+        let mut record = |file_path: &str, item: &str, user: String| {
+            let users = usages.entry((file_path.to_string(), item.to_string())).or_default();
+            if !users.contains(&user) {
+                users.push(user);
+            }
+        };
+
+        for contract in contracts {
+            let visible_units = Self::visible_source_units(&contract.file_path, &snapshot);
+            for func in contract.functions.iter().filter(|f| f.inherited_from.is_none()) {
+                let user = format!("{}.{}", contract.name, func.signature);
+                for callee in &func.calls_free_functions {
+                    if let Some(unit) = visible_units.iter().find(|u| u.functions.iter().any(|f| &f.signature == callee)) {
+                        record(&unit.file_path, callee, user.clone());
+                    }
+                }
+                for constant in &func.reads_file_constants {
+                    if let Some(unit) = visible_units.iter().find(|u| u.constants.iter().any(|c| &c.name == constant)) {
+                        record(&unit.file_path, constant, user.clone());
+                    }
+                }
+            }
+
+            for error in contract.errors.iter().filter(|e| e.is_inherited && e.inherited_from.is_none()) {
+                let Some(unit) = visible_units.iter().find(|u| u.errors.iter().any(|e| e.name == error.name)) else {
+                    continue;
+                };
+                // used_in holds function signatures and modifier names
+                for user in &error.used_in {
+                    let inherited = contract.functions.iter().any(|f| &f.signature == user && f.inherited_from.is_some())
+                        || contract.modifiers.iter().any(|m| &m.name == user && m.inherited_from.is_some());
+                    if !inherited {
+                        record(&unit.file_path, &error.name, format!("{}.{}", contract.name, user));
+                    }
+                }
+            }
+        }
+
+        for unit in &snapshot {
+            let visible_units = Self::visible_source_units(&unit.file_path, &snapshot);
+            for func in &unit.functions {
+                let find_unit = |has: &dyn Fn(&SourceUnit) -> bool| visible_units.iter().find(|u| has(u)).map(|u| u.file_path.clone());
+                for callee in &func.calls_free_functions {
+                    if let Some(file) = find_unit(&|u| u.functions.iter().any(|f| &f.signature == callee)) {
+                        record(&file, callee, func.signature.clone());
+                    }
+                }
+                for constant in &func.reads_file_constants {
+                    if let Some(file) = find_unit(&|u| u.constants.iter().any(|c| &c.name == constant)) {
+                        record(&file, constant, func.signature.clone());
+                    }
+                }
+                for error in &func.uses_errors {
+                    if let Some(file) = find_unit(&|u| u.errors.iter().any(|e| &e.name == error)) {
+                        record(&file, error, func.signature.clone());
+                    }
+                }
+            }
+        }
+
+        for unit in units.iter_mut() {
+            unit.usages = usages.iter()
+                .filter(|((file, _), _)| *file == unit.file_path)
+                .map(|((_, item), users)| {
+                    let mut users = users.clone();
+                    users.sort();
+                    (item.clone(), users)
+                })
+                .collect();
+        }
+    }
+
     /// Find all state variables that are modified in a function body
     /// Excludes storage parameters - those are handled by inter-procedural analysis
     fn find_state_modifications(
@@ -263,9 +787,11 @@ impl StateModificationAnalyzer {
         }
 
         // Filter out storage parameters - they will be resolved at call sites
-        modified.into_iter()
+        let mut modified: Vec<String> = modified.into_iter()
             .filter(|var| !param_names.contains(var))
-            .collect()
+            .collect();
+        modified.sort();
+        modified
     }
 
     /// Find field-level state modifications (e.g., "lpInfo.consolidatedShares")
@@ -293,7 +819,9 @@ impl StateModificationAnalyzer {
             );
         }
 
-        modified_fields.into_iter().collect()
+        let mut modified_fields: Vec<String> = modified_fields.into_iter().collect();
+        modified_fields.sort();
+        modified_fields
     }
 
     /// Find all state variables that are READ (but not modified) in a function body
@@ -316,9 +844,11 @@ impl StateModificationAnalyzer {
         }
 
         // Filter out storage parameters
-        read_vars.into_iter()
+        let mut reads: Vec<String> = read_vars.into_iter()
             .filter(|var| !param_names.contains(var))
-            .collect()
+            .collect();
+        reads.sort();
+        reads
     }
 
     /// Recursively scan statements for state variable reads
@@ -412,6 +942,10 @@ impl StateModificationAnalyzer {
                     Self::scan_expression_for_reads(arg, state_vars, read_vars, storage_var_mapping, false);
                 }
             }
+            pt::Statement::Assembly { block, .. } => {
+                let (_, reads) = YulAnalyzer::state_accesses(block, state_vars, storage_var_mapping);
+                read_vars.extend(reads);
+            }
             _ => {}
         }
     }
@@ -462,22 +996,18 @@ impl StateModificationAnalyzer {
                 }
             }
             // Variable access - this is a read unless it's an lvalue
-            pt::Expression::Variable(ident) => {
-                if !is_lvalue {
-                    let var_name = &ident.name;
-                    if let Some(state_var) = Self::resolve_to_state_var(var_name, state_vars, storage_var_mapping) {
-                        read_vars.insert(state_var);
-                    }
+            pt::Expression::Variable(ident) if !is_lvalue => {
+                let var_name = &ident.name;
+                if let Some(state_var) = Self::resolve_to_state_var(var_name, state_vars, storage_var_mapping) {
+                    read_vars.insert(state_var);
                 }
             }
             // Member access (e.g., myStruct.field)
-            pt::Expression::MemberAccess(_, _base, _) => {
-                // Extract base variable and mark as read (unless we're on the left side of assignment)
-                if !is_lvalue {
-                    if let Some(var_name) = Self::extract_base_variable(expr) {
-                        if let Some(state_var) = Self::resolve_to_state_var(&var_name, state_vars, storage_var_mapping) {
-                            read_vars.insert(state_var);
-                        }
+            // Extract base variable and mark as read (unless we're on the left side of assignment)
+            pt::Expression::MemberAccess(_, _base, _) if !is_lvalue => {
+                if let Some(var_name) = Self::extract_base_variable(expr) {
+                    if let Some(state_var) = Self::resolve_to_state_var(&var_name, state_vars, storage_var_mapping) {
+                        read_vars.insert(state_var);
                     }
                 }
             }
@@ -600,6 +1130,10 @@ impl StateModificationAnalyzer {
             pt::Statement::DoWhile(_, body, _) => {
                 Self::scan_statement_for_modifications(body, state_vars, modified, storage_var_mapping);
             }
+            pt::Statement::Assembly { block, .. } => {
+                let (writes, _) = YulAnalyzer::state_accesses(block, state_vars, storage_var_mapping);
+                modified.extend(writes);
+            }
             pt::Statement::Return(_, Some(e)) => {
                 Self::scan_expression_for_modifications(e, state_vars, modified, storage_var_mapping);
             }
@@ -621,8 +1155,9 @@ impl StateModificationAnalyzer {
                         }
                     }
                     // Check if this is a storage reference to a state variable
+                    // (value and memory copies don't alias it)
                     else if let Some(base_var) = Self::extract_base_variable(expr) {
-                        if state_vars.contains(&base_var) {
+                        if state_vars.contains(&base_var) && Self::is_storage_reference(decl) {
                             // Map local_var -> state_var
                             storage_var_mapping.insert(local_var_name, base_var);
                         }
@@ -713,8 +1248,13 @@ impl StateModificationAnalyzer {
         }
     }
 
+    /// Whether a local variable declaration is a `storage` reference
+    fn is_storage_reference(decl: &pt::VariableDeclaration) -> bool {
+        matches!(decl.storage, Some(pt::StorageLocation::Storage(_)))
+    }
+
     /// Extract the base variable name from an expression (handles mappings and arrays)
-    fn extract_base_variable(expr: &pt::Expression) -> Option<String> {
+    pub(crate) fn extract_base_variable(expr: &pt::Expression) -> Option<String> {
         match expr {
             pt::Expression::Variable(ident) => Some(ident.name.clone()),
             pt::Expression::MemberAccess(_, base, _) => Self::extract_base_variable(base),
@@ -880,8 +1420,9 @@ impl StateModificationAnalyzer {
                         }
                     }
                     // Check if this is a storage reference to a state variable
+                    // (value and memory copies don't alias it)
                     else if let Some(base_var) = Self::extract_base_variable(expr) {
-                        if state_vars.contains(&base_var) {
+                        if state_vars.contains(&base_var) && Self::is_storage_reference(decl) {
                             // Map local_var -> state_var
                             storage_var_mapping.insert(local_var_name, base_var);
                         }
@@ -1089,6 +1630,8 @@ impl StateModificationAnalyzer {
                             storage_params.push(StorageParamInfo {
                                 param_index: index,
                                 param_name: param_name.name.clone(),
+                                is_modified: false, // Filled in below
+                                is_read: false,
                             });
                         }
                     }
@@ -1096,6 +1639,18 @@ impl StateModificationAnalyzer {
             }
         }
 
+        // Direct writes and reads through each reference; forwarding to other functions
+        // is handled by propagate_storage_param_effects
+        let param_names: HashSet<String> = storage_params.iter()
+            .map(|p| p.param_name.clone())
+            .collect();
+        let written = Self::find_state_modifications(func, &param_names, &[]);
+        let read = Self::find_state_reads(func, &param_names, &[]);
+        for param in &mut storage_params {
+            param.is_modified = written.contains(&param.param_name);
+            param.is_read = read.contains(&param.param_name);
+        }
+
         storage_params
     }
 
@@ -1106,10 +1661,10 @@ impl StateModificationAnalyzer {
         function_bodies: &HashMap<String, &pt::FunctionDefinition>,
         state_vars: &HashSet<String>,
     ) {
-        // Build index for quick lookup
-        let mut func_map: HashMap<String, usize> = HashMap::new();
+        // Build index for quick lookup (one name can have several overloads)
+        let mut func_map: HashMap<String, Vec<usize>> = HashMap::new();
         for (i, func) in functions.iter().enumerate() {
-            func_map.insert(func.name.clone(), i);
+            func_map.entry(func.name.clone()).or_default().push(i);
         }
 
         // Iterate until no new modifications are found (fixed-point)
@@ -1123,19 +1678,24 @@ impl StateModificationAnalyzer {
 
             // For each function, check if it calls other functions with storage args
             for func_idx in 0..functions.len() {
-                let func_name = functions[func_idx].name.clone();
+                let func_signature = functions[func_idx].signature.clone();
 
-                if let Some(body) = function_bodies.get(&func_name) {
+                if let Some(body) = function_bodies.get(&func_signature) {
                     // Find all function calls with their arguments
                     let call_info = Self::extract_function_calls_with_args(body, state_vars);
 
                     // For each call, check if we're passing storage references
                     let mut new_modifications = HashSet::new();
+                    let mut new_reads = HashSet::new();
 
                     for (callee_name, args) in call_info {
-                        // Look up the callee function
-                        if let Some(&callee_idx) = func_map.get(&callee_name) {
+                        // Look up the callee overload(s) taking this many arguments
+                        let callee_indices = func_map.get(&callee_name).cloned().unwrap_or_default();
+                        for callee_idx in callee_indices {
                             let callee = &functions[callee_idx];
+                            if callee.parameters.len() != args.len() {
+                                continue;
+                            }
 
                             // For each storage parameter of the callee
                             for storage_param in &callee.storage_params {
@@ -1143,18 +1703,24 @@ impl StateModificationAnalyzer {
                                 if let Some(arg_var) = args.get(storage_param.param_index) {
                                     // Check if this storage parameter is modified by the callee
                                     // (check if any of callee's modifications match the parameter name)
-                                    let param_modified = callee.modifies_states.iter().any(|mod_var| {
+                                    let param_modified = storage_param.is_modified || callee.modifies_states.iter().any(|mod_var| {
                                         // The callee modifies the parameter if it modifies a variable
                                         // that resolves to the parameter name
                                         mod_var == &storage_param.param_name ||
                                         mod_var.starts_with(&format!("{}.", storage_param.param_name))
                                     });
 
+                                    if arg_var.is_empty() {
+                                        continue;
+                                    }
                                     if param_modified {
                                         // The argument is being modified through the callee
                                         // Add this state variable to caller's modifications
                                         new_modifications.insert(arg_var.clone());
                                     }
+                                    if storage_param.is_read {
+                                        new_reads.insert(arg_var.clone());
+                                    }
                                 }
                             }
                         }
@@ -1167,11 +1733,123 @@ impl StateModificationAnalyzer {
                             changed = true;
                         }
                     }
+                    for new_read in new_reads {
+                        if !functions[func_idx].reads_states.contains(&new_read) {
+                            functions[func_idx].reads_states.push(new_read);
+                            changed = true;
+                        }
+                    }
                 }
             }
         }
     }
 
+    /// Mark storage parameters as written/read when a function forwards them to another
+    /// function of the same contract that does (e.g. library helpers taking `self`)
+    fn propagate_storage_param_effects(
+        functions: &mut [FunctionDef],
+        function_bodies: &HashMap<String, &pt::FunctionDefinition>,
+    ) {
+        const MAX_ITERATIONS: usize = 10;
+
+        for _ in 0..MAX_ITERATIONS {
+            let mut updates: Vec<(usize, usize, bool, bool)> = Vec::new(); // (function, param, modified, read)
+
+            for (func_idx, func) in functions.iter().enumerate() {
+                let Some(body) = function_bodies.get(&func.signature) else {
+                    continue;
+                };
+                let param_names: HashSet<String> = func.storage_params.iter()
+                    .map(|p| p.param_name.clone())
+                    .collect();
+                if param_names.is_empty() {
+                    continue;
+                }
+
+                for (callee_name, args) in Self::extract_function_calls_with_args(body, &param_names) {
+                    let callees = functions.iter()
+                        .filter(|f| f.name == callee_name && f.parameters.len() == args.len());
+                    for callee_param in callees.flat_map(|f| f.storage_params.iter()) {
+                        let Some(arg) = args.get(callee_param.param_index) else {
+                            continue;
+                        };
+                        if let Some(param_idx) = func.storage_params.iter().position(|p| &p.param_name == arg) {
+                            let param = &func.storage_params[param_idx];
+                            let modified = callee_param.is_modified && !param.is_modified;
+                            let read = callee_param.is_read && !param.is_read;
+                            if modified || read {
+                                updates.push((func_idx, param_idx, modified, read));
+                            }
+                        }
+                    }
+                }
+            }
+
+            if updates.is_empty() {
+                break;
+            }
+            for (func_idx, param_idx, modified, read) in updates {
+                let param = &mut functions[func_idx].storage_params[param_idx];
+                param.is_modified |= modified;
+                param.is_read |= read;
+            }
+        }
+    }
+
+    /// Attribute writes and reads that library functions make through storage
+    /// parameters to the state variables passed in, for `Library.fn(x)` and bound `x.fn()`
+    fn propagate_library_effects(
+        functions: &mut [FunctionDef],
+        function_bodies: &HashMap<String, &pt::FunctionDefinition>,
+        state_vars: &HashSet<String>,
+        library_scope: &LibraryScope,
+        state_var_types: &HashMap<String, String>,
+    ) {
+        for func in functions.iter_mut() {
+            let Some(body) = function_bodies.get(&func.signature) else {
+                continue;
+            };
+
+            for (callee_name, args) in Self::extract_function_calls_with_args(body, state_vars) {
+                let Some((receiver, member)) = callee_name.split_once('.') else {
+                    continue;
+                };
+                // Member calls carry the receiver as their first argument
+                let (callees, args) = if library_scope.library(receiver).is_some() {
+                    let args = &args[1..];
+                    (library_scope.resolve_direct(receiver, member, &vec![None; args.len()]), args)
+                } else if !args[0].is_empty() {
+                    // The receiver may be a member or element of the state variable, whose type differs
+                    let arg_types = vec![None; args.len() - 1];
+                    let receiver_type = state_var_types.get(&args[0]).map(String::as_str);
+                    let mut callees = library_scope.resolve_bound(receiver_type, member, &arg_types);
+                    if callees.is_empty() {
+                        callees = library_scope.resolve_bound(None, member, &arg_types);
+                    }
+                    (callees, &args[..])
+                } else {
+                    continue;
+                };
+
+                for param in callees.iter().flat_map(|c| c.function.storage_params.iter()) {
+                    let Some(state_var) = args.get(param.param_index).filter(|a| !a.is_empty()) else {
+                        continue;
+                    };
+                    if param.is_modified && !func.modifies_states.contains(state_var) {
+                        func.modifies_states.push(state_var.clone());
+                    }
+                    if param.is_read && !func.reads_states.contains(state_var) {
+                        func.reads_states.push(state_var.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_internal(func: &FunctionDef) -> bool {
+        matches!(func.visibility.as_str(), "internal" | "private")
+    }
+
     /// Extract function calls along with their arguments resolved to state variables
     /// Returns: Vec<(callee_name, Vec<state_var_name>)> where index = parameter position
     fn extract_function_calls_with_args(
@@ -1203,7 +1881,7 @@ impl StateModificationAnalyzer {
         match stmt {
             pt::Statement::VariableDefinition(_, decl, Some(expr)) => {
                 if let Some(base_var) = Self::extract_base_variable(expr) {
-                    if state_vars.contains(&base_var) {
+                    if state_vars.contains(&base_var) && Self::is_storage_reference(decl) {
                         if let Some(var_name) = &decl.name {
                             storage_var_mapping.insert(var_name.name.clone(), base_var);
                         }
@@ -1278,7 +1956,7 @@ impl StateModificationAnalyzer {
                     Self::scan_for_calls_with_args(body_stmt, state_vars, storage_var_mapping, calls_with_args);
                 }
             }
-            pt::Statement::Return(_, Some(e)) => {
+            pt::Statement::Return(_, Some(e)) | pt::Statement::VariableDefinition(_, _, Some(e)) => {
                 Self::scan_expr_for_calls_with_args(e, state_vars, storage_var_mapping, calls_with_args);
             }
             _ => {}
@@ -1317,6 +1995,18 @@ impl StateModificationAnalyzer {
 
                     calls_with_args.push((func_name, resolved_args));
                 }
+                // Member calls (`Library.fn(x)`, bound `x.fn()`): the receiver comes first
+                if let pt::Expression::MemberAccess(_, base, member) = &**func_expr {
+                    let receiver = Self::extract_base_variable(base);
+                    let mut resolved_args = vec![receiver.as_ref()
+                        .and_then(|var| Self::resolve_to_state_var(var, state_vars, storage_var_mapping))
+                        .unwrap_or_default()];
+                    resolved_args.extend(args.iter().map(|arg| Self::extract_base_variable(arg)
+                        .and_then(|var| Self::resolve_to_state_var(&var, state_vars, storage_var_mapping))
+                        .unwrap_or_default()));
+                    let name = format!("{}.{}", receiver.unwrap_or_else(|| "_".to_string()), member.name);
+                    calls_with_args.push((name, resolved_args));
+                }
 
                 // Recursively scan arguments for nested calls
                 for arg in args {
@@ -1326,81 +2016,378 @@ impl StateModificationAnalyzer {
             pt::Expression::MemberAccess(_, base, _) => {
                 Self::scan_expr_for_calls_with_args(base, state_vars, storage_var_mapping, calls_with_args);
             }
-            _ => {}
+            _ => {
+                // Operators and assignments, e.g. `(a, b) = Library.fn(x)`
+                let (left, right) = expr.components();
+                for operand in [left, right].into_iter().flatten() {
+                    Self::scan_expr_for_calls_with_args(operand, state_vars, storage_var_mapping, calls_with_args);
+                }
+            }
         }
     }
 
     /// Find all function calls within a function body
-    fn find_function_calls(func: &pt::FunctionDefinition) -> Vec<String> {
-        let mut calls = HashSet::new();
+    fn find_function_calls(func: &pt::FunctionDefinition, var_types: &HashMap<String, String>) -> Vec<CallSite> {
+        let mut calls = Vec::new();
 
         if let Some(body) = &func.body {
-            Self::scan_statement_for_calls(body, &mut calls);
+            Self::scan_statement_for_calls(body, var_types, &mut calls);
         }
 
-        calls.into_iter().collect()
+        calls
     }
 
-    /// Recursively scan statements for function calls
-    fn scan_statement_for_calls(stmt: &pt::Statement, calls: &mut HashSet<String>) {
-        match stmt {
-            pt::Statement::Expression(_, expr) => {
-                Self::scan_expression_for_calls(expr, calls);
+    /// Resolve a call site to the signature(s) of the function(s) it may invoke
+    /// `super.fn` searches the linearization after the contract declaring the caller,
+    /// `Base.fn` searches from Base. Overloads are narrowed by argument count and types;
+    /// if the types can't tell overloads apart, all remaining candidates are returned.
+    fn resolve_call(
+        call: &CallSite,
+        defining_contract: &str,
+        contract_name: &str,
+        linearization: &[String],
+        functions: &[FunctionDef],
+    ) -> Vec<String> {
+        let origin = |f: &FunctionDef| f.inherited_from.clone().unwrap_or_else(|| contract_name.to_string());
+
+        let (start, member) = if let Some(member) = call.name.strip_prefix("super.") {
+            // Lookup starts right after the contract that declares the calling function
+            match linearization.iter().position(|c| c == defining_contract) {
+                Some(pos) => (pos + 1, member),
+                None => return Vec::new(),
             }
-            pt::Statement::Block { statements, .. } => {
-                for s in statements {
-                    Self::scan_statement_for_calls(s, calls);
-                }
+        } else if let Some((base, member)) = call.name.split_once('.') {
+            match linearization.iter().position(|c| c == base) {
+                Some(pos) => (pos, member),
+                None => return Vec::new(),
             }
-            pt::Statement::If(_, cond, if_branch, else_branch) => {
-                Self::scan_expression_for_calls(cond, calls);
-                Self::scan_statement_for_calls(if_branch, calls);
-                if let Some(else_stmt) = else_branch {
-                    Self::scan_statement_for_calls(else_stmt, calls);
-                }
+        } else {
+            let candidates: Vec<&FunctionDef> = functions.iter()
+                .filter(|f| f.name == call.name)
+                .collect();
+            return Self::select_overloads(candidates, &call.arg_types)
+                .into_iter()
+                .map(|f| f.signature.clone())
+                .collect();
+        };
+
+        for base in &linearization[start..] {
+            // Overridden implementations are kept under their qualified name
+            let qualified = format!("{}.{}", base, member);
+            let candidates: Vec<&FunctionDef> = functions.iter()
+                .filter(|f| f.name == qualified || (f.name == member && origin(f) == *base))
+                .collect();
+            let selected = Self::select_overloads(candidates, &call.arg_types);
+            if !selected.is_empty() {
+                return selected.into_iter().map(|f| f.signature.clone()).collect();
             }
-            pt::Statement::While(_, cond, body) => {
-                Self::scan_expression_for_calls(cond, calls);
-                Self::scan_statement_for_calls(body, calls);
+        }
+
+        Vec::new()
+    }
+
+    /// Narrow same-named functions down to the overload(s) matching the call arguments
+    fn select_overloads<'a>(candidates: Vec<&'a FunctionDef>, arg_types: &[Option<String>]) -> Vec<&'a FunctionDef> {
+        let by_arity: Vec<&FunctionDef> = candidates.into_iter()
+            .filter(|f| f.parameters.len() == arg_types.len())
+            .collect();
+        if by_arity.len() <= 1 {
+            return by_arity;
+        }
+
+        let param_types = |f: &FunctionDef| SolidityParser::signature_param_types(&f.signature);
+
+        // Exact match on every known argument type wins outright
+        let exact: Vec<&FunctionDef> = by_arity.iter()
+            .copied()
+            .filter(|f| param_types(f).iter().zip(arg_types)
+                .all(|(param, arg)| arg.as_ref().is_none_or(|arg| arg == param)))
+            .collect();
+        if exact.len() == 1 {
+            return exact;
+        }
+
+        let compatible: Vec<&FunctionDef> = by_arity.iter()
+            .copied()
+            .filter(|f| param_types(f).iter().zip(arg_types)
+                .all(|(param, arg)| arg.as_ref().is_none_or(|arg| Self::is_implicitly_convertible(arg, param))))
+            .collect();
+        if compatible.is_empty() {
+            by_arity
+        } else {
+            compatible
+        }
+    }
+
+    /// Resolve a plain call to the free function overload(s) it may invoke
+    fn resolve_free_call(call: &CallSite, units: &[&SourceUnit]) -> Vec<String> {
+        if call.name.contains('.') {
+            return Vec::new();
+        }
+        units.iter()
+            .map(|unit| unit.functions.iter().filter(|f| f.name == call.name).collect::<Vec<_>>())
+            .find(|candidates| !candidates.is_empty())
+            .map(|candidates| Self::select_overloads(candidates, &call.arg_types)
+                .into_iter()
+                .map(|f| f.signature.clone())
+                .collect())
+            .unwrap_or_default()
+    }
+
+    /// Resolve the call sites of one function (or modifier) body
+    /// Returns the contract and internal library functions called, then the free functions called.
+    fn resolve_call_sites(
+        calls: Vec<CallSite>,
+        defining_contract: &str,
+        linearization: &[String],
+        functions: &[FunctionDef],
+        visible_units: &[&SourceUnit],
+        library_scope: &LibraryScope,
+    ) -> (Vec<String>, Vec<String>) {
+        let mut calls_functions: Vec<String> = Vec::new();
+        let mut calls_free_functions: Vec<String> = Vec::new();
+
+        for call in calls {
+            let callees = Self::resolve_call(
+                &call,
+                defining_contract,
+                &linearization[0],
+                linearization,
+                functions,
+            );
+            if callees.is_empty() {
+                // Contract members shadow free functions of the same name
+                for callee in Self::resolve_free_call(&call, visible_units) {
+                    if !calls_free_functions.contains(&callee) {
+                        calls_free_functions.push(callee);
+                    }
+                }
+                // Public and external library functions run via delegatecall and are
+                // reported as external calls instead
+                for callee in library_scope.resolve(&call) {
+                    let (callee, calls) = match callee.library {
+                        Some(library) if Self::is_internal(callee.function) => {
+                            (format!("{}.{}", library, callee.function.signature), &mut calls_functions)
+                        }
+                        None => (callee.function.signature.clone(), &mut calls_free_functions),
+                        Some(_) => continue,
+                    };
+                    if !calls.contains(&callee) {
+                        calls.push(callee);
+                    }
+                }
+            }
+            for callee in callees {
+                if !calls_functions.contains(&callee) {
+                    calls_functions.push(callee);
+                }
+            }
+        }
+
+        (calls_functions, calls_free_functions)
+    }
+
+    /// Whether an argument of type `arg` can be passed to a parameter of type `param`
+    /// "#number" stands for an untyped numeric literal.
+    fn is_implicitly_convertible(arg: &str, param: &str) -> bool {
+        if arg == param {
+            return true;
+        }
+        if arg == "#number" {
+            return param.starts_with("uint") || param.starts_with("int");
+        }
+        // Integers widen within the same signedness
+        let width = |ty: &str, prefix: &str| -> Option<u32> {
+            ty.strip_prefix(prefix).and_then(|bits| bits.parse().ok())
+        };
+        for prefix in ["uint", "int"] {
+            if let (Some(from), Some(to)) = (width(arg, prefix), width(param, prefix)) {
+                return from <= to;
+            }
+        }
+        false
+    }
+
+    /// Collect declared types of parameters and local variables in a function
+    pub(crate) fn collect_local_types(func: &pt::FunctionDefinition, var_types: &mut HashMap<String, String>) {
+        for (_, param) in func.params.iter().chain(func.returns.iter()) {
+            if let Some(param) = param {
+                if let Some(name) = &param.name {
+                    var_types.insert(name.name.clone(), SolidityParser::canonical_type(&param.ty));
+                }
+            }
+        }
+        if let Some(body) = &func.body {
+            Self::collect_statement_local_types(body, var_types);
+        }
+    }
+
+    fn collect_statement_local_types(stmt: &pt::Statement, var_types: &mut HashMap<String, String>) {
+        match stmt {
+            pt::Statement::VariableDefinition(_, decl, _) => {
+                if let Some(name) = &decl.name {
+                    var_types.insert(name.name.clone(), SolidityParser::canonical_type(&decl.ty));
+                }
+            }
+            pt::Statement::Block { statements, .. } => {
+                for s in statements {
+                    Self::collect_statement_local_types(s, var_types);
+                }
+            }
+            pt::Statement::If(_, _, if_branch, else_branch) => {
+                Self::collect_statement_local_types(if_branch, var_types);
+                if let Some(else_stmt) = else_branch {
+                    Self::collect_statement_local_types(else_stmt, var_types);
+                }
+            }
+            pt::Statement::While(_, _, body) | pt::Statement::DoWhile(_, body, _) => {
+                Self::collect_statement_local_types(body, var_types);
+            }
+            pt::Statement::For(_, init, _, _, body) => {
+                if let Some(init_stmt) = init {
+                    Self::collect_statement_local_types(init_stmt, var_types);
+                }
+                if let Some(body_stmt) = body {
+                    Self::collect_statement_local_types(body_stmt, var_types);
+                }
+            }
+            // `try x.f() returns (IPool pool) { ... } catch Error(string memory reason) { ... }`
+            pt::Statement::Try(_, expr, returns, catches) => {
+                if let Some((params, _)) = returns {
+                    for param in params.iter().filter_map(|(_, p)| p.as_ref()) {
+                        if let Some(name) = &param.name {
+                            var_types.insert(name.name.clone(), SolidityParser::canonical_type(&param.ty));
+                        }
+                    }
+                }
+                if let (_, Some(body)) = Self::try_call(expr, returns) {
+                    Self::collect_statement_local_types(body, var_types);
+                }
+                for clause in catches {
+                    match clause {
+                        pt::CatchClause::Simple(_, _, body) | pt::CatchClause::Named(_, _, _, body) => {
+                            Self::collect_statement_local_types(body, var_types);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Best-effort static type of a call argument
+    fn infer_argument_type(expr: &pt::Expression, var_types: &HashMap<String, String>) -> Option<String> {
+        match expr {
+            pt::Expression::NumberLiteral(..)
+            | pt::Expression::HexNumberLiteral(..)
+            | pt::Expression::RationalNumberLiteral(..) => Some("#number".to_string()),
+            pt::Expression::BoolLiteral(..) => Some("bool".to_string()),
+            pt::Expression::StringLiteral(_) => Some("string".to_string()),
+            pt::Expression::AddressLiteral(..) => Some("address".to_string()),
+            pt::Expression::Variable(ident) => var_types.get(&ident.name).cloned(),
+            pt::Expression::Parenthesis(_, inner) => Self::infer_argument_type(inner, var_types),
+            pt::Expression::MemberAccess(_, base, member) => match (base.as_ref(), member.name.as_str()) {
+                (pt::Expression::Variable(b), "sender") if b.name == "msg" => Some("address".to_string()),
+                (pt::Expression::Variable(b), "origin") if b.name == "tx" => Some("address".to_string()),
+                (pt::Expression::Variable(b), "value") if b.name == "msg" => Some("uint256".to_string()),
+                (pt::Expression::Variable(b), "timestamp" | "number") if b.name == "block" => Some("uint256".to_string()),
+                _ => None,
+            },
+            // Explicit conversions: address(x), uint128(x), payable(x)
+            pt::Expression::FunctionCall(_, func_expr, _) => match func_expr.as_ref() {
+                pt::Expression::Type(_, pt::Type::Payable) => Some("address".to_string()),
+                pt::Expression::Type(..) => Some(SolidityParser::canonical_type(func_expr)),
+                pt::Expression::Variable(ident) if ident.name == "payable" => Some("address".to_string()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Recursively scan statements for function calls
+    fn scan_statement_for_calls(stmt: &pt::Statement, var_types: &HashMap<String, String>, calls: &mut Vec<CallSite>) {
+        match stmt {
+            pt::Statement::Expression(_, expr) => {
+                Self::scan_expression_for_calls(expr, var_types, calls);
+            }
+            pt::Statement::Block { statements, .. } => {
+                for s in statements {
+                    Self::scan_statement_for_calls(s, var_types, calls);
+                }
+            }
+            pt::Statement::If(_, cond, if_branch, else_branch) => {
+                Self::scan_expression_for_calls(cond, var_types, calls);
+                Self::scan_statement_for_calls(if_branch, var_types, calls);
+                if let Some(else_stmt) = else_branch {
+                    Self::scan_statement_for_calls(else_stmt, var_types, calls);
+                }
+            }
+            pt::Statement::While(_, cond, body) => {
+                Self::scan_expression_for_calls(cond, var_types, calls);
+                Self::scan_statement_for_calls(body, var_types, calls);
             }
             pt::Statement::For(_, init, cond, update, body) => {
                 if let Some(init_stmt) = init {
-                    Self::scan_statement_for_calls(init_stmt, calls);
+                    Self::scan_statement_for_calls(init_stmt, var_types, calls);
                 }
                 if let Some(cond_expr) = cond {
-                    Self::scan_expression_for_calls(cond_expr, calls);
+                    Self::scan_expression_for_calls(cond_expr, var_types, calls);
                 }
                 if let Some(update_expr) = update {
-                    Self::scan_expression_for_calls(update_expr, calls);
+                    Self::scan_expression_for_calls(update_expr, var_types, calls);
                 }
                 if let Some(body_stmt) = body {
-                    Self::scan_statement_for_calls(body_stmt, calls);
+                    Self::scan_statement_for_calls(body_stmt, var_types, calls);
                 }
             }
-            pt::Statement::Return(_, Some(e)) => {
-                Self::scan_expression_for_calls(e, calls);
+            pt::Statement::Return(_, Some(e)) | pt::Statement::VariableDefinition(_, _, Some(e)) => {
+                Self::scan_expression_for_calls(e, var_types, calls);
             }
             _ => {}
         }
     }
 
     /// Scan expressions for function calls
-    fn scan_expression_for_calls(expr: &pt::Expression, calls: &mut HashSet<String>) {
+    fn scan_expression_for_calls(expr: &pt::Expression, var_types: &HashMap<String, String>, calls: &mut Vec<CallSite>) {
         match expr {
             pt::Expression::FunctionCall(_, func_expr, args) => {
+                let arg_types = || args.iter()
+                    .map(|arg| Self::infer_argument_type(arg, var_types))
+                    .collect();
+
                 // Extract function name
                 if let pt::Expression::Variable(ident) = &**func_expr {
-                    calls.insert(ident.name.clone());
+                    calls.push(CallSite { name: ident.name.clone(), arg_types: arg_types(), receiver_type: None });
+                }
+                // super.fn(), Base.fn() or Library.fn() - resolved by the caller;
+                // x.fn() may be a library function bound to the type of x
+                if let pt::Expression::MemberAccess(_, base, member) = &**func_expr {
+                    let receiver = match &**base {
+                        pt::Expression::Variable(base_ident) => base_ident.name.clone(),
+                        _ => "_".to_string(),
+                    };
+                    calls.push(CallSite {
+                        name: format!("{}.{}", receiver, member.name),
+                        arg_types: arg_types(),
+                        receiver_type: Self::infer_argument_type(base, var_types),
+                    });
+                    Self::scan_expression_for_calls(base, var_types, calls);
                 }
                 // Scan arguments
                 for arg in args {
-                    Self::scan_expression_for_calls(arg, calls);
+                    Self::scan_expression_for_calls(arg, var_types, calls);
                 }
             }
             pt::Expression::MemberAccess(_, base, _) => {
-                Self::scan_expression_for_calls(base, calls);
+                Self::scan_expression_for_calls(base, var_types, calls);
+            }
+            _ => {
+                // Operators and assignments, e.g. `total += clamp(amount)`
+                let (left, right) = expr.components();
+                for operand in [left, right].into_iter().flatten() {
+                    Self::scan_expression_for_calls(operand, var_types, calls);
+                }
             }
-            _ => {}
         }
     }
 
@@ -1408,7 +2395,7 @@ impl StateModificationAnalyzer {
     fn build_call_graph(functions: &[FunctionDef]) -> HashMap<String, Vec<String>> {
         functions
             .iter()
-            .map(|f| (f.name.clone(), f.calls_functions.clone()))
+            .map(|f| (f.signature.clone(), f.calls_functions.clone()))
             .collect()
     }
 
@@ -1417,6 +2404,7 @@ impl StateModificationAnalyzer {
         state_var: &str,
         functions: &[FunctionDef],
         call_graph: &HashMap<String, Vec<String>>,
+        via_modifier: &HashMap<(String, String), String>,
     ) -> Vec<ModificationChain> {
         let mut chains = Vec::new();
 
@@ -1429,14 +2417,15 @@ impl StateModificationAnalyzer {
         for modifier_func in direct_modifiers {
             // Build the call chain for this direct modifier
             let call_chain = Self::build_reverse_call_chain(
-                &modifier_func.name,
+                &modifier_func.signature,
                 functions,
                 call_graph,
             );
 
             chains.push(ModificationChain {
-                direct_modifier: modifier_func.name.clone(),
+                direct_modifier: modifier_func.signature.clone(),
                 direct_modifier_visibility: modifier_func.visibility.clone(),
+                via_modifier: via_modifier.get(&(modifier_func.signature.clone(), state_var.to_string())).cloned(),
                 call_chain,
             });
         }
@@ -1449,6 +2438,7 @@ impl StateModificationAnalyzer {
         state_var: &str,
         functions: &[FunctionDef],
         call_graph: &HashMap<String, Vec<String>>,
+        via_modifier: &HashMap<(String, String), String>,
     ) -> Vec<ModificationChain> {
         let mut chains = Vec::new();
 
@@ -1461,14 +2451,15 @@ impl StateModificationAnalyzer {
         for reader_func in direct_readers {
             // Build the call chain for this direct reader
             let call_chain = Self::build_reverse_call_chain(
-                &reader_func.name,
+                &reader_func.signature,
                 functions,
                 call_graph,
             );
 
             chains.push(ModificationChain {
-                direct_modifier: reader_func.name.clone(),
+                direct_modifier: reader_func.signature.clone(),
                 direct_modifier_visibility: reader_func.visibility.clone(),
+                via_modifier: via_modifier.get(&(reader_func.signature.clone(), state_var.to_string())).cloned(),
                 call_chain,
             });
         }
@@ -1513,20 +2504,67 @@ impl StateModificationAnalyzer {
 
         // Find functions that call the target
         for func in functions {
-            if let Some(calls) = call_graph.get(&func.name) {
+            if let Some(calls) = call_graph.get(&func.signature) {
                 if calls.contains(&target.to_string()) {
                     chain.push(FunctionCall {
-                        function_name: func.name.clone(),
+                        function_name: func.signature.clone(),
                         visibility: func.visibility.clone(),
                     });
 
                     // Recursively find callers of this function
-                    Self::find_callers(&func.name, functions, call_graph, chain, visited);
+                    Self::find_callers(&func.signature, functions, call_graph, chain, visited);
                 }
             }
         }
     }
 
+    /// Split a modifier at its `_` placeholder into copies holding only the statements
+    /// before and after it (sides without statements are left out). A statement with `_`
+    /// nested inside a branch or loop counts as running before the body.
+    fn split_at_placeholder(modifier: &pt::FunctionDefinition) -> Vec<(ModifierPhase, pt::FunctionDefinition)> {
+        let statements: &[pt::Statement] = match &modifier.body {
+            Some(pt::Statement::Block { statements, .. }) => statements,
+            _ => return Vec::new(),
+        };
+        let (before, after) = match statements.iter().position(Self::contains_placeholder) {
+            Some(pos) if Self::is_placeholder(&statements[pos]) => (&statements[..pos], &statements[pos + 1..]),
+            Some(pos) => (&statements[..=pos], &statements[pos + 1..]),
+            None => (statements, &[][..]),
+        };
+
+        [(ModifierPhase::BeforeBody, before), (ModifierPhase::AfterBody, after)]
+            .into_iter()
+            .filter(|(_, part)| !part.is_empty())
+            .map(|(phase, part)| {
+                let mut copy = modifier.clone();
+                copy.body = Some(pt::Statement::Block {
+                    loc: modifier.loc,
+                    unchecked: false,
+                    statements: part.to_vec(),
+                });
+                (phase, copy)
+            })
+            .collect()
+    }
+
+    pub(crate) fn is_placeholder(stmt: &pt::Statement) -> bool {
+        matches!(stmt, pt::Statement::Expression(_, pt::Expression::Variable(ident)) if ident.name == "_")
+    }
+
+    fn contains_placeholder(stmt: &pt::Statement) -> bool {
+        match stmt {
+            pt::Statement::Block { statements, .. } => statements.iter().any(Self::contains_placeholder),
+            pt::Statement::If(_, _, if_branch, else_branch) => {
+                Self::contains_placeholder(if_branch)
+                    || else_branch.as_deref().is_some_and(Self::contains_placeholder)
+            }
+            pt::Statement::While(_, _, body)
+            | pt::Statement::DoWhile(_, body, _)
+            | pt::Statement::For(_, _, _, _, Some(body)) => Self::contains_placeholder(body),
+            _ => Self::is_placeholder(stmt),
+        }
+    }
+
     /// Find all events emitted in a function
     fn find_event_emissions(func: &pt::FunctionDefinition, event_names: &HashSet<String>) -> Vec<String> {
         let mut emitted = HashSet::new();
@@ -1643,6 +2681,218 @@ impl StateModificationAnalyzer {
         }
     }
 
+    /// Whether a function body contains `+`, `-`, `*`, `**` or `++`/`--`, which can overflow
+    fn has_overflowing_arithmetic(func: &pt::FunctionDefinition) -> bool {
+        let mut found = false;
+        if let Some(body) = &func.body {
+            Self::visit_statement_expressions(body, &mut |expr| {
+                found |= matches!(expr,
+                    pt::Expression::Add(..)
+                    | pt::Expression::Subtract(..)
+                    | pt::Expression::Multiply(..)
+                    | pt::Expression::Power(..)
+                    | pt::Expression::AssignAdd(..)
+                    | pt::Expression::AssignSubtract(..)
+                    | pt::Expression::AssignMultiply(..)
+                    | pt::Expression::PreIncrement(..)
+                    | pt::Expression::PostIncrement(..)
+                    | pt::Expression::PreDecrement(..)
+                    | pt::Expression::PostDecrement(..));
+            });
+        }
+        found
+    }
+
+    /// Explicit `uintN`/`intN`/`bytesN` conversions of a value that can lose bits or change sign
+    fn find_narrowing_casts(
+        func: &pt::FunctionDefinition,
+        typer: &ExpressionTyper,
+        constants: &HashMap<&str, &ConstantValue>,
+        source: Option<&SourceUnit>,
+    ) -> Vec<TypeCast> {
+        let mut casts = Vec::new();
+        let Some(body) = &func.body else {
+            return casts;
+        };
+
+        Self::visit_statement_expressions(body, &mut |expr| {
+            let pt::Expression::FunctionCall(loc, callee, args) = expr else {
+                return;
+            };
+            let (pt::Expression::Type(_, target), [value]) = (callee.as_ref(), args.as_slice()) else {
+                return;
+            };
+            // Literals are checked by the compiler
+            if matches!(value,
+                pt::Expression::NumberLiteral(..)
+                | pt::Expression::HexNumberLiteral(..)
+                | pt::Expression::RationalNumberLiteral(..)
+                | pt::Expression::HexLiteral(_)
+                | pt::Expression::StringLiteral(_))
+            {
+                return;
+            }
+            // A constant whose folded value fits is as safe as the literal
+            let folded = match value {
+                pt::Expression::Variable(ident) => constants.get(ident.name.as_str()),
+                _ => None,
+            };
+            let fits = match (target, folded) {
+                (pt::Type::Uint(bits), Some(value)) => ConstantFolder::fits(value, false, *bits),
+                (pt::Type::Int(bits), Some(value)) => ConstantFolder::fits(value, true, *bits),
+                _ => false,
+            };
+            if fits {
+                return;
+            }
+            let Some(from) = typer.infer(value) else {
+                return;
+            };
+
+            let narrows = match (target, from.integer(), from.fixed_bytes()) {
+                (pt::Type::Uint(to), Some((signed, bits)), _) => *to < bits || signed,
+                (pt::Type::Int(to), Some((signed, bits)), _) => *to < bits || !signed,
+                (pt::Type::Bytes(to), _, Some(bytes)) => *to < bytes,
+                (pt::Type::Bytes(_), _, None) => from == SolType::elementary("bytes"),
+                _ => false,
+            };
+            if narrows {
+                casts.push(TypeCast {
+                    expression: expr.to_string(),
+                    from: from.to_string(),
+                    to: target.to_string(),
+                    line_number: source.map(|u| u.line_at(loc.start())).unwrap_or_default(),
+                });
+            }
+        });
+        casts
+    }
+
+    /// The call and the success block of a `try` statement. Without `returns (...)`, solang reads
+    /// `try x.f() { ... }` as call options on the call, so the block is taken back from them.
+    pub(crate) fn try_call<'s>(expr: &'s pt::Expression, returns: &'s Option<(pt::ParameterList, Box<pt::Statement>)>) -> (&'s pt::Expression, Option<&'s pt::Statement>) {
+        match (expr, returns) {
+            (pt::Expression::FunctionCallBlock(_, call, block), None)
+                if matches!(call.as_ref(), pt::Expression::FunctionCall(..)) && matches!(block.as_ref(), pt::Statement::Block { .. }) =>
+            {
+                (call, Some(block))
+            }
+            _ => (expr, returns.as_ref().map(|(_, body)| body.as_ref())),
+        }
+    }
+
+    /// Call `visit` on every expression of a statement tree, outer expressions first
+    pub(crate) fn visit_statement_expressions(stmt: &pt::Statement, visit: &mut dyn FnMut(&pt::Expression)) {
+        match stmt {
+            pt::Statement::Block { statements, .. } => {
+                for s in statements {
+                    Self::visit_statement_expressions(s, visit);
+                }
+            }
+            pt::Statement::Expression(_, expr)
+            | pt::Statement::Emit(_, expr)
+            | pt::Statement::Return(_, Some(expr))
+            | pt::Statement::VariableDefinition(_, _, Some(expr)) => Self::visit_expression(expr, visit),
+            pt::Statement::Revert(_, _, args) => {
+                for arg in args {
+                    Self::visit_expression(arg, visit);
+                }
+            }
+            pt::Statement::RevertNamedArgs(_, _, args) => {
+                for arg in args {
+                    Self::visit_expression(&arg.expr, visit);
+                }
+            }
+            pt::Statement::If(_, cond, if_branch, else_branch) => {
+                Self::visit_expression(cond, visit);
+                Self::visit_statement_expressions(if_branch, visit);
+                if let Some(else_stmt) = else_branch {
+                    Self::visit_statement_expressions(else_stmt, visit);
+                }
+            }
+            pt::Statement::While(_, cond, body) | pt::Statement::DoWhile(_, body, cond) => {
+                Self::visit_expression(cond, visit);
+                Self::visit_statement_expressions(body, visit);
+            }
+            pt::Statement::For(_, init, cond, update, body) => {
+                if let Some(init) = init {
+                    Self::visit_statement_expressions(init, visit);
+                }
+                for expr in [cond, update].into_iter().flatten() {
+                    Self::visit_expression(expr, visit);
+                }
+                if let Some(body) = body {
+                    Self::visit_statement_expressions(body, visit);
+                }
+            }
+            pt::Statement::Try(_, expr, returns, catches) => {
+                let (call, success) = Self::try_call(expr, returns);
+                Self::visit_expression(call, visit);
+                if let Some(body) = success {
+                    Self::visit_statement_expressions(body, visit);
+                }
+                for clause in catches {
+                    match clause {
+                        pt::CatchClause::Simple(_, _, body) | pt::CatchClause::Named(_, _, _, body) => {
+                            Self::visit_statement_expressions(body, visit);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Call `visit` on an expression and every sub-expression, outer expressions first
+    fn visit_expression(expr: &pt::Expression, visit: &mut dyn FnMut(&pt::Expression)) {
+        visit(expr);
+        match expr {
+            pt::Expression::FunctionCall(_, func, args) => {
+                Self::visit_expression(func, visit);
+                for arg in args {
+                    Self::visit_expression(arg, visit);
+                }
+            }
+            pt::Expression::NamedFunctionCall(_, func, args) => {
+                Self::visit_expression(func, visit);
+                for arg in args {
+                    Self::visit_expression(&arg.expr, visit);
+                }
+            }
+            pt::Expression::FunctionCallBlock(_, func, _) | pt::Expression::MemberAccess(_, func, _) => {
+                Self::visit_expression(func, visit);
+            }
+            pt::Expression::ArraySubscript(_, base, index) => {
+                Self::visit_expression(base, visit);
+                if let Some(index) = index {
+                    Self::visit_expression(index, visit);
+                }
+            }
+            pt::Expression::ArraySlice(_, base, from, to) => {
+                Self::visit_expression(base, visit);
+                for bound in [from, to].into_iter().flatten() {
+                    Self::visit_expression(bound, visit);
+                }
+            }
+            pt::Expression::ConditionalOperator(_, cond, left, right) => {
+                for operand in [cond, left, right] {
+                    Self::visit_expression(operand, visit);
+                }
+            }
+            pt::Expression::ArrayLiteral(_, items) => {
+                for item in items {
+                    Self::visit_expression(item, visit);
+                }
+            }
+            _ => {
+                let (left, right) = expr.components();
+                for operand in [left, right].into_iter().flatten() {
+                    Self::visit_expression(operand, visit);
+                }
+            }
+        }
+    }
+
     fn scan_for_unchecked(stmt: &pt::Statement) -> bool {
         match stmt {
             pt::Statement::Block { unchecked, statements, .. } => {
@@ -1682,62 +2932,170 @@ impl StateModificationAnalyzer {
         contract_info: &mut ContractInfo,
         ast: &pt::ContractDefinition,
         all_contracts: &[ContractInfo],
+        source_units: &[SourceUnit],
     ) -> Vec<ExternalCall> {
         let mut external_calls = Vec::new();
+        let library_scope = LibraryScope::new(
+            &contract_info.name,
+            &contract_info.file_path,
+            &contract_info.linearized_bases,
+            all_contracts,
+            source_units,
+        );
         let mut function_external_calls: HashMap<String, Vec<ExternalCall>> = HashMap::new();
 
         // Build a map of state variable name -> type
-        let mut var_types: HashMap<String, String> = HashMap::new();
-        for part in &ast.parts {
-            if let pt::ContractPart::VariableDefinition(var) = part {
-                if let Some(ident) = &var.name {
-                    let var_name = ident.name.clone();
-                    let var_type = Self::extract_type_name(&var.ty);
-                    var_types.insert(var_name, var_type);
-                }
-            }
-        }
+        let state_var_types: HashMap<String, String> = contract_info.state_variables
+            .iter()
+            .map(|v| (v.name.clone(), v.var_type.replace("address payable", "address")))
+            .collect();
 
-        // Build a map of type name -> contract name
-        let mut type_to_contract: HashMap<String, String> = HashMap::new();
+        // Build a map of type name -> contracts a call through that type can land in.
+        // Interfaces and abstract contracts map to their concrete implementers.
+        let mut type_to_contract: HashMap<String, Vec<String>> = HashMap::new();
         for contract in all_contracts {
-            type_to_contract.insert(contract.name.clone(), contract.name.clone());
+            let targets = if contract.is_concrete() || contract.kind == ContractKind::Library {
+                vec![contract.name.clone()]
+            } else {
+                contract.implementers.clone()
+            };
+            type_to_contract.entry(contract.name.clone()).or_insert(targets);
         }
 
+        // Names of types that can never hold a contract address
+        let visible_units = Self::visible_source_units(&contract_info.file_path, source_units);
+        let value_types: HashSet<String> = all_contracts.iter()
+            .flat_map(|c| c.structs.iter().map(|s| s.name.clone()).chain(c.enums.iter().map(|e| e.name.clone())))
+            .chain(visible_units.iter().flat_map(|u| {
+                u.structs.iter().map(|s| s.name.clone())
+                    .chain(u.enums.iter().map(|e| e.name.clone()))
+                    .chain(u.user_types.iter().map(|t| t.name.clone()))
+            }))
+            .collect();
+        let function_names: HashSet<String> = contract_info.functions.iter()
+            .map(|f| f.name.clone())
+            .chain(visible_units.iter().flat_map(|u| u.functions.iter().map(|f| f.name.clone())))
+            .collect();
+        let types = ReceiverTypes {
+            state_var_types: &state_var_types,
+            type_to_contract: &type_to_contract,
+            value_types: &value_types,
+            function_names: &function_names,
+        };
+
+        // Line numbers come from the file that declares the function or modifier
+        let source_of = |inherited_from: &Option<String>| {
+            let file_path = match inherited_from {
+                Some(base) => all_contracts.iter()
+                    .find(|c| &c.name == base)
+                    .map(|c| c.file_path.as_str())
+                    .unwrap_or(&contract_info.file_path),
+                None => &contract_info.file_path,
+            };
+            source_units.iter().find(|u| u.file_path == file_path)
+        };
+
+        let declared = contract_info.clone();
+        let mut typer = ExpressionTyper::new(&declared, all_contracts, source_units);
+
         // Scan each function for external calls
         let mut function_bodies: HashMap<String, &pt::FunctionDefinition> = HashMap::new();
+        let mut modifier_bodies: HashMap<String, &pt::FunctionDefinition> = HashMap::new();
         for part in &ast.parts {
             if let pt::ContractPart::FunctionDefinition(f) = part {
-                if !matches!(f.ty, pt::FunctionTy::Modifier) {
-                    let func_name = f.name.as_ref()
-                        .map(|n| n.name.clone())
-                        .unwrap_or_else(|| "constructor".to_string());
-                    function_bodies.insert(func_name, f);
+                if matches!(f.ty, pt::FunctionTy::Modifier) {
+                    if let Some(name) = &f.name {
+                        modifier_bodies.insert(name.name.clone(), f);
+                    }
+                } else {
+                    function_bodies.insert(SolidityParser::function_signature(f), f);
                 }
             }
         }
 
         for func in &contract_info.functions {
-            if let Some(body) = function_bodies.get(&func.name) {
+            if let Some(body) = function_bodies.get(&func.signature) {
                 if let Some(func_body) = &body.body {
+                    let (var_types, receiver_kinds) = Self::receiver_scope(body, &types);
+                    typer.enter_function(body);
                     let mut ctx = ExternalCallContext {
                         contract_name: &contract_info.name,
-                        function_name: &func.name,
-                        var_types: &var_types,
-                        type_to_contract: &type_to_contract,
+                        function_name: &func.signature,
+                        var_types,
+                        receiver_kinds,
+                        types: &types,
+                        source: source_of(&func.inherited_from),
                         line_number: func.line_number,
                         external_calls: &mut external_calls,
                         all_contracts,
                         function_external_calls: &mut function_external_calls,
+                        library_scope: &library_scope,
+                        typer: &typer,
                     };
                     Self::scan_for_external_calls(func_body, &mut ctx);
                 }
             }
         }
 
+        // Calls made by a modifier, per side of `_`
+        let mut modifier_calls: HashMap<(String, ModifierPhase), Vec<ExternalCall>> = HashMap::new();
+        for modifier in &contract_info.modifiers {
+            let Some(body) = modifier_bodies.get(&modifier.name) else {
+                continue;
+            };
+            for (phase, part) in Self::split_at_placeholder(body) {
+                let mut calls = Vec::new();
+                let (var_types, receiver_kinds) = Self::receiver_scope(&part, &types);
+                typer.enter_function(&part);
+                let mut ctx = ExternalCallContext {
+                    contract_name: &contract_info.name,
+                    function_name: &modifier.name,
+                    var_types,
+                    receiver_kinds,
+                    types: &types,
+                    source: source_of(&modifier.inherited_from),
+                    line_number: modifier.line_number,
+                    external_calls: &mut calls,
+                    all_contracts,
+                    function_external_calls: &mut HashMap::new(),
+                    library_scope: &library_scope,
+                    typer: &typer,
+                };
+                if let Some(part_body) = &part.body {
+                    Self::scan_for_external_calls(part_body, &mut ctx);
+                }
+                modifier_calls.insert((modifier.name.clone(), phase), calls);
+            }
+        }
+
+        // ...which run as part of every function using the modifier
+        for func in &mut contract_info.functions {
+            for effect in &mut func.modifier_effects {
+                let calls = modifier_calls.get(&(effect.modifier.clone(), effect.phase)).cloned().unwrap_or_default();
+                effect.external_calls = calls.into_iter()
+                    .map(|mut call| {
+                        call.source_function = func.signature.clone();
+                        call.via_modifier = Some(effect.modifier.clone());
+                        call
+                    })
+                    .collect();
+                external_calls.extend(effect.external_calls.iter().cloned());
+                function_external_calls.entry(func.signature.clone())
+                    .or_default()
+                    .extend(effect.external_calls.iter().cloned());
+            }
+        }
+        for modifier in &mut contract_info.modifiers {
+            for effect in &mut modifier.effects {
+                if let Some(calls) = modifier_calls.get(&(modifier.name.clone(), effect.phase)) {
+                    effect.external_calls = calls.clone();
+                }
+            }
+        }
+
         // Populate the external_calls field on each function
         for func in &mut contract_info.functions {
-            if let Some(calls) = function_external_calls.get(&func.name) {
+            if let Some(calls) = function_external_calls.get(&func.signature) {
                 func.external_calls = calls.clone();
             }
         }
@@ -1745,24 +3103,28 @@ impl StateModificationAnalyzer {
         external_calls
     }
 
-    fn extract_type_name(ty: &pt::Expression) -> String {
-        match ty {
-            pt::Expression::Type(_, ty) => Self::type_expr_to_string(ty),
-            pt::Expression::Variable(ident) => ident.name.clone(),
-            _ => "unknown".to_string(),
-        }
-    }
+    /// Names a function body can call through: state variables, parameters and locals
+    /// (locals and parameters shadow state variables)
+    fn receiver_scope(
+        func: &pt::FunctionDefinition,
+        types: &ReceiverTypes,
+    ) -> (HashMap<String, String>, HashMap<String, ReceiverKind>) {
+        let mut var_types = types.state_var_types.clone();
+        let mut receiver_kinds: HashMap<String, ReceiverKind> = var_types.keys()
+            .map(|name| (name.clone(), ReceiverKind::StateVariable))
+            .collect();
 
-    fn type_expr_to_string(ty: &pt::Type) -> String {
-        match ty {
-            pt::Type::Address => "address".to_string(),
-            pt::Type::Bool => "bool".to_string(),
-            pt::Type::String => "string".to_string(),
-            pt::Type::Uint(size) => format!("uint{}", size),
-            pt::Type::Int(size) => format!("int{}", size),
-            pt::Type::Bytes(size) => format!("bytes{}", size),
-            _ => "unknown".to_string(),
+        let mut locals = HashMap::new();
+        Self::collect_local_types(func, &mut locals);
+        let params: HashSet<String> = func.params.iter()
+            .filter_map(|(_, p)| p.as_ref()?.name.as_ref().map(|n| n.name.clone()))
+            .collect();
+        for (name, ty) in locals {
+            let kind = if params.contains(&name) { ReceiverKind::Parameter } else { ReceiverKind::LocalVariable };
+            receiver_kinds.insert(name.clone(), kind);
+            var_types.insert(name, ty);
         }
+        (var_types, receiver_kinds)
     }
 
     fn scan_for_external_calls(stmt: &pt::Statement, ctx: &mut ExternalCallContext) {
@@ -1775,19 +3137,24 @@ impl StateModificationAnalyzer {
                     Self::scan_for_external_calls(s, ctx);
                 }
             }
-            pt::Statement::If(_, _, if_branch, else_branch) => {
+            pt::Statement::If(_, cond, if_branch, else_branch) => {
+                Self::scan_expression_for_external_calls(cond, ctx);
                 Self::scan_for_external_calls(if_branch, ctx);
                 if let Some(else_stmt) = else_branch {
                     Self::scan_for_external_calls(else_stmt, ctx);
                 }
             }
-            pt::Statement::While(_, _, body) | pt::Statement::DoWhile(_, body, _) => {
+            pt::Statement::While(_, cond, body) | pt::Statement::DoWhile(_, body, cond) => {
+                Self::scan_expression_for_external_calls(cond, ctx);
                 Self::scan_for_external_calls(body, ctx);
             }
-            pt::Statement::For(_, init, _, _, body) => {
+            pt::Statement::For(_, init, cond, next, body) => {
                 if let Some(init_stmt) = init {
                     Self::scan_for_external_calls(init_stmt, ctx);
                 }
+                for expr in [cond, next].into_iter().flatten() {
+                    Self::scan_expression_for_external_calls(expr, ctx);
+                }
                 if let Some(body_stmt) = body {
                     Self::scan_for_external_calls(body_stmt, ctx);
                 }
@@ -1797,104 +3164,422 @@ impl StateModificationAnalyzer {
                 // e.g., uint256 x = contract.externalCall();
                 Self::scan_expression_for_external_calls(init_expr, ctx);
             }
-            pt::Statement::Return(_, Some(expr)) => {
-                // Scan return expression for external calls
-                // e.g., return contract.externalCall();
+            pt::Statement::Return(_, Some(expr)) | pt::Statement::Emit(_, expr) => {
+                // e.g., return contract.externalCall(); emit Paid(token.balanceOf(this))
                 Self::scan_expression_for_external_calls(expr, ctx);
             }
+            pt::Statement::Revert(_, _, args) => {
+                for arg in args {
+                    Self::scan_expression_for_external_calls(arg, ctx);
+                }
+            }
+            pt::Statement::RevertNamedArgs(_, _, args) => {
+                for arg in args {
+                    Self::scan_expression_for_external_calls(&arg.expr, ctx);
+                }
+            }
+            pt::Statement::Try(_, expr, returns, catches) => {
+                // The tried expression is the call itself
+                let (call, success) = Self::try_call(expr, returns);
+                Self::scan_expression_for_external_calls(call, ctx);
+                if let Some(body) = success {
+                    Self::scan_for_external_calls(body, ctx);
+                }
+                for clause in catches {
+                    match clause {
+                        pt::CatchClause::Simple(_, _, body) | pt::CatchClause::Named(_, _, _, body) => {
+                            Self::scan_for_external_calls(body, ctx);
+                        }
+                    }
+                }
+            }
+            pt::Statement::Assembly { block, .. } => {
+                for (call, loc) in YulAnalyzer::calls(block) {
+                    let call_kind = match call.opcode.as_str() {
+                        "call" | "staticcall" => ExternalCallKind::Call,
+                        "delegatecall" | "callcode" => ExternalCallKind::DelegateCall,
+                        _ => continue,
+                    };
+                    let target_type = ctx.var_types.get(&call.target).cloned()
+                        .unwrap_or_else(|| "address".to_string());
+                    let (target_contract, candidate_contracts) =
+                        Self::resolve_contract_from_type(&target_type, ctx.types.type_to_contract);
+                    let state_mutability = match (call.opcode.as_str(), call.value.as_deref()) {
+                        ("staticcall", _) => "view",
+                        ("call", Some("0")) => "nonpayable",
+                        ("call", Some(_)) => "payable",
+                        _ => "unknown",
+                    };
+                    let ext_call = ExternalCall {
+                        source_contract: ctx.contract_name.to_string(),
+                        source_function: ctx.function_name.to_string(),
+                        target_variable: call.target,
+                        target_type,
+                        target_function: call.opcode,
+                        target_signature: None,
+                        target_contract,
+                        candidate_contracts,
+                        state_mutability: state_mutability.to_string(),
+                        line_number: ctx.line_of(&loc),
+                        receiver_kind: ReceiverKind::Assembly,
+                        target_modifies_states: Vec::new(),
+                        target_reads_states: Vec::new(),
+                        call_kind,
+                        via_modifier: None,
+                        via_proxy: None,
+                    };
+                    ctx.record(ext_call);
+                }
+            }
             _ => {}
         }
     }
 
-    /// Resolve a type name to a contract name, with fallback for interface names
+    /// Resolve a type name to the contract implementing it, using declared `is` relationships.
+    /// Returns the resolved contract (if unique) and the full candidate list when ambiguous.
     fn resolve_contract_from_type(
         var_type: &str,
-        type_to_contract: &HashMap<String, String>
-    ) -> Option<String> {
-        // If the type name looks like an interface (starts with I + uppercase),
-        // try removing the "I" prefix first to find the implementation
-        if var_type.starts_with('I') && var_type.len() > 1 {
-            if let Some(second_char) = var_type.chars().nth(1) {
-                if second_char.is_uppercase() {
-                    let without_i = &var_type[1..];
-                    if let Some(contract) = type_to_contract.get(without_i) {
-                        return Some(contract.clone());
-                    }
-                }
-            }
-        }
-
-        // Fall back to direct lookup
-        if let Some(contract) = type_to_contract.get(var_type) {
-            return Some(contract.clone());
+        type_to_contract: &HashMap<String, Vec<String>>
+    ) -> (Option<String>, Vec<String>) {
+        match type_to_contract.get(var_type).map(|targets| targets.as_slice()) {
+            Some([single]) => (Some(single.clone()), Vec::new()),
+            Some(targets) if targets.len() > 1 => (None, targets.to_vec()),
+            _ => (None, Vec::new()),
         }
-
-        None
     }
 
     fn scan_expression_for_external_calls(expr: &pt::Expression, ctx: &mut ExternalCallContext) {
         match expr {
-            pt::Expression::FunctionCall(_, func_expr, args) => {
-                // Check if this is a member access call (e.g., token.transfer())
-                if let pt::Expression::MemberAccess(_, obj_expr, member_ident) = func_expr.as_ref() {
-                    // Get the variable name
-                    if let pt::Expression::Variable(var_ident) = obj_expr.as_ref() {
-                        let var_name = var_ident.name.clone();
-                        let target_func = member_ident.name.clone();
-
-                        // Look up the variable type
-                        if let Some(var_type) = ctx.var_types.get(&var_name) {
-                            // Try to match to a known contract (with interface name resolution)
-                            let target_contract = Self::resolve_contract_from_type(var_type, ctx.type_to_contract);
-
-                            // Try to get state mutability and state modifications from target function
-                            let (state_mutability, target_modifies_states, target_reads_states) =
-                                if let Some(ref tc_name) = target_contract {
-                                    ctx.all_contracts.iter()
-                                        .find(|c| &c.name == tc_name)
-                                        .and_then(|tc| tc.functions.iter().find(|f| f.name == target_func))
-                                        .map(|f| (
-                                            f.state_mutability.clone(),
-                                            f.modifies_states.clone(),
-                                            f.reads_states.clone()
-                                        ))
-                                        .unwrap_or_else(|| ("unknown".to_string(), Vec::new(), Vec::new()))
-                                } else {
-                                    ("unknown".to_string(), Vec::new(), Vec::new())
-                                };
-
-                            let ext_call = ExternalCall {
-                                source_contract: ctx.contract_name.to_string(),
-                                source_function: ctx.function_name.to_string(),
-                                target_variable: var_name,
-                                target_type: var_type.clone(),
-                                target_function: target_func,
-                                target_contract,
-                                state_mutability,
-                                line_number: ctx.line_number,
-                                target_modifies_states,
-                                target_reads_states,
-                            };
-
-                            ctx.external_calls.push(ext_call.clone());
-                            ctx.function_external_calls
-                                .entry(ctx.function_name.to_string())
-                                .or_default()
-                                .push(ext_call);
-                        }
+            pt::Expression::FunctionCall(loc, func_expr, args) => {
+                // `x.call{value: v}(...)` wraps the member access in call options
+                let (callee, options) = match func_expr.as_ref() {
+                    pt::Expression::FunctionCallBlock(_, inner, options) => (inner.as_ref(), Some(options.as_ref())),
+                    other => (other, None),
+                };
+                if let pt::Expression::MemberAccess(_, base, member) = callee {
+                    let sends_value = matches!(options, Some(pt::Statement::Args(_, named))
+                        if named.iter().any(|arg| arg.name.name == "value"));
+                    let arg_types = args.iter().map(|arg| ctx.typer.argument_type(arg)).collect();
+                    Self::record_member_call(base, &member.name, arg_types, sends_value, loc, ctx);
+                }
+                // Chained calls, casts of call results and call options
+                Self::scan_expression_for_external_calls(callee, ctx);
+                if let Some(pt::Statement::Args(_, named)) = options {
+                    for arg in named {
+                        Self::scan_expression_for_external_calls(&arg.expr, ctx);
                     }
                 }
 
-                // Recursively scan arguments for nested external calls
+                // Recursively scan arguments for nested external calls (including require/assert)
                 for arg in args {
                     Self::scan_expression_for_external_calls(arg, ctx);
                 }
             }
+            pt::Expression::NamedFunctionCall(loc, func_expr, args) => {
+                if let pt::Expression::MemberAccess(_, base, member) = func_expr.as_ref() {
+                    Self::record_member_call(base, &member.name, vec![None; args.len()], false, loc, ctx);
+                }
+                Self::scan_expression_for_external_calls(func_expr, ctx);
+                for arg in args {
+                    Self::scan_expression_for_external_calls(&arg.expr, ctx);
+                }
+            }
             // Recursively check subexpressions
             pt::Expression::MemberAccess(_, sub_expr, _) => {
                 Self::scan_expression_for_external_calls(sub_expr, ctx);
             }
-            _ => {}
+            pt::Expression::ArraySubscript(_, base, index) => {
+                Self::scan_expression_for_external_calls(base, ctx);
+                if let Some(index_expr) = index {
+                    Self::scan_expression_for_external_calls(index_expr, ctx);
+                }
+            }
+            pt::Expression::ConditionalOperator(_, cond, if_true, if_false) => {
+                for operand in [cond, if_true, if_false] {
+                    Self::scan_expression_for_external_calls(operand, ctx);
+                }
+            }
+            pt::Expression::ArrayLiteral(_, items) => {
+                for item in items {
+                    Self::scan_expression_for_external_calls(item, ctx);
+                }
+            }
+            pt::Expression::List(_, items) => {
+                for (_, param) in items {
+                    if let Some(param) = param {
+                        Self::scan_expression_for_external_calls(&param.ty, ctx);
+                    }
+                }
+            }
+            _ => {
+                // Operators and assignments, e.g. `balance += token.balanceOf(this)`
+                let (left, right) = expr.components();
+                for operand in [left, right].into_iter().flatten() {
+                    Self::scan_expression_for_external_calls(operand, ctx);
+                }
+            }
+        }
+    }
+
+    /// Record `base.member(...)` if it leaves the contract
+    fn record_member_call(
+        base: &pt::Expression,
+        member: &str,
+        arg_types: Vec<Option<String>>,
+        sends_value: bool,
+        loc: &pt::Loc,
+        ctx: &mut ExternalCallContext,
+    ) {
+        let receiver = Self::resolve_receiver(base, ctx);
+
+        // Library calls: `Library.fn()` or `x.fn()` attached with `using ... for`
+        let direct_library = match base {
+            pt::Expression::Variable(ident) if !ctx.var_types.contains_key(&ident.name) => {
+                ctx.library_scope.library(&ident.name).map(|_| ident.name.as_str())
+            }
+            _ => None,
+        };
+        let library_callees = match (direct_library, &receiver) {
+            (Some(library), _) => ctx.library_scope.resolve_direct(library, member, &arg_types),
+            (None, Some(receiver)) => {
+                let receiver_type = Some(receiver.ty.as_str()).filter(|ty| *ty != "unknown");
+                ctx.library_scope.resolve_bound(receiver_type, member, &arg_types)
+            }
+            (None, None) => Vec::new(),
+        };
+
+        if !library_callees.is_empty() {
+            // Internal library functions are inlined; public and external ones
+            // are delegatecalls executing against the caller's storage
+            let target_variable = receiver.as_ref()
+                .map(|r| r.name.clone())
+                .unwrap_or_else(|| Self::expression_display(base));
+            for callee in library_callees.iter().filter(|c| !Self::is_internal(c.function)) {
+                let Some(library) = callee.library else {
+                    continue;
+                };
+                let ext_call = ExternalCall {
+                    source_contract: ctx.contract_name.to_string(),
+                    source_function: ctx.function_name.to_string(),
+                    target_variable: target_variable.clone(),
+                    target_type: library.to_string(),
+                    target_function: member.to_string(),
+                    target_signature: Some(callee.function.signature.clone()),
+                    target_contract: Some(library.to_string()),
+                    candidate_contracts: Vec::new(),
+                    state_mutability: callee.function.state_mutability.clone(),
+                    line_number: ctx.line_of(loc),
+                    receiver_kind: ReceiverKind::Library,
+                    target_modifies_states: callee.function.modifies_states.clone(),
+                    target_reads_states: callee.function.reads_states.clone(),
+                    call_kind: ExternalCallKind::DelegateCall,
+                    via_modifier: None,
+                    via_proxy: None,
+                };
+                ctx.record(ext_call);
+            }
+            return;
+        }
+
+        let Some(receiver) = receiver else {
+            return;
+        };
+
+        // Members of `address`: low-level calls and ether transfers
+        if receiver.ty == "address" {
+            let (call_kind, state_mutability) = match member {
+                "call" if sends_value => (ExternalCallKind::Call, "payable"),
+                "call" => (ExternalCallKind::Call, "unknown"),
+                "staticcall" => (ExternalCallKind::Call, "view"),
+                "delegatecall" => (ExternalCallKind::DelegateCall, "unknown"),
+                "transfer" | "send" => (ExternalCallKind::Call, "payable"),
+                _ => return,
+            };
+            let ext_call = ExternalCall {
+                source_contract: ctx.contract_name.to_string(),
+                source_function: ctx.function_name.to_string(),
+                target_variable: receiver.name,
+                target_type: receiver.ty,
+                target_function: member.to_string(),
+                target_signature: None,
+                target_contract: None,
+                candidate_contracts: Vec::new(),
+                state_mutability: state_mutability.to_string(),
+                line_number: ctx.line_of(loc),
+                receiver_kind: receiver.kind,
+                target_modifies_states: Vec::new(),
+                target_reads_states: Vec::new(),
+                call_kind,
+                via_modifier: None,
+                via_proxy: None,
+            };
+            ctx.record(ext_call);
+            return;
+        }
+
+        if !Self::is_contract_type(&receiver.ty, ctx.types)
+            || ctx.library_scope.is_unresolved_bound_call(&receiver.ty, member, ctx.all_contracts)
+        {
+            return;
+        }
+
+        // Try to match to a known contract (with interface name resolution)
+        let (target_contract, candidate_contracts) =
+            Self::resolve_contract_from_type(&receiver.ty, ctx.types.type_to_contract);
+        let (target_contract, via_proxy) = Self::route_through_proxy(target_contract, member, ctx.all_contracts);
+
+        // Try to get state mutability and state modifications from target function
+        let target_def = target_contract.as_ref()
+            .and_then(|tc_name| ctx.all_contracts.iter().find(|c| &c.name == tc_name))
+            .and_then(|tc| {
+                let candidates = tc.functions.iter()
+                    .filter(|f| f.name == member)
+                    .collect();
+                let selected = Self::select_overloads(candidates, &arg_types);
+                // Only attribute effects when the overload is unambiguous
+                if selected.len() == 1 { Some(selected[0]) } else { None }
+            });
+        let (target_signature, state_mutability, target_modifies_states, target_reads_states) =
+            target_def
+                .map(|f| (
+                    Some(f.signature.clone()),
+                    f.state_mutability.clone(),
+                    f.modifies_states.clone(),
+                    f.reads_states.clone()
+                ))
+                .unwrap_or_else(|| (None, "unknown".to_string(), Vec::new(), Vec::new()));
+
+        let ext_call = ExternalCall {
+            source_contract: ctx.contract_name.to_string(),
+            source_function: ctx.function_name.to_string(),
+            target_variable: receiver.name,
+            target_type: receiver.ty,
+            target_function: member.to_string(),
+            target_signature,
+            target_contract,
+            candidate_contracts,
+            state_mutability,
+            line_number: ctx.line_of(loc),
+            receiver_kind: receiver.kind,
+            target_modifies_states,
+            target_reads_states,
+            call_kind: ExternalCallKind::Call,
+            via_modifier: None,
+            via_proxy,
+        };
+        ctx.record(ext_call);
+    }
+
+    /// A call to a proxy linked to an implementation runs the implementation's code, unless the
+    /// proxy itself declares the function (e.g. admin functions of a transparent proxy)
+    fn route_through_proxy(
+        target_contract: Option<String>,
+        member: &str,
+        all_contracts: &[ContractInfo],
+    ) -> (Option<String>, Option<String>) {
+        let proxy = target_contract.as_ref()
+            .and_then(|name| all_contracts.iter().find(|c| &c.name == name))
+            .filter(|c| !c.functions.iter().any(|f| f.name == member));
+        match proxy.and_then(|p| p.proxy.as_ref()?.implementation.clone().map(|i| (i, p.name.clone()))) {
+            Some((implementation, proxy)) => (Some(implementation), Some(proxy)),
+            None => (target_contract, None),
+        }
+    }
+
+    /// What a call receiver expression is, when its type can be determined
+    fn resolve_receiver(expr: &pt::Expression, ctx: &ExternalCallContext) -> Option<Receiver> {
+        let receiver = |ty: String, kind: ReceiverKind| Some(Receiver {
+            name: Self::expression_display(expr),
+            ty,
+            kind,
+        });
+
+        match expr {
+            pt::Expression::Variable(ident) if ident.name == "this" => {
+                receiver(ctx.contract_name.to_string(), ReceiverKind::This)
+            }
+            pt::Expression::Variable(ident) => {
+                let ty = ctx.var_types.get(&ident.name)?.clone();
+                let kind = ctx.receiver_kinds.get(&ident.name).copied().unwrap_or(ReceiverKind::StateVariable);
+                receiver(ty, kind)
+            }
+            pt::Expression::Parenthesis(_, inner) => Self::resolve_receiver(inner, ctx),
+            // Conversions: `address(x)`, `payable(x)`, `IERC20(x)`
+            pt::Expression::FunctionCall(_, func_expr, args) if args.len() == 1 && !matches!(func_expr.as_ref(), pt::Expression::MemberAccess(..)) => {
+                match func_expr.as_ref() {
+                    pt::Expression::Type(_, pt::Type::Address | pt::Type::AddressPayable | pt::Type::Payable) => {
+                        receiver("address".to_string(), ReceiverKind::Cast)
+                    }
+                    pt::Expression::Variable(ident) if ident.name == "payable" => {
+                        receiver("address".to_string(), ReceiverKind::Cast)
+                    }
+                    pt::Expression::Variable(ident) if Self::is_contract_type(&ident.name, ctx.types)
+                        && !ctx.types.function_names.contains(&ident.name) =>
+                    {
+                        receiver(ident.name.clone(), ReceiverKind::Cast)
+                    }
+                    // Internal and free functions returning a contract
+                    _ => receiver(ctx.typer.infer(expr)?.receiver_name()?, ReceiverKind::CallResult),
+                }
+            }
+            // Chained calls: the receiver is what the inner call returns
+            pt::Expression::FunctionCall(..) => {
+                receiver(ctx.typer.infer(expr)?.receiver_name()?, ReceiverKind::CallResult)
+            }
+            pt::Expression::ArraySubscript(_, _, Some(_)) => {
+                receiver(ctx.typer.infer(expr)?.receiver_name()?, ReceiverKind::Element)
+            }
+            pt::Expression::MemberAccess(_, base, _) => {
+                let kind = match base.as_ref() {
+                    pt::Expression::Variable(ident) if matches!(ident.name.as_str(), "msg" | "tx" | "block")
+                        && !ctx.var_types.contains_key(&ident.name) => ReceiverKind::Global,
+                    _ => ReceiverKind::StructField,
+                };
+                receiver(ctx.typer.infer(expr)?.receiver_name()?, kind)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a value of this type is a contract reference (known or from an unloaded file)
+    fn is_contract_type(ty: &str, types: &ReceiverTypes) -> bool {
+        if types.type_to_contract.contains_key(ty) {
+            return true;
+        }
+        let elementary = ["uint", "int", "bytes", "bool", "string", "address", "mapping", "function", "fixed", "ufixed"];
+        let last_segment = ty.rsplit('.').next().unwrap_or(ty);
+        ty != "unknown"
+            && !elementary.iter().any(|prefix| ty.starts_with(prefix))
+            && !ty.contains(['[', '(', ' '])
+            && !types.value_types.contains(last_segment)
+            && ty.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+    }
+
+    /// Render an expression as written, for naming call receivers
+    fn expression_display(expr: &pt::Expression) -> String {
+        match expr {
+            pt::Expression::Variable(ident) => ident.name.clone(),
+            pt::Expression::MemberAccess(_, base, member) => {
+                format!("{}.{}", Self::expression_display(base), member.name)
+            }
+            pt::Expression::ArraySubscript(_, base, index) => format!(
+                "{}[{}]",
+                Self::expression_display(base),
+                index.as_ref().map(|i| Self::expression_display(i)).unwrap_or_default()
+            ),
+            pt::Expression::FunctionCall(_, func_expr, args) => format!(
+                "{}({})",
+                Self::expression_display(func_expr),
+                args.iter().map(Self::expression_display).collect::<Vec<_>>().join(", ")
+            ),
+            pt::Expression::FunctionCallBlock(_, func_expr, _) => Self::expression_display(func_expr),
+            pt::Expression::Parenthesis(_, inner) => format!("({})", Self::expression_display(inner)),
+            pt::Expression::Type(_, pt::Type::Payable) => "payable".to_string(),
+            pt::Expression::Type(..) => SolidityParser::canonical_type(expr),
+            pt::Expression::NumberLiteral(_, value, _, _) | pt::Expression::HexNumberLiteral(_, value, _) => value.clone(),
+            pt::Expression::AddressLiteral(_, value) => value.clone(),
+            pt::Expression::BoolLiteral(_, value) => value.to_string(),
+            _ => "…".to_string(),
         }
     }
 
@@ -1902,7 +3587,7 @@ impl StateModificationAnalyzer {
     /// Converts "@storage_struct._balances" to "_balances" (actual storage field)
     fn resolve_storage_struct_fields(contract_info: &mut ContractInfo) {
         // Check if this contract uses upgradeable storage
-        if contract_info.upgradeable_storage.is_none() {
+        if contract_info.upgradeable_storage.is_empty() {
             return;
         }
 
@@ -1950,14 +3635,12 @@ impl StateModificationAnalyzer {
     ) {
         use crate::models::StateVariable;
 
-        // Check if this contract uses upgradeable storage
-        let upgradeable_storage = match &contract_info.upgradeable_storage {
-            Some(storage) => storage.clone(),
-            None => return,
-        };
+        // Create a virtual state variable for each field of each namespaced storage struct
+        let fields: Vec<(StructMember, usize)> = contract_info.upgradeable_storage.iter()
+            .flat_map(|storage| storage.struct_fields.iter().map(|f| (f.clone(), storage.line_number)))
+            .collect();
 
-        // Create a virtual state variable for each field in the storage struct
-        for field in &upgradeable_storage.struct_fields {
+        for (field, line_number) in &fields {
             let field_name = field.name.clone();
 
             // Build modification chains for this field
@@ -1965,6 +3648,7 @@ impl StateModificationAnalyzer {
                 &field_name,
                 &contract_info.functions,
                 call_graph,
+                &HashMap::new(),
             );
 
             // Create the virtual state variable
@@ -1974,9 +3658,16 @@ impl StateModificationAnalyzer {
                 visibility: "private".to_string(), // Storage struct fields are private
                 is_constant: false,
                 is_immutable: false,
-                line_number: upgradeable_storage.line_number,
+                line_number: *line_number,
                 modification_chains,
                 read_chains: Vec::new(), // Read chains not computed for virtual variables yet
+                inherited_from: None,
+                slot: None, // Lives in the namespaced slot, not in sequential storage
+                offset: None,
+                natspec: None,
+                resolved_type: None,
+                value: None,
+                assignments: Vec::new(),
             };
 
             // Add to state variables list
@@ -2114,3 +3805,149 @@ impl StateModificationAnalyzer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+    use crate::AnalyzedProject;
+
+    fn calls<'a>(contract: &'a ContractInfo, signature: &str) -> &'a [String] {
+        &contract.functions.iter().find(|f| f.signature == signature).unwrap().calls_functions
+    }
+
+    #[test]
+    fn overloads_resolve_by_arity_argument_type_and_super() {
+        let project = analyze(r#"
+            contract Base {
+                uint256 internal a;
+                address internal b;
+                function set(uint256 x) public virtual { a = x; }
+                function set(address x) public virtual { b = x; }
+            }
+            contract C is Base {
+                uint256 internal c;
+                uint256 internal d;
+                function set(uint256 x) public override { c = x; super.set(x); }
+                function put(uint256 x, uint256 y) internal { d = x + y; }
+                function put(uint256 x) internal { c = x; }
+                function byArity() external { put(1, 2); }
+                function byLiteral() external { set(5); }
+                function byType(address who) external { set(who); }
+                function byBase(uint256 x) external { Base.set(x); }
+            }
+        "#);
+        let c = contract(&project, "C");
+        assert!(c.functions.iter().any(|f| f.signature == "Base.set(uint256)"));
+        assert_eq!(calls(c, "byArity()"), ["put(uint256,uint256)"]);
+        assert_eq!(calls(c, "byLiteral()"), ["set(uint256)"]);
+        assert_eq!(calls(c, "byType(address)"), ["set(address)"]);
+        assert_eq!(calls(c, "set(uint256)"), ["Base.set(uint256)"]);
+        assert_eq!(calls(c, "byBase(uint256)"), ["Base.set(uint256)"]);
+
+        // Writes follow the resolved overload only
+        let d = c.state_variables.iter().find(|v| v.name == "d").unwrap();
+        assert!(d.modification_chains.iter().any(|chain| chain.call_chain.iter().any(|step| step.function_name == "byArity()")));
+        let a = c.state_variables.iter().find(|v| v.name == "a").unwrap();
+        assert!(!a.modification_chains.iter().any(|chain| chain.call_chain.iter().any(|step| step.function_name == "byType(address)")));
+    }
+
+    #[test]
+    fn modifier_effects_are_split_at_the_placeholder() {
+        let mut project = analyze(r#"
+            interface IHook { function after() external; }
+            contract Counter {
+                uint256 internal count;
+                bool internal locked;
+                IHook internal hook;
+
+                modifier tracked() {
+                    require(!locked);
+                    count += 1;
+                    _;
+                    hook.after();
+                    locked = false;
+                }
+
+                function act() external tracked {}
+            }
+        "#);
+        let act = contract(&project, "Counter").functions.iter().find(|f| f.name == "act").unwrap();
+        assert_eq!(act.modifier_order, ["tracked"]);
+        let [before, after] = act.modifier_effects.as_slice() else {
+            panic!("{:?}", act.modifier_effects);
+        };
+        assert_eq!((before.phase, after.phase), (ModifierPhase::BeforeBody, ModifierPhase::AfterBody));
+        assert_eq!(before.modifies_states, ["count"]);
+        assert!(before.reads_states.contains(&"locked".to_string()));
+        assert_eq!(after.modifies_states, ["locked"]);
+
+        // The call after `_` is attributed to the function, through the modifier
+        let i = project.contracts.iter().position(|c| c.name == "Counter").unwrap();
+        let snapshot = project.contracts.clone();
+        let calls = StateModificationAnalyzer::detect_external_calls(&mut project.contracts[i],
+            &project.contract_asts[i], &snapshot, &project.source_units);
+        assert_eq!(calls.len(), 1);
+        assert_eq!((calls[0].source_function.as_str(), calls[0].via_modifier.as_deref()), ("act()", Some("tracked")));
+        let act = project.contracts[i].functions.iter().find(|f| f.name == "act").unwrap();
+        assert!(act.modifier_effects[0].external_calls.is_empty());
+        assert_eq!(act.modifier_effects[1].external_calls[0].target_function, "after");
+    }
+
+    fn external_calls(project: &mut AnalyzedProject, name: &str) -> Vec<ExternalCall> {
+        let i = project.contracts.iter().position(|c| c.name == name).unwrap();
+        let snapshot = project.contracts.clone();
+        StateModificationAnalyzer::detect_external_calls(&mut project.contracts[i],
+            &project.contract_asts[i], &snapshot, &project.source_units)
+    }
+
+    #[test]
+    fn calls_tried_without_returns_are_detected() {
+        let mut project = analyze(r#"
+            interface IPool { function sync() external returns (uint256); }
+            contract Router {
+                IPool internal pool;
+                uint256 internal synced;
+                function plain() external { try pool.sync() { synced += 1; } catch {} }
+                function withReturns() external { try pool.sync() returns (uint256 n) { synced = n; } catch {} }
+            }
+        "#);
+        let calls: Vec<(String, String)> = external_calls(&mut project, "Router").into_iter()
+            .map(|c| (c.source_function, c.target_function))
+            .collect();
+        assert_eq!(calls, [("plain()".to_string(), "sync".to_string()), ("withReturns()".to_string(), "sync".to_string())]);
+    }
+
+    #[test]
+    fn external_calls_record_how_the_receiver_was_obtained() {
+        let mut project = analyze(r#"
+            interface IPool { function sync() external; }
+            contract Pool is IPool { function sync() external {} }
+            contract Router {
+                IPool internal pool;
+                address internal treasury;
+                mapping(uint256 => IPool) internal pools;
+                function viaState() external { pool.sync(); }
+                function viaStateAddress() external { (bool ok,) = treasury.call(""); require(ok); }
+                function viaParameter(IPool p) external { p.sync(); }
+                function viaSender() external { (bool ok,) = msg.sender.call(""); require(ok); }
+                function viaCast(address a) external { IPool(a).sync(); }
+                function viaLocal() external { IPool local = pool; local.sync(); }
+                function viaElement(uint256 id) external { pools[id].sync(); }
+            }
+        "#);
+        let calls: Vec<(String, String, ReceiverKind, Option<String>)> = external_calls(&mut project, "Router").into_iter()
+            .map(|c| (c.source_function, c.target_variable, c.receiver_kind, c.target_contract))
+            .collect();
+        let pool = || Some("Pool".to_string());
+        assert_eq!(calls, [
+            ("viaState()".to_string(), "pool".to_string(), ReceiverKind::StateVariable, pool()),
+            ("viaStateAddress()".to_string(), "treasury".to_string(), ReceiverKind::StateVariable, None),
+            ("viaParameter(IPool)".to_string(), "p".to_string(), ReceiverKind::Parameter, pool()),
+            ("viaSender()".to_string(), "msg.sender".to_string(), ReceiverKind::Global, None),
+            ("viaCast(address)".to_string(), "IPool(a)".to_string(), ReceiverKind::Cast, pool()),
+            ("viaLocal()".to_string(), "local".to_string(), ReceiverKind::LocalVariable, pool()),
+            ("viaElement(uint256)".to_string(), "pools[id]".to_string(), ReceiverKind::Element, pool()),
+        ]);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::models::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub struct CallGraphReportGenerator;
 
@@ -48,11 +48,11 @@ impl CallGraphReportGenerator {
         section.push_str("### 📞 External Contract Calls\n\n");
 
         // Group external calls by calling function
-        let mut calls_by_function: HashMap<String, Vec<&ExternalCall>> = HashMap::new();
+        let mut calls_by_function: BTreeMap<String, Vec<&ExternalCall>> = BTreeMap::new();
 
         for func in &contract.functions {
             if !func.external_calls.is_empty() {
-                calls_by_function.insert(func.name.clone(), func.external_calls.iter().collect());
+                calls_by_function.insert(func.signature.clone(), func.external_calls.iter().collect());
             }
         }
 
@@ -60,13 +60,22 @@ impl CallGraphReportGenerator {
             section.push_str("*No external calls detected*\n\n");
         } else {
             for (func_name, calls) in calls_by_function.iter() {
-                let func = contract.functions.iter().find(|f| &f.name == func_name).unwrap();
-                section.push_str(&format!("**From `{}()`** *({})*:\n", func_name, func.visibility));
+                let func = contract.functions.iter().find(|f| &f.signature == func_name).unwrap();
+                section.push_str(&format!("**From `{}`** *({})*:\n", func_name, func.visibility));
 
                 for call in calls {
-                    let target_interface = call.target_contract.as_ref()
-                        .map(|s| format!(" *[{}]*", s))
-                        .unwrap_or_default();
+                    let target_interface = match &call.target_contract {
+                        Some(target) => format!(" *[{}]*", target),
+                        None if !call.candidate_contracts.is_empty() => {
+                            format!(" *[ambiguous: {}]*", call.candidate_contracts.join(" | "))
+                        }
+                        None => String::new(),
+                    };
+
+                    let call_kind = match call.call_kind {
+                        ExternalCallKind::DelegateCall => " *(delegatecall)*",
+                        ExternalCallKind::Call => "",
+                    };
 
                     let target_var = if call.target_variable.is_empty() {
                         "<unknown>".to_string()
@@ -74,8 +83,16 @@ impl CallGraphReportGenerator {
                         call.target_variable.clone()
                     };
 
-                    section.push_str(&format!("   - → `{}.{}()`{}\n",
-                        target_var, call.target_function, target_interface));
+                    let via = call.via_modifier.as_ref()
+                        .map(|m| format!(" *(via modifier `{}`)*", m))
+                        .unwrap_or_default();
+                    let proxy = call.via_proxy.as_ref()
+                        .map(|p| format!(" *(via proxy `{}`)*", p))
+                        .unwrap_or_default();
+
+                    section.push_str(&format!("   - → `{}.{}`{}{}{}{} — {}, line {}\n",
+                        target_var, call.target_display(), target_interface, call_kind, via, proxy,
+                        call.receiver_kind.label(), call.line_number));
                 }
                 section.push('\n');
             }
@@ -102,7 +119,7 @@ impl CallGraphReportGenerator {
             .collect();
 
         // Sort by name for consistent output
-        root_functions.sort_by(|a, b| a.name.cmp(&b.name));
+        root_functions.sort_by(|a, b| a.signature.cmp(&b.signature));
 
         if root_functions.is_empty() {
             section.push_str("*No public/external functions detected*\n\n");
@@ -110,7 +127,7 @@ impl CallGraphReportGenerator {
         }
 
         for root in root_functions {
-            section.push_str(&Self::generate_call_tree(&root.name, &call_graph, &contract.functions, 0, &mut HashSet::new()));
+            section.push_str(&Self::generate_call_tree(&root.signature, &call_graph, &contract.functions, 0, &mut HashSet::new()));
             section.push('\n');
         }
 
@@ -123,13 +140,13 @@ impl CallGraphReportGenerator {
         let orphaned: Vec<&FunctionDef> = contract
             .functions
             .iter()
-            .filter(|f| !matches!(f.visibility.as_str(), "external" | "public") && !called_functions.contains(&f.name))
+            .filter(|f| !matches!(f.visibility.as_str(), "external" | "public") && !called_functions.contains(&f.signature))
             .collect();
 
         if !orphaned.is_empty() {
             section.push_str("**⚠️  Orphaned Internal Functions** *(never called)*:\n");
             for func in orphaned {
-                section.push_str(&format!("   - `{}` *({})*\n", func.name, func.visibility));
+                section.push_str(&format!("   - `{}` *({})*\n", func.signature, func.visibility));
             }
             section.push('\n');
         }
@@ -148,7 +165,7 @@ impl CallGraphReportGenerator {
         let mut tree = String::new();
 
         // Get function details
-        let func = all_functions.iter().find(|f| f.name == func_name);
+        let func = all_functions.iter().find(|f| f.signature == func_name);
         let visibility = func.map(|f| f.visibility.as_str()).unwrap_or("unknown");
 
         // Indentation
@@ -160,7 +177,7 @@ impl CallGraphReportGenerator {
 
 
         // Function name with visibility
-        tree.push_str(&format!("{}`{}()`** *({})***\n",
+        tree.push_str(&format!("{}`{}`** *({})***\n",
             indent,
             func_name,
             visibility
@@ -184,13 +201,9 @@ impl CallGraphReportGenerator {
                     format!("{}   ", indent)
                 };
 
-                let callee_vis = all_functions
-                    .iter()
-                    .find(|f| &f.name == callee)
-                    .map(|f| f.visibility.as_str())
-                    .unwrap_or("unknown");
+                let callee_vis = Self::callee_visibility(callee, all_functions);
 
-                tree.push_str(&format!("{}{} `{}()` *({})*\n",
+                tree.push_str(&format!("{}{} `{}` *({})*\n",
                     new_indent,
                     if is_last { "└─>" } else { "├─>" },
                     callee,
@@ -219,6 +232,15 @@ impl CallGraphReportGenerator {
         tree
     }
 
+    /// Visibility of a callee; calls into other contracts are internal library functions
+    fn callee_visibility<'a>(callee: &str, all_functions: &'a [FunctionDef]) -> &'a str {
+        match all_functions.iter().find(|f| f.signature == callee) {
+            Some(f) => f.visibility.as_str(),
+            None if callee.contains('.') => "library",
+            None => "unknown",
+        }
+    }
+
     /// Generate subtree for nested calls
     fn generate_call_tree_subtree(
         func_name: &str,
@@ -243,13 +265,9 @@ impl CallGraphReportGenerator {
                 let indent = "   ".repeat(depth);
                 let connector = if parent_is_last { " " } else { "│" };
 
-                let callee_vis = all_functions
-                    .iter()
-                    .find(|f| &f.name == callee)
-                    .map(|f| f.visibility.as_str())
-                    .unwrap_or("unknown");
+                let callee_vis = Self::callee_visibility(callee, all_functions);
 
-                tree.push_str(&format!("{}{}   {} `{}()` *({})*\n",
+                tree.push_str(&format!("{}{}   {} `{}` *({})*\n",
                     indent,
                     connector,
                     if is_last { "└─>" } else { "├─>" },
@@ -284,7 +302,7 @@ impl CallGraphReportGenerator {
         for func in functions {
             // Use the calls_functions field which already tracks internal function calls
             if !func.calls_functions.is_empty() {
-                graph.insert(func.name.clone(), func.calls_functions.clone());
+                graph.insert(func.signature.clone(), func.calls_functions.clone());
             }
         }
 
@@ -0,0 +1,984 @@
+use crate::abi::AbiGenerator;
+use crate::analyzer::StateModificationAnalyzer;
+use crate::models::*;
+use crate::parser::SolidityParser;
+use solang_parser::pt;
+use std::collections::{HashMap, HashSet};
+
+// Name fragments of constants that read as a share of some scale, and of the scales themselves
+const RATIO_WORDS: &[&str] = &["FEE", "BPS", "RATE", "PERCENT", "RATIO", "SHARE", "CUT", "SLIPPAGE", "MARGIN"];
+const SCALE_WORDS: &[&str] = &["PRECISION", "UNIT", "DENOMINATOR", "BASIS", "SCALE", "BPS", "PERCENT"];
+const DURATION_WORDS: &[&str] = &["DELAY", "DURATION", "PERIOD", "TIMEOUT", "WINDOW", "INTERVAL", "LOCK"];
+
+/// 256-bit unsigned integer, most significant limb first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct U256([u64; 4]);
+
+impl U256 {
+    const ZERO: U256 = U256([0; 4]);
+    const ONE: U256 = U256([0, 0, 0, 1]);
+    const MAX: U256 = U256([u64::MAX; 4]);
+
+    fn from_u64(value: u64) -> U256 {
+        U256([0, 0, 0, value])
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == U256::ZERO
+    }
+
+    fn bits(&self) -> u32 {
+        self.0.iter()
+            .position(|&limb| limb != 0)
+            .map_or(0, |i| (4 - i as u32) * 64 - self.0[i].leading_zeros())
+    }
+
+    fn bit(&self, i: u32) -> bool {
+        i < 256 && (self.0[3 - (i / 64) as usize] >> (i % 64)) & 1 == 1
+    }
+
+    /// `2^bits - 1`
+    fn mask(bits: u32) -> U256 {
+        if bits >= 256 {
+            U256::MAX
+        } else {
+            U256::ONE.shl(bits).checked_sub(U256::ONE).unwrap_or(U256::ZERO)
+        }
+    }
+
+    fn checked_add(self, other: U256) -> Option<U256> {
+        let mut result = [0u64; 4];
+        let mut carry = 0u128;
+        for i in (0..4).rev() {
+            let sum = self.0[i] as u128 + other.0[i] as u128 + carry;
+            result[i] = sum as u64;
+            carry = sum >> 64;
+        }
+        (carry == 0).then_some(U256(result))
+    }
+
+    fn checked_sub(self, other: U256) -> Option<U256> {
+        if self < other {
+            return None;
+        }
+        let mut result = [0u64; 4];
+        let mut borrow = false;
+        for i in (0..4).rev() {
+            let (value, b1) = self.0[i].overflowing_sub(other.0[i]);
+            let (value, b2) = value.overflowing_sub(borrow as u64);
+            result[i] = value;
+            borrow = b1 || b2;
+        }
+        Some(U256(result))
+    }
+
+    fn checked_mul(self, other: U256) -> Option<U256> {
+        let (a, b): (Vec<u64>, Vec<u64>) = (self.0.iter().rev().copied().collect(), other.0.iter().rev().copied().collect());
+        let mut product = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let t = a[i] as u128 * b[j] as u128 + product[i + j] as u128 + carry;
+                product[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            product[i + 4] = carry as u64;
+        }
+        product[4..].iter().all(|&limb| limb == 0)
+            .then_some(U256([product[3], product[2], product[1], product[0]]))
+    }
+
+    fn div_rem(self, divisor: U256) -> Option<(U256, U256)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let mut quotient = U256::ZERO;
+        let mut remainder = U256::ZERO;
+        for i in (0..self.bits()).rev() {
+            remainder = remainder.shl(1);
+            if self.bit(i) {
+                remainder.0[3] |= 1;
+            }
+            if remainder >= divisor {
+                remainder = remainder.checked_sub(divisor)?;
+                quotient.0[3 - (i / 64) as usize] |= 1 << (i % 64);
+            }
+        }
+        Some((quotient, remainder))
+    }
+
+    fn checked_pow(self, exponent: U256) -> Option<U256> {
+        if self.bits() <= 1 {
+            return Some(if exponent.is_zero() { U256::ONE } else { self });
+        }
+        if exponent.bits() > 8 {
+            return None; // At least 2^256
+        }
+        let mut result = U256::ONE;
+        for _ in 0..exponent.0[3] {
+            result = result.checked_mul(self)?;
+        }
+        Some(result)
+    }
+
+    fn shl(self, n: u32) -> U256 {
+        if n >= 256 {
+            return U256::ZERO;
+        }
+        let (limbs, bits) = ((n / 64) as usize, n % 64);
+        let mut result = [0u64; 4];
+        for (i, limb) in result.iter_mut().enumerate() {
+            let src = i + limbs;
+            if src < 4 {
+                *limb = self.0[src] << bits;
+                if bits > 0 && src + 1 < 4 {
+                    *limb |= self.0[src + 1] >> (64 - bits);
+                }
+            }
+        }
+        U256(result)
+    }
+
+    fn shr(self, n: u32) -> U256 {
+        if n >= 256 {
+            return U256::ZERO;
+        }
+        let (limbs, bits) = ((n / 64) as usize, n % 64);
+        let mut result = [0u64; 4];
+        for (i, limb) in result.iter_mut().enumerate().skip(limbs) {
+            let src = i - limbs;
+            *limb = self.0[src] >> bits;
+            if bits > 0 && src > 0 {
+                *limb |= self.0[src - 1] << (64 - bits);
+            }
+        }
+        U256(result)
+    }
+
+    fn zip(self, other: U256, op: impl Fn(u64, u64) -> u64) -> U256 {
+        U256([0, 1, 2, 3].map(|i| op(self.0[i], other.0[i])))
+    }
+
+    fn parse(digits: &str, radix: u32) -> Option<U256> {
+        let digits = digits.replace('_', "");
+        if digits.is_empty() {
+            return None;
+        }
+        digits.chars().try_fold(U256::ZERO, |value, c| {
+            value.checked_mul(U256::from_u64(radix as u64))?.checked_add(U256::from_u64(c.to_digit(radix)? as u64))
+        })
+    }
+
+    fn from_be_bytes(bytes: &[u8]) -> U256 {
+        let mut padded = [0u8; 32];
+        let bytes = &bytes[bytes.len().saturating_sub(32)..];
+        padded[32 - bytes.len()..].copy_from_slice(bytes);
+        U256([0, 1, 2, 3].map(|i| u64::from_be_bytes(padded[i * 8..i * 8 + 8].try_into().unwrap_or_default())))
+    }
+
+    fn to_be_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, limb) in self.0.iter().enumerate() {
+            bytes[i * 8..i * 8 + 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    fn to_decimal(self) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let mut digits = Vec::new();
+        let mut value = self;
+        while !value.is_zero() {
+            let Some((quotient, remainder)) = value.div_rem(U256::from_u64(10)) else {
+                break;
+            };
+            digits.push(char::from(b'0' + remainder.0[3] as u8));
+            value = quotient;
+        }
+        digits.iter().rev().collect()
+    }
+}
+
+/// A folded value while evaluating; converted to `ConstantValue` for the model
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Int { negative: bool, magnitude: U256 },
+    Bool(bool),
+    Str(String),
+    Bytes { data: Vec<u8>, fixed: bool }, // `fixed` for bytesN
+    Address(U256),
+}
+
+impl Value {
+    fn int(negative: bool, magnitude: U256) -> Value {
+        Value::Int { negative: negative && !magnitude.is_zero(), magnitude }
+    }
+
+    fn unsigned(&self) -> Option<U256> {
+        match self {
+            Value::Int { negative: false, magnitude } => Some(*magnitude),
+            _ => None,
+        }
+    }
+
+    /// Two's complement in 256 bits
+    fn twos_complement(&self) -> Option<U256> {
+        match self {
+            Value::Int { negative: false, magnitude } => Some(*magnitude),
+            Value::Int { negative: true, magnitude } => U256::MAX.checked_sub(*magnitude)?.checked_add(U256::ONE),
+            _ => None,
+        }
+    }
+
+    fn into_model(self) -> ConstantValue {
+        match self {
+            Value::Int { negative, magnitude } => {
+                ConstantValue::Integer(format!("{}{}", if negative { "-" } else { "" }, magnitude.to_decimal()))
+            }
+            Value::Bool(value) => ConstantValue::Bool(value),
+            Value::Str(value) => ConstantValue::String(value),
+            Value::Bytes { data, .. } => ConstantValue::Bytes(format!("0x{}", Self::hex(&data))),
+            Value::Address(value) => ConstantValue::Address(format!("0x{}", Self::hex(&value.to_be_bytes()[12..]))),
+        }
+    }
+
+    fn from_model(value: &ConstantValue) -> Option<Value> {
+        let hex_bytes = |hex: &str| -> Option<Vec<u8>> {
+            let hex = hex.strip_prefix("0x")?;
+            (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+        };
+        Some(match value {
+            ConstantValue::Integer(value) => match value.strip_prefix('-') {
+                Some(magnitude) => Value::int(true, U256::parse(magnitude, 10)?),
+                None => Value::int(false, U256::parse(value, 10)?),
+            },
+            ConstantValue::Bool(value) => Value::Bool(*value),
+            ConstantValue::String(value) => Value::Str(value.clone()),
+            ConstantValue::Bytes(value) => {
+                let data = hex_bytes(value)?;
+                Value::Bytes { fixed: data.len() <= 32, data }
+            }
+            ConstantValue::Address(value) => Value::Address(U256::from_be_bytes(&hex_bytes(value)?)),
+        })
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Where a name in a constant expression is looked up
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    contract: Option<usize>, // Index into the contract ASTs; None at file level
+    file_path: &'a str,
+}
+
+/// Evaluates `constant` state variables and file-level constants, and records how constructors
+/// set immutables
+pub struct ConstantFolder<'a> {
+    contracts: &'a [ContractInfo],
+    contract_asts: &'a [pt::ContractDefinition],
+    source_units: &'a [SourceUnit],
+    constant_asts: &'a [Vec<pt::VariableDefinition>],
+    values: HashMap<String, Option<Value>>, // "<contract index>.NAME" or "<file>:NAME"
+    in_progress: HashSet<String>,           // Guards against self-referencing constants
+}
+
+impl<'a> ConstantFolder<'a> {
+    /// Fill `value` of constants and immutables and `assignments` of immutables project-wide,
+    /// then the slots of namespaced storage from their slot constants
+    pub fn fold(
+        contracts: &mut [ContractInfo],
+        contract_asts: &[pt::ContractDefinition],
+        source_units: &mut [SourceUnit],
+        constant_asts: &[Vec<pt::VariableDefinition>],
+    ) {
+        let contracts_snapshot = contracts.to_vec();
+        let units_snapshot = source_units.to_vec();
+        let mut folder = ConstantFolder {
+            contracts: &contracts_snapshot,
+            contract_asts,
+            source_units: &units_snapshot,
+            constant_asts,
+            values: HashMap::new(),
+            in_progress: HashSet::new(),
+        };
+
+        for unit in source_units.iter_mut() {
+            let scope = Scope { contract: None, file_path: &unit.file_path };
+            for constant in &mut unit.constants {
+                constant.value = folder.constant(scope, &constant.name)
+                    .and_then(|value| folder.convert(value, &constant.var_type))
+                    .map(Value::into_model);
+            }
+        }
+
+        for (i, contract) in contracts.iter_mut().enumerate() {
+            let Some(ast) = contract_asts.get(i) else {
+                continue;
+            };
+            let scope = Scope { contract: Some(i), file_path: &contracts_snapshot[i].file_path };
+
+            for var in contract.state_variables.iter_mut().filter(|v| v.is_constant || v.is_immutable) {
+                let initial = if var.is_constant {
+                    folder.constant(scope, &var.name)
+                } else {
+                    Self::declaration(ast, &var.name)
+                        .and_then(|decl| decl.initializer.as_ref())
+                        .and_then(|init| folder.eval(init, scope))
+                };
+                var.value = initial.and_then(|value| folder.convert(value, &var.var_type)).map(Value::into_model);
+            }
+
+            // Array lengths written with constants, so layouts see the actual size
+            for part in &ast.parts {
+                match part {
+                    pt::ContractPart::VariableDefinition(decl) => {
+                        let Some(var) = contract.state_variables.iter_mut()
+                            .find(|v| decl.name.as_ref().is_some_and(|n| n.name == v.name)) else {
+                            continue;
+                        };
+                        let declarer = var.inherited_from.as_ref().and_then(|base| folder.contract_index(base)).unwrap_or(i);
+                        let scope = Scope { contract: Some(declarer), file_path: &contracts_snapshot[declarer].file_path };
+                        var.var_type = folder.folded_type(&decl.ty, scope);
+                    }
+                    pt::ContractPart::StructDefinition(def) => {
+                        let Some(target) = contract.structs.iter_mut()
+                            .find(|s| def.name.as_ref().is_some_and(|n| n.name == s.name)) else {
+                            continue;
+                        };
+                        for field in &def.fields {
+                            let Some(member) = target.members.iter_mut()
+                                .find(|m| field.name.as_ref().is_some_and(|n| n.name == m.name)) else {
+                                continue;
+                            };
+                            member.member_type = folder.folded_type(&field.ty, scope);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let assignments = folder.immutable_assignments(i, ast);
+            for var in contract.state_variables.iter_mut().filter(|v| v.is_immutable) {
+                let sets: Vec<&(String, ImmutableAssignment, Option<Value>)> = assignments.iter()
+                    .filter(|(name, _, _)| *name == var.name)
+                    .collect();
+                // Only an immutable that always gets the same constant has a known value
+                if let [(_, _, Some(value))] = sets.as_slice() {
+                    var.value = folder.convert(value.clone(), &var.var_type).map(Value::into_model);
+                }
+                var.assignments = sets.into_iter().map(|(_, assignment, _)| assignment.clone()).collect();
+            }
+
+            // The verifier checks the slot constant's folded value, whatever form it is written in
+            for storage in &mut contract.upgradeable_storage {
+                if storage.storage_location_constant.is_empty() {
+                    continue;
+                }
+                let slot = contract.state_variables.iter()
+                    .find(|v| v.name == storage.storage_location_constant)
+                    .and_then(|v| Self::slot_hex(v.value.as_ref()?));
+                if let Some(slot) = slot {
+                    storage.storage_slot = slot;
+                }
+            }
+        }
+    }
+
+    /// A 32-byte word as 0x-prefixed hex, for slot constants
+    pub fn slot_hex(value: &ConstantValue) -> Option<String> {
+        let word = match Value::from_model(value)? {
+            Value::Int { negative: false, magnitude } => magnitude.to_be_bytes(),
+            Value::Bytes { data, fixed: true } if data.len() == 32 => U256::from_be_bytes(&data).to_be_bytes(),
+            _ => return None,
+        };
+        Some(format!("0x{}", Value::hex(&word)))
+    }
+
+    /// Whether an integer value is representable as `intN`/`uintN`
+    pub fn fits(value: &ConstantValue, signed: bool, bits: u16) -> bool {
+        let Some(Value::Int { negative, magnitude }) = Value::from_model(value) else {
+            return false;
+        };
+        match (signed, negative) {
+            (false, true) => false,
+            (false, false) => magnitude.bits() <= bits as u32,
+            // -2^(N-1) ..= 2^(N-1) - 1
+            (true, true) => magnitude.checked_sub(U256::ONE).is_some_and(|m| m.bits() < bits as u32),
+            (true, false) => magnitude.bits() < bits as u32,
+        }
+    }
+
+    /// Value as shown in reports with a reading aid, e.g. "`5000` (50%)" or "`86400` (1 days)";
+    /// percentages are taken against a scale constant of the same contract (e.g. `PRECISION`)
+    /// or basis points
+    pub fn describe(var: &StateVariable, constants: &[StateVariable]) -> Option<String> {
+        let value = var.value.as_ref()?;
+        let rendered = format!("`{}`", value);
+        let Some(Value::Int { negative: false, magnitude }) = Value::from_model(value) else {
+            return Some(rendered);
+        };
+
+        let mut hints = Vec::new();
+        let name = var.name.to_uppercase();
+        if magnitude.bits() >= 8 && magnitude == U256::mask(magnitude.bits()) && magnitude.bits() % 8 == 0 {
+            hints.push(format!("type(uint{}).max", magnitude.bits()));
+        } else if let Some(scientific) = Self::scientific(magnitude) {
+            hints.push(scientific);
+        }
+
+        if RATIO_WORDS.iter().any(|w| name.contains(w)) {
+            // The tightest scale that still covers the value, falling back to basis points
+            let scale = constants.iter()
+                .filter(|c| c.name != var.name)
+                .filter(|c| SCALE_WORDS.iter().any(|w| c.name.to_uppercase().contains(w)))
+                .filter_map(|c| Value::from_model(c.value.as_ref()?)?.unsigned())
+                .filter(|scale| Self::power_of_ten(*scale).is_some_and(|exp| exp >= 2))
+                .chain(std::iter::once(U256::from_u64(10_000)))
+                .filter(|scale| magnitude <= *scale)
+                .min();
+            if let Some(percent) = scale.and_then(|scale| Self::percent(magnitude, scale)) {
+                hints.push(percent);
+            }
+        } else if DURATION_WORDS.iter().any(|w| name.contains(w)) && magnitude.bits() <= 63 {
+            let seconds = magnitude.0[3];
+            let duration = [(604_800, "weeks"), (86_400, "days"), (3_600, "hours"), (60, "minutes")]
+                .into_iter()
+                .find(|(unit, _)| seconds > 0 && seconds % unit == 0)
+                .map(|(unit, label)| format!("{} {}", seconds / unit, label));
+            hints.extend(duration);
+        }
+
+        if hints.is_empty() {
+            Some(rendered)
+        } else {
+            Some(format!("{} ({})", rendered, hints.join(", ")))
+        }
+    }
+
+    /// "25e16" for values with at least 6 trailing zeros
+    fn scientific(value: U256) -> Option<String> {
+        let digits = value.to_decimal();
+        let mantissa = digits.trim_end_matches('0');
+        let exponent = digits.len() - mantissa.len();
+        (exponent >= 6 && !mantissa.is_empty()).then(|| format!("{}e{}", mantissa, exponent))
+    }
+
+    fn power_of_ten(value: U256) -> Option<usize> {
+        let digits = value.to_decimal();
+        (digits.starts_with('1') && digits[1..].bytes().all(|b| b == b'0')).then(|| digits.len() - 1)
+    }
+
+    /// `value / scale` as a percentage with up to two decimals
+    fn percent(value: U256, scale: U256) -> Option<String> {
+        let hundredths = value.checked_mul(U256::from_u64(10_000))?.div_rem(scale)?.0;
+        let (whole, fraction) = hundredths.div_rem(U256::from_u64(100))?;
+        let fraction = fraction.0[3];
+        Some(match fraction {
+            0 => format!("{}%", whole.to_decimal()),
+            f if f % 10 == 0 => format!("{}.{}%", whole.to_decimal(), f / 10),
+            f => format!("{}.{:02}%", whole.to_decimal(), f),
+        })
+    }
+
+    fn declaration<'d>(ast: &'d pt::ContractDefinition, name: &str) -> Option<&'d pt::VariableDefinition> {
+        ast.parts.iter().find_map(|part| match part {
+            pt::ContractPart::VariableDefinition(var) if var.name.as_ref().is_some_and(|n| n.name == name) => Some(var.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Value of the constant `name` as seen from `scope`: contract members (including inherited
+    /// ones) first, then file-level constants of the visible files
+    fn constant(&mut self, scope: Scope, name: &str) -> Option<Value> {
+        if let Some(i) = scope.contract {
+            if let Some(decl) = Self::declaration(&self.contract_asts[i], name) {
+                let is_constant = decl.attrs.iter().any(|a| matches!(a, pt::VariableAttribute::Constant(_)));
+                let key = format!("{}.{}", i, name);
+                return self.memoized(key, |folder| {
+                    if !is_constant {
+                        return None; // Immutables and storage variables aren't compile-time values
+                    }
+                    let value = folder.eval(decl.initializer.as_ref()?, scope)?;
+                    folder.convert(value, &SolidityParser::type_to_string(&decl.ty))
+                });
+            }
+        }
+
+        let units = StateModificationAnalyzer::visible_source_units(scope.file_path, self.source_units);
+        let (unit_index, decl) = units.iter().find_map(|unit| {
+            let index = self.source_units.iter().position(|u| u.file_path == unit.file_path)?;
+            let decl = self.constant_asts.get(index)?.iter().find(|c| c.name.as_ref().is_some_and(|n| n.name == name))?;
+            Some((index, decl))
+        })?;
+        let file_scope = Scope { contract: None, file_path: &self.source_units[unit_index].file_path };
+        self.memoized(format!("{}:{}", file_scope.file_path, name), |folder| {
+            let value = folder.eval(decl.initializer.as_ref()?, file_scope)?;
+            folder.convert(value, &SolidityParser::type_to_string(&decl.ty))
+        })
+    }
+
+    fn memoized(&mut self, key: String, compute: impl FnOnce(&mut Self) -> Option<Value>) -> Option<Value> {
+        if let Some(value) = self.values.get(&key) {
+            return value.clone();
+        }
+        if !self.in_progress.insert(key.clone()) {
+            return None;
+        }
+        let value = compute(self);
+        self.in_progress.remove(&key);
+        self.values.insert(key, value.clone());
+        value
+    }
+
+    /// A declared type as `type_to_string` renders it, with constant array lengths evaluated
+    fn folded_type(&mut self, ty: &pt::Expression, scope: Scope) -> String {
+        match ty {
+            pt::Expression::ArraySubscript(_, base, Some(length)) => {
+                let evaluated = match length.as_ref() {
+                    pt::Expression::NumberLiteral(..) => None,
+                    length => self.eval(length, scope).and_then(|v| v.unsigned()).map(U256::to_decimal),
+                };
+                format!("{}[{}]", self.folded_type(base, scope),
+                    evaluated.unwrap_or_else(|| SolidityParser::expr_to_string(length)))
+            }
+            pt::Expression::ArraySubscript(_, base, None) => format!("{}[]", self.folded_type(base, scope)),
+            pt::Expression::Type(_, pt::Type::Mapping { key, value, .. }) => {
+                format!("mapping({} => {})", self.folded_type(key, scope), self.folded_type(value, scope))
+            }
+            _ => SolidityParser::type_to_string(ty),
+        }
+    }
+
+    fn contract_index(&self, name: &str) -> Option<usize> {
+        self.contracts.iter().position(|c| c.name == name)
+    }
+
+    fn eval(&mut self, expr: &pt::Expression, scope: Scope) -> Option<Value> {
+        match expr {
+            pt::Expression::NumberLiteral(_, integer, exponent, unit) => {
+                Self::scaled(U256::parse(integer, 10)?, "", exponent, unit)
+            }
+            pt::Expression::RationalNumberLiteral(_, integer, fraction, exponent, unit) => {
+                let digits = format!("{}{}", integer, fraction);
+                Self::scaled(U256::parse(&digits, 10)?, fraction, exponent, unit)
+            }
+            pt::Expression::HexNumberLiteral(_, hex, unit) => {
+                let value = U256::parse(hex.trim_start_matches("0x"), 16)?;
+                Self::scaled(value, "", "", unit)
+            }
+            pt::Expression::BoolLiteral(_, value) => Some(Value::Bool(*value)),
+            pt::Expression::StringLiteral(parts) => Some(Value::Str(parts.iter().map(|p| p.string.as_str()).collect())),
+            pt::Expression::HexLiteral(parts) => {
+                let hex: String = parts.iter().map(|p| p.hex.replace('_', "")).collect();
+                let data = (0..hex.len()).step_by(2)
+                    .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                    .collect::<Option<Vec<u8>>>()?;
+                Some(Value::Bytes { data, fixed: false })
+            }
+            pt::Expression::AddressLiteral(_, address) => {
+                Some(Value::Address(U256::parse(address.trim_start_matches("0x"), 16)?))
+            }
+            pt::Expression::Variable(ident) => self.constant(scope, &ident.name),
+            pt::Expression::Parenthesis(_, inner) => self.eval(inner, scope),
+            pt::Expression::MemberAccess(_, base, member) => self.member(base, &member.name),
+            pt::Expression::FunctionCall(_, callee, args) => self.call(callee, args, scope),
+            pt::Expression::ConditionalOperator(_, condition, if_true, if_false) => {
+                match self.eval(condition, scope)? {
+                    Value::Bool(true) => self.eval(if_true, scope),
+                    Value::Bool(false) => self.eval(if_false, scope),
+                    _ => None,
+                }
+            }
+
+            pt::Expression::Negate(_, inner) => match self.eval(inner, scope)? {
+                Value::Int { negative, magnitude } => Some(Value::int(!negative, magnitude)),
+                _ => None,
+            },
+            pt::Expression::Not(_, inner) => match self.eval(inner, scope)? {
+                Value::Bool(value) => Some(Value::Bool(!value)),
+                _ => None,
+            },
+            pt::Expression::BitwiseNot(_, inner) => match self.eval(inner, scope)? {
+                Value::Bytes { data, fixed: true } => Some(Value::Bytes { data: data.iter().map(|b| !b).collect(), fixed: true }),
+                value => Some(Value::int(false, U256::MAX.zip(value.twos_complement()?, |max, v| max ^ v))),
+            },
+
+            pt::Expression::Add(_, left, right) => {
+                let (left, right) = (self.eval(left, scope)?, self.eval(right, scope)?);
+                Self::add(left, right)
+            }
+            pt::Expression::Subtract(_, left, right) => {
+                let (left, right) = (self.eval(left, scope)?, self.eval(right, scope)?);
+                match right {
+                    Value::Int { negative, magnitude } => Self::add(left, Value::int(!negative, magnitude)),
+                    _ => None,
+                }
+            }
+            pt::Expression::Multiply(_, left, right) => match (self.eval(left, scope)?, self.eval(right, scope)?) {
+                (Value::Int { negative: a, magnitude: x }, Value::Int { negative: b, magnitude: y }) => {
+                    Some(Value::int(a != b, x.checked_mul(y)?))
+                }
+                _ => None,
+            },
+            // Integer division truncates towards zero; the remainder takes the dividend's sign
+            pt::Expression::Divide(_, left, right) => match (self.eval(left, scope)?, self.eval(right, scope)?) {
+                (Value::Int { negative: a, magnitude: x }, Value::Int { negative: b, magnitude: y }) => {
+                    Some(Value::int(a != b, x.div_rem(y)?.0))
+                }
+                _ => None,
+            },
+            pt::Expression::Modulo(_, left, right) => match (self.eval(left, scope)?, self.eval(right, scope)?) {
+                (Value::Int { negative, magnitude: x }, Value::Int { magnitude: y, .. }) => {
+                    Some(Value::int(negative, x.div_rem(y)?.1))
+                }
+                _ => None,
+            },
+            pt::Expression::Power(_, base, exponent) => match (self.eval(base, scope)?, self.eval(exponent, scope)?) {
+                (Value::Int { negative, magnitude }, Value::Int { negative: false, magnitude: exponent }) => {
+                    Some(Value::int(negative && exponent.bit(0), magnitude.checked_pow(exponent)?))
+                }
+                _ => None,
+            },
+            pt::Expression::ShiftLeft(_, left, right) => {
+                let (value, shift) = (self.eval(left, scope)?.unsigned()?, self.eval(right, scope)?.unsigned()?);
+                (shift.bits() <= 16 && (value.bits() + shift.0[3] as u32) <= 256)
+                    .then(|| Value::int(false, value.shl(shift.0[3] as u32)))
+            }
+            pt::Expression::ShiftRight(_, left, right) => {
+                let (value, shift) = (self.eval(left, scope)?.unsigned()?, self.eval(right, scope)?.unsigned()?);
+                let shift = if shift.bits() > 16 { 256 } else { shift.0[3] as u32 };
+                Some(Value::int(false, value.shr(shift)))
+            }
+            pt::Expression::BitwiseAnd(_, left, right) => self.bitwise(left, right, scope, |a, b| a & b),
+            pt::Expression::BitwiseOr(_, left, right) => self.bitwise(left, right, scope, |a, b| a | b),
+            pt::Expression::BitwiseXor(_, left, right) => self.bitwise(left, right, scope, |a, b| a ^ b),
+
+            pt::Expression::Equal(_, left, right) => Some(Value::Bool(self.eval(left, scope)? == self.eval(right, scope)?)),
+            pt::Expression::NotEqual(_, left, right) => Some(Value::Bool(self.eval(left, scope)? != self.eval(right, scope)?)),
+            pt::Expression::Less(_, left, right) => self.compare(left, right, scope, |o| o.is_lt()),
+            pt::Expression::LessEqual(_, left, right) => self.compare(left, right, scope, |o| o.is_le()),
+            pt::Expression::More(_, left, right) => self.compare(left, right, scope, |o| o.is_gt()),
+            pt::Expression::MoreEqual(_, left, right) => self.compare(left, right, scope, |o| o.is_ge()),
+            pt::Expression::And(_, left, right) => match (self.eval(left, scope)?, self.eval(right, scope)?) {
+                (Value::Bool(a), Value::Bool(b)) => Some(Value::Bool(a && b)),
+                _ => None,
+            },
+            pt::Expression::Or(_, left, right) => match (self.eval(left, scope)?, self.eval(right, scope)?) {
+                (Value::Bool(a), Value::Bool(b)) => Some(Value::Bool(a || b)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// A literal with its exponent and denomination applied (`1.5e18`, `2 days`, `1 ether`);
+    /// None unless the result is an integer
+    fn scaled(digits: U256, fraction: &str, exponent: &str, unit: &Option<pt::Identifier>) -> Option<Value> {
+        let exponent: i64 = if exponent.is_empty() { 0 } else { exponent.replace('_', "").parse().ok()? };
+        let exponent = exponent - fraction.len() as i64;
+        let ten = U256::from_u64(10);
+        let mut value = if exponent >= 0 {
+            digits.checked_mul(ten.checked_pow(U256::from_u64(exponent as u64))?)?
+        } else {
+            let (quotient, remainder) = digits.div_rem(ten.checked_pow(U256::from_u64(exponent.unsigned_abs()))?)?;
+            if !remainder.is_zero() {
+                return None; // Fractional values only exist as intermediate rationals
+            }
+            quotient
+        };
+
+        if let Some(unit) = unit {
+            let multiplier: u64 = match unit.name.as_str() {
+                "wei" | "seconds" => 1,
+                "gwei" => 1_000_000_000,
+                "ether" => 1_000_000_000_000_000_000,
+                "minutes" => 60,
+                "hours" => 3_600,
+                "days" => 86_400,
+                "weeks" => 604_800,
+                _ => return None,
+            };
+            value = value.checked_mul(U256::from_u64(multiplier))?;
+        }
+        Some(Value::int(false, value))
+    }
+
+    fn add(left: Value, right: Value) -> Option<Value> {
+        let (Value::Int { negative: a, magnitude: x }, Value::Int { negative: b, magnitude: y }) = (left, right) else {
+            return None;
+        };
+        if a == b {
+            return Some(Value::int(a, x.checked_add(y)?));
+        }
+        // Opposite signs: the larger magnitude decides the sign
+        if x >= y {
+            Some(Value::int(a, x.checked_sub(y)?))
+        } else {
+            Some(Value::int(b, y.checked_sub(x)?))
+        }
+    }
+
+    fn bitwise(&mut self, left: &pt::Expression, right: &pt::Expression, scope: Scope, op: fn(u64, u64) -> u64) -> Option<Value> {
+        match (self.eval(left, scope)?, self.eval(right, scope)?) {
+            (Value::Bytes { data: a, fixed: true }, Value::Bytes { data: b, fixed: true }) if a.len() == b.len() => {
+                let result = U256::from_be_bytes(&a).zip(U256::from_be_bytes(&b), op).to_be_bytes();
+                Some(Value::Bytes { data: result[32 - a.len()..].to_vec(), fixed: true })
+            }
+            (left, right) => Some(Value::int(false, left.unsigned()?.zip(right.unsigned()?, op))),
+        }
+    }
+
+    fn compare(
+        &mut self,
+        left: &pt::Expression,
+        right: &pt::Expression,
+        scope: Scope,
+        holds: fn(std::cmp::Ordering) -> bool,
+    ) -> Option<Value> {
+        let (Value::Int { negative: a, magnitude: x }, Value::Int { negative: b, magnitude: y }) =
+            (self.eval(left, scope)?, self.eval(right, scope)?)
+        else {
+            return None;
+        };
+        let ordering = match (a, b) {
+            (false, false) => x.cmp(&y),
+            (true, true) => y.cmp(&x),
+            (false, true) => std::cmp::Ordering::Greater,
+            (true, false) => std::cmp::Ordering::Less,
+        };
+        Some(Value::Bool(holds(ordering)))
+    }
+
+    fn member(&mut self, base: &pt::Expression, member: &str) -> Option<Value> {
+        match base {
+            // `type(uint64).max`, `type(int8).min`
+            pt::Expression::FunctionCall(_, callee, args) if matches!(callee.as_ref(), pt::Expression::Variable(t) if t.name == "type") => {
+                let (signed, bits) = match args.first()? {
+                    pt::Expression::Type(_, pt::Type::Uint(bits)) => (false, *bits as u32),
+                    pt::Expression::Type(_, pt::Type::Int(bits)) => (true, *bits as u32),
+                    _ => return None,
+                };
+                match (member, signed) {
+                    ("max", false) => Some(Value::int(false, U256::mask(bits))),
+                    ("min", false) => Some(Value::int(false, U256::ZERO)),
+                    ("max", true) => Some(Value::int(false, U256::mask(bits - 1))),
+                    ("min", true) => Some(Value::int(true, U256::ONE.shl(bits - 1))),
+                    _ => None,
+                }
+            }
+            // `Library.CONSTANT`, `Contract.CONSTANT`
+            pt::Expression::Variable(ident) => {
+                let index = self.contract_index(&ident.name)?;
+                let contract_scope = Scope { contract: Some(index), file_path: &self.contracts[index].file_path };
+                self.constant(contract_scope, member)
+            }
+            _ => None,
+        }
+    }
+
+    fn call(&mut self, callee: &pt::Expression, args: &[pt::Expression], scope: Scope) -> Option<Value> {
+        match callee {
+            pt::Expression::Type(_, ty) => {
+                let [arg] = args else {
+                    return None;
+                };
+                let value = self.eval(arg, scope)?;
+                let target = match ty {
+                    pt::Type::Payable => "address".to_string(),
+                    ty => ty.to_string(),
+                };
+                self.convert(value, &target)
+            }
+            pt::Expression::Variable(ident) if ident.name == "keccak256" => {
+                let data = match self.eval(args.first()?, scope)? {
+                    Value::Str(text) => text.into_bytes(),
+                    Value::Bytes { data, .. } => data,
+                    _ => return None,
+                };
+                Some(Value::Bytes { data: AbiGenerator::keccak256(&data).to_vec(), fixed: true })
+            }
+            pt::Expression::MemberAccess(_, base, member) if matches!(base.as_ref(), pt::Expression::Variable(b) if b.name == "abi") => {
+                let values = args.iter().map(|arg| self.eval(arg, scope)).collect::<Option<Vec<Value>>>()?;
+                let mut encoded = Vec::new();
+                for value in values {
+                    match (member.name.as_str(), value) {
+                        // Static values take one word each
+                        ("encode", Value::Bytes { data, fixed: true }) => {
+                            let mut word = data;
+                            word.resize(32, 0);
+                            encoded.extend(word);
+                        }
+                        ("encode", Value::Bool(value)) => encoded.extend(U256::from_u64(value as u64).to_be_bytes()),
+                        ("encode", Value::Address(value)) => encoded.extend(value.to_be_bytes()),
+                        ("encode", value @ Value::Int { .. }) => encoded.extend(value.twos_complement()?.to_be_bytes()),
+                        ("encodePacked", Value::Str(text)) => encoded.extend(text.into_bytes()),
+                        ("encodePacked", Value::Bytes { data, .. }) => encoded.extend(data),
+                        ("encodePacked", Value::Address(value)) => encoded.extend(&value.to_be_bytes()[12..]),
+                        ("encodePacked", Value::Bool(value)) => encoded.push(value as u8),
+                        _ => return None, // Dynamic `abi.encode` items and untyped packed integers
+                    }
+                }
+                Some(Value::Bytes { data: encoded, fixed: false })
+            }
+            _ => None,
+        }
+    }
+
+    /// Explicit (or declared) conversion to an elementary type
+    fn convert(&self, value: Value, ty: &str) -> Option<Value> {
+        let ty = ty.trim();
+        let bits = |prefix: &str| -> Option<u32> {
+            let rest = ty.strip_prefix(prefix)?;
+            if rest.is_empty() { Some(256) } else { rest.parse().ok() }
+        };
+
+        if let Some(bits) = bits("uint") {
+            let raw = match value {
+                Value::Int { .. } => value.twos_complement()?,
+                Value::Bytes { data, fixed: true } => U256::from_be_bytes(&data),
+                Value::Address(address) => address,
+                _ => return None,
+            };
+            return Some(Value::int(false, raw.zip(U256::mask(bits), |v, m| v & m)));
+        }
+        if let Some(bits) = bits("int") {
+            let raw = value.twos_complement()?.zip(U256::mask(bits), |v, m| v & m);
+            return Some(if raw.bit(bits - 1) {
+                Value::int(true, U256::mask(bits).checked_sub(raw)?.checked_add(U256::ONE)?)
+            } else {
+                Value::int(false, raw)
+            });
+        }
+        if let Some(size) = ty.strip_prefix("bytes").and_then(|n| n.parse::<usize>().ok()) {
+            let data = match value {
+                // Integers keep their low-order bytes, byte strings their leading ones
+                Value::Int { .. } => value.twos_complement()?.to_be_bytes()[32 - size..].to_vec(),
+                Value::Bytes { mut data, .. } => {
+                    data.resize(size, 0);
+                    data
+                }
+                Value::Str(text) => {
+                    let mut data = text.into_bytes();
+                    data.resize(size, 0);
+                    data
+                }
+                _ => return None,
+            };
+            return Some(Value::Bytes { data, fixed: true });
+        }
+        match (ty, value) {
+            ("address" | "address payable", Value::Int { negative: false, magnitude }) => {
+                Some(Value::Address(magnitude.zip(U256::mask(160), |v, m| v & m)))
+            }
+            ("address" | "address payable", Value::Bytes { data, fixed: true }) => Some(Value::Address(U256::from_be_bytes(&data))),
+            ("bytes", Value::Str(text)) => Some(Value::Bytes { data: text.into_bytes(), fixed: false }),
+            ("bytes", Value::Bytes { data, .. }) => Some(Value::Bytes { data, fixed: false }),
+            ("string", Value::Bytes { data, fixed: false }) => String::from_utf8(data).ok().map(Value::Str),
+            (_, value @ (Value::Address(_) | Value::Bool(_) | Value::Str(_))) if matches!(ty, "address" | "address payable" | "bool" | "string") => Some(value),
+            // Contracts, enums and user-defined value types keep the value unchanged
+            (_, value) if ty.chars().next().is_some_and(|c| c.is_ascii_uppercase()) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// `NAME = expr` statements in the constructors of a contract and of its bases, with the
+    /// folded value of `expr` when it is a constant expression
+    fn immutable_assignments(&mut self, index: usize, ast: &pt::ContractDefinition) -> Vec<(String, ImmutableAssignment, Option<Value>)> {
+        let contract = &self.contracts[index];
+        let scope = Scope { contract: Some(index), file_path: &contract.file_path };
+        let immutables: HashSet<&str> = contract.state_variables.iter()
+            .filter(|v| v.is_immutable)
+            .map(|v| v.name.as_str())
+            .collect();
+
+        let mut found = Vec::new();
+        for part in &ast.parts {
+            let pt::ContractPart::FunctionDefinition(func) = part else {
+                continue;
+            };
+            let (true, Some(body)) = (matches!(func.ty, pt::FunctionTy::Constructor), &func.body) else {
+                continue;
+            };
+            // Base constructors are merged in under a qualified name
+            let declaring_file = func.name.as_ref()
+                .and_then(|n| n.name.split_once('.'))
+                .and_then(|(base, _)| self.contracts.iter().find(|c| c.name == base))
+                .map_or(contract.file_path.as_str(), |c| c.file_path.as_str());
+            let source = self.source_units.iter().find(|u| u.file_path == declaring_file);
+            let function = crate::parser::SolidityParser::function_signature(func);
+
+            let mut assignments = Vec::new();
+            StateModificationAnalyzer::visit_statement_expressions(body, &mut |expr| {
+                if let pt::Expression::Assign(loc, target, value) = expr {
+                    if let pt::Expression::Variable(ident) = target.as_ref() {
+                        if immutables.contains(ident.name.as_str()) {
+                            assignments.push((ident.name.clone(), value.as_ref().clone(), loc.start()));
+                        }
+                    }
+                }
+            });
+
+            for (name, value, offset) in assignments {
+                let assignment = ImmutableAssignment {
+                    function: function.clone(),
+                    expression: value.to_string(),
+                    line_number: source.map(|u| u.line_at(offset)).unwrap_or_default(),
+                };
+                let folded = self.eval(&value, scope);
+                found.push((name, assignment, folded));
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+
+    #[test]
+    fn folds_constants_across_contract_and_file_scope() {
+        let project = analyze(r#"
+            uint256 constant WAD = 1e18;
+            contract C {
+                uint256 constant HALF = WAD / 2;
+                int256 constant NEGATIVE = -int256(HALF) * 3;
+                bytes32 constant EMPTY = keccak256("");
+            }
+        "#);
+        let value = |name: &str| contract(&project, "C").state_variables.iter()
+            .find(|v| v.name == name)
+            .and_then(|v| v.value.clone());
+        assert_eq!(value("HALF"), Some(ConstantValue::Integer("500000000000000000".to_string())));
+        assert_eq!(value("NEGATIVE"), Some(ConstantValue::Integer("-1500000000000000000".to_string())));
+        assert_eq!(value("EMPTY"), Some(ConstantValue::Bytes(
+            "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470".to_string())));
+    }
+
+    #[test]
+    fn namespaced_slot_comes_from_the_folded_constant() {
+        let project = analyze(r#"
+            contract Main {
+                /// @custom:storage-location erc7201:example.main
+                struct MainStorage { uint256 x; }
+
+                bytes32 private constant NAMESPACE = keccak256("example.main");
+                bytes32 private constant MAIN_STORAGE_LOCATION =
+                    keccak256(abi.encode(uint256(NAMESPACE) - 1)) & ~bytes32(uint256(0xff));
+
+                function _getMainStorage() private pure returns (MainStorage storage $) {
+                    assembly { $.slot := MAIN_STORAGE_LOCATION }
+                }
+            }
+        "#);
+        let storage = &contract(&project, "Main").upgradeable_storage[0];
+        assert_eq!(storage.storage_slot, "0x183a6125c38840424c4a85fa12bab2ab606c4b6d0e7cc73c0c06ba5300eab500");
+        assert!(storage.issues.is_empty(), "{:?}", storage.issues);
+    }
+}
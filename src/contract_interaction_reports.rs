@@ -1,11 +1,11 @@
 use crate::models::*;
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
 // Type alias for contract interaction mappings
-type InteractionMap = HashMap<(String, String), Vec<(String, String, String)>>;
+type InteractionMap = BTreeMap<(String, String), Vec<(String, String, String)>>;
 
 // Type alias for cross-contract state dependencies
 // (source_contract, source_function, target_contract, target_function, is_modification, modifies_states, reads_states)
@@ -24,7 +24,7 @@ impl ContractInteractionReports {
         md.push_str("════════════════════════════════════════════════════════════════════════════════\n\n");
 
         // Build interaction map: (source_contract, target_contract) -> Vec<(function_name, target_function)>
-        let mut interactions: InteractionMap = HashMap::new();
+        let mut interactions: InteractionMap = BTreeMap::new();
 
         for contract in contracts {
             for func in &contract.functions {
@@ -35,8 +35,8 @@ impl ContractInteractionReports {
                             interactions.entry(key)
                                 .or_default()
                                 .push((
-                                    func.name.clone(),
-                                    ext_call.target_function.clone(),
+                                    func.signature.clone(),
+                                    ext_call.target_display(),
                                     ext_call.state_mutability.clone()
                                 ));
                         }
@@ -61,7 +61,7 @@ impl ContractInteractionReports {
             md.push_str(&format!("**{} total function calls**\n\n", calls.len()));
 
             // Group by source function
-            let mut grouped: HashMap<String, Vec<(String, String)>> = HashMap::new();
+            let mut grouped: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
             for (src_func, target_func, mutability) in calls {
                 grouped.entry(src_func.clone())
                     .or_default()
@@ -75,7 +75,7 @@ impl ContractInteractionReports {
                 if i > 0 {
                     md.push_str("───────────────────────────────────────\n\n");
                 }
-                md.push_str(&format!("### 🔹 `{}()`\n\n", src_func));
+                md.push_str(&format!("### 🔹 `{}`\n\n", src_func));
                 md.push_str("**Calls:**\n");
 
                 for (target_func, mutability) in *target_calls {
@@ -84,7 +84,7 @@ impl ContractInteractionReports {
                         "payable" => "💰",
                         _ => "✏️",
                     };
-                    md.push_str(&format!("  - {} `{}()` *[{}]*\n", icon, target_func, mutability));
+                    md.push_str(&format!("  - {} `{}` *[{}]*\n", icon, target_func, mutability));
                 }
                 md.push('\n');
             }
@@ -100,7 +100,7 @@ impl ContractInteractionReports {
 
     /// Generate JSON report for contract interactions
     pub fn generate_contract_interactions_json(contracts: &[ContractInfo]) -> Value {
-        let mut interactions: HashMap<String, HashMap<String, Vec<Value>>> = HashMap::new();
+        let mut interactions: BTreeMap<String, BTreeMap<String, Vec<Value>>> = BTreeMap::new();
 
         for contract in contracts {
             for func in &contract.functions {
@@ -108,9 +108,10 @@ impl ContractInteractionReports {
                     if let Some(ref target_contract) = ext_call.target_contract {
                         if target_contract != &contract.name {
                             let call_info = json!({
-                                "source_function": func.name,
+                                "source_function": func.signature,
                                 "source_function_visibility": func.visibility,
                                 "target_function": ext_call.target_function,
+                                "target_signature": ext_call.target_signature,
                                 "target_function_mutability": ext_call.state_mutability,
                                 "line_number": ext_call.line_number
                             });
@@ -152,9 +153,9 @@ impl ContractInteractionReports {
                             if has_modifications || has_reads {
                                 dependencies.push((
                                     contract.name.clone(),
-                                    func.name.clone(),
+                                    func.signature.clone(),
                                     target_contract.clone(),
-                                    ext_call.target_function.clone(),
+                                    ext_call.target_display(),
                                     has_modifications,
                                     ext_call.target_modifies_states.clone(),
                                     ext_call.target_reads_states.clone(),
@@ -172,7 +173,7 @@ impl ContractInteractionReports {
         }
 
         // Group by source contract
-        let mut by_contract: HashMap<String, Vec<_>> = HashMap::new();
+        let mut by_contract: BTreeMap<String, Vec<_>> = BTreeMap::new();
         for dep in dependencies {
             by_contract.entry(dep.0.clone()).or_default().push(dep);
         }
@@ -188,7 +189,7 @@ impl ContractInteractionReports {
             md.push_str("════════════════════════════════════════════════════════════════════════════════\n\n");
 
             // Group by target contract
-            let mut by_target: HashMap<String, Vec<_>> = HashMap::new();
+            let mut by_target: BTreeMap<String, Vec<_>> = BTreeMap::new();
             for dep in deps {
                 by_target.entry(dep.2.clone()).or_default().push(dep);
             }
@@ -210,7 +211,7 @@ impl ContractInteractionReports {
                         md.push_str("   ╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌\n\n");
                     }
 
-                    md.push_str(&format!("#### 🔗 `{}.{}()` → `{}.{}()`\n\n",
+                    md.push_str(&format!("#### 🔗 `{}.{}` → `{}.{}`\n\n",
                         source_contract, src_func, target_contract, target_func));
 
                     if !modifies.is_empty() {
@@ -244,7 +245,7 @@ impl ContractInteractionReports {
 
     /// Generate JSON report for cross-contract state dependencies
     pub fn generate_cross_contract_state_dependencies_json(contracts: &[ContractInfo]) -> Value {
-        let mut dependencies: HashMap<String, HashMap<String, Vec<Value>>> = HashMap::new();
+        let mut dependencies: BTreeMap<String, BTreeMap<String, Vec<Value>>> = BTreeMap::new();
 
         for contract in contracts {
             for func in &contract.functions {
@@ -256,9 +257,11 @@ impl ContractInteractionReports {
 
                             if has_modifications || has_reads {
                                 let dep_info = json!({
-                                    "source_function": func.name,
+                                    "source_function": func.signature,
                                     "source_function_visibility": func.visibility,
                                     "target_function": ext_call.target_function,
+                                    "target_signature": ext_call.target_signature,
+                                "target_signature": ext_call.target_signature,
                                     "target_function_mutability": ext_call.state_mutability,
                                     "modifies_state_variables": ext_call.target_modifies_states,
                                     "reads_state_variables": ext_call.target_reads_states,
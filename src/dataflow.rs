@@ -1,4 +1,5 @@
 use crate::models::{ContractInfo, FunctionDef};
+use crate::yul::YulAnalyzer;
 use serde::{Deserialize, Serialize};
 use solang_parser::pt;
 use std::collections::{HashMap, HashSet};
@@ -166,7 +167,7 @@ impl DataFlowAnalyzer {
 
         // Analyze each function
         for func in &contract.functions {
-            if let Some(body) = function_bodies.get(&func.name) {
+            if let Some(body) = function_bodies.get(&func.signature) {
                 // Find taint sources in this function
                 let sources = Self::identify_taint_sources(func, body);
 
@@ -208,7 +209,7 @@ impl DataFlowAnalyzer {
 
                 if !param_name.is_empty() {
                     sources.push(TaintSource::FunctionParameter {
-                        function_name: func.name.clone(),
+                        function_name: func.signature.clone(),
                         param_index: i,
                         param_name,
                     });
@@ -282,20 +283,14 @@ impl DataFlowAnalyzer {
                 if let pt::Expression::Variable(ident) = base.as_ref() {
                     if ident.name == "msg" {
                         match member.name.as_str() {
-                            "sender" => {
-                                if !sources.contains(&TaintSource::MsgSender) {
-                                    sources.push(TaintSource::MsgSender);
-                                }
+                            "sender" if !sources.contains(&TaintSource::MsgSender) => {
+                                sources.push(TaintSource::MsgSender);
                             }
-                            "value" => {
-                                if !sources.contains(&TaintSource::MsgValue) {
-                                    sources.push(TaintSource::MsgValue);
-                                }
+                            "value" if !sources.contains(&TaintSource::MsgValue) => {
+                                sources.push(TaintSource::MsgValue);
                             }
-                            "data" => {
-                                if !sources.contains(&TaintSource::MsgData) {
-                                    sources.push(TaintSource::MsgData);
-                                }
+                            "data" if !sources.contains(&TaintSource::MsgData) => {
+                                sources.push(TaintSource::MsgData);
                             }
                             _ => {}
                         }
@@ -422,6 +417,21 @@ impl DataFlowAnalyzer {
             });
         }
 
+        // Low-level calls in inline assembly
+        for call in func.assembly.iter().flat_map(|a| a.calls.iter()) {
+            let target_expr = call.target.clone();
+            match call.opcode.as_str() {
+                "delegatecall" | "callcode" => sinks.push(TaintSink::DelegateCall { target_expr }),
+                "call" => sinks.push(TaintSink::ValueTransfer { target_expr }),
+                "selfdestruct" => sinks.push(TaintSink::SelfDestruct { target_expr }),
+                "staticcall" => sinks.push(TaintSink::ExternalCall {
+                    target_var: target_expr,
+                    function_name: call.opcode.clone(),
+                }),
+                _ => {}
+            }
+        }
+
         // Scan body for delegatecall, selfdestruct, transfer/send
         if let Some(stmt) = &body.body {
             Self::scan_for_taint_sinks(stmt, &mut sinks);
@@ -430,6 +440,20 @@ impl DataFlowAnalyzer {
         sinks
     }
 
+    /// Targets of Solidity-level `.delegatecall(...)` in a function body
+    pub fn delegatecall_targets(func: &pt::FunctionDefinition) -> Vec<String> {
+        let mut sinks = Vec::new();
+        if let Some(body) = &func.body {
+            Self::scan_for_taint_sinks(body, &mut sinks);
+        }
+        sinks.into_iter()
+            .filter_map(|sink| match sink {
+                TaintSink::DelegateCall { target_expr } => Some(target_expr),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Scan statement tree for dangerous sinks
     fn scan_for_taint_sinks(stmt: &pt::Statement, sinks: &mut Vec<TaintSink>) {
         match stmt {
@@ -647,7 +671,7 @@ impl DataFlowAnalyzer {
                 let influence_type = Self::determine_influence_type(param_name, &influenced_vars, body, state_vars);
 
                 influences.push(ParameterInfluence {
-                    function_name: func.name.clone(),
+                    function_name: func.signature.clone(),
                     param_index: i,
                     param_name: param_name.clone(),
                     influenced_state_vars: influenced_vars,
@@ -712,7 +736,7 @@ impl DataFlowAnalyzer {
                     flows.push(TaintFlow {
                         source: source.clone(),
                         sink: sink.clone(),
-                        function_name: func.name.clone(),
+                        function_name: func.signature.clone(),
                         path,
                         is_validated,
                         severity,
@@ -829,6 +853,11 @@ impl DataFlowAnalyzer {
                     }
                 }
             }
+            pt::Statement::Assembly { block, .. } => {
+                for (source, target) in YulAnalyzer::value_flows(block, state_vars) {
+                    flow_graph.entry(source).or_default().insert(target);
+                }
+            }
             _ => {}
         }
     }
@@ -1028,7 +1057,9 @@ impl DataFlowAnalyzer {
             }
         }
 
-        influenced.into_iter().collect()
+        let mut influenced: Vec<String> = influenced.into_iter().collect();
+        influenced.sort();
+        influenced
     }
 
     /// Build the flow path from source to sink
@@ -0,0 +1,586 @@
+use crate::analyzer::StateModificationAnalyzer;
+use crate::models::*;
+use crate::parser::SolidityParser;
+use anyhow::Result;
+use colored::*;
+use serde_json::json;
+use solang_parser::pt;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// EIP-2535 Diamonds: facets sharing Diamond storage, their selectors and clashes
+pub struct DiamondAnalyzer;
+
+// A Diamond storage struct as seen from the code using it
+struct SharedStorage {
+    key: String, // "Library.Struct"
+    library: String,
+    storage_struct: String,
+    accessor: String,
+}
+
+// Shared storage fields a function or modifier touches directly, as "Library.Struct.field"
+#[derive(Default, Clone)]
+struct Access {
+    reads: BTreeSet<String>,
+    writes: BTreeSet<String>,
+}
+
+impl Access {
+    fn merge(&mut self, other: &Access) {
+        self.reads.extend(other.reads.iter().cloned());
+        self.writes.extend(other.writes.iter().cloned());
+    }
+
+    fn is_empty(&self) -> bool {
+        self.reads.is_empty() && self.writes.is_empty()
+    }
+}
+
+// Walk state for one function body
+struct Scope<'a> {
+    storages: &'a [SharedStorage],
+    contract: &'a str,
+    locals: HashMap<String, &'a str>, // Storage pointer -> shared storage key
+    access: Access,
+}
+
+impl<'a> Scope<'a> {
+    /// Shared storage a declared type refers to (`Lib.S`, or `S` inside `Lib`)
+    fn storage_of_type(&self, ty: &str) -> Option<&'a str> {
+        self.storages.iter()
+            .find(|s| ty == s.key || (ty == s.storage_struct && self.contract == s.library))
+            .map(|s| s.key.as_str())
+    }
+
+    fn bind(&mut self, decl_ty: &pt::Expression, name: Option<&pt::Identifier>) {
+        if let (Some(key), Some(name)) = (self.storage_of_type(&SolidityParser::type_to_string(decl_ty)), name) {
+            self.locals.insert(name.name.clone(), key);
+        }
+    }
+
+    /// Shared storage an expression evaluates to: a bound pointer or an accessor call
+    fn root(&self, expr: &pt::Expression) -> Option<&'a str> {
+        match expr.strip_parentheses() {
+            pt::Expression::Variable(id) => self.locals.get(&id.name).copied(),
+            pt::Expression::FunctionCall(_, func, args) if args.is_empty() => {
+                let storages = self.storages;
+                match &**func {
+                    pt::Expression::MemberAccess(_, base, member) => {
+                        let pt::Expression::Variable(library) = &**base else {
+                            return None;
+                        };
+                        storages.iter()
+                            .find(|s| s.library == library.name && s.accessor == member.name)
+                            .map(|s| s.key.as_str())
+                    }
+                    pt::Expression::Variable(id) => storages.iter()
+                        .find(|s| s.library == self.contract && s.accessor == id.name)
+                        .map(|s| s.key.as_str()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The shared storage field an access path starts with (`ds.owners[x].y` -> `Lib.S.owners`)
+    fn field(&self, expr: &pt::Expression) -> Option<String> {
+        match expr.strip_parentheses() {
+            pt::Expression::MemberAccess(_, base, member) => match self.root(base) {
+                Some(key) => Some(format!("{}.{}", key, member.name)),
+                None => self.field(base),
+            },
+            pt::Expression::ArraySubscript(_, base, _) | pt::Expression::ArraySlice(_, base, _, _) => self.field(base),
+            _ => None,
+        }
+    }
+
+    fn walk_statement(&mut self, stmt: &pt::Statement) {
+        match stmt {
+            pt::Statement::Block { statements, .. } => {
+                for s in statements {
+                    self.walk_statement(s);
+                }
+            }
+            pt::Statement::VariableDefinition(_, decl, init) => {
+                if let Some(init) = init {
+                    self.walk_expression(init);
+                }
+                self.bind(&decl.ty, decl.name.as_ref());
+            }
+            pt::Statement::Expression(_, expr) | pt::Statement::Emit(_, expr) => self.walk_expression(expr),
+            pt::Statement::Return(_, Some(expr)) => self.walk_expression(expr),
+            pt::Statement::Revert(_, _, args) => {
+                for arg in args {
+                    self.walk_expression(arg);
+                }
+            }
+            pt::Statement::RevertNamedArgs(_, _, args) => {
+                for arg in args {
+                    self.walk_expression(&arg.expr);
+                }
+            }
+            pt::Statement::If(_, cond, then, otherwise) => {
+                self.walk_expression(cond);
+                self.walk_statement(then);
+                if let Some(otherwise) = otherwise {
+                    self.walk_statement(otherwise);
+                }
+            }
+            pt::Statement::While(_, cond, body) | pt::Statement::DoWhile(_, body, cond) => {
+                self.walk_expression(cond);
+                self.walk_statement(body);
+            }
+            pt::Statement::For(_, init, cond, update, body) => {
+                if let Some(init) = init {
+                    self.walk_statement(init);
+                }
+                for expr in [cond, update].into_iter().flatten() {
+                    self.walk_expression(expr);
+                }
+                if let Some(body) = body {
+                    self.walk_statement(body);
+                }
+            }
+            pt::Statement::Try(_, expr, returns, catches) => {
+                let (call, success) = StateModificationAnalyzer::try_call(expr, returns);
+                self.walk_expression(call);
+                if let Some(body) = success {
+                    self.walk_statement(body);
+                }
+                for clause in catches {
+                    match clause {
+                        pt::CatchClause::Simple(_, _, body) | pt::CatchClause::Named(_, _, _, body) => self.walk_statement(body),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn walk_expression(&mut self, expr: &pt::Expression) {
+        match expr {
+            pt::Expression::Assign(_, target, value) => {
+                self.walk_target(target);
+                self.walk_expression(value);
+            }
+            // Compound assignments and `++`/`--` read and write their target
+            pt::Expression::AssignAdd(_, target, value)
+            | pt::Expression::AssignSubtract(_, target, value)
+            | pt::Expression::AssignMultiply(_, target, value)
+            | pt::Expression::AssignDivide(_, target, value)
+            | pt::Expression::AssignModulo(_, target, value)
+            | pt::Expression::AssignOr(_, target, value)
+            | pt::Expression::AssignAnd(_, target, value)
+            | pt::Expression::AssignXor(_, target, value)
+            | pt::Expression::AssignShiftLeft(_, target, value)
+            | pt::Expression::AssignShiftRight(_, target, value) => {
+                self.walk_target(target);
+                self.walk_expression(target);
+                self.walk_expression(value);
+            }
+            pt::Expression::PreIncrement(_, target)
+            | pt::Expression::PostIncrement(_, target)
+            | pt::Expression::PreDecrement(_, target)
+            | pt::Expression::PostDecrement(_, target) => {
+                self.walk_target(target);
+                self.walk_expression(target);
+            }
+            pt::Expression::Delete(_, target) => self.walk_target(target),
+            pt::Expression::FunctionCall(_, func, args) => {
+                // `ds.list.push(x)` and `ds.list.pop()` resize the array
+                if let pt::Expression::MemberAccess(_, base, member) = &**func {
+                    if matches!(member.name.as_str(), "push" | "pop") {
+                        self.walk_target(base);
+                    }
+                }
+                self.walk_expression(func);
+                for arg in args {
+                    self.walk_expression(arg);
+                }
+            }
+            pt::Expression::FunctionCallBlock(_, func, _) => self.walk_expression(func),
+            pt::Expression::NamedFunctionCall(_, func, args) => {
+                self.walk_expression(func);
+                for arg in args {
+                    self.walk_expression(&arg.expr);
+                }
+            }
+            pt::Expression::MemberAccess(_, base, _) => {
+                match self.field(expr) {
+                    Some(field) => {
+                        self.access.reads.insert(field);
+                    }
+                    None => self.walk_expression(base),
+                }
+                self.walk_indices(expr);
+            }
+            pt::Expression::ArraySubscript(..) | pt::Expression::ArraySlice(..) => {
+                match self.field(expr) {
+                    Some(field) => {
+                        self.access.reads.insert(field);
+                    }
+                    None => {
+                        if let pt::Expression::ArraySubscript(_, base, _) | pt::Expression::ArraySlice(_, base, _, _) = expr {
+                            self.walk_expression(base);
+                        }
+                    }
+                }
+                self.walk_indices(expr);
+            }
+            pt::Expression::ConditionalOperator(_, cond, left, right) => {
+                for operand in [cond, left, right] {
+                    self.walk_expression(operand);
+                }
+            }
+            pt::Expression::ArrayLiteral(_, items) => {
+                for item in items {
+                    self.walk_expression(item);
+                }
+            }
+            pt::Expression::List(_, params) => {
+                for (_, param) in params {
+                    if let Some(param) = param {
+                        self.walk_expression(&param.ty);
+                    }
+                }
+            }
+            _ => {
+                let (left, right) = expr.components();
+                for operand in [left, right].into_iter().flatten() {
+                    self.walk_expression(operand);
+                }
+            }
+        }
+    }
+
+    /// Record a write to the field an assignment target names
+    fn walk_target(&mut self, target: &pt::Expression) {
+        if let pt::Expression::List(_, params) = target {
+            for (_, param) in params {
+                if let Some(param) = param {
+                    self.walk_target(&param.ty);
+                }
+            }
+            return;
+        }
+        if let Some(field) = self.field(target) {
+            self.access.writes.insert(field);
+        }
+        self.walk_indices(target);
+    }
+
+    /// Index expressions along an access path are reads (`ds.balances[ds.owner]`)
+    fn walk_indices(&mut self, expr: &pt::Expression) {
+        match expr.strip_parentheses() {
+            pt::Expression::MemberAccess(_, base, _) => self.walk_indices(base),
+            pt::Expression::ArraySubscript(_, base, index) => {
+                if let Some(index) = index {
+                    self.walk_expression(index);
+                }
+                self.walk_indices(base);
+            }
+            pt::Expression::ArraySlice(_, base, from, to) => {
+                for bound in [from, to].into_iter().flatten() {
+                    self.walk_expression(bound);
+                }
+                self.walk_indices(base);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl DiamondAnalyzer {
+    /// Build the combined view of every facet sharing Diamond storage; marks contracts whose
+    /// fallback dispatches through Diamond storage as diamonds. None if the project has no
+    /// Diamond storage.
+    pub fn build(contracts: &mut [ContractInfo], contract_asts: &[pt::ContractDefinition]) -> Option<DiamondView> {
+        let storages: Vec<SharedStorage> = contracts.iter()
+            .flat_map(|c| c.diamond_storage.iter().map(|s| SharedStorage {
+                key: format!("{}.{}", c.name, s.storage_struct),
+                library: c.name.clone(),
+                storage_struct: s.storage_struct.clone(),
+                accessor: s.accessor_function.clone(),
+            }))
+            .collect();
+        if storages.is_empty() {
+            return None;
+        }
+
+        // Direct accesses of every function (by "Contract.signature") and modifier
+        let mut direct: HashMap<String, Access> = HashMap::new();
+        let mut modifiers: HashMap<String, Access> = HashMap::new();
+        for (contract, ast) in contracts.iter().zip(contract_asts) {
+            for part in &ast.parts {
+                let pt::ContractPart::FunctionDefinition(f) = part else {
+                    continue;
+                };
+                let Some(body) = &f.body else {
+                    continue;
+                };
+
+                let mut scope = Scope {
+                    storages: &storages,
+                    contract: &contract.name,
+                    locals: HashMap::new(),
+                    access: Access::default(),
+                };
+                for param in f.params.iter().chain(f.returns.iter()).filter_map(|(_, p)| p.as_ref()) {
+                    scope.bind(&param.ty, param.name.as_ref());
+                }
+                scope.walk_statement(body);
+
+                if matches!(f.ty, pt::FunctionTy::Modifier) {
+                    let name = f.name.as_ref().map(|n| n.name.clone()).unwrap_or_default();
+                    modifiers.insert(format!("{}.{}", contract.name, name), scope.access);
+                } else {
+                    direct.insert(format!("{}.{}", contract.name, SolidityParser::function_signature(f)), scope.access);
+                }
+            }
+        }
+
+        let contracts_snapshot: &[ContractInfo] = contracts;
+        let access_of = |contract: &ContractInfo, func: &FunctionDef| {
+            let mut access = Access::default();
+            Self::collect_access(contract, func, contracts_snapshot, &direct, &modifiers, &mut HashSet::new(), &mut access);
+            access
+        };
+
+        // Diamonds: delegating fallbacks that read Diamond storage to pick the facet
+        let mut diamonds = Vec::new();
+        for contract in contracts_snapshot {
+            let Some(fallback) = contract.functions.iter().find(|f| f.name == "fallback") else {
+                continue;
+            };
+            if contract.proxy.as_ref().is_some_and(|p| p.kind.delegates()) && !access_of(contract, fallback).is_empty() {
+                diamonds.push(contract.name.clone());
+            }
+        }
+
+        let mut facets = Vec::new();
+        let mut selectors = Vec::new();
+        for contract in contracts_snapshot {
+            if contract.kind != ContractKind::Contract || contract.is_abstract || contract.is_context_only {
+                continue;
+            }
+            let is_diamond = diamonds.contains(&contract.name);
+
+            let mut exposed = Vec::new();
+            for func in &contract.functions {
+                let Some(selector) = &func.selector else {
+                    continue;
+                };
+                if !matches!(func.visibility.as_str(), "public" | "external") {
+                    continue;
+                }
+                let access = access_of(contract, func);
+                exposed.push(DiamondSelector {
+                    selector: selector.clone(),
+                    facet: contract.name.clone(),
+                    signature: func.signature.clone(),
+                    reads: access.reads.into_iter().collect(),
+                    writes: access.writes.into_iter().collect(),
+                });
+            }
+
+            let shares_storage = exposed.iter().any(|s| !s.reads.is_empty() || !s.writes.is_empty());
+            if is_diamond || shares_storage || contract.name.ends_with("Facet") {
+                if !is_diamond {
+                    facets.push(contract.name.clone());
+                }
+                selectors.extend(exposed);
+            }
+        }
+        selectors.sort_by(|a, b| (&a.selector, &a.facet).cmp(&(&b.selector, &b.facet)));
+
+        let mut by_selector: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for entry in &selectors {
+            by_selector.entry(entry.selector.as_str())
+                .or_default()
+                .push(format!("{}.{}", entry.facet, entry.signature));
+        }
+        let clashes = by_selector.into_iter()
+            .filter(|(_, functions)| functions.len() > 1)
+            .map(|(selector, functions)| SelectorClash { selector: selector.to_string(), functions })
+            .collect();
+
+        for contract in contracts.iter_mut().filter(|c| diamonds.contains(&c.name)) {
+            if let Some(proxy) = &mut contract.proxy {
+                proxy.kind = ProxyKind::Diamond;
+                proxy.evidence.push("fallback looks up the facet in Diamond storage".to_string());
+            }
+        }
+
+        Some(DiamondView {
+            diamonds,
+            storages: storages.into_iter().map(|s| s.key).collect(),
+            facets,
+            selectors,
+            clashes,
+        })
+    }
+
+    /// Accesses of a function, its modifiers and every internal or library function it reaches
+    fn collect_access(
+        contract: &ContractInfo,
+        func: &FunctionDef,
+        contracts: &[ContractInfo],
+        direct: &HashMap<String, Access>,
+        modifiers: &HashMap<String, Access>,
+        visited: &mut HashSet<String>,
+        access: &mut Access,
+    ) {
+        let key = format!("{}.{}", contract.name, func.signature);
+        if !visited.insert(key.clone()) {
+            return;
+        }
+
+        if let Some(own) = direct.get(&key) {
+            access.merge(own);
+        }
+        for modifier in &func.uses_modifiers {
+            if let Some(modifier_access) = modifiers.get(&format!("{}.{}", contract.name, modifier)) {
+                access.merge(modifier_access);
+            }
+        }
+
+        let callees = func.calls_functions.iter()
+            .chain(func.modifier_effects.iter().flat_map(|e| e.calls_functions.iter()));
+        for callee in callees {
+            // Internal library calls are recorded as "Library.signature"
+            let (owner, signature) = match callee.split_once('.') {
+                Some((library, signature)) if !library.contains('(') => (library, signature),
+                _ => (contract.name.as_str(), callee.as_str()),
+            };
+            let Some(owner) = contracts.iter().find(|c| c.name == owner) else {
+                continue;
+            };
+            if let Some(target) = owner.functions.iter().find(|f| f.signature == signature) {
+                Self::collect_access(owner, target, contracts, direct, modifiers, visited, access);
+            }
+        }
+    }
+
+    pub fn print_summary(view: &DiamondView) {
+        println!();
+        println!("{}", "💎 Diamond (EIP-2535)".bold());
+        if !view.diamonds.is_empty() {
+            println!("  Diamond: {}", view.diamonds.join(", "));
+        }
+        println!("  Shared storage: {}", view.storages.join(", "));
+        println!("  {} facet(s), {} selector(s)", view.facets.len(), view.selectors.len());
+        for clash in &view.clashes {
+            println!("  {} selector {} clashes: {}", "⚠️".yellow(), clash.selector, clash.functions.join(", "));
+        }
+    }
+
+    /// Write `diamond.md` and `diamond.json`
+    pub fn save_reports(view: &DiamondView, output_dir: &Path) -> Result<()> {
+        fs::create_dir_all(output_dir)?;
+
+        fs::write(output_dir.join("diamond.json"), serde_json::to_string_pretty(&json!(view))?)?;
+        println!("  {} diamond.json", "✓".green());
+
+        fs::write(output_dir.join("diamond.md"), Self::generate_markdown(view))?;
+        println!("  {} diamond.md", "✓".green());
+        Ok(())
+    }
+
+    fn generate_markdown(view: &DiamondView) -> String {
+        let mut md = String::new();
+        let code = |items: &[String]| items.iter().map(|i| format!("`{}`", i)).collect::<Vec<_>>().join(", ");
+
+        md.push_str("# Diamond View (EIP-2535)\n\n");
+        if !view.diamonds.is_empty() {
+            md.push_str(&format!("**Diamond:** {}\n", code(&view.diamonds)));
+        }
+        md.push_str(&format!("**Shared Storage:** {}\n", code(&view.storages)));
+        md.push_str(&format!("**Facets:** {}\n\n", code(&view.facets)));
+
+        if view.clashes.is_empty() {
+            md.push_str("✅ **No selector clashes**\n\n");
+        } else {
+            md.push_str(&format!("❌ **{} selector clash(es)**\n\n", view.clashes.len()));
+            for clash in &view.clashes {
+                md.push_str(&format!("- `{}`: {}\n", clash.selector, code(&clash.functions)));
+            }
+            md.push('\n');
+        }
+
+        md.push_str("| Selector | Facet | Function | Reads | Writes |\n");
+        md.push_str("|----------|-------|----------|-------|--------|\n");
+        for entry in &view.selectors {
+            let clashes = view.clashes.iter().any(|c| c.selector == entry.selector);
+            md.push_str(&format!("| `{}`{} | `{}` | `{}` | {} | {} |\n",
+                entry.selector,
+                if clashes { " ⚠️" } else { "" },
+                entry.facet,
+                entry.signature,
+                code(&entry.reads),
+                code(&entry.writes),
+            ));
+        }
+
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::analyze;
+
+    fn view(facets: &str) -> DiamondView {
+        let project = analyze(&format!(r#"
+            library LibToken {{
+                bytes32 constant STORAGE_POSITION = keccak256("token.storage");
+                struct TokenStorage {{ uint256 supply; mapping(address => uint256) balances; }}
+                function tokenStorage() internal pure returns (TokenStorage storage ts) {{
+                    bytes32 position = STORAGE_POSITION;
+                    assembly {{ ts.slot := position }}
+                }}
+            }}
+            {}
+        "#, facets));
+        project.diamond_view.expect("no Diamond storage found")
+    }
+
+    #[test]
+    fn facets_exposing_the_same_selector_clash() {
+        // `burn(uint256)` and `collate_propagate_storage(bytes16)` both hash to 0x42966c68
+        let view = view(r#"
+            contract BurnFacet {
+                function burn(uint256 amount) external { LibToken.tokenStorage().supply -= amount; }
+            }
+            contract StorageFacet {
+                function collate_propagate_storage(bytes16 key) external view returns (uint256) {
+                    return LibToken.tokenStorage().supply;
+                }
+            }
+        "#);
+        assert_eq!(view.facets, ["BurnFacet", "StorageFacet"]);
+        assert_eq!(view.clashes.len(), 1);
+        assert_eq!(view.clashes[0].selector, "0x42966c68");
+        assert_eq!(view.clashes[0].functions, ["BurnFacet.burn(uint256)", "StorageFacet.collate_propagate_storage(bytes16)"]);
+    }
+
+    #[test]
+    fn facets_with_distinct_selectors_do_not_clash() {
+        let view = view(r#"
+            contract BurnFacet {
+                function burn(uint256 amount) external { LibToken.tokenStorage().supply -= amount; }
+            }
+            contract BalanceFacet {
+                function balanceOf(address account) external view returns (uint256) {
+                    return LibToken.tokenStorage().balances[account];
+                }
+            }
+        "#);
+        assert_eq!(view.selectors.len(), 2);
+        assert!(view.clashes.is_empty(), "{:?}", view.clashes);
+        assert_eq!(view.selectors.iter().find(|s| s.facet == "BalanceFacet").unwrap().reads, ["LibToken.TokenStorage.balances"]);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::abi::AbiGenerator;
+use crate::models::*;
+use std::collections::BTreeMap;
+
+/// Checks ERC-7201 namespaced storage: slot constants against the formula, and namespaces
+/// shared by several structs
+pub struct Erc7201Verifier;
+
+impl Erc7201Verifier {
+    /// `keccak256(abi.encode(uint256(keccak256(id)) - 1)) & ~bytes32(uint256(0xff))`
+    pub fn namespace_slot(namespace: &str) -> String {
+        let mut inner = AbiGenerator::keccak256(namespace.as_bytes());
+
+        // Subtract 1 from the big-endian 256-bit value
+        for byte in inner.iter_mut().rev() {
+            let (value, borrow) = byte.overflowing_sub(1);
+            *byte = value;
+            if !borrow {
+                break;
+            }
+        }
+
+        let mut slot = AbiGenerator::keccak256(&inner);
+        slot[31] = 0;
+        format!("0x{}", slot.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+    }
+
+    /// Fill `expected_slot` and `issues` of every namespaced struct in the project
+    pub fn verify(contracts: &mut [ContractInfo]) {
+        // (formula, namespace) -> "Contract.Struct" declaring it
+        let mut declarations: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+        for contract in contracts.iter() {
+            for storage in contract.upgradeable_storage.iter().filter(|s| !s.namespace.is_empty()) {
+                declarations.entry((storage.formula.clone(), storage.namespace.clone()))
+                    .or_default()
+                    .push(format!("{}.{}", contract.name, storage.storage_struct));
+            }
+        }
+
+        for contract in contracts.iter_mut() {
+            for storage in &mut contract.upgradeable_storage {
+                if storage.namespace.is_empty() {
+                    continue;
+                }
+
+                // Other formulas derive the slot differently; only the namespace is checked
+                if storage.formula == "erc7201" {
+                    Self::check_slot(storage);
+                }
+
+                let own = format!("{}.{}", contract.name, storage.storage_struct);
+                let others: Vec<String> = declarations.get(&(storage.formula.clone(), storage.namespace.clone()))
+                    .into_iter()
+                    .flatten()
+                    .filter(|d| **d != own)
+                    .map(|d| format!("`{}`", d))
+                    .collect();
+                if !others.is_empty() {
+                    storage.issues.push(format!(
+                        "namespace `{}` is also used by {}; the structs share the same slots",
+                        storage.namespace,
+                        others.join(", ")
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Compare the slot constant of an `erc7201` namespace against the formula
+    fn check_slot(storage: &mut UpgradeableStorage) {
+        let expected = Self::namespace_slot(&storage.namespace);
+        if storage.storage_location_constant.is_empty() {
+            storage.issues.push("no bytes32 slot constant found for this namespace".to_string());
+        } else if storage.storage_slot.is_empty() {
+            storage.issues.push(format!(
+                "value of `{}` could not be evaluated; slot not verified",
+                storage.storage_location_constant
+            ));
+        } else if Self::normalize(&storage.storage_slot) != Self::normalize(&expected) {
+            storage.issues.push(format!(
+                "`{}` is {} but namespace `{}` gives {}",
+                storage.storage_location_constant, storage.storage_slot, storage.namespace, expected
+            ));
+        }
+        storage.expected_slot = Some(expected);
+    }
+
+    /// Lowercase hex without prefix, separators or leading zeros
+    fn normalize(value: &str) -> String {
+        let value = value.trim().to_lowercase().replace('_', "");
+        let digits = value.strip_prefix("0x").unwrap_or(&value);
+        digits.trim_start_matches('0').to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+
+    const EXAMPLE_MAIN: &str = "0x183a6125c38840424c4a85fa12bab2ab606c4b6d0e7cc73c0c06ba5300eab500";
+
+    fn namespaced(slot_expression: &str) -> UpgradeableStorage {
+        located("erc7201:example.main", slot_expression)
+    }
+
+    fn located(location: &str, slot_expression: &str) -> UpgradeableStorage {
+        let project = analyze(&format!(r#"
+            contract Main {{
+                /// @custom:storage-location {}
+                struct MainStorage {{ uint256 x; }}
+
+                bytes32 private constant MAIN_STORAGE_LOCATION = {};
+
+                function _getMainStorage() private pure returns (MainStorage storage $) {{
+                    assembly {{ $.slot := MAIN_STORAGE_LOCATION }}
+                }}
+            }}
+        "#, location, slot_expression));
+        contract(&project, "Main").upgradeable_storage[0].clone()
+    }
+
+    #[test]
+    fn formula_matches_the_eip_examples() {
+        assert_eq!(Erc7201Verifier::namespace_slot("example.main"), EXAMPLE_MAIN);
+        assert_eq!(Erc7201Verifier::namespace_slot("openzeppelin.storage.ERC20"),
+            "0x52c63247e1f47db19d5ce0460030c497f067ca4cebf71ba98eeadabe20bace00");
+    }
+
+    #[test]
+    fn formula_written_in_solidity_is_evaluated() {
+        let storage = namespaced(r#"keccak256(abi.encode(uint256(keccak256("example.main")) - 1)) & ~bytes32(uint256(0xff))"#);
+        assert_eq!(storage.storage_slot, EXAMPLE_MAIN);
+        assert!(storage.issues.is_empty(), "{:?}", storage.issues);
+    }
+
+    #[test]
+    fn literal_slot_is_checked() {
+        assert!(namespaced(EXAMPLE_MAIN).issues.is_empty());
+        let storage = namespaced("0x183a6125c38840424c4a85fa12bab2ab606c4b6d0e7cc73c0c06ba5300eab600");
+        assert!(storage.issues[0].contains("but namespace `example.main` gives"), "{:?}", storage.issues);
+    }
+
+    #[test]
+    fn variations_of_the_formula_are_not_assumed_correct() {
+        let storage = namespaced(r#"keccak256(abi.encode(uint256(keccak256("example.main")) - 2)) & ~bytes32(uint256(0xff))"#);
+        assert_ne!(storage.storage_slot, EXAMPLE_MAIN);
+        assert!(storage.issues[0].contains("but namespace `example.main` gives"), "{:?}", storage.issues);
+
+        let storage = namespaced(r#"keccak256(abi.encode(uint256(keccak256("example.main")) - 1))"#);
+        assert!(storage.issues[0].contains("but namespace `example.main` gives"), "{:?}", storage.issues);
+    }
+
+    #[test]
+    fn slot_that_cannot_be_evaluated_is_left_empty() {
+        let storage = namespaced(r#"keccak256(abi.encode(uint256(keccak256("example.main")) - 1, "dynamic"))"#);
+        assert_eq!(storage.storage_slot, "");
+        assert!(storage.issues[0].contains("could not be evaluated"), "{:?}", storage.issues);
+    }
+
+    #[test]
+    fn other_formulas_are_not_checked_against_erc7201() {
+        let storage = located("custom:example.main", r#"keccak256("example.main")"#);
+        assert_eq!(storage.formula, "custom");
+        assert_eq!(storage.expected_slot, None);
+        assert!(storage.issues.is_empty(), "{:?}", storage.issues);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::models::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
@@ -36,21 +36,32 @@ impl GraphGenerator {
                     _ => "white",
                 };
 
-                let node_id = format!("{}_{}", contract.name, func.name);
+                let node_id = format!("{}_{}", contract.name, func.signature);
                 dot.push_str(&format!("    \"{}\" [label=\"{}\", fillcolor={}];\n",
-                    node_id, func.name, color));
+                    node_id, func.signature, color));
             }
 
             dot.push('\n');
 
             // Add edges for internal calls
             for func in &contract.functions {
-                let from_id = format!("{}_{}", contract.name, func.name);
+                let from_id = format!("{}_{}", contract.name, func.signature);
 
                 for called_func in &func.calls_functions {
-                    let to_id = format!("{}_{}", contract.name, called_func);
-                    dot.push_str(&format!("    \"{}\" -> \"{}\" [color=blue, label=\"internal\"];\n",
-                        from_id, to_id));
+                    let is_member = contract.functions.iter().any(|f| &f.signature == called_func);
+                    match called_func.split_once('.') {
+                        // Internal library function, "Library.fn(...)"
+                        Some((library, library_func)) if !is_member => {
+                            let to_id = format!("{}_{}", library, library_func);
+                            dot.push_str(&format!("    \"{}\" -> \"{}\" [color=blue, style=dashed, label=\"library\"];\n",
+                                from_id, to_id));
+                        }
+                        _ => {
+                            let to_id = format!("{}_{}", contract.name, called_func);
+                            dot.push_str(&format!("    \"{}\" -> \"{}\" [color=blue, label=\"internal\"];\n",
+                                from_id, to_id));
+                        }
+                    }
                 }
             }
 
@@ -60,8 +71,8 @@ impl GraphGenerator {
             for func in &contract.functions {
                 for ext_call in &func.external_calls {
                     if let Some(ref target_contract) = ext_call.target_contract {
-                        let from_id = format!("{}_{}", contract.name, func.name);
-                        let to_id = format!("{}_{}", target_contract, ext_call.target_function);
+                        let from_id = format!("{}_{}", contract.name, func.signature);
+                        let to_id = format!("{}_{}", target_contract, ext_call.target_display());
 
                         let color = if ext_call.state_mutability == "payable" {
                             "red"
@@ -137,7 +148,7 @@ impl GraphGenerator {
             dot.push_str("      rank=same;\n");
             dot.push_str("      // Functions\n");
             for func in &contract.functions {
-                let node_id = format!("{}_{}", contract.name, func.name);
+                let node_id = format!("{}_{}", contract.name, func.signature);
                 let color = match func.visibility.as_str() {
                     "external" => "lightgreen",
                     "public" => "palegreen",
@@ -147,7 +158,7 @@ impl GraphGenerator {
                 };
 
                 dot.push_str(&format!("      \"{}\" [label=\"{}\", shape=box, style=\"rounded,filled\", fillcolor={}];\n",
-                    node_id, func.name, color));
+                    node_id, func.signature, color));
             }
             dot.push_str("    }\n");
 
@@ -227,63 +238,29 @@ impl GraphGenerator {
 
         dot.push('\n');
 
-        // Build interface to implementation mapping
-        let mut interface_map: HashMap<String, String> = HashMap::new();
-        for contract in contracts {
-            // Check contract name patterns - if a contract implements an interface,
-            // we can infer it from common patterns or explicit inheritance
-            // For now, we'll check if contract name contains the interface name without "I"
-            for other_contract in contracts {
-                if other_contract.name != contract.name {
-                    // Check if contract implements interface (simple heuristic)
-                    // E.g., GuaranteedMinimumPayoutCalculator implements IPayoutCalculator
-                    let interface_candidates = vec![
-                        format!("I{}", contract.name), // IJackpot
-                        format!("I{}", contract.name.replace("Guaranteed", "").replace("Minimum", "")), // Various patterns
-                    ];
-
-                    for candidate in interface_candidates {
-                        if other_contract.name.contains(candidate.trim_start_matches('I')) {
-                            interface_map.insert(candidate, contract.name.clone());
-                        }
-                    }
-                }
-            }
-        }
-
         // Track interactions between contracts
-        let mut interactions: HashMap<(String, String), Vec<String>> = HashMap::new();
+        let mut interactions: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
 
         for contract in contracts {
             for func in &contract.functions {
                 for ext_call in &func.external_calls {
-                    // Try to resolve target contract
-                    let target = if let Some(ref target_contract) = ext_call.target_contract {
-                        // Already resolved
-                        target_contract.clone()
+                    // Calls through an ambiguous interface get an edge to every implementer;
+                    // unresolved types are shown under their declared type name
+                    let targets = if let Some(ref target_contract) = ext_call.target_contract {
+                        vec![target_contract.clone()]
+                    } else if !ext_call.candidate_contracts.is_empty() {
+                        ext_call.candidate_contracts.clone()
                     } else {
-                        // Not resolved - try to infer from interface type
-                        // Strip "I" prefix from interface name and search for implementation
-                        let interface_name = ext_call.target_type.trim_start_matches('I');
-                        let mut found_impl = None;
-
-                        for impl_contract in contracts {
-                            // Check if contract name contains the interface name (case-insensitive)
-                            // E.g., "GuaranteedMinimumPayoutCalculator" contains "PayoutCalculator"
-                            if impl_contract.name.to_lowercase().contains(&interface_name.to_lowercase()) {
-                                found_impl = Some(impl_contract.name.clone());
-                                break;
-                            }
-                        }
-
-                        found_impl.unwrap_or_else(|| ext_call.target_type.clone())
+                        vec![ext_call.target_type.clone()]
                     };
 
-                    if target != contract.name {
-                        let key = (contract.name.clone(), target.clone());
-                        interactions.entry(key)
-                            .or_default()
-                            .push(ext_call.target_function.clone());
+                    for target in targets {
+                        if target != contract.name {
+                            let key = (contract.name.clone(), target);
+                            interactions.entry(key)
+                                .or_default()
+                                .push(ext_call.target_function.clone());
+                        }
                     }
                 }
             }
@@ -310,7 +287,7 @@ impl GraphGenerator {
         let mut graph = HashMap::new();
         for func in functions {
             if !func.calls_functions.is_empty() {
-                graph.insert(func.name.clone(), func.calls_functions.clone());
+                graph.insert(func.signature.clone(), func.calls_functions.clone());
             }
         }
         graph
@@ -327,14 +304,14 @@ impl GraphGenerator {
         for func in functions {
             if func.modifies_states.contains(&var_name.to_string())
                 || func.modifies_state_fields.iter().any(|f| f.starts_with(&format!("{}.", var_name))) {
-                direct.push(func.name.clone());
+                direct.push(func.signature.clone());
             }
         }
 
         for func in functions {
-            if !direct.contains(&func.name) {
-                if let Some(chain) = Self::find_call_chain_to_targets(&func.name, &direct, call_graph) {
-                    indirect.push((func.name.clone(), chain));
+            if !direct.contains(&func.signature) {
+                if let Some(chain) = Self::find_call_chain_to_targets(&func.signature, &direct, call_graph) {
+                    indirect.push((func.signature.clone(), chain));
                 }
             }
         }
@@ -352,14 +329,14 @@ impl GraphGenerator {
 
         for func in functions {
             if func.reads_states.contains(&var_name.to_string()) {
-                direct.push(func.name.clone());
+                direct.push(func.signature.clone());
             }
         }
 
         for func in functions {
-            if !direct.contains(&func.name) {
-                if let Some(chain) = Self::find_call_chain_to_targets(&func.name, &direct, call_graph) {
-                    indirect.push((func.name.clone(), chain));
+            if !direct.contains(&func.signature) {
+                if let Some(chain) = Self::find_call_chain_to_targets(&func.signature, &direct, call_graph) {
+                    indirect.push((func.signature.clone(), chain));
                 }
             }
         }
@@ -450,7 +427,7 @@ impl GraphGenerator {
                         // Create edges for state variables that the external function modifies
                         for modified_var in &ext_call.target_modifies_states {
                             let target_node_id = format!("{}_{}", target_contract_name, modified_var);
-                            let source_label = format!("{}.{}", contract.name, func.name);
+                            let source_label = format!("{}.{}", contract.name, func.signature);
 
                             dot.push_str(&format!(
                                 "  \"{}\" [label=\"{}\", shape=ellipse, style=filled, fillcolor=lightgreen];\n",
@@ -468,7 +445,7 @@ impl GraphGenerator {
                         // Create edges for state variables that the external function reads
                         for read_var in &ext_call.target_reads_states {
                             let target_node_id = format!("{}_{}", target_contract_name, read_var);
-                            let source_label = format!("{}.{}", contract.name, func.name);
+                            let source_label = format!("{}.{}", contract.name, func.signature);
 
                             dot.push_str(&format!(
                                 "  \"{}\" [label=\"{}\", shape=ellipse, style=filled, fillcolor=lightgreen];\n",
@@ -0,0 +1,272 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A solc import remapping: `[context:]prefix=target`
+#[derive(Debug, Clone, Serialize)]
+pub struct Remapping {
+    pub context: Option<String>, // Only applies to files under this path (relative to the project root)
+    pub prefix: String,
+    pub target: String,          // Relative to the project root
+}
+
+impl Remapping {
+    pub fn parse(line: &str) -> Option<Remapping> {
+        let (lhs, target) = line.trim().split_once('=')?;
+        let (context, prefix) = match lhs.split_once(':') {
+            Some((context, prefix)) => (Some(context.to_string()).filter(|c| !c.is_empty()), prefix),
+            None => (None, lhs),
+        };
+        if prefix.is_empty() {
+            return None;
+        }
+        Some(Remapping {
+            context,
+            prefix: prefix.to_string(),
+            target: target.trim().to_string(),
+        })
+    }
+}
+
+/// An import that could not be mapped to a file
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportDiagnostic {
+    pub file: String,        // Importing file
+    pub import_path: String, // Path as written in the import directive
+    pub line_number: usize,
+    pub message: String,
+}
+
+/// File import graph with unresolved imports
+#[derive(Debug, Default, Serialize)]
+pub struct ImportGraph {
+    pub root: String,                          // Project root imports are resolved against
+    pub remappings: Vec<Remapping>,
+    pub edges: BTreeMap<String, Vec<String>>, // File -> files it imports
+    pub context_only: Vec<String>,             // Dependencies loaded only to resolve names
+    pub unresolved: Vec<ImportDiagnostic>,
+}
+
+/// Resolves import paths the way solc does with Foundry or Hardhat projects:
+/// relative imports, then remappings (longest prefix wins), then the project root,
+/// `node_modules` (searched upwards from the importing file) and Foundry `libs` directories.
+pub struct ImportResolver {
+    root: PathBuf,
+    remappings: Vec<Remapping>,
+    lib_dirs: Vec<PathBuf>,
+}
+
+// Files whose presence marks the root of a Foundry or Hardhat project
+const PROJECT_MARKERS: &[&str] = &[
+    "foundry.toml",
+    "remappings.txt",
+    "hardhat.config.js",
+    "hardhat.config.ts",
+    "package.json",
+];
+
+impl ImportResolver {
+    /// Find the project root above the scanned path and load its remappings
+    pub fn discover(scan_path: &Path) -> ImportResolver {
+        let start = fs::canonicalize(scan_path).unwrap_or_else(|_| scan_path.to_path_buf());
+        let root = start.ancestors()
+            .find(|dir| PROJECT_MARKERS.iter().any(|marker| dir.join(marker).is_file()))
+            .map(Path::to_path_buf)
+            .unwrap_or(start);
+
+        let mut remappings = Vec::new();
+        let mut lib_names = vec!["lib".to_string()];
+
+        if let Ok(content) = fs::read_to_string(root.join("foundry.toml")) {
+            if let Ok(config) = content.parse::<toml::Table>() {
+                let profile = config.get("profile")
+                    .and_then(|p| p.get("default"))
+                    .and_then(|p| p.as_table());
+                let strings = |key: &str| -> Vec<String> {
+                    profile.and_then(|p| p.get(key))
+                        .and_then(|v| v.as_array())
+                        .map(|items| items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+                        .unwrap_or_default()
+                };
+                remappings.extend(strings("remappings").iter().filter_map(|r| Remapping::parse(r)));
+                let libs = strings("libs");
+                if !libs.is_empty() {
+                    lib_names = libs;
+                }
+            }
+        }
+
+        // remappings.txt takes precedence over foundry.toml, as in forge
+        if let Ok(content) = fs::read_to_string(root.join("remappings.txt")) {
+            let from_file: Vec<Remapping> = content.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .filter_map(Remapping::parse)
+                .collect();
+            remappings.retain(|r| !from_file.iter().any(|f| f.prefix == r.prefix && f.context == r.context));
+            remappings.extend(from_file);
+        }
+
+        let lib_dirs = lib_names.iter().map(|l| root.join(l)).collect();
+
+        ImportResolver { root, remappings, lib_dirs }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn remappings(&self) -> &[Remapping] {
+        &self.remappings
+    }
+
+    /// Resolve an import directive of `importing_file` to an existing file
+    pub fn resolve(&self, importing_file: &Path, import_path: &str) -> Option<PathBuf> {
+        let importing_file = fs::canonicalize(importing_file).unwrap_or_else(|_| importing_file.to_path_buf());
+
+        if import_path.starts_with("./") || import_path.starts_with("../") {
+            let dir = importing_file.parent()?;
+            return Self::existing(&dir.join(import_path));
+        }
+
+        if let Some(remapped) = self.remap(&importing_file, import_path) {
+            if let Some(found) = Self::existing(&self.root.join(remapped)) {
+                return Some(found);
+            }
+        }
+
+        if let Some(found) = Self::existing(&self.root.join(import_path)) {
+            return Some(found);
+        }
+
+        // Hardhat: node_modules of the importing package or any parent directory
+        let from_node_modules = importing_file.ancestors()
+            .skip(1)
+            .find_map(|dir| Self::existing(&dir.join("node_modules").join(import_path)));
+        if from_node_modules.is_some() {
+            return from_node_modules;
+        }
+
+        self.lib_dirs.iter().find_map(|lib| Self::existing(&lib.join(import_path)))
+    }
+
+    /// Apply the longest matching remapping whose context contains the importing file
+    fn remap(&self, importing_file: &Path, import_path: &str) -> Option<String> {
+        let relative = importing_file.strip_prefix(&self.root).unwrap_or(importing_file);
+        let relative = relative.to_string_lossy();
+
+        self.remappings.iter()
+            .filter(|r| import_path.starts_with(&r.prefix))
+            .filter(|r| r.context.as_ref().is_none_or(|c| relative.starts_with(c.as_str())))
+            // Longer contexts, then longer prefixes are more specific
+            .max_by_key(|r| (r.context.as_ref().map_or(0, |c| c.len()), r.prefix.len()))
+            .map(|r| format!("{}{}", r.target, &import_path[r.prefix.len()..]))
+    }
+
+    fn existing(path: &Path) -> Option<PathBuf> {
+        let normalized = Self::normalize(path);
+        if normalized.is_file() {
+            Some(fs::canonicalize(&normalized).unwrap_or(normalized))
+        } else {
+            None
+        }
+    }
+
+    /// Lexically remove `.` and `..` components (solc resolves imports lexically, not through symlinks)
+    pub fn normalize(path: &Path) -> PathBuf {
+        let mut result = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if !result.pop() {
+                        result.push("..");
+                    }
+                }
+                other => result.push(other.as_os_str()),
+            }
+        }
+        result
+    }
+
+    /// Path as shown in reports: relative to the working directory when possible
+    pub fn display_path(path: &Path) -> String {
+        std::env::current_dir().ok()
+            .and_then(|cwd| fs::canonicalize(cwd).ok())
+            .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+            .unwrap_or_else(|| path.to_path_buf())
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::scratch_dir;
+
+    const SOURCE: &str = "// SPDX-License-Identifier: MIT\n";
+
+    fn resolved(resolver: &ImportResolver, importing_file: &str, import_path: &str) -> Option<String> {
+        resolver.resolve(&resolver.root().join(importing_file), import_path)
+            .map(|p| p.strip_prefix(resolver.root()).unwrap().to_string_lossy().to_string())
+    }
+
+    #[test]
+    fn parses_context_prefix_and_target() {
+        let remapping = Remapping::parse(" src/legacy:@oz/=lib/oz-v4/ ").unwrap();
+        assert_eq!(remapping.context.as_deref(), Some("src/legacy"));
+        assert_eq!((remapping.prefix.as_str(), remapping.target.as_str()), ("@oz/", "lib/oz-v4/"));
+        assert_eq!(Remapping::parse(":@oz/=lib/oz/").unwrap().context, None);
+        assert!(Remapping::parse("=lib/oz/").is_none());
+        assert!(Remapping::parse("no-target").is_none());
+    }
+
+    #[test]
+    fn remappings_txt_overrides_foundry_toml_and_longest_prefix_wins() {
+        let dir = scratch_dir(&[
+            ("foundry.toml", "[profile.default]\nremappings = [\"@oz/=lib/stale/\", \"solmate/=lib/solmate/src/\"]\n"),
+            ("remappings.txt", "# comment\n@oz/=lib/oz/\n@oz/contracts/token/=lib/oz-token/\nsrc/legacy:@oz/=lib/oz-v4/\n"),
+            ("src/Vault.sol", SOURCE),
+            ("src/legacy/Old.sol", SOURCE),
+            ("lib/oz/contracts/access/Ownable.sol", SOURCE),
+            ("lib/oz-token/ERC20.sol", SOURCE),
+            ("lib/oz-v4/contracts/access/Ownable.sol", SOURCE),
+            ("lib/solmate/src/auth/Owned.sol", SOURCE),
+        ]);
+        let resolver = ImportResolver::discover(&dir.join("src"));
+        assert_eq!(resolver.root(), dir);
+        assert_eq!(resolver.remappings().len(), 4);
+
+        assert_eq!(resolved(&resolver, "src/Vault.sol", "@oz/contracts/access/Ownable.sol").as_deref(),
+            Some("lib/oz/contracts/access/Ownable.sol"));
+        assert_eq!(resolved(&resolver, "src/Vault.sol", "@oz/contracts/token/ERC20.sol").as_deref(),
+            Some("lib/oz-token/ERC20.sol"));
+        assert_eq!(resolved(&resolver, "src/legacy/Old.sol", "@oz/contracts/access/Ownable.sol").as_deref(),
+            Some("lib/oz-v4/contracts/access/Ownable.sol"));
+        assert_eq!(resolved(&resolver, "src/Vault.sol", "solmate/auth/Owned.sol").as_deref(),
+            Some("lib/solmate/src/auth/Owned.sol"));
+        assert_eq!(resolved(&resolver, "src/Vault.sol", "./legacy/Old.sol").as_deref(), Some("src/legacy/Old.sol"));
+        assert_eq!(resolved(&resolver, "src/Vault.sol", "@oz/contracts/Missing.sol"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn falls_back_to_node_modules_then_libs() {
+        let dir = scratch_dir(&[
+            ("package.json", "{}"),
+            ("contracts/Token.sol", SOURCE),
+            ("node_modules/@openzeppelin/contracts/token/ERC20/ERC20.sol", SOURCE),
+            ("lib/forge-std/src/Test.sol", SOURCE),
+        ]);
+        let resolver = ImportResolver::discover(&dir.join("contracts"));
+        assert!(resolver.remappings().is_empty());
+        assert_eq!(resolved(&resolver, "contracts/Token.sol", "@openzeppelin/contracts/token/ERC20/ERC20.sol").as_deref(),
+            Some("node_modules/@openzeppelin/contracts/token/ERC20/ERC20.sol"));
+        assert_eq!(resolved(&resolver, "contracts/Token.sol", "forge-std/src/Test.sol").as_deref(),
+            Some("lib/forge-std/src/Test.sol"));
+        assert_eq!(resolved(&resolver, "contracts/Token.sol", "contracts/Token.sol").as_deref(), Some("contracts/Token.sol"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
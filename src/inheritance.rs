@@ -0,0 +1,436 @@
+use solang_parser::pt;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::*;
+use crate::parser::SolidityParser;
+
+/// Output of project-wide inheritance resolution
+pub struct InheritanceResolution {
+    /// ASTs with inherited parts appended, index-aligned with the contracts slice
+    pub merged_asts: Vec<pt::ContractDefinition>,
+    /// Linearization failures and other non-fatal problems
+    pub warnings: Vec<String>,
+}
+
+pub struct InheritanceResolver;
+
+impl InheritanceResolver {
+    /// Linearize every contract (C3, as solc does) and merge inherited members into each ContractInfo
+    /// Inherited items are copied from the declaring base with `inherited_from` set.
+    /// Overridden base functions are kept under a qualified name (e.g., "Ownable.transferOwnership")
+    /// so that `super.fn()` and `Base.fn()` calls can still be resolved.
+    pub fn resolve(contracts: &mut [ContractInfo], asts: &[pt::ContractDefinition]) -> InheritanceResolution {
+        let mut warnings = Vec::new();
+
+        // Index contracts by name (first definition wins if names collide across files)
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for (i, contract) in contracts.iter().enumerate() {
+            index.entry(contract.name.clone()).or_insert(i);
+        }
+
+        // Declared bases in source order (e.g., `is A, B` -> [A, B])
+        let mut bases_of: HashMap<String, Vec<String>> = HashMap::new();
+        for contract in contracts.iter() {
+            bases_of.entry(contract.name.clone())
+                .or_insert_with(|| contract.base_contracts.clone());
+        }
+
+        // Snapshot of locally declared members before anything gets merged
+        let own_infos: Vec<ContractInfo> = contracts.to_vec();
+
+        let mut cache: HashMap<String, Vec<String>> = HashMap::new();
+        let mut merged_asts = Vec::new();
+
+        for (i, ast) in asts.iter().enumerate() {
+            let name = contracts[i].name.clone();
+
+            let linearization = match Self::linearize(&name, &bases_of, &mut cache, &mut Vec::new()) {
+                Ok(lin) => lin,
+                Err(e) => {
+                    warnings.push(format!("{}: {}", name, e));
+                    Self::fallback_linearization(&name, &bases_of)
+                }
+            };
+
+            let bases: Vec<String> = linearization.into_iter().skip(1).collect();
+            contracts[i].unresolved_bases = bases.iter()
+                .filter(|b| !index.contains_key(*b))
+                .cloned()
+                .collect();
+            contracts[i].linearized_bases = bases.clone();
+
+            let resolved_bases: Vec<usize> = bases.iter()
+                .filter_map(|b| index.get(b).copied())
+                .collect();
+
+            merged_asts.push(Self::merge_contract(
+                &mut contracts[i],
+                ast,
+                &resolved_bases,
+                &own_infos,
+                asts,
+            ));
+        }
+
+        Self::assign_implementers(contracts);
+
+        InheritanceResolution { merged_asts, warnings }
+    }
+
+    /// Record, for every interface and abstract contract, the concrete contracts that inherit it
+    /// (directly or transitively). This is what calls through an interface type resolve to.
+    fn assign_implementers(contracts: &mut [ContractInfo]) {
+        let mut implementers: HashMap<String, Vec<String>> = HashMap::new();
+        for contract in contracts.iter().filter(|c| c.is_concrete()) {
+            for base in &contract.linearized_bases {
+                let entry = implementers.entry(base.clone()).or_default();
+                if !entry.contains(&contract.name) {
+                    entry.push(contract.name.clone());
+                }
+            }
+        }
+
+        for contract in contracts.iter_mut() {
+            if contract.is_concrete() || contract.kind == ContractKind::Library {
+                continue;
+            }
+            let mut found = implementers.get(&contract.name).cloned().unwrap_or_default();
+            found.sort();
+            contract.implementers = found;
+        }
+    }
+
+    /// C3 linearization: L(C) = [C] + merge(L(Bn), ..., L(B1), [Bn, ..., B1])
+    /// Solidity lists bases from "most base-like" to "most derived", hence the reversal.
+    /// Bases that are not part of the project are treated as leaves.
+    fn linearize(
+        name: &str,
+        bases_of: &HashMap<String, Vec<String>>,
+        cache: &mut HashMap<String, Vec<String>>,
+        visiting: &mut Vec<String>,
+    ) -> Result<Vec<String>, String> {
+        if let Some(lin) = cache.get(name) {
+            return Ok(lin.clone());
+        }
+        if visiting.iter().any(|v| v == name) {
+            return Err(format!("cyclic inheritance involving `{}`", name));
+        }
+        visiting.push(name.to_string());
+
+        let declared: Vec<String> = bases_of.get(name).cloned().unwrap_or_default();
+        let mut sequences: Vec<Vec<String>> = Vec::new();
+        for base in declared.iter().rev() {
+            sequences.push(Self::linearize(base, bases_of, cache, visiting)?);
+        }
+        sequences.push(declared.iter().rev().cloned().collect());
+
+        let mut result = vec![name.to_string()];
+        loop {
+            sequences.retain(|s| !s.is_empty());
+            if sequences.is_empty() {
+                break;
+            }
+
+            // Pick the first head that does not appear in the tail of any sequence
+            let candidate = sequences.iter()
+                .map(|s| s[0].clone())
+                .find(|head| !sequences.iter().any(|s| s[1..].contains(head)));
+
+            match candidate {
+                Some(head) => {
+                    for seq in &mut sequences {
+                        if seq[0] == head {
+                            seq.remove(0);
+                        }
+                    }
+                    result.push(head);
+                }
+                None => {
+                    visiting.pop();
+                    return Err("Linearization of inheritance graph impossible".to_string());
+                }
+            }
+        }
+
+        visiting.pop();
+        cache.insert(name.to_string(), result.clone());
+        Ok(result)
+    }
+
+    /// Best-effort order used when C3 fails: depth-first over declared bases, most derived first
+    fn fallback_linearization(name: &str, bases_of: &HashMap<String, Vec<String>>) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut stack = vec![name.to_string()];
+        let mut seen = HashSet::new();
+
+        while let Some(current) = stack.pop() {
+            if !seen.insert(current.clone()) {
+                continue;
+            }
+            result.push(current.clone());
+            if let Some(bases) = bases_of.get(&current) {
+                // Push in declared order so the last (most derived) base is visited first
+                for base in bases {
+                    stack.push(base.clone());
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Merge inherited members of `bases` (most derived first) into `info`
+    /// Returns the contract AST with inherited parts appended
+    fn merge_contract(
+        info: &mut ContractInfo,
+        ast: &pt::ContractDefinition,
+        bases: &[usize],
+        own_infos: &[ContractInfo],
+        asts: &[pt::ContractDefinition],
+    ) -> pt::ContractDefinition {
+        let mut merged = ast.clone();
+
+        // Signatures already provided by a more derived contract
+        let mut seen_functions: HashSet<String> = HashSet::new();
+        let mut seen_function_names: HashSet<String> = HashSet::new();
+        for part in &ast.parts {
+            if let pt::ContractPart::FunctionDefinition(f) = part {
+                if !matches!(f.ty, pt::FunctionTy::Modifier) {
+                    seen_functions.insert(SolidityParser::function_signature(f));
+                    seen_function_names.insert(SolidityParser::function_name(f));
+                }
+            }
+        }
+        // Public state variables can override external functions
+        for var in &info.state_variables {
+            seen_function_names.insert(var.name.clone());
+        }
+
+        let mut seen_modifiers: HashSet<String> = info.modifiers.iter().map(|m| m.name.clone()).collect();
+        let mut seen_state_vars: HashSet<String> = info.state_variables.iter().map(|v| v.name.clone()).collect();
+        let mut seen_events: HashSet<String> = info.events.iter().map(|e| e.name.clone()).collect();
+        let mut seen_errors: HashSet<String> = info.errors.iter().map(|e| e.name.clone()).collect();
+        let mut seen_structs: HashSet<String> = info.structs.iter().map(|s| s.name.clone()).collect();
+        let mut seen_enums: HashSet<String> = info.enums.iter().map(|e| e.name.clone()).collect();
+
+        let mut inherited_parts = Vec::new();
+        let mut inherited_functions = Vec::new();
+        // Storage order: state variables of the most base contract come first
+        let mut inherited_vars_by_base: Vec<Vec<StateVariable>> = Vec::new();
+
+        for &base_idx in bases {
+            let base_info = &own_infos[base_idx];
+            let base_ast = &asts[base_idx];
+            let base_name = base_info.name.clone();
+            let mut base_vars = Vec::new();
+            let mut function_position = 0;
+
+            for part in &base_ast.parts {
+                match part {
+                    pt::ContractPart::FunctionDefinition(f) if matches!(f.ty, pt::FunctionTy::Modifier) => {
+                        let mod_name = f.name.as_ref().map(|n| n.name.clone()).unwrap_or_default();
+                        if !seen_modifiers.insert(mod_name.clone()) {
+                            continue; // Overridden by a more derived modifier
+                        }
+                        if let Some(m) = base_info.modifiers.iter().find(|m| m.name == mod_name) {
+                            let mut m = m.clone();
+                            m.inherited_from = Some(base_name.clone());
+                            info.modifiers.push(m);
+                        }
+                        inherited_parts.push(part.clone());
+                    }
+                    pt::ContractPart::FunctionDefinition(f) => {
+                        let signature = SolidityParser::function_signature(f);
+                        let name = SolidityParser::function_name(f);
+                        // The parser extracts functions in AST order, so positions line up
+                        let extracted = base_info.functions.get(function_position).cloned();
+                        function_position += 1;
+
+                        // Constructors are never callable, and overridden functions are only
+                        // reachable through `super` - both are kept under a qualified name
+                        let qualified = matches!(f.ty, pt::FunctionTy::Constructor)
+                            || seen_functions.contains(&signature);
+
+                        if qualified && f.body.is_none() {
+                            continue;
+                        }
+
+                        let (new_name, new_signature) = if qualified {
+                            (format!("{}.{}", base_name, name), format!("{}.{}", base_name, signature))
+                        } else {
+                            seen_functions.insert(signature.clone());
+                            seen_function_names.insert(name.clone());
+                            (name, signature)
+                        };
+
+                        // Unimplemented interface functions add nothing if the signature is already provided
+                        if !qualified && f.body.is_none() && inherited_functions.iter()
+                            .chain(info.functions.iter())
+                            .any(|func: &FunctionDef| func.signature == new_signature) {
+                            continue;
+                        }
+
+                        if let Some(mut func) = extracted {
+                            func.name = new_name.clone();
+                            func.signature = new_signature;
+                            func.inherited_from = Some(base_name.clone());
+                            inherited_functions.push(func);
+                        }
+
+                        let mut renamed = (**f).clone();
+                        if qualified {
+                            renamed.name = Some(pt::Identifier {
+                                loc: f.name.as_ref().map(|n| n.loc).unwrap_or(f.loc),
+                                name: new_name,
+                            });
+                        }
+                        inherited_parts.push(pt::ContractPart::FunctionDefinition(Box::new(renamed)));
+                    }
+                    pt::ContractPart::VariableDefinition(var) => {
+                        let var_name = var.name.as_ref().map(|n| n.name.clone()).unwrap_or_default();
+                        if !seen_state_vars.insert(var_name.clone()) {
+                            continue;
+                        }
+                        if let Some(v) = base_info.state_variables.iter().find(|v| v.name == var_name) {
+                            let mut v = v.clone();
+                            v.inherited_from = Some(base_name.clone());
+                            base_vars.push(v);
+                        }
+                        inherited_parts.push(part.clone());
+                    }
+                    pt::ContractPart::EventDefinition(_) | pt::ContractPart::ErrorDefinition(_) => {
+                        // Event and error names are merged from the extracted info below
+                        inherited_parts.push(part.clone());
+                    }
+                    _ => inherited_parts.push(part.clone()),
+                }
+            }
+
+            // Implicit getters of public base variables have no AST part
+            for getter in base_info.functions.iter().filter(|f| {
+                base_info.state_variables.iter().any(|v| v.name == f.name && v.line_number == f.line_number)
+            }) {
+                if seen_function_names.insert(getter.name.clone()) {
+                    let mut getter = getter.clone();
+                    getter.inherited_from = Some(base_name.clone());
+                    inherited_functions.push(getter);
+                }
+            }
+
+            for event in &base_info.events {
+                if seen_events.insert(event.name.clone()) {
+                    let mut event = event.clone();
+                    event.inherited_from = Some(base_name.clone());
+                    info.events.push(event);
+                }
+            }
+
+            for error in &base_info.errors {
+                if seen_errors.insert(error.name.clone()) {
+                    let mut error = error.clone();
+                    error.is_inherited = true;
+                    error.inherited_from = Some(base_name.clone());
+                    error.declared_at = Some(format!("{}:{}", base_info.file_path, error.line_number));
+                    info.errors.push(error);
+                }
+            }
+
+            for s in &base_info.structs {
+                if seen_structs.insert(s.name.clone()) {
+                    info.structs.push(s.clone());
+                }
+            }
+
+            for e in &base_info.enums {
+                if seen_enums.insert(e.name.clone()) {
+                    info.enums.push(e.clone());
+                }
+            }
+
+            inherited_vars_by_base.push(base_vars);
+        }
+
+        // Most base contract's variables first, then the derived contract's own variables
+        let mut state_variables: Vec<StateVariable> = inherited_vars_by_base.into_iter().rev().flatten().collect();
+        state_variables.append(&mut info.state_variables);
+        info.state_variables = state_variables;
+
+        info.functions.extend(inherited_functions);
+        merged.parts.extend(inherited_parts);
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::StateModificationAnalyzer;
+    use crate::test_support::{analyze, contract};
+
+    fn linearize(name: &str, bases: &[(&str, &[&str])]) -> Result<Vec<String>, String> {
+        let bases_of: HashMap<String, Vec<String>> = bases.iter()
+            .map(|(contract, bases)| (contract.to_string(), bases.iter().map(|b| b.to_string()).collect()))
+            .collect();
+        InheritanceResolver::linearize(name, &bases_of, &mut HashMap::new(), &mut Vec::new())
+    }
+
+    #[test]
+    fn diamond_puts_the_last_listed_base_first() {
+        let lin = linearize("D", &[("B", &["A"]), ("C", &["A"]), ("D", &["B", "C"])]).unwrap();
+        assert_eq!(lin, ["D", "C", "B", "A"]);
+    }
+
+    #[test]
+    fn shared_base_follows_every_contract_deriving_from_it() {
+        let lin = linearize("B", &[("A", &["X"]), ("B", &["X", "A"])]).unwrap();
+        assert_eq!(lin, ["B", "A", "X"]);
+    }
+
+    #[test]
+    fn bases_listed_most_derived_first_cannot_be_linearized() {
+        assert!(linearize("B", &[("A", &["X"]), ("B", &["A", "X"])]).is_err());
+    }
+
+    #[test]
+    fn bases_outside_the_project_are_leaves() {
+        let lin = linearize("Token", &[("Token", &["ERC20", "Ownable"])]).unwrap();
+        assert_eq!(lin, ["Token", "Ownable", "ERC20"]);
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let err = linearize("A", &[("A", &["B"]), ("B", &["A"])]).unwrap_err();
+        assert!(err.contains("cyclic"));
+    }
+
+    #[test]
+    fn calls_through_interfaces_land_in_their_implementers() {
+        let project = analyze(r#"
+            interface IPool { function deposit() external; }
+            abstract contract BasePool is IPool {}
+            contract Pool is BasePool {
+                uint256 public total;
+                function deposit() external { total += 1; }
+            }
+            library Math {}
+            contract User {
+                IPool internal pool;
+                function go() external { pool.deposit(); }
+            }
+        "#);
+        let (pool, base) = (contract(&project, "Pool"), contract(&project, "BasePool"));
+        assert_eq!(contract(&project, "IPool").kind, ContractKind::Interface);
+        assert_eq!(contract(&project, "Math").kind, ContractKind::Library);
+        assert_eq!((base.kind_label(), pool.kind_label()), ("abstract contract", "contract"));
+        assert_eq!(pool.base_contracts, ["BasePool"]);
+        assert_eq!(pool.linearized_bases, ["BasePool", "IPool"]);
+        assert_eq!(contract(&project, "IPool").implementers, ["Pool"]);
+        assert_eq!(base.implementers, ["Pool"]);
+
+        let user = project.contracts.iter().position(|c| c.name == "User").unwrap();
+        let calls = StateModificationAnalyzer::detect_external_calls(&mut project.contracts[user].clone(),
+            &project.contract_asts[user], &project.contracts, &project.source_units);
+        assert_eq!(calls[0].target_contract.as_deref(), Some("Pool"));
+        assert_eq!(calls[0].target_modifies_states, ["total"]);
+    }
+}
@@ -21,10 +21,10 @@ impl JsonReportGenerator {
                     .filter_map(|called_func_name| {
                         // Find the called function to get its details
                         contract.functions.iter()
-                            .find(|f| &f.name == called_func_name)
+                            .find(|f| &f.signature == called_func_name)
                             .map(|called_func| {
                                 json!({
-                                    "function_name": called_func.name,
+                                    "function_name": called_func.signature,
                                     "parameters": called_func.parameters,
                                     "returns": if called_func.returns.is_empty() {
                                         Value::Null
@@ -41,18 +41,27 @@ impl JsonReportGenerator {
                     .map(|ext_call| {
                         json!({
                             "target_contract": ext_call.target_contract.clone().unwrap_or_else(|| "unknown".to_string()),
+                            "candidate_contracts": ext_call.candidate_contracts,
                             "target_variable": ext_call.target_variable,
                             "function_name": ext_call.target_function,
+                            "function_signature": ext_call.target_signature,
                             "state_mutability": ext_call.state_mutability,
-                            "is_value_transfer": ext_call.state_mutability == "payable"
+                            "is_value_transfer": ext_call.state_mutability == "payable",
+                            "is_delegatecall": ext_call.call_kind == ExternalCallKind::DelegateCall,
+                            "via_modifier": ext_call.via_modifier,
+                            "via_proxy": ext_call.via_proxy,
+                            "receiver_kind": ext_call.receiver_kind,
+                            "line_number": ext_call.line_number
                         })
                     })
                     .collect();
 
+                func_data.insert("inherited_from".to_string(), json!(func.inherited_from));
                 func_data.insert("internal_calls".to_string(), json!(internal_calls));
                 func_data.insert("external_calls".to_string(), json!(external_calls));
+                func_data.insert("assembly".to_string(), json!(func.assembly));
 
-                contract_funcs.insert(func.name.clone(), Value::Object(func_data));
+                contract_funcs.insert(func.signature.clone(), Value::Object(func_data));
             }
 
             report.insert(contract.name.clone(), Value::Object(contract_funcs));
@@ -138,6 +147,8 @@ impl JsonReportGenerator {
                     "visibility": var.visibility,
                     "is_constant": var.is_constant,
                     "is_immutable": var.is_immutable,
+                    "value": var.value,
+                    "inherited_from": var.inherited_from,
                     "readers": {
                         "direct": readers_direct,
                         "via_call_chains": readers_via_calls
@@ -162,7 +173,7 @@ impl JsonReportGenerator {
         let mut graph = HashMap::new();
         for func in functions {
             if !func.calls_functions.is_empty() {
-                graph.insert(func.name.clone(), func.calls_functions.clone());
+                graph.insert(func.signature.clone(), func.calls_functions.clone());
             }
         }
         graph
@@ -181,15 +192,15 @@ impl JsonReportGenerator {
         for func in functions {
             if func.modifies_states.contains(&var_name.to_string())
                 || func.modifies_state_fields.iter().any(|f| f.starts_with(&format!("{}.", var_name))) {
-                direct.push(func.name.clone());
+                direct.push(func.signature.clone());
             }
         }
 
         // Find indirect writers (functions that call direct writers)
         for func in functions {
-            if !direct.contains(&func.name) {
-                if let Some(chain) = Self::find_call_chain_to_targets(&func.name, &direct, call_graph) {
-                    indirect.push((func.name.clone(), chain));
+            if !direct.contains(&func.signature) {
+                if let Some(chain) = Self::find_call_chain_to_targets(&func.signature, &direct, call_graph) {
+                    indirect.push((func.signature.clone(), chain));
                 }
             }
         }
@@ -209,15 +220,15 @@ impl JsonReportGenerator {
         // Find direct readers
         for func in functions {
             if func.reads_states.contains(&var_name.to_string()) {
-                direct.push(func.name.clone());
+                direct.push(func.signature.clone());
             }
         }
 
         // Find indirect readers (functions that call direct readers)
         for func in functions {
-            if !direct.contains(&func.name) {
-                if let Some(chain) = Self::find_call_chain_to_targets(&func.name, &direct, call_graph) {
-                    indirect.push((func.name.clone(), chain));
+            if !direct.contains(&func.signature) {
+                if let Some(chain) = Self::find_call_chain_to_targets(&func.signature, &direct, call_graph) {
+                    indirect.push((func.signature.clone(), chain));
                 }
             }
         }
@@ -262,7 +273,7 @@ impl JsonReportGenerator {
     fn get_func_visibility(func_name: &str, functions: &[FunctionDef]) -> String {
         functions
             .iter()
-            .find(|f| f.name == func_name)
+            .find(|f| f.signature == func_name)
             .map(|f| f.visibility.clone())
             .unwrap_or_else(|| "unknown".to_string())
     }
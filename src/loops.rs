@@ -0,0 +1,705 @@
+use crate::analyzer::StateModificationAnalyzer;
+use crate::models::*;
+use crate::parser::SolidityParser;
+use crate::reentrancy::ADDRESS_CALL_HELPERS;
+use crate::types::ExpressionTyper;
+use colored::*;
+use solang_parser::pt;
+use std::collections::{HashMap, HashSet};
+
+// Internal calls followed from a loop body before giving up
+const MAX_CALL_DEPTH: usize = 8;
+
+// Local variables followed back to their initializer when classifying a bound
+const MAX_ALIAS_DEPTH: usize = 3;
+
+/// Finds `for`/`while`/`do` loops whose iteration count or per-iteration work callers can
+/// drive, and the entry points and unguarded functions that reach or grow them
+pub struct LoopAnalyzer;
+
+/// Names a function body declares, for resolving loop bounds and writes
+#[derive(Default)]
+struct Locals<'a> {
+    params: HashSet<String>,
+    inits: HashMap<String, &'a pt::Expression>, // Local -> initializer
+    uninitialized: HashSet<String>,             // Named returns and locals declared without a value
+    storage_refs: HashMap<String, String>,      // Storage pointers -> state variable they point into
+}
+
+/// Something a loop body does on each iteration
+enum Effect {
+    Risk(LoopRiskKind, String),
+    InternalCall(String, usize), // Name and arity
+}
+
+struct LoopScanner<'a> {
+    contract: &'a ContractInfo,
+    all_contracts: &'a [ContractInfo],
+    ast: &'a pt::ContractDefinition,
+    typer: ExpressionTyper<'a>,
+}
+
+impl LoopAnalyzer {
+    pub fn analyze(contracts: &mut [ContractInfo], contract_asts: &[pt::ContractDefinition], source_units: &[SourceUnit]) {
+        let snapshot = contracts.to_vec();
+        for ((contract, declared), ast) in contracts.iter_mut().zip(&snapshot).zip(contract_asts) {
+            if contract.kind != ContractKind::Contract {
+                continue;
+            }
+            let mut scanner = LoopScanner {
+                contract: declared,
+                all_contracts: &snapshot,
+                ast,
+                typer: ExpressionTyper::new(declared, &snapshot, source_units),
+            };
+            contract.loops = scanner.check_contract(source_units);
+        }
+    }
+
+    pub fn print_summary(contracts: &[ContractInfo]) {
+        let affected: Vec<&ContractInfo> = contracts.iter().filter(|c| !c.loops.is_empty()).collect();
+        if affected.is_empty() {
+            return;
+        }
+
+        println!();
+        println!("{}", "🔄 Loops (gas and denial of service)".bold());
+        for contract in affected {
+            let count = |severity: LoopSeverity| contract.loops.iter().filter(|l| l.severity == severity).count();
+            let growable = contract.loops.iter().filter(|l| !l.growable_by.is_empty()).count();
+            println!("  {} {}: {} loop(s), {} high, {} medium, {} growable by anyone",
+                "⚠️".yellow(),
+                contract.name,
+                contract.loops.len(),
+                count(LoopSeverity::High),
+                count(LoopSeverity::Medium),
+                growable
+            );
+        }
+    }
+
+    fn severity(bound: &LoopBound, risks: &[LoopRisk], growable: bool) -> LoopSeverity {
+        let calls = risks.iter().any(|r| r.kind != LoopRiskKind::StorageWrite);
+        let writes = risks.iter().any(|r| r.kind == LoopRiskKind::StorageWrite);
+        match bound {
+            LoopBound::StorageArray(_) | LoopBound::StateVariable(_) => {
+                if growable && (calls || writes) {
+                    LoopSeverity::High
+                } else if growable || calls {
+                    LoopSeverity::Medium
+                } else {
+                    LoopSeverity::Low
+                }
+            }
+            // A caller choosing a long input only spends their own gas
+            LoopBound::Parameter(_) | LoopBound::Fixed(_) | LoopBound::Other(_) => LoopSeverity::Low,
+        }
+    }
+
+    /// Public and external functions reaching `signature` through internal calls
+    fn entry_points(contract: &ContractInfo, signature: &str) -> Vec<String> {
+        let mut reached: HashSet<&str> = HashSet::from([signature]);
+        let mut queue = vec![signature];
+        while let Some(current) = queue.pop() {
+            for caller in &contract.functions {
+                if caller.calls_functions.iter().any(|c| c == current) && reached.insert(&caller.signature) {
+                    queue.push(&caller.signature);
+                }
+            }
+        }
+        contract.functions.iter()
+            .filter(|f| reached.contains(f.signature.as_str()) && matches!(f.visibility.as_str(), "public" | "external"))
+            .map(|f| f.signature.clone())
+            .collect()
+    }
+
+    fn collect_loops<'s>(stmt: &'s pt::Statement, loops: &mut Vec<&'s pt::Statement>) {
+        match stmt {
+            pt::Statement::Block { statements, .. } => {
+                for s in statements {
+                    Self::collect_loops(s, loops);
+                }
+            }
+            pt::Statement::If(_, _, if_branch, else_branch) => {
+                Self::collect_loops(if_branch, loops);
+                if let Some(else_stmt) = else_branch {
+                    Self::collect_loops(else_stmt, loops);
+                }
+            }
+            pt::Statement::For(_, _, _, _, body) => {
+                loops.push(stmt);
+                if let Some(body) = body {
+                    Self::collect_loops(body, loops);
+                }
+            }
+            pt::Statement::While(_, _, body) | pt::Statement::DoWhile(_, body, _) => {
+                loops.push(stmt);
+                Self::collect_loops(body, loops);
+            }
+            pt::Statement::Try(_, expr, returns, catches) => {
+                if let (_, Some(body)) = StateModificationAnalyzer::try_call(expr, returns) {
+                    Self::collect_loops(body, loops);
+                }
+                for clause in catches {
+                    match clause {
+                        pt::CatchClause::Simple(_, _, body) | pt::CatchClause::Named(_, _, _, body) => {
+                            Self::collect_loops(body, loops);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a> LoopScanner<'a> {
+    fn check_contract(&mut self, source_units: &[SourceUnit]) -> Vec<LoopFinding> {
+        let mut findings = Vec::new();
+        let ast = self.ast;
+        for part in &ast.parts {
+            let pt::ContractPart::FunctionDefinition(func) = part else {
+                continue;
+            };
+            if !matches!(func.ty, pt::FunctionTy::Function | pt::FunctionTy::Fallback | pt::FunctionTy::Receive) {
+                continue;
+            }
+            let Some(body) = &func.body else {
+                continue;
+            };
+            let signature = SolidityParser::function_signature(func);
+            let Some(declared) = self.contract.functions.iter().find(|f| f.signature == signature) else {
+                continue;
+            };
+
+            let mut loops = Vec::new();
+            LoopAnalyzer::collect_loops(body, &mut loops);
+            if loops.is_empty() {
+                continue;
+            }
+            // Inherited loops are reported again where the contract's own functions reach them,
+            // with the entry points and growers of the whole contract
+            let entry_points = LoopAnalyzer::entry_points(self.contract, &signature);
+            if declared.inherited_from.is_some() && entry_points.is_empty() {
+                continue;
+            }
+            let declaring_file = declared.inherited_from.as_ref()
+                .and_then(|base| self.all_contracts.iter().find(|c| &c.name == base))
+                .map_or(self.contract.file_path.as_str(), |c| c.file_path.as_str());
+            let source = source_units.iter().find(|u| u.file_path == declaring_file);
+            let locals = Self::locals(func);
+
+            for stmt in loops {
+                let (loc, init, cond, body) = match stmt {
+                    pt::Statement::For(loc, init, cond, _, body) => (loc, init.as_deref(), cond.as_deref(), body.as_deref()),
+                    pt::Statement::While(loc, cond, body) | pt::Statement::DoWhile(loc, body, cond) => {
+                        (loc, None, Some(cond), Some(body.as_ref()))
+                    }
+                    _ => continue,
+                };
+                let loop_vars: HashSet<String> = match init {
+                    Some(pt::Statement::VariableDefinition(_, decl, _)) => decl.name.iter().map(|n| n.name.clone()).collect(),
+                    _ => HashSet::new(),
+                };
+                let (bound, bound_root) = self.bound(cond, &locals, &loop_vars);
+                let risks = match body {
+                    Some(body) => self.risks(func, body),
+                    None => Vec::new(),
+                };
+                let reportable = matches!(bound,
+                    LoopBound::StorageArray(_) | LoopBound::StateVariable(_) | LoopBound::Parameter(_));
+                if !reportable && risks.is_empty() {
+                    continue;
+                }
+
+                let growable_by = bound_root.map(|root| self.growable_by(&root)).unwrap_or_default();
+                findings.push(LoopFinding {
+                    function: signature.clone(),
+                    line_number: source.map(|u| u.line_at(loc.start())).unwrap_or_default(),
+                    severity: LoopAnalyzer::severity(&bound, &risks, !growable_by.is_empty()),
+                    bound,
+                    risks,
+                    entry_points: entry_points.clone(),
+                    growable_by,
+                });
+            }
+        }
+        findings
+    }
+
+    fn locals(func: &'a pt::FunctionDefinition) -> Locals<'a> {
+        let mut locals = Locals::default();
+        for (_, param) in &func.params {
+            if let Some(name) = param.as_ref().and_then(|p| p.name.as_ref()) {
+                locals.params.insert(name.name.clone());
+            }
+        }
+        for (_, param) in &func.returns {
+            if let Some(name) = param.as_ref().and_then(|p| p.name.as_ref()) {
+                locals.uninitialized.insert(name.name.clone());
+            }
+        }
+        if let Some(body) = &func.body {
+            Self::collect_locals(body, &mut locals);
+        }
+        locals
+    }
+
+    fn collect_locals(stmt: &'a pt::Statement, locals: &mut Locals<'a>) {
+        match stmt {
+            pt::Statement::VariableDefinition(_, decl, init) => {
+                let Some(name) = &decl.name else {
+                    return;
+                };
+                match init {
+                    Some(init) => {
+                        locals.inits.insert(name.name.clone(), init);
+                        let root = StateModificationAnalyzer::extract_base_variable(init);
+                        if let (Some(pt::StorageLocation::Storage(_)), Some(root)) = (&decl.storage, root) {
+                            locals.storage_refs.insert(name.name.clone(), root);
+                        }
+                    }
+                    None => {
+                        locals.uninitialized.insert(name.name.clone());
+                    }
+                }
+            }
+            pt::Statement::Block { statements, .. } => {
+                for s in statements {
+                    Self::collect_locals(s, locals);
+                }
+            }
+            pt::Statement::If(_, _, if_branch, else_branch) => {
+                Self::collect_locals(if_branch, locals);
+                if let Some(else_stmt) = else_branch {
+                    Self::collect_locals(else_stmt, locals);
+                }
+            }
+            pt::Statement::For(_, init, _, _, body) => {
+                for s in [init, body].into_iter().flatten() {
+                    Self::collect_locals(s, locals);
+                }
+            }
+            pt::Statement::While(_, _, body) | pt::Statement::DoWhile(_, body, _) => Self::collect_locals(body, locals),
+            pt::Statement::Try(_, expr, returns, catches) => {
+                if let (_, Some(body)) = StateModificationAnalyzer::try_call(expr, returns) {
+                    Self::collect_locals(body, locals);
+                }
+                for clause in catches {
+                    match clause {
+                        pt::CatchClause::Simple(_, _, body) | pt::CatchClause::Named(_, _, _, body) => {
+                            Self::collect_locals(body, locals);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn state_variable(&self, name: &str) -> Option<&'a StateVariable> {
+        self.contract.state_variables.iter().find(|v| v.name == name)
+    }
+
+    /// What limits the loop, preferring storage over parameters over literals, and the state behind it
+    fn bound(&self, cond: Option<&pt::Expression>, locals: &Locals, loop_vars: &HashSet<String>) -> (LoopBound, Option<String>) {
+        let Some(cond) = cond else {
+            return (LoopBound::Other("no condition".to_string()), None);
+        };
+        let mut best: Option<(u8, LoopBound, Option<String>)> = None;
+        self.classify_bound(cond, locals, loop_vars, 0, &mut best);
+        best.map_or((LoopBound::Other(cond.to_string()), None), |(_, bound, root)| (bound, root))
+    }
+
+    fn classify_bound(
+        &self,
+        expr: &pt::Expression,
+        locals: &Locals,
+        loop_vars: &HashSet<String>,
+        depth: usize,
+        best: &mut Option<(u8, LoopBound, Option<String>)>,
+    ) {
+        let mut offer = |bound: LoopBound, root: Option<String>| {
+            let rank = match bound {
+                LoopBound::Fixed(_) => 0,
+                LoopBound::Other(_) => 1,
+                LoopBound::Parameter(_) => 2,
+                LoopBound::StateVariable(_) => 3,
+                LoopBound::StorageArray(_) => 4,
+            };
+            if best.as_ref().is_none_or(|(r, ..)| rank > *r) {
+                *best = Some((rank, bound, root));
+            }
+        };
+        let text = expr.to_string();
+
+        match expr {
+            pt::Expression::NumberLiteral(..)
+            | pt::Expression::HexNumberLiteral(..)
+            | pt::Expression::RationalNumberLiteral(..) => offer(LoopBound::Fixed(text), None),
+
+            pt::Expression::Variable(ident) => {
+                let name = &ident.name;
+                if loop_vars.contains(name) {
+                    return;
+                }
+                if locals.params.contains(name) {
+                    offer(LoopBound::Parameter(text), None);
+                } else if let Some(init) = locals.inits.get(name) {
+                    match depth < MAX_ALIAS_DEPTH {
+                        true => self.classify_bound(init, locals, loop_vars, depth + 1, best),
+                        false => offer(LoopBound::Other(text), None),
+                    }
+                } else if locals.uninitialized.contains(name) {
+                    offer(LoopBound::Other(text), None);
+                } else {
+                    match self.state_variable(name) {
+                        Some(var) if var.is_constant || var.is_immutable => offer(LoopBound::Fixed(text), None),
+                        Some(_) => offer(LoopBound::StateVariable(text), Some(name.clone())),
+                        None => offer(LoopBound::Other(text), None),
+                    }
+                }
+            }
+
+            pt::Expression::MemberAccess(_, base, _) | pt::Expression::ArraySubscript(_, base, _) => {
+                let is_length = matches!(expr, pt::Expression::MemberAccess(_, _, member) if member.name == "length");
+                let Some(root) = StateModificationAnalyzer::extract_base_variable(base) else {
+                    offer(LoopBound::Other(text), None);
+                    return;
+                };
+                let state = locals.storage_refs.get(&root).cloned().or_else(|| {
+                    let shadowed = locals.params.contains(&root) || locals.inits.contains_key(&root)
+                        || locals.uninitialized.contains(&root);
+                    (!shadowed && self.state_variable(&root).is_some_and(|v| !v.is_constant && !v.is_immutable))
+                        .then(|| root.clone())
+                });
+                if let Some(state) = state {
+                    match is_length {
+                        true => offer(LoopBound::StorageArray(text), Some(state)),
+                        false => offer(LoopBound::StateVariable(text), Some(state)),
+                    }
+                } else if locals.params.contains(&root) {
+                    offer(LoopBound::Parameter(text), None);
+                } else if let (true, Some(init)) = (depth < MAX_ALIAS_DEPTH, locals.inits.get(&root)) {
+                    // `address[] memory list = holders;` copies the storage array
+                    let mut aliased = None;
+                    self.classify_bound(init, locals, loop_vars, depth + 1, &mut aliased);
+                    match aliased {
+                        Some((_, LoopBound::StorageArray(_) | LoopBound::StateVariable(_), state)) if is_length => {
+                            offer(LoopBound::StorageArray(text), state)
+                        }
+                        Some((_, LoopBound::Parameter(_), _)) => offer(LoopBound::Parameter(text), None),
+                        _ => offer(LoopBound::Other(text), None),
+                    }
+                } else {
+                    offer(LoopBound::Other(text), None);
+                }
+            }
+
+            // Conversions like `uint256(count)` keep the bound of their argument
+            pt::Expression::FunctionCall(_, callee, args)
+                if args.len() == 1 && matches!(callee.as_ref(), pt::Expression::Type(..)) =>
+            {
+                self.classify_bound(&args[0], locals, loop_vars, depth, best);
+            }
+
+            pt::Expression::Parenthesis(_, inner)
+            | pt::Expression::Not(_, inner)
+            | pt::Expression::PostIncrement(_, inner)
+            | pt::Expression::PreIncrement(_, inner)
+            | pt::Expression::PostDecrement(_, inner)
+            | pt::Expression::PreDecrement(_, inner) => self.classify_bound(inner, locals, loop_vars, depth, best),
+
+            pt::Expression::Less(_, left, right)
+            | pt::Expression::More(_, left, right)
+            | pt::Expression::LessEqual(_, left, right)
+            | pt::Expression::MoreEqual(_, left, right)
+            | pt::Expression::Equal(_, left, right)
+            | pt::Expression::NotEqual(_, left, right)
+            | pt::Expression::And(_, left, right)
+            | pt::Expression::Or(_, left, right)
+            | pt::Expression::Add(_, left, right)
+            | pt::Expression::Subtract(_, left, right)
+            | pt::Expression::Multiply(_, left, right)
+            | pt::Expression::Divide(_, left, right)
+            | pt::Expression::Modulo(_, left, right) => {
+                self.classify_bound(left, locals, loop_vars, depth, best);
+                self.classify_bound(right, locals, loop_vars, depth, best);
+            }
+
+            _ => offer(LoopBound::Other(text), None),
+        }
+    }
+
+    /// External calls, value transfers and storage writes of a loop body and the functions it calls
+    fn risks(&mut self, func: &'a pt::FunctionDefinition, body: &'a pt::Statement) -> Vec<LoopRisk> {
+        let mut risks = Vec::new();
+        let mut visited: HashSet<*const pt::FunctionDefinition> = HashSet::from([func as *const _]);
+        self.scan(func, body, None, 0, &mut visited, &mut risks);
+        risks
+    }
+
+    fn scan(
+        &mut self,
+        func: &'a pt::FunctionDefinition,
+        body: &'a pt::Statement,
+        via: Option<&str>,
+        depth: usize,
+        visited: &mut HashSet<*const pt::FunctionDefinition>,
+        risks: &mut Vec<LoopRisk>,
+    ) {
+        self.typer.enter_function(func);
+        let locals = Self::locals(func);
+        let mut effects = Vec::new();
+        StateModificationAnalyzer::visit_statement_expressions(body, &mut |expr| {
+            if let Some(effect) = self.effect(expr, &locals) {
+                effects.push(effect);
+            }
+        });
+
+        let mut callees = Vec::new();
+        for effect in effects {
+            match effect {
+                Effect::Risk(kind, target) => {
+                    let risk = LoopRisk { kind, target, via: via.map(str::to_string) };
+                    if !risks.contains(&risk) {
+                        risks.push(risk);
+                    }
+                }
+                Effect::InternalCall(name, arity) => callees.push((name, arity)),
+            }
+        }
+        if depth >= MAX_CALL_DEPTH {
+            return;
+        }
+        for (name, arity) in callees {
+            let Some(callee) = self.internal_part(&name, arity) else {
+                continue;
+            };
+            if let (true, Some(callee_body)) = (visited.insert(callee as *const _), &callee.body) {
+                self.scan(callee, callee_body, Some(&name), depth + 1, visited, risks);
+            }
+        }
+    }
+
+    fn effect(&self, expr: &pt::Expression, locals: &Locals) -> Option<Effect> {
+        match expr {
+            pt::Expression::Assign(_, target, _)
+            | pt::Expression::AssignOr(_, target, _)
+            | pt::Expression::AssignAnd(_, target, _)
+            | pt::Expression::AssignXor(_, target, _)
+            | pt::Expression::AssignShiftLeft(_, target, _)
+            | pt::Expression::AssignShiftRight(_, target, _)
+            | pt::Expression::AssignAdd(_, target, _)
+            | pt::Expression::AssignSubtract(_, target, _)
+            | pt::Expression::AssignMultiply(_, target, _)
+            | pt::Expression::AssignDivide(_, target, _)
+            | pt::Expression::AssignModulo(_, target, _)
+            | pt::Expression::PreIncrement(_, target)
+            | pt::Expression::PostIncrement(_, target)
+            | pt::Expression::PreDecrement(_, target)
+            | pt::Expression::PostDecrement(_, target)
+            | pt::Expression::Delete(_, target) => self.storage_write(target, locals),
+
+            pt::Expression::FunctionCall(_, callee, args) => {
+                let (callee, sends_value) = match callee.as_ref() {
+                    pt::Expression::FunctionCallBlock(_, inner, options) => {
+                        let sends_value = matches!(options.as_ref(),
+                            pt::Statement::Args(_, named) if named.iter().any(|a| a.name.name == "value"));
+                        (inner.as_ref(), sends_value)
+                    }
+                    other => (other, false),
+                };
+                match callee {
+                    pt::Expression::Variable(ident) => self.internal_part(&ident.name, args.len())
+                        .map(|_| Effect::InternalCall(ident.name.clone(), args.len())),
+                    pt::Expression::MemberAccess(_, base, member) => {
+                        self.member_call(expr, base, &member.name, sends_value, locals)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn storage_write(&self, target: &pt::Expression, locals: &Locals) -> Option<Effect> {
+        let root = StateModificationAnalyzer::extract_base_variable(target)?;
+        let state = match locals.storage_refs.get(&root) {
+            Some(state) => state.clone(),
+            None if locals.params.contains(&root) || locals.inits.contains_key(&root)
+                || locals.uninitialized.contains(&root) => return None,
+            None => self.state_variable(&root).map(|v| v.name.clone())?,
+        };
+        Some(Effect::Risk(LoopRiskKind::StorageWrite, state))
+    }
+
+    /// `x.f(...)`: an array method, a call on an address or a call into another contract
+    fn member_call(&self, expr: &pt::Expression, base: &pt::Expression, member: &str, sends_value: bool, locals: &Locals) -> Option<Effect> {
+        if matches!(member, "push" | "pop") {
+            return self.storage_write(base, locals);
+        }
+        if let pt::Expression::Variable(ident) = base {
+            let is_type = self.all_contracts.iter().any(|c| c.name == ident.name);
+            if is_type || matches!(ident.name.as_str(), "this" | "super" | "abi" | "msg" | "block" | "tx" | "string" | "bytes" | "type") {
+                return None;
+            }
+        }
+        let receiver = self.typer.infer(base).and_then(|t| t.receiver_name())?;
+
+        let kind = if receiver == "address" {
+            match member {
+                "call" | "delegatecall" if sends_value => LoopRiskKind::ValueTransfer,
+                "call" | "delegatecall" => LoopRiskKind::ExternalCall,
+                "transfer" | "send" => LoopRiskKind::ValueTransfer,
+                _ if ADDRESS_CALL_HELPERS.contains(&member) && (member.contains("Value") || member.contains("ETH")) => {
+                    LoopRiskKind::ValueTransfer
+                }
+                _ if ADDRESS_CALL_HELPERS.contains(&member) => LoopRiskKind::ExternalCall,
+                _ => return None,
+            }
+        } else if self.is_read_only(&receiver, member) {
+            return None;
+        } else if sends_value {
+            LoopRiskKind::ValueTransfer
+        } else {
+            LoopRiskKind::ExternalCall
+        };
+        Some(Effect::Risk(kind, expr.to_string()))
+    }
+
+    /// Whether calling `member` on a `receiver` contract can't change state; functions
+    /// the project doesn't declare may
+    fn is_read_only(&self, receiver: &str, member: &str) -> bool {
+        let candidates: Vec<&FunctionDef> = self.all_contracts.iter()
+            .find(|c| c.name == receiver)
+            .map(|c| c.functions.iter().filter(|f| f.name == member).collect())
+            .unwrap_or_default();
+        !candidates.is_empty() && candidates.iter().all(|f| matches!(f.state_mutability.as_str(), "view" | "pure"))
+    }
+
+    fn internal_part(&self, name: &str, arity: usize) -> Option<&'a pt::FunctionDefinition> {
+        self.ast.parts.iter().find_map(|part| match part {
+            pt::ContractPart::FunctionDefinition(f)
+                if matches!(f.ty, pt::FunctionTy::Function)
+                    && f.body.is_some()
+                    && f.params.len() == arity
+                    && SolidityParser::function_name(f) == name => Some(f.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Entry points without access guards that push to `state` or count it up
+    fn growable_by(&self, state: &str) -> Vec<String> {
+        let growers: Vec<String> = self.ast.parts.iter()
+            .filter_map(|part| match part {
+                pt::ContractPart::FunctionDefinition(f)
+                    if !matches!(f.ty, pt::FunctionTy::Modifier | pt::FunctionTy::Constructor) =>
+                {
+                    let body = f.body.as_ref()?;
+                    let locals = Self::locals(f);
+                    let mut grows = false;
+                    StateModificationAnalyzer::visit_statement_expressions(body, &mut |expr| {
+                        grows |= self.grows(expr, state, &locals);
+                    });
+                    grows.then(|| SolidityParser::function_signature(f))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut entry_points: Vec<String> = Vec::new();
+        for grower in &growers {
+            for entry in LoopAnalyzer::entry_points(self.contract, grower) {
+                let guarded = self.contract.access_control.iter()
+                    .any(|a| a.function == entry && !a.guards.is_empty());
+                let read_only = self.contract.functions.iter()
+                    .any(|f| f.signature == entry && matches!(f.state_mutability.as_str(), "view" | "pure"));
+                if !guarded && !read_only && !entry_points.contains(&entry) {
+                    entry_points.push(entry);
+                }
+            }
+        }
+        entry_points
+    }
+
+    fn grows(&self, expr: &pt::Expression, state: &str, locals: &Locals) -> bool {
+        let refers_to_state = |target: &pt::Expression| {
+            StateModificationAnalyzer::extract_base_variable(target).is_some_and(|root| match locals.storage_refs.get(&root) {
+                Some(pointed) => pointed == state,
+                None => root == state && !locals.params.contains(&root) && !locals.inits.contains_key(&root),
+            })
+        };
+        match expr {
+            pt::Expression::FunctionCall(_, callee, _) => matches!(callee.as_ref(),
+                pt::Expression::MemberAccess(_, base, member) if member.name == "push" && refers_to_state(base)),
+            pt::Expression::PreIncrement(_, target)
+            | pt::Expression::PostIncrement(_, target)
+            | pt::Expression::AssignAdd(_, target, _) => refers_to_state(target),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+
+    #[test]
+    fn push_behind_a_check_that_only_rejects_some_callers_grows_the_loop() {
+        let project = analyze(r#"
+            contract Airdrop {
+                address public owner;
+                address[] public holders;
+
+                function join(address referrer) external {
+                    require(referrer != msg.sender);
+                    holders.push(msg.sender);
+                }
+
+                function add(address holder) external {
+                    require(msg.sender == owner);
+                    holders.push(holder);
+                }
+
+                function payout() external {
+                    for (uint256 i = 0; i < holders.length; i++) {
+                        payable(holders[i]).transfer(1);
+                    }
+                }
+            }
+        "#);
+        let airdrop = contract(&project, "Airdrop");
+        let payout = airdrop.loops.iter().find(|l| l.function == "payout()").unwrap();
+        assert_eq!(payout.bound, LoopBound::StorageArray("holders.length".to_string()));
+        assert_eq!(payout.growable_by, vec!["join(address)".to_string()]);
+        assert_eq!(payout.severity, LoopSeverity::High);
+    }
+
+    #[test]
+    fn inherited_loops_are_reported_with_the_derived_call_graph() {
+        let project = analyze(r#"
+            abstract contract Base {
+                address[] internal users;
+
+                function _distribute() internal {
+                    for (uint256 i = 0; i < users.length; i++) {
+                        payable(users[i]).transfer(1);
+                    }
+                }
+            }
+
+            contract Child is Base {
+                function join() external { users.push(msg.sender); }
+                function payout() external { _distribute(); }
+            }
+        "#);
+        let child = contract(&project, "Child");
+        let distribute = child.loops.iter().find(|l| l.function == "_distribute()").expect("loop inherited from Base");
+        assert_eq!(distribute.entry_points, ["payout()"]);
+        assert_eq!(distribute.growable_by, ["join()"]);
+        assert_eq!(distribute.severity, LoopSeverity::High);
+        assert_eq!(distribute.line_number, 6);
+    }
+}
@@ -1,7 +1,9 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::*;
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use solang_parser::pt;
+use std::path::{Path, PathBuf};
 
 mod models;
 mod scanner;
@@ -14,6 +16,25 @@ mod call_graph_report;
 mod json_reports;
 mod graph_generator;
 mod contract_interaction_reports;
+mod inheritance;
+mod abi;
+mod imports;
+mod yul;
+mod storage_layout;
+mod storage_diff;
+mod erc7201;
+mod proxy;
+mod diamond;
+mod pragma;
+mod natspec;
+mod types;
+mod constants;
+mod reentrancy;
+mod access_control;
+mod upgradeability;
+mod loops;
+#[cfg(test)]
+mod test_support;
 
 use scanner::FileScanner;
 use parser::SolidityParser;
@@ -24,6 +45,23 @@ use call_graph_report::CallGraphReportGenerator;
 use json_reports::JsonReportGenerator;
 use graph_generator::GraphGenerator;
 use contract_interaction_reports::ContractInteractionReports;
+use inheritance::InheritanceResolver;
+use abi::AbiGenerator;
+use storage_layout::StorageLayoutEngine;
+use storage_diff::StorageDiffer;
+use erc7201::Erc7201Verifier;
+use proxy::ProxyDetector;
+use diamond::DiamondAnalyzer;
+use pragma::PragmaChecker;
+use natspec::NatSpecCoverage;
+use types::TypeAnnotator;
+use constants::ConstantFolder;
+use reentrancy::ReentrancyDetector;
+use access_control::AccessControlAnalyzer;
+use upgradeability::UpgradeabilityChecker;
+use loops::LoopAnalyzer;
+use imports::{ImportDiagnostic, ImportGraph, ImportResolver};
+use models::{ContractInfo, ContractKind, DiamondView, DocCoverage, SourceUnit};
 
 #[derive(Parser)]
 #[command(name = "sol-analyzer")]
@@ -62,6 +100,21 @@ enum Commands {
         #[arg(long, default_value = "true")]
         relations: bool,
     },
+
+    /// Compare the storage layouts of two versions of a project (exits with 1 on unsafe changes)
+    DiffStorage {
+        /// Contracts directory of the deployed version (e.g. a checkout of the previous release)
+        #[arg(long)]
+        old: PathBuf,
+
+        /// Contracts directory of the upgraded version
+        #[arg(long)]
+        new: PathBuf,
+
+        /// Output directory for storage_diff.md and storage_diff.json
+        #[arg(long, default_value = "./storage-diff")]
+        output: PathBuf,
+    },
 }
 
 fn main() -> Result<()> {
@@ -71,6 +124,11 @@ fn main() -> Result<()> {
         Commands::Analyze { path, format, export, save_md, md_output, relations } => {
             analyze_contracts(path, format, export, save_md, md_output, relations)?;
         }
+        Commands::DiffStorage { old, new, output } => {
+            if !diff_storage(&old, &new, &output)? {
+                std::process::exit(1);
+            }
+        }
     }
 
     Ok(())
@@ -94,66 +152,69 @@ fn analyze_contracts(
         println!("  {} Removed old reports directory", "✓".green());
     }
 
-    // Scan for .sol files
-    let scanner = FileScanner::new(path.clone());
-    let sol_files = scanner.scan_contracts()?;
-
-    if sol_files.is_empty() {
-        println!("{}", "⚠️  No Solidity files found!".yellow());
-        println!("Looking in: {}", path.display());
-        return Ok(());
-    }
-
-    println!("{} {} Solidity file(s)", "📂 Found".green(), sol_files.len());
-
-    // Parse each file and store ASTs for relationship analysis
-    let mut all_contracts = Vec::new();
-    let mut contract_asts = Vec::new();
-    let mut errors = Vec::new();
-
-    for file in &sol_files {
-        match SolidityParser::parse_file_with_ast(file) {
-            Ok((contracts, asts)) => {
-                println!("  {} {}", "✓".green(), file.display());
-                all_contracts.extend(contracts);
-                contract_asts.extend(asts);
-            }
-            Err(e) => {
-                println!("  {} {} - {}", "✗".red(), file.display(), e);
-                errors.push((file.clone(), e));
-            }
-        }
-    }
-
-    println!();
-
-    if all_contracts.is_empty() {
-        println!("{}", "⚠️  No contracts found or all files had errors!".yellow());
+    let Some(project) = load_project(&path)? else {
         return Ok(());
-    }
+    };
+    let AnalyzedProject {
+        contracts: mut all_contracts,
+        contract_asts,
+        source_units,
+        diamond_view,
+        compiler_warnings,
+        doc_coverage,
+        import_graph,
+        errors,
+    } = analyze_project(project);
+
+    // Context-only dependencies take part in resolution but are not reported
+    let project_contracts: Vec<ContractInfo> = all_contracts.iter()
+        .filter(|c| !c.is_context_only)
+        .cloned()
+        .collect();
 
     // Output results
     match format.as_str() {
         "json" => {
-            let json = serde_json::to_string_pretty(&all_contracts)?;
+            let json = serde_json::to_string_pretty(&project_contracts)?;
             println!("{}", json);
         }
         "detailed" => {
-            OutputFormatter::print_detailed(&all_contracts);
+            OutputFormatter::print_detailed(&project_contracts);
         }
         _ => {
-            OutputFormatter::print_summary(&all_contracts);
+            OutputFormatter::print_summary(&project_contracts);
         }
     }
 
+    if let Some(view) = &diamond_view {
+        DiamondAnalyzer::print_summary(view);
+    }
+    PragmaChecker::print_summary(&compiler_warnings, &source_units);
+    NatSpecCoverage::print_summary(&doc_coverage);
+    ReentrancyDetector::print_summary(&project_contracts);
+    AccessControlAnalyzer::print_summary(&project_contracts);
+    UpgradeabilityChecker::print_summary(&project_contracts);
+    LoopAnalyzer::print_summary(&project_contracts);
+
     // Export if requested
     if let Some(export_path) = export {
-        OutputFormatter::export_json(&all_contracts, &export_path)?;
+        OutputFormatter::export_json(&project_contracts, &export_path)?;
     }
 
     // Save markdown reports if requested
     if save_md {
-        OutputFormatter::save_markdown_reports(&all_contracts, &md_output)?;
+        OutputFormatter::save_markdown_reports(&project_contracts, &md_output)?;
+        OutputFormatter::save_source_units_report(&source_units, &md_output)?;
+        AbiGenerator::save_abi_files(&all_contracts, &source_units, &md_output)?;
+        StorageLayoutEngine::save_layout_file(&all_contracts, &md_output)?;
+        if let Some(view) = &diamond_view {
+            DiamondAnalyzer::save_reports(view, &md_output)?;
+        }
+        PragmaChecker::save_report(&compiler_warnings, &source_units, &md_output)?;
+        NatSpecCoverage::save_report(&doc_coverage, &md_output)?;
+        AccessControlAnalyzer::save_report(&project_contracts, &md_output)?;
+        JsonReportGenerator::save_json(&serde_json::to_value(&import_graph)?, &md_output.join("imports.json"))?;
+        println!("  {} imports.json", "✓".green());
     }
 
     // Generate contract relationships if requested
@@ -172,17 +233,35 @@ fn analyze_contracts(
                     &mut all_contracts[i],
                     &contract_asts[i],
                     &contracts_snapshot,
+                    &source_units,
                 );
                 all_external_calls.extend(external_calls);
             }
         }
 
+        let all_contracts: Vec<ContractInfo> = all_contracts.into_iter()
+            .filter(|c| !c.is_context_only)
+            .collect();
+        all_external_calls.retain(|c| all_contracts.iter().any(|p| p.name == c.source_contract));
+
         if all_external_calls.is_empty() {
             println!("  {} No external contract calls detected", "ℹ️".blue());
         } else {
             println!("  {} {} external call(s) detected", "✓".green(), all_external_calls.len());
         }
 
+        for call in all_external_calls.iter().filter(|c| !c.candidate_contracts.is_empty()) {
+            println!("  {} {}.{} calls {}.{}() through `{}`, implemented by several contracts: {}",
+                "⚠️".yellow(),
+                call.source_contract,
+                call.source_function,
+                call.target_variable,
+                call.target_function,
+                call.target_type,
+                call.candidate_contracts.join(", ")
+            );
+        }
+
         // Set up output directory
         let relations_dir = md_output.join("0_relations");
         std::fs::create_dir_all(&relations_dir)?;
@@ -260,3 +339,270 @@ fn analyze_contracts(
 
     Ok(())
 }
+
+/// Results of the analysis pipeline, for reporting
+struct AnalyzedProject {
+    contracts: Vec<ContractInfo>,
+    contract_asts: Vec<pt::ContractDefinition>, // Merged with inherited parts
+    source_units: Vec<SourceUnit>,
+    diamond_view: Option<DiamondView>,
+    compiler_warnings: Vec<String>,
+    doc_coverage: Vec<DocCoverage>,
+    import_graph: ImportGraph,
+    errors: Vec<(PathBuf, anyhow::Error)>,
+}
+
+/// Run every analysis pass over a loaded project, in dependency order
+fn analyze_project(project: LoadedProject) -> AnalyzedProject {
+    let LoadedProject {
+        contracts: mut all_contracts,
+        contract_asts,
+        mut source_units,
+        free_function_asts,
+        constant_asts,
+        import_graph,
+        errors,
+    } = project;
+
+    // Resolve inheritance project-wide, then analyze each contract with its merged view
+    let resolution = InheritanceResolver::resolve(&mut all_contracts, &contract_asts);
+    for warning in &resolution.warnings {
+        println!("  {} {}", "⚠️".yellow(), warning);
+    }
+    let contract_asts = resolution.merged_asts;
+    ConstantFolder::fold(&mut all_contracts, &contract_asts, &mut source_units, &constant_asts);
+    Erc7201Verifier::verify(&mut all_contracts);
+    TypeAnnotator::annotate(&mut all_contracts, &source_units);
+
+    // File-level definitions first: contracts look up free functions, constants and errors there
+    let units_snapshot = source_units.clone();
+    for (unit, asts) in source_units.iter_mut().zip(&free_function_asts) {
+        StateModificationAnalyzer::analyze_source_unit(unit, asts, &units_snapshot);
+    }
+
+    // Libraries first, so that callers see how library functions use their storage parameters
+    for analyze_libraries in [true, false] {
+        let contracts_snapshot = all_contracts.clone();
+        for (contract, ast) in all_contracts.iter_mut().zip(&contract_asts) {
+            if (contract.kind == ContractKind::Library) == analyze_libraries {
+                // Analyze state modifications and call chains
+                StateModificationAnalyzer::analyze(contract, ast, &source_units, &contracts_snapshot);
+            }
+        }
+    }
+
+    StateModificationAnalyzer::resolve_library_errors(&mut all_contracts);
+    StateModificationAnalyzer::record_source_unit_usages(&mut source_units, &all_contracts);
+    AbiGenerator::assign_selectors(&mut all_contracts, &source_units);
+    AbiGenerator::assign_file_level_selectors(&mut source_units, &all_contracts);
+    StorageLayoutEngine::compute(&mut all_contracts, &source_units);
+
+    // Proxies are linked before relations so that calls through them reach the implementation
+    for warning in ProxyDetector::detect_all(&mut all_contracts, &contract_asts, &ProxyDetector::load_links()) {
+        println!("  {} {}", "⚠️".yellow(), warning);
+    }
+    let diamond_view = DiamondAnalyzer::build(&mut all_contracts, &contract_asts);
+    let compiler_warnings = PragmaChecker::check(&mut all_contracts, &mut source_units);
+    let doc_coverage = NatSpecCoverage::check(&all_contracts);
+    AccessControlAnalyzer::analyze(&mut all_contracts, &contract_asts, &source_units);
+    UpgradeabilityChecker::check(&mut all_contracts, &contract_asts);
+    ReentrancyDetector::detect(&mut all_contracts, &contract_asts, &source_units);
+    LoopAnalyzer::analyze(&mut all_contracts, &contract_asts, &source_units);
+
+    AnalyzedProject {
+        contracts: all_contracts,
+        contract_asts,
+        source_units,
+        diamond_view,
+        compiler_warnings,
+        doc_coverage,
+        import_graph,
+        errors,
+    }
+}
+
+/// Diff the storage layouts of two source trees; returns false if any change is unsafe
+fn diff_storage(old: &Path, new: &Path, output: &Path) -> Result<bool> {
+    println!("{}", "🚀 Comparing storage layouts...".bold());
+    println!();
+
+    let mut versions = Vec::new();
+    for path in [old, new] {
+        println!("{} {}", "📦 Loading".bold(), path.display());
+        let Some(project) = load_project(path)? else {
+            anyhow::bail!("no contracts found in {}", path.display());
+        };
+        let LoadedProject { contracts: mut all_contracts, contract_asts, mut source_units, constant_asts, .. } = project;
+
+        // Layouts only need inheritance, type definitions and constant array lengths, not the full analysis
+        let resolution = InheritanceResolver::resolve(&mut all_contracts, &contract_asts);
+        for warning in &resolution.warnings {
+            println!("  {} {}", "⚠️".yellow(), warning);
+        }
+        ConstantFolder::fold(&mut all_contracts, &resolution.merged_asts, &mut source_units, &constant_asts);
+        StorageLayoutEngine::compute(&mut all_contracts, &source_units);
+        versions.push(all_contracts);
+        println!();
+    }
+
+    let diffs = StorageDiffer::compare(&versions[0], &versions[1]);
+    let only_in_old = StorageDiffer::only_in(&versions[0], &versions[1]);
+    let only_in_new = StorageDiffer::only_in(&versions[1], &versions[0]);
+
+    StorageDiffer::print_summary(&diffs);
+    for name in &only_in_old {
+        println!("  {} {} only exists in the old version", "ℹ️".blue(), name);
+    }
+    for name in &only_in_new {
+        println!("  {} {} only exists in the new version", "ℹ️".blue(), name);
+    }
+
+    println!();
+    StorageDiffer::save_reports(&diffs, old, new, &only_in_old, &only_in_new, output)?;
+
+    let is_safe = diffs.iter().all(|d| d.is_safe());
+    println!();
+    if is_safe {
+        println!("{}", "✅ No unsafe storage changes".green().bold());
+    } else {
+        println!("{}", "❌ Unsafe storage changes detected".red().bold());
+    }
+    Ok(is_safe)
+}
+
+/// Sources parsed from a directory, plus everything they import
+struct LoadedProject {
+    contracts: Vec<ContractInfo>,
+    contract_asts: Vec<pt::ContractDefinition>,
+    source_units: Vec<SourceUnit>,
+    free_function_asts: Vec<Vec<pt::FunctionDefinition>>,
+    constant_asts: Vec<Vec<pt::VariableDefinition>>, // File-level constants, per source unit
+    import_graph: ImportGraph,
+    errors: Vec<(PathBuf, anyhow::Error)>,
+}
+
+/// Scan `path` and parse each file and everything it imports; None if there is nothing to analyze
+fn load_project(path: &Path) -> Result<Option<LoadedProject>> {
+    // Scan for .sol files
+    let scanner = FileScanner::new(path.to_path_buf());
+    let sol_files = scanner.scan_contracts()?;
+
+    if sol_files.is_empty() {
+        println!("{}", "⚠️  No Solidity files found!".yellow());
+        println!("Looking in: {}", path.display());
+        return Ok(None);
+    }
+
+    println!("{} {} Solidity file(s)", "📂 Found".green(), sol_files.len());
+
+    // Parse each file and everything it imports; dependencies outside the scanned set
+    // (e.g. lib/, node_modules) are loaded as context-only sources
+    let resolver = ImportResolver::discover(path);
+    let mut all_contracts = Vec::new();
+    let mut contract_asts = Vec::new();
+    let mut source_units = Vec::new();
+    let mut free_function_asts = Vec::new();
+    let mut constant_asts = Vec::new();
+    let mut import_graph = ImportGraph {
+        root: ImportResolver::display_path(resolver.root()),
+        remappings: resolver.remappings().to_vec(),
+        ..Default::default()
+    };
+    if !import_graph.remappings.is_empty() {
+        println!("{} {} remapping(s) from {}", "🗺️  Loaded".green(), import_graph.remappings.len(), import_graph.root);
+    }
+    let mut errors = Vec::new();
+
+    // Canonical path -> path as reported
+    let mut loaded: HashMap<PathBuf, String> = HashMap::new();
+    let mut queue: VecDeque<(PathBuf, bool)> = VecDeque::new();
+    for file in &sol_files {
+        let canonical = std::fs::canonicalize(file).unwrap_or_else(|_| file.clone());
+        if loaded.insert(canonical, file.to_string_lossy().to_string()).is_none() {
+            queue.push_back((file.clone(), false));
+        }
+    }
+
+    while let Some((file, context_only)) = queue.pop_front() {
+        match SolidityParser::parse_file_with_ast(&file) {
+            Ok(mut parsed) => {
+                if context_only {
+                    println!("  {} {} {}", "↳".dimmed(), file.display(), "(context only)".dimmed());
+                } else {
+                    println!("  {} {}", "✓".green(), file.display());
+                }
+
+                for import in &mut parsed.source_unit.imports {
+                    match resolver.resolve(&file, &import.path) {
+                        Some(target) => {
+                            let reported = loaded.entry(target.clone()).or_insert_with(|| {
+                                let reported = ImportResolver::display_path(&target);
+                                queue.push_back((PathBuf::from(&reported), true));
+                                reported
+                            });
+                            import.resolved = Some(reported.clone());
+                        }
+                        None => import_graph.unresolved.push(ImportDiagnostic {
+                            file: parsed.source_unit.file_path.clone(),
+                            import_path: import.path.clone(),
+                            line_number: import.line_number,
+                            message: "no matching file (checked relative path, remappings, project root, node_modules and libs)".to_string(),
+                        }),
+                    }
+                }
+
+                for contract in &mut parsed.contracts {
+                    contract.is_context_only = context_only;
+                }
+                parsed.source_unit.is_context_only = context_only;
+                if context_only {
+                    import_graph.context_only.push(parsed.source_unit.file_path.clone());
+                }
+                import_graph.edges.insert(
+                    parsed.source_unit.file_path.clone(),
+                    parsed.source_unit.imports.iter().filter_map(|i| i.resolved.clone()).collect(),
+                );
+
+                all_contracts.extend(parsed.contracts);
+                contract_asts.extend(parsed.contract_asts);
+                source_units.push(parsed.source_unit);
+                free_function_asts.push(parsed.free_function_asts);
+                constant_asts.push(parsed.constant_asts);
+            }
+            Err(e) => {
+                println!("  {} {} - {}", "✗".red(), file.display(), e);
+                errors.push((file.clone(), e));
+            }
+        }
+    }
+
+    println!();
+
+    if !import_graph.unresolved.is_empty() {
+        println!("  {} {} unresolved import(s):", "⚠️".yellow(), import_graph.unresolved.len());
+        for diagnostic in &import_graph.unresolved {
+            println!("    {} {}:{} imports \"{}\"",
+                "→".dimmed(),
+                diagnostic.file,
+                diagnostic.line_number,
+                diagnostic.import_path
+            );
+        }
+        println!();
+    }
+
+    if all_contracts.iter().all(|c| c.is_context_only) {
+        println!("{}", "⚠️  No contracts found or all files had errors!".yellow());
+        return Ok(None);
+    }
+
+    Ok(Some(LoadedProject {
+        contracts: all_contracts,
+        contract_asts,
+        source_units,
+        free_function_asts,
+        constant_asts,
+        import_graph,
+        errors,
+    }))
+}
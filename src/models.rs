@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use crate::dataflow::DataFlowAnalysis;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractInfo {
     pub name: String,
     pub file_path: String,
+    pub kind: ContractKind,
+    pub is_abstract: bool,
+    pub base_contracts: Vec<String>, // Bases as declared after `is`, in source order
     pub state_variables: Vec<StateVariable>,
     pub structs: Vec<StructDef>,
     pub enums: Vec<EnumDef>,
@@ -12,8 +17,55 @@ pub struct ContractInfo {
     pub functions: Vec<FunctionDef>,
     pub modifiers: Vec<ModifierDef>,
     pub errors: Vec<ErrorDef>,
-    pub upgradeable_storage: Option<UpgradeableStorage>, // ERC-7201 pattern info
+    pub upgradeable_storage: Vec<UpgradeableStorage>, // ERC-7201 namespaced storage structs
     pub dataflow_analysis: Option<DataFlowAnalysis>, // Data flow and taint analysis
+    pub linearized_bases: Vec<String>, // C3 linearization of bases, most derived first (excludes self)
+    pub unresolved_bases: Vec<String>, // Bases in the linearization that are not part of the project
+    pub implementers: Vec<String>, // Concrete contracts inheriting this interface/abstract contract
+    pub is_context_only: bool, // Imported dependency loaded only to resolve names; not reported
+    pub using_directives: Vec<UsingDirective>, // `using ... for ...` declared in this contract
+    pub storage_layout: Option<StorageLayout>, // Sequential storage layout (None for interfaces and libraries)
+    pub proxy: Option<ProxyInfo>, // Proxy, UUPS implementation or beacon role
+    pub diamond_storage: Vec<DiamondStorage>, // Fixed-slot structs shared by Diamond facets (libraries only)
+    pub compiler: CompilerInfo, // Pragmas and SPDX license of the declaring file
+    pub natspec: Option<NatSpec>, // Doc comment above the contract
+    pub reentrancy: Vec<ReentrancyFinding>, // State written or events emitted after external calls
+    pub cross_reentrancy: Vec<CrossReentrancyFinding>, // Other entry points that see that state stale
+    pub access_control: Vec<EntryPointAccess>, // Who may call each state-changing entry point
+    pub unprotected_writes: Vec<UnprotectedWrite>, // Sensitive state any caller can change
+    pub initializers: Vec<Initializer>, // Functions that set the contract up in place of a constructor
+    pub upgrade_issues: Vec<UpgradeIssue>, // Initializer and storage hygiene problems
+    pub loops: Vec<LoopFinding>, // Loops whose length or per-iteration work callers can drive
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ContractKind {
+    Contract,
+    Interface,
+    Library,
+}
+
+impl ContractInfo {
+    /// Human-readable kind, e.g. "abstract contract"
+    pub fn kind_label(&self) -> &'static str {
+        match (self.kind, self.is_abstract) {
+            (ContractKind::Contract, true) => "abstract contract",
+            (ContractKind::Contract, false) => "contract",
+            (ContractKind::Interface, _) => "interface",
+            (ContractKind::Library, _) => "library",
+        }
+    }
+
+    /// True if the contract can be deployed and therefore be the target of a call
+    pub fn is_concrete(&self) -> bool {
+        self.kind == ContractKind::Contract && !self.is_abstract
+    }
+
+    /// The public state variable a function is the implicit getter of
+    pub fn getter_variable(&self, func: &FunctionDef) -> Option<&StateVariable> {
+        self.state_variables.iter()
+            .find(|v| v.name == func.name && v.visibility == "public" && v.line_number == func.line_number)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +78,42 @@ pub struct StateVariable {
     pub line_number: usize,
     pub modification_chains: Vec<ModificationChain>,
     pub read_chains: Vec<ModificationChain>, // Functions that read this variable (reusing ModificationChain structure)
+    pub inherited_from: Option<String>, // Base contract that declares this variable (None if declared locally)
+    pub slot: Option<u64>, // Storage slot; None for constants, immutables and namespaced storage
+    pub offset: Option<usize>, // Byte offset within the slot (packed variables share a slot)
+    pub natspec: Option<NatSpec>,
+    pub resolved_type: Option<SolType>, // `var_type` resolved against the project's declarations
+    pub value: Option<ConstantValue>, // Folded value of a constant, or of an immutable set to a constant expression
+    pub assignments: Vec<ImmutableAssignment>, // Constructor assignments of an immutable
+}
+
+// Compile-time value of a constant expression
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum ConstantValue {
+    Integer(String), // Decimal, with a leading '-' when negative
+    Bool(bool),
+    String(String),
+    Bytes(String),   // 0x-prefixed hex, for bytesN, bytes and hashes
+    Address(String), // 0x-prefixed, 40 hex digits
+}
+
+impl fmt::Display for ConstantValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstantValue::Integer(value) | ConstantValue::Bytes(value) | ConstantValue::Address(value) => write!(f, "{}", value),
+            ConstantValue::Bool(value) => write!(f, "{}", value),
+            ConstantValue::String(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+// A constructor statement setting an immutable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImmutableAssignment {
+    pub function: String,   // e.g. "constructor(address)" or "Base.constructor(address)"
+    pub expression: String, // Assigned expression as written
+    pub line_number: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +143,11 @@ pub struct EventDef {
     pub parameters: Vec<EventParam>,
     pub line_number: usize,
     pub emitted_in: Vec<String>, // Functions that emit this event
+    pub inherited_from: Option<String>, // Base contract that declares this event
+    pub is_anonymous: bool,
+    pub signature: Option<String>, // ABI signature, e.g. "Transfer(address,address,uint256)"
+    pub topic0: Option<String>,    // keccak256 of the signature (None for anonymous events)
+    pub natspec: Option<NatSpec>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +160,7 @@ pub struct EventParam {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionDef {
     pub name: String,
+    pub signature: String, // Canonical signature, e.g. "deposit(uint256,address)"
     pub visibility: String,
     pub state_mutability: String,
     pub parameters: Vec<String>,
@@ -76,21 +170,43 @@ pub struct FunctionDef {
     pub modifies_state_fields: Vec<String>, // Granular field-level modifications (e.g., "lpInfo.consolidatedShares")
     pub reads_states: Vec<String>, // State variables this function reads from (non-modifying access)
     pub calls_functions: Vec<String>, // Other functions this function calls
+    pub calls_free_functions: Vec<String>, // File-level (free) functions this function calls
+    pub reads_file_constants: Vec<String>, // File-level constants this function reads
     pub external_calls: Vec<ExternalCall>, // External contract calls this function makes
     pub storage_params: Vec<StorageParamInfo>, // Storage reference parameters
     pub uses_modifiers: Vec<String>,  // Modifiers applied to this function
     pub modifier_order: Vec<String>,  // Modifiers in execution order
+    pub modifier_effects: Vec<ModifierEffect>, // What applied modifiers do around the body, in execution order
     pub emits_events: Vec<String>,    // Events emitted by this function
     pub uses_errors: Vec<String>,     // Custom errors thrown by this function
-    pub has_unchecked: bool,          // Whether function contains unchecked blocks
+    pub has_unchecked: bool,          // Arithmetic can wrap: `unchecked` blocks, or any arithmetic when the pragma allows < 0.8.0
+    pub assembly: Option<AssemblyInfo>, // Inline assembly in the body (None if there is none)
     pub return_value_usage: Vec<ReturnValueUsage>, // How return values from calls are used
     pub ignored_returns: Vec<IgnoredReturn>, // Function calls whose return values are ignored
+    pub inherited_from: Option<String>, // Base contract that declares this function
+    pub abi_signature: Option<String>, // Signature as hashed by the ABI (structs as tuples, contracts as address)
+    pub selector: Option<String>,      // 4-byte selector for public/external functions (e.g., "0xa9059cbb")
+    pub natspec: Option<NatSpec>,      // Doc comment (a public variable's getter carries the variable's)
+    pub parameter_types: Vec<SolType>, // `parameters` resolved against the project's declarations
+    pub return_types: Vec<SolType>,    // `returns` resolved likewise
+    pub narrowing_casts: Vec<TypeCast>, // Explicit conversions that can truncate or change sign
+}
+
+// Explicit conversion to a smaller or differently signed type, e.g. `uint128(amount)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeCast {
+    pub expression: String, // e.g. "uint128(amount)"
+    pub from: String,       // Inferred type of the converted value, e.g. "uint256"
+    pub to: String,
+    pub line_number: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageParamInfo {
     pub param_index: usize,    // Position in parameter list (0-based)
     pub param_name: String,    // Parameter name (e.g., "_lp")
+    pub is_modified: bool,     // Function writes through this reference
+    pub is_read: bool,         // Function reads through this reference
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,6 +215,25 @@ pub struct ModifierDef {
     pub parameters: Vec<String>,
     pub line_number: usize,
     pub used_in: Vec<String>, // Functions that use this modifier
+    pub inherited_from: Option<String>, // Base contract that declares this modifier
+    pub effects: Vec<ModifierEffect>,   // Code before and after the `_` placeholder
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ModifierPhase {
+    BeforeBody, // Statements preceding `_`
+    AfterBody,  // Statements following `_`
+}
+
+// State accesses and calls of one side of a modifier's `_`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifierEffect {
+    pub modifier: String,
+    pub phase: ModifierPhase,
+    pub modifies_states: Vec<String>,
+    pub reads_states: Vec<String>,
+    pub calls_functions: Vec<String>,
+    pub external_calls: Vec<ExternalCall>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +243,11 @@ pub struct ErrorDef {
     pub line_number: usize,
     pub used_in: Vec<String>, // Functions that throw this error
     pub is_inherited: bool,    // True if error is used but not defined locally
+    pub inherited_from: Option<String>, // Base contract that declares this error (if known)
+    pub declared_at: Option<String>, // "path:line" of the declaration when it lives outside this contract
+    pub signature: Option<String>, // ABI signature (None if the declaration is not part of the project)
+    pub selector: Option<String>,  // 4-byte error selector
+    pub natspec: Option<NatSpec>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,11 +256,157 @@ pub struct ErrorParam {
     pub param_type: String,
 }
 
+// Everything declared at file level in one .sol file, outside any contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceUnit {
+    pub file_path: String,
+    pub functions: Vec<FunctionDef>,       // Free functions
+    pub constants: Vec<StateVariable>,     // File-level constants
+    pub structs: Vec<StructDef>,
+    pub enums: Vec<EnumDef>,
+    pub errors: Vec<ErrorDef>,
+    pub events: Vec<EventDef>,
+    pub user_types: Vec<UserDefinedType>,  // `type Price is uint256;`
+    pub usages: BTreeMap<String, Vec<String>>, // Item name -> "Contract.function(...)" that use it
+    pub imports: Vec<ImportDirective>,
+    pub is_context_only: bool, // Imported dependency loaded only to resolve names; not reported
+    pub using_directives: Vec<UsingDirective>, // File-level `using ... for ...`
+    pub compiler: CompilerInfo,
+    #[serde(skip)]
+    pub line_starts: Vec<usize>, // Byte offset at which each line starts, to map AST locations to lines
+}
+
+impl SourceUnit {
+    /// 1-based line containing the byte offset
+    pub fn line_at(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset).max(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+            && self.constants.is_empty()
+            && self.structs.is_empty()
+            && self.enums.is_empty()
+            && self.errors.is_empty()
+            && self.events.is_empty()
+            && self.user_types.is_empty()
+    }
+}
+
+// `using Library for Type;` or `using {f, Library.g} for Type global;`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsingDirective {
+    pub library: Option<String>,     // Bound library (None when functions are listed)
+    pub functions: Vec<String>,      // Listed functions, e.g. "add" or "Library.add"
+    pub target_type: Option<String>, // Bound type (None for `*`)
+    pub is_global: bool,
+    pub line_number: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportDirective {
+    pub path: String,             // As written, e.g. "@openzeppelin/contracts/access/Ownable.sol"
+    pub line_number: usize,
+    pub resolved: Option<String>, // File it resolves to (None if unresolved)
+}
+
+// User-defined value type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDefinedType {
+    pub name: String,
+    pub underlying: String, // Elementary type it wraps, e.g. "uint256"
+    pub line_number: usize,
+}
+
+// Storage layout in the shape of `forge inspect <Contract> storageLayout`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageLayout {
+    pub storage: Vec<StorageEntry>,
+    pub types: BTreeMap<String, StorageType>, // Type id -> description, e.g. "t_uint256"
+    pub missing_bases: Vec<String>, // Bases outside the project: their variables are missing and slots are shifted
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageEntry {
+    pub contract: String, // Declaring contract as "path:Name"
+    pub label: String,
+    pub offset: usize,
+    pub slot: u64, // Relative to the containing struct for struct members
+    pub type_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageType {
+    pub encoding: String, // "inplace", "mapping", "dynamic_array" or "bytes"
+    pub label: String, // Type as written by solc, e.g. "mapping(address => uint256)"
+    pub number_of_bytes: u64,
+    pub key: Option<String>, // Mappings
+    pub value: Option<String>, // Mappings
+    pub base: Option<String>, // Arrays
+    pub members: Vec<StorageEntry>, // Structs
+}
+
+// Storage layout changes of one contract between two versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageDiff {
+    pub contract: String,
+    pub changes: Vec<StorageChange>,
+    pub missing_bases: Vec<String>, // Bases outside either project; their variables can't be compared
+}
+
+impl StorageDiff {
+    pub fn is_safe(&self) -> bool {
+        self.changes.iter().all(|c| c.is_safe)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageChange {
+    pub kind: StorageChangeKind,
+    pub label: String, // Variable, "Struct.field" or namespace
+    pub before: Option<String>, // e.g. "slot 3, offset 0: uint256"
+    pub after: Option<String>,
+    pub is_safe: bool,
+    pub note: String, // Why the change is (un)safe
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StorageChangeKind {
+    Removed,          // Variable no longer declared
+    Inserted,         // New variable before existing ones
+    Appended,         // New variable after all existing ones (or inside a gap)
+    Reordered,        // Variables swapped order
+    Shifted,          // Same order but a different slot/offset
+    Retyped,          // Same slot, different type
+    Renamed,          // Same slot and type, different name
+    GapResized,       // `__gap` array changed length
+    StructChanged,    // Members of a struct stored in sequential storage changed
+    NamespaceChanged, // ERC-7201 namespace or its struct fields changed
+}
+
+impl StorageChangeKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StorageChangeKind::Removed => "removed",
+            StorageChangeKind::Inserted => "inserted",
+            StorageChangeKind::Appended => "appended",
+            StorageChangeKind::Reordered => "reordered",
+            StorageChangeKind::Shifted => "shifted",
+            StorageChangeKind::Retyped => "retyped",
+            StorageChangeKind::Renamed => "renamed",
+            StorageChangeKind::GapResized => "gap resized",
+            StorageChangeKind::StructChanged => "struct changed",
+            StorageChangeKind::NamespaceChanged => "namespace changed",
+        }
+    }
+}
+
 // Represents a modification chain: StateVar -> DirectFunc -> CallerFunc -> ExternalFunc
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModificationChain {
     pub direct_modifier: String,        // Function that directly modifies the state
     pub direct_modifier_visibility: String,
+    pub via_modifier: Option<String>,   // Access happens only inside this modifier of the direct function
     pub call_chain: Vec<FunctionCall>,  // Chain of callers leading to this modification
 }
 
@@ -138,23 +424,423 @@ pub struct ExternalCall {
     pub target_variable: String,      // Variable name (e.g., "stakingToken")
     pub target_type: String,          // Type (e.g., "IERC20")
     pub target_function: String,      // Function called (e.g., "transferFrom")
+    pub target_signature: Option<String>, // Resolved overload in the target (e.g., "transferFrom(address,address,uint256)")
     pub target_contract: Option<String>, // Matched contract name if found
+    pub candidate_contracts: Vec<String>, // All implementers when the target type is ambiguous
     pub state_mutability: String,     // "view", "pure", "nonpayable", "payable", or "unknown"
-    pub line_number: usize,
+    pub line_number: usize,           // Line of the call expression
+    pub receiver_kind: ReceiverKind,  // How the called address was obtained
     pub target_modifies_states: Vec<String>, // State variables modified in target function
     pub target_reads_states: Vec<String>,    // State variables read in target function
+    pub call_kind: ExternalCallKind,
+    pub via_modifier: Option<String>, // Made by this modifier of the source function
+    pub via_proxy: Option<String>, // Proxy the call goes through; `target_contract` is then its implementation
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ExternalCallKind {
+    Call,         // Regular message call into another contract
+    DelegateCall, // Public/external library function: runs in the caller's storage context
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ReceiverKind {
+    StateVariable, // `token.transfer(...)`
+    Parameter,     // Function or modifier parameter
+    LocalVariable, // Local variable, including named returns
+    Cast,          // `IERC20(addr).transfer(...)`, `address(x).call(...)`
+    Element,       // Mapping value or array element: `pools[id].sync()`
+    StructField,   // `config.oracle.latestAnswer()`
+    CallResult,    // Chained call: `factory.getPool(a, b).swap(...)`
+    Global,        // `msg.sender`, `tx.origin` or `block.coinbase`
+    This,          // `this.fn()`
+    Library,       // Public/external library function
+    Assembly,      // Low-level call in inline assembly
+}
+
+impl ReceiverKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReceiverKind::StateVariable => "state variable",
+            ReceiverKind::Parameter => "parameter",
+            ReceiverKind::LocalVariable => "local variable",
+            ReceiverKind::Cast => "cast",
+            ReceiverKind::Element => "mapping/array element",
+            ReceiverKind::StructField => "struct field",
+            ReceiverKind::CallResult => "call result",
+            ReceiverKind::Global => "global",
+            ReceiverKind::This => "this",
+            ReceiverKind::Library => "library",
+            ReceiverKind::Assembly => "assembly",
+        }
+    }
+}
+
+impl ExternalCall {
+    /// Called function as shown in reports: the resolved signature if known, else `name()`
+    pub fn target_display(&self) -> String {
+        self.target_signature.clone()
+            .unwrap_or_else(|| format!("{}()", self.target_function))
+    }
+}
+
+// What the inline assembly blocks of one function do
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssemblyInfo {
+    pub blocks: usize,                     // Number of `assembly { ... }` blocks
+    pub memory_safe: bool,                 // Every block is marked ("memory-safe")
+    pub storage_accesses: Vec<SlotAccess>, // sload/sstore/tload/tstore
+    pub slot_pointers: Vec<SlotPointer>,   // Storage pointers re-targeted with `p.slot := ...`
+    pub calls: Vec<AssemblyCall>,          // call/staticcall/delegatecall/callcode/create/create2/selfdestruct
+    pub memory_tricks: Vec<String>,        // e.g. "writes free memory pointer", "return halts execution"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotAccess {
+    pub opcode: String,           // "sload", "sstore", "tload" or "tstore"
+    pub slot: String,             // Slot expression as written (e.g., "owner.slot")
+    pub resolved: Option<String>, // State variable or namespaced storage struct the slot maps to
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotPointer {
+    pub pointer: String,          // Storage pointer variable (e.g., "$")
+    pub slot: String,             // Slot expression assigned to it
+    pub resolved: Option<String>, // State variable or namespaced storage struct it now points at
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssemblyCall {
+    pub opcode: String,        // "call", "staticcall", "delegatecall", "callcode", "create", "create2" or "selfdestruct"
+    pub target: String,        // Address expression as written (empty for create/create2)
+    pub value: Option<String>, // Wei sent, for opcodes that take a value
+}
+
+// Upgradeability role of a contract and what identified it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyInfo {
+    pub kind: ProxyKind,
+    pub evidence: Vec<String>,          // e.g. "EIP-1967 implementation slot", "fallback delegatecall"
+    pub upgrade_functions: Vec<String>, // Functions that change the implementation or beacon
+    pub implementation: Option<String>, // Implementation contract linked in .analyzerproxies
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ProxyKind {
+    Transparent,        // Delegating proxy with an admin (EIP-1967 admin slot, `ifAdmin`)
+    Beacon,             // Delegating proxy that asks a beacon for the implementation
+    Erc1967,            // Delegating proxy with only the implementation slot (UUPS deployments)
+    Generic,            // Delegates in its fallback without EIP-1967 slots
+    Diamond,            // EIP-2535: the fallback picks a facet per selector from Diamond storage
+    UupsImplementation, // Logic contract that upgrades itself (`upgradeToAndCall`, `_authorizeUpgrade`)
+    UpgradeableBeacon,  // Holds the implementation for beacon proxies
+}
+
+impl ProxyKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProxyKind::Transparent => "transparent proxy",
+            ProxyKind::Beacon => "beacon proxy",
+            ProxyKind::Erc1967 => "ERC-1967 proxy",
+            ProxyKind::Generic => "delegating proxy",
+            ProxyKind::Diamond => "EIP-2535 diamond",
+            ProxyKind::UupsImplementation => "UUPS implementation",
+            ProxyKind::UpgradeableBeacon => "upgradeable beacon",
+        }
+    }
+
+    /// Whether calls to the contract run another contract's code
+    pub fn delegates(&self) -> bool {
+        matches!(self, ProxyKind::Transparent | ProxyKind::Beacon | ProxyKind::Erc1967 | ProxyKind::Generic | ProxyKind::Diamond)
+    }
+}
+
+// A `for`/`while` loop that can run out of gas or be made to revert
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoopFinding {
+    pub function: String,          // Function declaring the loop
+    pub line_number: usize,
+    pub severity: LoopSeverity,
+    pub bound: LoopBound,
+    pub risks: Vec<LoopRisk>,      // Work done per iteration, including in called functions
+    pub entry_points: Vec<String>, // Public/external functions that reach the loop
+    pub growable_by: Vec<String>,  // Entry points without access guards that grow the bound
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "expression", rename_all = "snake_case")]
+pub enum LoopBound {
+    StorageArray(String),  // Length of a storage array, e.g. "holders.length"
+    StateVariable(String), // Other storage value, e.g. "userCount"
+    Parameter(String),     // Chosen by the caller, e.g. "ids.length"
+    Fixed(String),         // Literals, constants and immutables
+    Other(String),         // Call results, locals and conditions without a bound
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoopRisk {
+    pub kind: LoopRiskKind,
+    pub target: String,      // Call expression or written state variable
+    pub via: Option<String>, // Internal function doing it, when not the loop body itself
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopRiskKind {
+    ExternalCall,  // One reverting callee blocks every iteration
+    ValueTransfer, // Push payment: a recipient that rejects ether blocks the rest
+    StorageWrite,  // SSTORE per iteration, the costliest gas item
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LoopSeverity {
+    Low,    // Bounded by the caller's own input, or without costly work
+    Medium, // Grows without bound, or calls out on every iteration
+    High,   // Anyone can grow it and every iteration calls out or writes storage
+}
+
+impl LoopSeverity {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            LoopSeverity::Low => "⚠️",
+            LoopSeverity::Medium => "🟡",
+            LoopSeverity::High => "🔴",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoopSeverity::Low => "LOW",
+            LoopSeverity::Medium => "MEDIUM",
+            LoopSeverity::High => "HIGH",
+        }
+    }
+}
+
+// A function that sets up a contract deployed behind a proxy (or initialized in steps)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Initializer {
+    pub function: String,
+    pub guard: Option<String>,     // What stops a second call: "initializer", "reinitializer(2)", "`initialized` flag" or an access guard
+    pub parent_inits: Vec<String>, // `__X_init` functions it calls, directly or through other init functions
+    pub line_number: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeIssue {
+    pub kind: UpgradeIssueKind,
+    pub subject: String, // Function or state variable concerned (the contract for a missing constructor)
+    pub detail: String,
+    pub line_number: usize, // 0 when there is no line to point at
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeIssueKind {
+    UnguardedInitializer,       // Anyone can call it, or call it again
+    MissingDisableInitializers, // The implementation itself can be initialized by anyone
+    MissingParentInit,          // An upgradeable base is never initialized
+    ConstructorWritesState,     // Lands in the implementation's storage, not the proxy's
+    ImmutableVariable,          // Baked into the implementation's code and shared by every proxy
+    MissingStorageGap,          // Base with sequential storage and no room to grow
+}
+
+impl UpgradeIssueKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            UpgradeIssueKind::UnguardedInitializer => "Unguarded initializer",
+            UpgradeIssueKind::MissingDisableInitializers => "Implementation can be initialized",
+            UpgradeIssueKind::MissingParentInit => "Parent initializer not called",
+            UpgradeIssueKind::ConstructorWritesState => "Constructor writes state",
+            UpgradeIssueKind::ImmutableVariable => "Immutable in upgradeable contract",
+            UpgradeIssueKind::MissingStorageGap => "Missing storage gap",
+        }
+    }
 }
 
 // Represents an upgradeable storage pattern (ERC-7201)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpgradeableStorage {
     pub namespace: String,                    // e.g., "openzeppelin.storage.ERC20"
+    pub formula: String,                      // Formula the namespace id is hashed with, e.g. "erc7201"
     pub storage_struct: String,               // Name of the storage struct (e.g., "ERC20Storage")
     pub storage_location_constant: String,    // Name of the constant (e.g., "ERC20StorageLocation")
     pub storage_slot: String,                 // The actual slot value (bytes32 hex)
     pub accessor_function: String,            // Name of the getter function (e.g., "_getERC20Storage")
     pub struct_fields: Vec<StructMember>,     // Fields in the storage struct
+    pub field_layout: Vec<StorageEntry>,      // Slots and offsets of the fields, relative to the namespace
     pub line_number: usize,                   // Line where the struct is defined
+    pub expected_slot: Option<String>,        // Slot the ERC-7201 formula gives for the namespace
+    pub issues: Vec<String>,                  // Slot mismatch, duplicate namespace, missing annotation...
+}
+
+// Compiler directives of a source file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompilerInfo {
+    pub spdx_license: Option<String>, // e.g., "MIT"
+    pub solidity: Option<String>,     // Version requirement as written, e.g. "^0.8.20" (several pragmas are joined)
+    pub abicoder: Option<String>,     // "v1" or "v2" when declared
+    pub experimental: Vec<String>,    // `pragma experimental ...` features
+    pub issues: Vec<String>,          // Features the version requirement doesn't support
+}
+
+// A type resolved against the declarations visible where it is written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SolType {
+    Elementary { name: String }, // e.g. "uint256", "address payable", "bytes", "string"
+    Mapping { key: Box<SolType>, value: Box<SolType> },
+    Array { base: Box<SolType>, length: Option<String> }, // Length as written; None for dynamic arrays
+    Struct { name: String, owner: Option<String> }, // Owner is the declaring contract (None at file level)
+    Enum { name: String, owner: Option<String> },
+    Contract { name: String }, // Contract, interface or library
+    UserDefined { name: String, underlying: String }, // `type Price is uint128;`
+    Function { params: Vec<SolType>, returns: Vec<SolType>, external: bool },
+    Unresolved { name: String }, // Not declared in the project, most likely an interface
+}
+
+impl SolType {
+    /// Value type of a mapping, element type of an array or `bytes1` for `bytes`
+    pub fn element(&self) -> Option<SolType> {
+        match self {
+            SolType::Mapping { value, .. } => Some(value.as_ref().clone()),
+            SolType::Array { base, .. } => Some(base.as_ref().clone()),
+            SolType::Elementary { name } if name == "bytes" => Some(SolType::elementary("bytes1")),
+            _ => None,
+        }
+    }
+
+    /// Signedness and width of `uintN`/`intN`
+    pub fn integer(&self) -> Option<(bool, u16)> {
+        let SolType::Elementary { name } = self else {
+            return None;
+        };
+        let bits = |prefix: &str| name.strip_prefix(prefix).and_then(|n| n.parse().ok());
+        bits("uint").map(|b| (false, b)).or_else(|| bits("int").map(|b| (true, b)))
+    }
+
+    /// Width in bytes of `bytesN`
+    pub fn fixed_bytes(&self) -> Option<u8> {
+        match self {
+            SolType::Elementary { name } => name.strip_prefix("bytes")?.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn is_address(&self) -> bool {
+        matches!(self, SolType::Elementary { name } if name == "address" || name == "address payable")
+    }
+
+    /// Name a call through a value of this type is made on: the contract, or "address"
+    pub fn receiver_name(&self) -> Option<String> {
+        match self {
+            SolType::Contract { name } | SolType::Unresolved { name } => Some(name.clone()),
+            _ if self.is_address() => Some("address".to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn elementary(name: &str) -> SolType {
+        SolType::Elementary { name: name.to_string() }
+    }
+}
+
+impl fmt::Display for SolType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |types: &[SolType]| types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(",");
+        match self {
+            SolType::Elementary { name } | SolType::Contract { name } | SolType::Unresolved { name } => write!(f, "{}", name),
+            SolType::UserDefined { name, .. } => write!(f, "{}", name),
+            SolType::Mapping { key, value } => write!(f, "mapping({} => {})", key, value),
+            SolType::Array { base, length } => write!(f, "{}[{}]", base, length.as_deref().unwrap_or("")),
+            SolType::Struct { name, owner } | SolType::Enum { name, owner } => match owner {
+                Some(owner) => write!(f, "{}.{}", owner, name),
+                None => write!(f, "{}", name),
+            },
+            SolType::Function { params, returns, external } => {
+                write!(f, "function({})", list(params))?;
+                if *external {
+                    write!(f, " external")?;
+                }
+                if !returns.is_empty() {
+                    write!(f, " returns ({})", list(returns))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// NatSpec tags of a `///` or `/** */` doc comment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NatSpec {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub notice: Option<String>,           // Untagged text counts as @notice
+    pub dev: Option<String>,
+    pub params: Vec<(String, String)>,    // @param name and description, in written order
+    pub returns: Vec<String>,             // @return descriptions, in written order
+    pub inheritdoc: Option<String>,       // Base contract named by @inheritdoc
+    pub custom: BTreeMap<String, String>, // @custom:<tag> -> text
+}
+
+impl NatSpec {
+    /// True if the comment says what the item does (@notice or @dev)
+    pub fn has_description(&self) -> bool {
+        self.notice.is_some() || self.dev.is_some()
+    }
+}
+
+// Documentation coverage of one contract's own declarations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocCoverage {
+    pub contract: String,
+    pub file_path: String,
+    pub documented_functions: usize, // Public/external functions with a description (own or inherited)
+    pub total_functions: usize,
+    pub documented_events: usize,
+    pub total_events: usize,
+    pub documented_errors: usize,
+    pub total_errors: usize,
+    pub issues: Vec<String>, // Missing docs and @param/@return/@inheritdoc mismatches
+}
+
+// Diamond storage (EIP-2535): a struct a library pins to a fixed slot with `ds.slot := position`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiamondStorage {
+    pub storage_struct: String,          // e.g., "DiamondStorage"
+    pub slot_constant: String,           // e.g., "DIAMOND_STORAGE_POSITION"
+    pub slot: String,                    // Slot value (bytes32 hex), empty if not a literal or keccak256("id")
+    pub slot_id: Option<String>,         // String hashed into the slot (e.g., "diamond.standard.diamond.storage")
+    pub accessor_function: String,       // e.g., "diamondStorage"
+    pub fields: Vec<StructMember>,
+    pub line_number: usize,
+}
+
+// Combined view of the facets sharing Diamond storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiamondView {
+    pub diamonds: Vec<String>,               // Contracts dispatching to facets from their fallback
+    pub storages: Vec<String>,               // Shared storage structs as "Library.Struct"
+    pub facets: Vec<String>,
+    pub selectors: Vec<DiamondSelector>,     // Sorted by selector
+    pub clashes: Vec<SelectorClash>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiamondSelector {
+    pub selector: String,                    // e.g., "0xf2fde38b"
+    pub facet: String,
+    pub signature: String,
+    pub reads: Vec<String>,                  // Shared storage fields read, as "Library.Struct.field"
+    pub writes: Vec<String>,                 // Shared storage fields written
+}
+
+// A selector exposed by several facets: only one of them can be registered in the diamond
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectorClash {
+    pub selector: String,
+    pub functions: Vec<String>,              // "Facet.signature" of every function with the selector
 }
 
 // Represents how a return value from a function call is used
@@ -202,6 +888,136 @@ pub enum IgnoredReturnSeverity {
     High,     // Critical - likely a bug (transfer, approve, etc.)
 }
 
+// A state-changing public/external function and the callers it admits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryPointAccess {
+    pub function: String,         // Signature, e.g. "setFee(uint256)"
+    pub guards: Vec<AccessGuard>, // Empty when anyone may call it
+    pub writes: Vec<String>,      // State variables written, including through internal calls and modifiers
+}
+
+// A check restricting `msg.sender`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessGuard {
+    pub role: String,          // Who passes it, e.g. "owner", "MINTER_ROLE", "pool"
+    pub kind: AccessGuardKind,
+    pub source: String,        // e.g. "onlyRole(MINTER_ROLE)", "require(msg.sender == pool)", "_checkOwner()"
+    pub line_number: usize,    // Line of the check in the file declaring it
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessGuardKind {
+    Modifier,      // Applied modifier
+    InlineCheck,   // `require`/`if ... revert` on `msg.sender` in the body
+    InternalCheck, // Internal call that performs the check, e.g. `_checkOwner()`
+}
+
+// A sensitive state variable written by an entry point without access guards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnprotectedWrite {
+    pub function: String,
+    pub variable: String,
+    pub category: SensitiveState,
+    pub line_number: usize, // Line of the function
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveState {
+    Ownership, // Owner, admin, governance and other roles
+    Pause,     // Pausing or emergency flags
+    Fee,       // Fees, rates and protocol parameters in basis points
+    Address,   // Other addresses and contract references
+}
+
+impl SensitiveState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SensitiveState::Ownership => "ownership",
+            SensitiveState::Pause => "pause flag",
+            SensitiveState::Fee => "fee",
+            SensitiveState::Address => "address",
+        }
+    }
+}
+
+// An effect that runs after an external call of the same entry point, while the callee can
+// still re-enter and observe the state before it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReentrancyFinding {
+    pub function: String,              // Entry point, e.g. "withdraw(uint256)"
+    pub severity: ReentrancySeverity,
+    pub call: String,                  // External call, e.g. "msg.sender.call{value: amount}(\"\")"
+    pub call_line: usize,
+    pub sends_value: bool,             // The call transfers ether
+    pub effect: ReentrancyEffect,
+    pub effect_line: usize,
+    pub via: Vec<String>,              // Internal functions and modifiers the effect is reached through
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum ReentrancyEffect {
+    StateWrite(String), // Written state variable
+    Event(String),      // Emitted event
+}
+
+// Another entry point that uses a state variable while an external call of `window_function`
+// runs and before that function updates it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossReentrancyFinding {
+    pub function: String,              // Entry point a re-entering callee can use, e.g. "claim()"
+    pub kind: CrossReentrancyKind,
+    pub severity: ReentrancySeverity,
+    pub variable: String,              // State variable that is stale during the call
+    pub access: StateAccess,           // What `function` does with it
+    pub window_function: String,       // Entry point making the call, e.g. "withdraw()"
+    pub call: String,
+    pub call_line: usize,
+    pub write_line: usize,             // Where `window_function` updates `variable` after the call
+    pub consumers: Vec<String>,        // Functions of other contracts calling `function`, e.g. "Lender.borrow(uint256)"
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossReentrancyKind {
+    CrossFunction, // State-changing function of the same contract
+    ReadOnly,      // View function, possibly consumed by other contracts
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StateAccess {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReentrancySeverity {
+    Low,    // Event emitted late, or the callee is fixed or gets too little gas to re-enter
+    Medium, // Callee is held in mutable storage
+    High,   // Callee is chosen by the caller
+}
+
+impl ReentrancySeverity {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            ReentrancySeverity::Low => "⚠️",
+            ReentrancySeverity::Medium => "🟡",
+            ReentrancySeverity::High => "🔴",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReentrancySeverity::Low => "LOW",
+            ReentrancySeverity::Medium => "MEDIUM",
+            ReentrancySeverity::High => "HIGH",
+        }
+    }
+}
+
 impl IgnoredReturnSeverity {
     pub fn emoji(&self) -> &'static str {
         match self {
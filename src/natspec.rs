@@ -0,0 +1,291 @@
+use crate::models::*;
+use anyhow::Result;
+use colored::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Entry points without a name to document
+const SPECIAL_FUNCTIONS: &[&str] = &["constructor", "fallback", "receive"];
+
+/// Measures how much of each contract's public surface is documented with NatSpec and checks
+/// `@param`, `@return` and `@inheritdoc` against the declarations they describe
+pub struct NatSpecCoverage;
+
+impl NatSpecCoverage {
+    pub fn check(contracts: &[ContractInfo]) -> Vec<DocCoverage> {
+        let by_name: HashMap<&str, &ContractInfo> = contracts.iter()
+            .map(|c| (c.name.as_str(), c))
+            .collect();
+
+        contracts.iter()
+            .filter(|c| !c.is_context_only)
+            .map(|contract| Self::check_contract(contract, &by_name))
+            .collect()
+    }
+
+    fn check_contract(contract: &ContractInfo, by_name: &HashMap<&str, &ContractInfo>) -> DocCoverage {
+        let mut issues = Vec::new();
+        let mut documented_functions = 0;
+        let mut total_functions = 0;
+
+        let functions = contract.functions.iter()
+            .filter(|f| f.inherited_from.is_none())
+            .filter(|f| matches!(f.visibility.as_str(), "public" | "external"))
+            .filter(|f| !SPECIAL_FUNCTIONS.contains(&f.name.as_str()));
+        for func in functions {
+            total_functions += 1;
+            let docs = Self::resolved_docs(contract, &func.signature, &func.natspec, by_name, &mut issues);
+            let Some(docs) = docs else {
+                issues.push(format!("`{}` has no NatSpec", func.signature));
+                continue;
+            };
+            if docs.has_description() {
+                documented_functions += 1;
+            } else {
+                issues.push(format!("`{}` has no `@notice` or `@dev`", func.signature));
+            }
+
+            // Docs inherited without `@inheritdoc` are checked where they are written
+            if func.natspec.is_none() {
+                continue;
+            }
+            // Getters are documented through their variable; their parameters are mapping keys
+            if contract.getter_variable(func).is_none() {
+                let names: Vec<&str> = func.parameters.iter().filter_map(|p| Self::parameter_name(p)).collect();
+                Self::check_params(&func.signature, &docs, &names, &mut issues);
+            }
+            if docs.returns.len() > func.returns.len() {
+                issues.push(format!(
+                    "`{}` has {} `@return` tag(s) but returns {} value(s)",
+                    func.signature, docs.returns.len(), func.returns.len()
+                ));
+            }
+        }
+
+        let events: Vec<&EventDef> = contract.events.iter().filter(|e| e.inherited_from.is_none()).collect();
+        for event in &events {
+            if let Some(docs) = &event.natspec {
+                let names: Vec<&str> = event.parameters.iter().map(|p| p.name.as_str()).collect();
+                Self::check_params(&format!("event {}", event.name), docs, &names, &mut issues);
+            }
+        }
+
+        let errors: Vec<&ErrorDef> = contract.errors.iter()
+            .filter(|e| e.inherited_from.is_none() && !e.is_inherited)
+            .collect();
+        for error in &errors {
+            if let Some(docs) = &error.natspec {
+                let names: Vec<&str> = error.parameters.iter().map(|p| p.name.as_str()).collect();
+                Self::check_params(&format!("error {}", error.name), docs, &names, &mut issues);
+            }
+        }
+
+        let described = |natspec: &Option<NatSpec>| natspec.as_ref().is_some_and(NatSpec::has_description);
+        DocCoverage {
+            contract: contract.name.clone(),
+            file_path: contract.file_path.clone(),
+            documented_functions,
+            total_functions,
+            documented_events: events.iter().filter(|e| described(&e.natspec)).count(),
+            total_events: events.len(),
+            documented_errors: errors.iter().filter(|e| described(&e.natspec)).count(),
+            total_errors: errors.len(),
+            issues,
+        }
+    }
+
+    /// Docs that apply to a function: its own, merged over `@inheritdoc`, or (like solc) the docs
+    /// of the base function it overrides when it has none
+    fn resolved_docs(
+        contract: &ContractInfo,
+        signature: &str,
+        natspec: &Option<NatSpec>,
+        by_name: &HashMap<&str, &ContractInfo>,
+        issues: &mut Vec<String>,
+    ) -> Option<NatSpec> {
+        let base_docs = |base: &ContractInfo| {
+            let func = base.functions.iter().find(|f| f.signature == signature && f.inherited_from.is_none())?;
+            Some(Self::resolved_docs(base, signature, &func.natspec, by_name, &mut Vec::new()))
+        };
+
+        let Some(own) = natspec else {
+            return contract.linearized_bases.iter()
+                .filter_map(|base| by_name.get(base.as_str()))
+                .find_map(|base| base_docs(base).flatten());
+        };
+        let Some(base_name) = &own.inheritdoc else {
+            return Some(own.clone());
+        };
+
+        if !contract.linearized_bases.contains(base_name) {
+            issues.push(format!("`{}`: `@inheritdoc {}` names a contract that is not a base", signature, base_name));
+            return Some(own.clone());
+        }
+        let Some(base) = by_name.get(base_name.as_str()) else {
+            return Some(own.clone()); // Base outside the project; nothing to compare against
+        };
+        let Some(inherited) = base_docs(base) else {
+            issues.push(format!("`{}`: `@inheritdoc {}` but `{}` declares no such function", signature, base_name, base_name));
+            return Some(own.clone());
+        };
+
+        // Tags written on the function itself take precedence over the inherited ones
+        let mut merged = inherited.unwrap_or_default();
+        merged.notice = own.notice.clone().or(merged.notice);
+        merged.dev = own.dev.clone().or(merged.dev);
+        if !own.params.is_empty() {
+            merged.params = own.params.clone();
+        }
+        if !own.returns.is_empty() {
+            merged.returns = own.returns.clone();
+        }
+        merged.custom.extend(own.custom.clone());
+        Some(merged)
+    }
+
+    fn check_params(item: &str, docs: &NatSpec, names: &[&str], issues: &mut Vec<String>) {
+        for (name, _) in &docs.params {
+            if !names.contains(&name.as_str()) {
+                issues.push(format!("`{}`: `@param {}` does not match any parameter", item, name));
+            }
+        }
+        for name in names.iter().filter(|n| !n.is_empty()) {
+            if !docs.params.iter().any(|(documented, _)| documented == name) {
+                issues.push(format!("`{}`: parameter `{}` has no `@param`", item, name));
+            }
+        }
+    }
+
+    /// Name of a parameter written as "type [location] name" (None if it is unnamed)
+    fn parameter_name(param: &str) -> Option<&str> {
+        let mut words = param.split_whitespace();
+        let last = words.next_back()?;
+        words.next()?;
+        if matches!(last, "memory" | "storage" | "calldata" | "payable") || last.ends_with(')') {
+            return None;
+        }
+        Some(last)
+    }
+
+    pub fn print_summary(coverage: &[DocCoverage]) {
+        let documented: usize = coverage.iter().map(|c| c.documented_functions).sum();
+        let total: usize = coverage.iter().map(|c| c.total_functions).sum();
+        if total == 0 {
+            return;
+        }
+
+        println!();
+        println!("{}", "📚 NatSpec coverage".bold());
+        println!("  {}/{} public/external functions documented", documented, total);
+        for entry in coverage.iter().filter(|c| !c.issues.is_empty()) {
+            println!("  {} {}: {} issue(s)", "⚠️".yellow(), entry.contract, entry.issues.len());
+        }
+    }
+
+    /// Write `natspec_coverage.md`: documented items per contract and the issues found
+    pub fn save_report(coverage: &[DocCoverage], output_dir: &Path) -> Result<()> {
+        fs::create_dir_all(output_dir)?;
+
+        let ratio = |documented: usize, total: usize| if total == 0 {
+            "-".to_string()
+        } else {
+            format!("{}/{} ({}%)", documented, total, documented * 100 / total)
+        };
+
+        let mut md = String::new();
+        md.push_str("# NatSpec Coverage\n\n");
+        let documented: usize = coverage.iter().map(|c| c.documented_functions).sum();
+        let total: usize = coverage.iter().map(|c| c.total_functions).sum();
+        md.push_str(&format!("**Public/external functions documented:** {}\n\n", ratio(documented, total)));
+
+        md.push_str("| Contract | Functions | Events | Errors | Issues |\n");
+        md.push_str("|----------|-----------|--------|--------|--------|\n");
+        for entry in coverage {
+            md.push_str(&format!("| `{}` | {} | {} | {} | {} |\n",
+                entry.contract,
+                ratio(entry.documented_functions, entry.total_functions),
+                ratio(entry.documented_events, entry.total_events),
+                ratio(entry.documented_errors, entry.total_errors),
+                entry.issues.len(),
+            ));
+        }
+
+        for entry in coverage.iter().filter(|c| !c.issues.is_empty()) {
+            md.push_str(&format!("\n## `{}`\n\n", entry.contract));
+            md.push_str(&format!("*{}*\n\n", entry.file_path));
+            for issue in &entry.issues {
+                md.push_str(&format!("- ⚠️ {}\n", issue));
+            }
+        }
+
+        fs::write(output_dir.join("natspec_coverage.md"), md)?;
+        println!("  {} natspec_coverage.md", "✓".green());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{analyze, contract};
+
+    const SOURCE: &str = r#"
+        /// @title Vault
+        /// @author Example
+        interface IVault {
+            /// @notice Deposit `amount` tokens
+            /// @param amount Number of tokens
+            /// @return shares Shares minted
+            function deposit(uint256 amount) external returns (uint256 shares);
+        }
+
+        /**
+         * @notice Holds deposits
+         * @custom:security-contact security@example.com
+         */
+        contract Vault is IVault {
+            /// @inheritdoc IVault
+            function deposit(uint256 amount) external returns (uint256 shares) {}
+
+            /// @notice Withdraw tokens
+            /// @param value Number of tokens
+            function withdraw(uint256 amount) external {}
+
+            function sweep() external {}
+
+            /// @notice Emitted on deposits
+            /// @param account Depositor
+            event Deposited(address account);
+        }
+    "#;
+
+    #[test]
+    fn tags_are_extracted_from_line_and_block_comments() {
+        let project = analyze(SOURCE);
+        let interface = contract(&project, "IVault");
+        let docs = interface.natspec.as_ref().unwrap();
+        assert_eq!((docs.title.as_deref(), docs.author.as_deref()), (Some("Vault"), Some("Example")));
+
+        let deposit = interface.functions[0].natspec.as_ref().unwrap();
+        assert_eq!(deposit.notice.as_deref(), Some("Deposit `amount` tokens"));
+        assert_eq!(deposit.params, [("amount".to_string(), "Number of tokens".to_string())]);
+        assert_eq!(deposit.returns, ["shares Shares minted"]);
+
+        let docs = contract(&project, "Vault").natspec.as_ref().unwrap();
+        assert_eq!(docs.notice.as_deref(), Some("Holds deposits"));
+        assert_eq!(docs.custom["security-contact"], "security@example.com");
+    }
+
+    #[test]
+    fn coverage_follows_inheritdoc_and_checks_params() {
+        let project = analyze(SOURCE);
+        let coverage = project.doc_coverage.iter().find(|c| c.contract == "Vault").unwrap();
+        assert_eq!((coverage.documented_functions, coverage.total_functions), (2, 3));
+        assert_eq!((coverage.documented_events, coverage.total_events), (1, 1));
+        assert_eq!(coverage.issues, [
+            "`withdraw(uint256)`: `@param value` does not match any parameter",
+            "`withdraw(uint256)`: parameter `amount` has no `@param`",
+            "`sweep()` has no NatSpec",
+        ]);
+    }
+}
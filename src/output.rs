@@ -1,4 +1,6 @@
+use crate::constants::ConstantFolder;
 use crate::models::*;
+use crate::pragma::VersionReq;
 use anyhow::Result;
 use colored::*;
 use prettytable::{Cell, Row, Table};
@@ -15,6 +17,7 @@ impl OutputFormatter {
         let mut table = Table::new();
         table.add_row(Row::new(vec![
             Cell::new("Contract").style_spec("Fb"),
+            Cell::new("Kind").style_spec("Fb"),
             Cell::new("Variables").style_spec("Fb"),
             Cell::new("Structs").style_spec("Fb"),
             Cell::new("Functions").style_spec("Fb"),
@@ -23,7 +26,9 @@ impl OutputFormatter {
         ]));
 
         for contract in contracts {
-            let upgradeable_status = if contract.upgradeable_storage.is_some() {
+            let upgradeable_status = if contract.upgradeable_storage.iter().any(|s| !s.issues.is_empty()) {
+                "⚠ ERC-7201".yellow()
+            } else if !contract.upgradeable_storage.is_empty() {
                 "✓ ERC-7201".green()
             } else {
                 "✗".normal()
@@ -31,6 +36,7 @@ impl OutputFormatter {
 
             table.add_row(Row::new(vec![
                 Cell::new(&contract.name),
+                Cell::new(contract.kind_label()),
                 Cell::new(&contract.state_variables.len().to_string()),
                 Cell::new(&contract.structs.len().to_string()),
                 Cell::new(&contract.functions.len().to_string()),
@@ -44,13 +50,30 @@ impl OutputFormatter {
 
         // Print details for upgradeable contracts
         for contract in contracts {
-            if let Some(ref upgradeable) = contract.upgradeable_storage {
+            for upgradeable in &contract.upgradeable_storage {
                 println!("{} {}", "🔄 Upgradeable Storage Detected:".bold().cyan(), contract.name.bold());
                 println!("   Namespace: {}", upgradeable.namespace);
                 println!("   Storage Struct: {}", upgradeable.storage_struct);
                 println!("   Storage Slot: {}", upgradeable.storage_slot);
                 println!("   Accessor: {}", upgradeable.accessor_function);
                 println!("   Fields: {}", upgradeable.struct_fields.len());
+                for issue in &upgradeable.issues {
+                    println!("   {} {}", "⚠️".yellow(), issue.yellow());
+                }
+                println!();
+            }
+        }
+
+        for contract in contracts {
+            if let Some(proxy) = &contract.proxy {
+                println!("{} {} ({})", "🔀 Proxy Detected:".bold().cyan(), contract.name.bold(), proxy.kind.label());
+                println!("   Evidence: {}", proxy.evidence.join(", "));
+                if !proxy.upgrade_functions.is_empty() {
+                    println!("   Upgrade Functions: {}", proxy.upgrade_functions.join(", "));
+                }
+                if let Some(implementation) = &proxy.implementation {
+                    println!("   Implementation: {}", implementation);
+                }
                 println!();
             }
         }
@@ -84,6 +107,145 @@ impl OutputFormatter {
         Ok(())
     }
 
+    /// Write `source_units.md`: definitions declared outside any contract, per file
+    pub fn save_source_units_report(units: &[SourceUnit], output_dir: &Path) -> Result<()> {
+        if units.iter().all(|u| u.is_empty() || u.is_context_only) {
+            return Ok(());
+        }
+        fs::create_dir_all(output_dir)?;
+
+        let filepath = output_dir.join("source_units.md");
+        fs::write(&filepath, Self::generate_source_units_markdown(units))?;
+        println!("  {} source_units.md", "✓".green());
+        Ok(())
+    }
+
+    fn generate_source_units_markdown(units: &[SourceUnit]) -> String {
+        let mut md = String::new();
+        let separator = "━".repeat(80);
+        let double_sep = "═".repeat(80);
+
+        md.push_str(&double_sep);
+        md.push('\n');
+        md.push_str("                        **FILE-LEVEL DEFINITIONS**\n");
+        md.push_str(&double_sep);
+        md.push_str("\n\n");
+
+        for unit in units.iter().filter(|u| !u.is_empty() && !u.is_context_only) {
+            md.push_str(&separator);
+            md.push('\n');
+            md.push_str(&format!("**FILE: `{}`**\n", unit.file_path));
+            md.push_str(&separator);
+            md.push_str("\n\n");
+
+            let used_by = |item: &str| -> String {
+                match unit.usages.get(item) {
+                    Some(users) if !users.is_empty() => users.iter()
+                        .map(|u| format!("`{}`", u))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    _ => "*None*".to_string(),
+                }
+            };
+
+            if !unit.constants.is_empty() {
+                md.push_str("**Constants**\n\n");
+                for constant in &unit.constants {
+                    let value = ConstantFolder::describe(constant, &unit.constants)
+                        .map(|v| format!(" = {}", v))
+                        .unwrap_or_default();
+                    md.push_str(&format!("- **`{}`** `{}`{} (line {})\n", constant.name, constant.var_type, value, constant.line_number));
+                    md.push_str(&format!("   **Read by:** {}\n", used_by(&constant.name)));
+                }
+                md.push('\n');
+            }
+
+            if !unit.functions.is_empty() {
+                md.push_str("**Free functions**\n\n");
+                for func in &unit.functions {
+                    let returns_str = if func.returns.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" → `{}`", func.returns.join(", "))
+                    };
+                    md.push_str(&format!("- **`{}({})`**{} (line {}, {})\n",
+                        func.name,
+                        func.parameters.join(", "),
+                        returns_str,
+                        func.line_number,
+                        func.state_mutability
+                    ));
+                    md.push_str(&format!("   **Called by:** {}\n", used_by(&func.signature)));
+                }
+                md.push('\n');
+            }
+
+            if !unit.errors.is_empty() {
+                md.push_str("**Custom errors**\n\n");
+                for error in &unit.errors {
+                    let selector = error.selector.as_ref()
+                        .map(|s| format!(" `{}`", s))
+                        .unwrap_or_default();
+                    md.push_str(&format!("- **`{}`**{} (line {})\n",
+                        error.signature.as_ref().unwrap_or(&error.name),
+                        selector,
+                        error.line_number
+                    ));
+                    md.push_str(&format!("   **Thrown in:** {}\n", used_by(&error.name)));
+                }
+                md.push('\n');
+            }
+
+            if !unit.events.is_empty() {
+                md.push_str("**Events**\n\n");
+                for event in &unit.events {
+                    let topic = event.topic0.as_ref()
+                        .map(|t| format!(" `{}`", t))
+                        .unwrap_or_default();
+                    md.push_str(&format!("- **`{}`**{} (line {})\n",
+                        event.signature.as_ref().unwrap_or(&event.name),
+                        topic,
+                        event.line_number
+                    ));
+                }
+                md.push('\n');
+            }
+
+            if !unit.structs.is_empty() {
+                md.push_str("**Structs**\n\n");
+                for s in &unit.structs {
+                    let members: Vec<String> = s.members.iter()
+                        .map(|m| format!("`{}` {}", m.member_type, m.name))
+                        .collect();
+                    md.push_str(&format!("- **`{}`** (line {}): {}\n", s.name, s.line_number, members.join(", ")));
+                }
+                md.push('\n');
+            }
+
+            if !unit.enums.is_empty() {
+                md.push_str("**Enums**\n\n");
+                for e in &unit.enums {
+                    md.push_str(&format!("- **`{}`** (line {}): {}\n", e.name, e.line_number, e.values.join(", ")));
+                }
+                md.push('\n');
+            }
+
+            if !unit.user_types.is_empty() {
+                md.push_str("**User-defined value types**\n\n");
+                for t in &unit.user_types {
+                    md.push_str(&format!("- **`{}`** is `{}` (line {})\n", t.name, t.underlying, t.line_number));
+                }
+                md.push('\n');
+            }
+        }
+
+        md.push_str(&double_sep);
+        md.push('\n');
+        md.push_str("*Generated by MainnetReady - Solidity Enhanced Analyzer*\n");
+
+        md
+    }
+
     fn generate_markdown(contract: &ContractInfo) -> String {
         let mut md = String::new();
         let separator = "━".repeat(80);
@@ -96,14 +258,99 @@ impl OutputFormatter {
         md.push_str(&double_sep);
         md.push_str("\n\n");
         md.push_str(&format!("**File:** `{}`\n", contract.file_path));
+        md.push_str(&format!("**Kind:** {}\n", contract.kind_label()));
+        if let Some(license) = &contract.compiler.spdx_license {
+            md.push_str(&format!("**License:** `{}`\n", license));
+        }
+        if let Some(solidity) = &contract.compiler.solidity {
+            let floating = VersionReq::parse(solidity).is_some_and(|req| !req.is_exact());
+            md.push_str(&format!("**Compiler:** `{}`{}\n", solidity, if floating { " *(floating)*" } else { "" }));
+        }
+        if let Some(abicoder) = &contract.compiler.abicoder {
+            md.push_str(&format!("**ABI Coder:** {}\n", abicoder));
+        }
+
+        if !contract.base_contracts.is_empty() {
+            let bases: Vec<String> = contract.base_contracts.iter()
+                .map(|b| format!("`{}`", b))
+                .collect();
+            md.push_str(&format!("**Bases (`is`):** {}\n", bases.join(", ")));
+        }
+
+        if !contract.linearized_bases.is_empty() {
+            let bases: Vec<String> = contract.linearized_bases.iter()
+                .map(|b| if contract.unresolved_bases.contains(b) {
+                    format!("`{}` *(not in project)*", b)
+                } else {
+                    format!("`{}`", b)
+                })
+                .collect();
+            md.push_str(&format!("**Inheritance (C3):** {}\n", bases.join(" → ")));
+        }
+
+        if contract.kind == ContractKind::Interface || contract.is_abstract {
+            if contract.implementers.is_empty() {
+                md.push_str("**Implemented by:** *(no implementation in project)*\n");
+            } else {
+                let implementers: Vec<String> = contract.implementers.iter()
+                    .map(|c| format!("`{}`", c))
+                    .collect();
+                md.push_str(&format!("**Implemented by:** {}\n", implementers.join(", ")));
+            }
+        }
+
+        for issue in &contract.compiler.issues {
+            md.push_str(&format!("⚠️ {}\n", issue));
+        }
+        md.push_str(&Self::natspec_summary(&contract.natspec, ""));
 
         // Upgradeable Storage Info (if present)
-        if let Some(ref upgradeable) = contract.upgradeable_storage {
+        for upgradeable in &contract.upgradeable_storage {
             md.push_str("\n🔄 **UPGRADEABLE STORAGE DETECTED (ERC-7201)**\n");
             md.push_str(&format!("   **Namespace:** `{}`\n", upgradeable.namespace));
             md.push_str(&format!("   **Storage Struct:** `{}`\n", upgradeable.storage_struct));
             md.push_str(&format!("   **Storage Slot:** `{}`\n", upgradeable.storage_slot));
+            if let Some(expected) = upgradeable.expected_slot.as_ref().filter(|e| !e.eq_ignore_ascii_case(&upgradeable.storage_slot)) {
+                md.push_str(&format!("   **Expected Slot:** `{}`\n", expected));
+            }
             md.push_str(&format!("   **Accessor Function:** `{}`\n", upgradeable.accessor_function));
+            for issue in &upgradeable.issues {
+                md.push_str(&format!("   ⚠️ {}\n", issue));
+            }
+        }
+
+        for storage in &contract.diamond_storage {
+            md.push_str("\n💎 **DIAMOND STORAGE DETECTED (EIP-2535)**\n");
+            md.push_str(&format!("   **Storage Struct:** `{}`\n", storage.storage_struct));
+            md.push_str(&format!("   **Slot Constant:** `{}`\n", storage.slot_constant));
+            if let Some(id) = &storage.slot_id {
+                md.push_str(&format!("   **Slot:** `{}` (`keccak256(\"{}\")`)\n", storage.slot, id));
+            } else if !storage.slot.is_empty() {
+                md.push_str(&format!("   **Slot:** `{}`\n", storage.slot));
+            }
+            md.push_str(&format!("   **Accessor Function:** `{}`\n", storage.accessor_function));
+            md.push_str(&format!("   **Fields:** {}\n", storage.fields.len()));
+        }
+
+        if let Some(proxy) = &contract.proxy {
+            md.push_str(&format!("\n🔀 **PROXY DETECTED ({})**\n", proxy.kind.label()));
+            for evidence in &proxy.evidence {
+                md.push_str(&format!("   - {}\n", evidence));
+            }
+            if !proxy.upgrade_functions.is_empty() {
+                let functions: Vec<String> = proxy.upgrade_functions.iter()
+                    .map(|f| format!("`{}`", f))
+                    .collect();
+                md.push_str(&format!("   **Upgrade Functions:** {}\n", functions.join(", ")));
+            }
+            if proxy.kind == ProxyKind::Diamond {
+                md.push_str("   **Facets:** see `diamond.md`\n");
+            } else if proxy.kind.delegates() {
+                match &proxy.implementation {
+                    Some(implementation) => md.push_str(&format!("   **Implementation:** `{}`\n", implementation)),
+                    None => md.push_str("   **Implementation:** *(not linked; add it to `.analyzerproxies`)*\n"),
+                }
+            }
         }
 
         md.push_str("\n\n");
@@ -117,8 +364,9 @@ impl OutputFormatter {
 
         // Count statistics
         let total_functions = contract.functions.len();
+        // Overridden base copies (`Base.fn`) are only reachable through `super`
         let public_external = contract.functions.iter()
-            .filter(|f| matches!(f.visibility.as_str(), "public" | "external"))
+            .filter(|f| matches!(f.visibility.as_str(), "public" | "external") && !f.name.contains('.'))
             .count();
         let total_state_vars = contract.state_variables.len();
         let mutable_vars = contract.state_variables.iter()
@@ -129,8 +377,8 @@ impl OutputFormatter {
         let mut total_security_findings = 0;
         let mut high_severity_findings = 0;
 
-        // Count ignored returns
-        for func in &contract.functions {
+        // Count ignored returns, once per call site rather than per overridden copy
+        for func in contract.functions.iter().filter(|f| !f.name.contains('.')) {
             for ignored in &func.ignored_returns {
                 total_security_findings += 1;
                 if ignored.severity == crate::models::IgnoredReturnSeverity::High {
@@ -185,7 +433,7 @@ impl OutputFormatter {
                     md.push('\n');
                 }
 
-                md.push_str(&format!("**`{}`**\n", var.name));
+                md.push_str(&format!("**`{}`**{}\n", var.name, Self::inherited_marker(&var.inherited_from)));
 
                 // Type and attributes with better formatting
                 let mut badges = vec![var.visibility.clone()];
@@ -199,6 +447,17 @@ impl OutputFormatter {
 
                 md.push_str(&format!("   **Type:** `{}`\n", var.var_type));
                 md.push_str(&format!("   **Visibility:** {}\n", badges.join(", ")));
+                if let (Some(slot), Some(offset)) = (var.slot, var.offset) {
+                    md.push_str(&format!("   **Slot:** {} (offset {})\n", slot, offset));
+                }
+                if let Some(value) = ConstantFolder::describe(var, &contract.state_variables) {
+                    md.push_str(&format!("   **Value:** {}\n", value));
+                }
+                for assignment in &var.assignments {
+                    md.push_str(&format!("   **Set in** `{}`: `{}` (line {})\n",
+                        assignment.function, assignment.expression, assignment.line_number));
+                }
+                md.push_str(&Self::natspec_summary(&var.natspec, "   "));
 
                 // Modifications
                 if !var.modification_chains.is_empty() {
@@ -208,15 +467,17 @@ impl OutputFormatter {
                         let prefix = if is_last { "└─" } else { "├─" };
 
                         if chain.call_chain.is_empty() {
-                            md.push_str(&format!("      {} `{}` *({})*\n",
+                            md.push_str(&format!("      {} `{}` *({})*{}\n",
                                 prefix,
                                 chain.direct_modifier,
-                                chain.direct_modifier_visibility
+                                chain.direct_modifier_visibility,
+                                Self::via_modifier_marker(chain)
                             ));
                         } else {
-                            let mut parts = vec![format!("`{}` *({})*",
+                            let mut parts = vec![format!("`{}` *({})*{}",
                                 chain.direct_modifier,
-                                chain.direct_modifier_visibility
+                                chain.direct_modifier_visibility,
+                                Self::via_modifier_marker(chain)
                             )];
                             for caller in &chain.call_chain {
                                 parts.push(format!("`{}` *({})*",
@@ -240,15 +501,17 @@ impl OutputFormatter {
                         let prefix = if is_last { "└─" } else { "├─" };
 
                         if chain.call_chain.is_empty() {
-                            md.push_str(&format!("      {} `{}` *({})*\n",
+                            md.push_str(&format!("      {} `{}` *({})*{}\n",
                                 prefix,
                                 chain.direct_modifier,
-                                chain.direct_modifier_visibility
+                                chain.direct_modifier_visibility,
+                                Self::via_modifier_marker(chain)
                             ));
                         } else {
-                            let mut parts = vec![format!("`{}` *({})*",
+                            let mut parts = vec![format!("`{}` *({})*{}",
                                 chain.direct_modifier,
-                                chain.direct_modifier_visibility
+                                chain.direct_modifier_visibility,
+                                Self::via_modifier_marker(chain)
                             )];
                             for caller in &chain.call_chain {
                                 parts.push(format!("`{}` *({})*",
@@ -266,6 +529,35 @@ impl OutputFormatter {
             md.push('\n');
         }
 
+        // 1b. STORAGE LAYOUT
+        if let Some(layout) = contract.storage_layout.as_ref().filter(|l| !l.storage.is_empty()) {
+            md.push_str(&separator);
+            md.push('\n');
+            md.push_str("**STORAGE LAYOUT**\n");
+            md.push_str(&separator);
+            md.push_str("\n\n");
+
+            if !layout.missing_bases.is_empty() {
+                md.push_str(&format!("⚠️ **Incomplete:** variables of {} (not in the project) are not included, so the slots below are shifted by their storage.\n\n",
+                    layout.missing_bases.iter().map(|b| format!("`{}`", b)).collect::<Vec<_>>().join(", ")));
+            }
+            md.push_str("| Slot | Offset | Bytes | Name | Type | Declared in |\n");
+            md.push_str("|------|--------|-------|------|------|-------------|\n");
+            for entry in &layout.storage {
+                let ty = layout.types.get(&entry.type_id);
+                let declarer = entry.contract.rsplit(':').next().unwrap_or(&entry.contract);
+                md.push_str(&format!("| {} | {} | {} | `{}` | `{}` | {} |\n",
+                    entry.slot,
+                    entry.offset,
+                    ty.map(|t| t.number_of_bytes).unwrap_or(32),
+                    entry.label,
+                    ty.map(|t| t.label.as_str()).unwrap_or(&entry.type_id),
+                    declarer
+                ));
+            }
+            md.push_str("\n\n");
+        }
+
         // 2. EVENTS
         if !contract.events.is_empty() {
             md.push_str(&separator);
@@ -286,12 +578,20 @@ impl OutputFormatter {
                     })
                     .collect();
 
-                md.push_str(&format!("**`{}`**\n", event.name));
+                md.push_str(&format!("**`{}`**{}\n", event.name, Self::inherited_marker(&event.inherited_from)));
 
                 if !params.is_empty() {
                     md.push_str(&format!("   **Parameters:** {}\n", params.join(", ")));
                 }
 
+                if let Some(signature) = &event.signature {
+                    match &event.topic0 {
+                        Some(topic0) => md.push_str(&format!("   **Topic0:** `{}` (`{}`)\n", topic0, signature)),
+                        None => md.push_str(&format!("   **Signature:** `{}` *(anonymous, no topic0)*\n", signature)),
+                    }
+                }
+                md.push_str(&Self::natspec_summary(&event.natspec, "   "));
+
                 if !event.emitted_in.is_empty() {
                     md.push_str("\n   **Emitted in:**\n");
                     for (j, func) in event.emitted_in.iter().enumerate() {
@@ -321,11 +621,23 @@ impl OutputFormatter {
                     md.push('\n');
                 }
 
-                md.push_str(&format!("**`{}({})`**\n",
+                md.push_str(&format!("**`{}({})`**{}\n",
                     modifier.name,
-                    modifier.parameters.join(", ")
+                    modifier.parameters.join(", "),
+                    Self::inherited_marker(&modifier.inherited_from)
                 ));
 
+                for effect in &modifier.effects {
+                    let summary = Self::modifier_effect_summary(effect);
+                    if !summary.is_empty() {
+                        let side = match effect.phase {
+                            ModifierPhase::BeforeBody => "Before `_`",
+                            ModifierPhase::AfterBody => "After `_`",
+                        };
+                        md.push_str(&format!("   **{}:** {}\n", side, summary));
+                    }
+                }
+
                 if !modifier.used_in.is_empty() {
                     md.push_str("\n   **Used in:**\n");
                     for (j, func) in modifier.used_in.iter().enumerate() {
@@ -360,7 +672,15 @@ impl OutputFormatter {
                     .collect();
 
                 // Add marker for inherited errors
-                if error.is_inherited {
+                if error.inherited_from.is_some() {
+                    md.push_str(&format!("**`{}`**{}\n", error.name, Self::inherited_marker(&error.inherited_from)));
+                } else if let Some(declared_at) = &error.declared_at {
+                    let scope = match error.name.rsplit_once('.') {
+                        Some((library, _)) => format!("declared in `{}`", library),
+                        None => "file-level".to_string(),
+                    };
+                    md.push_str(&format!("**`{}`** *({}, at `{}`)*\n", error.name, scope, declared_at));
+                } else if error.is_inherited {
                     md.push_str(&format!("**`{}`** *(inherited)*\n", error.name));
                 } else {
                     md.push_str(&format!("**`{}`**\n", error.name));
@@ -370,6 +690,11 @@ impl OutputFormatter {
                     md.push_str(&format!("   **Parameters:** {}\n", params.join(", ")));
                 }
 
+                if let (Some(selector), Some(signature)) = (&error.selector, &error.signature) {
+                    md.push_str(&format!("   **Selector:** `{}` (`{}`)\n", selector, signature));
+                }
+                md.push_str(&Self::natspec_summary(&error.natspec, "   "));
+
                 if !error.used_in.is_empty() {
                     md.push_str("\n   **Used in:**\n");
                     for (j, func) in error.used_in.iter().enumerate() {
@@ -406,29 +731,69 @@ impl OutputFormatter {
                 };
 
                 let unchecked = if func.has_unchecked { " *[unchecked]*" } else { "" };
+                let assembly = if func.assembly.is_some() { " *[assembly]*" } else { "" };
 
-                md.push_str(&format!("**`{}({})`**{}\n",
+                md.push_str(&format!("**`{}({})`**{}{}\n",
                     func.name,
                     func.parameters.join(", "),
-                    returns_str
+                    returns_str,
+                    Self::inherited_marker(&func.inherited_from)
                 ));
 
                 md.push_str(&format!("   **Visibility:** {}\n", func.visibility));
-                md.push_str(&format!("   **State Mutability:** {}{}\n",
+                md.push_str(&format!("   **State Mutability:** {}{}{}\n",
                     func.state_mutability,
-                    unchecked
+                    unchecked,
+                    assembly
                 ));
                 md.push_str(&format!("   **Line:** {}\n", func.line_number));
+                if let (Some(selector), Some(signature)) = (&func.selector, &func.abi_signature) {
+                    md.push_str(&format!("   **Selector:** `{}` (`{}`)\n", selector, signature));
+                }
+                md.push_str(&Self::natspec_summary(&func.natspec, "   "));
 
                 if !func.uses_modifiers.is_empty() {
                     md.push_str("\n   **Modifiers:**\n");
                     for (j, modifier) in func.uses_modifiers.iter().enumerate() {
                         let is_last = j == func.uses_modifiers.len() - 1;
                         let prefix = if is_last { "└─" } else { "├─" };
-                        md.push_str(&format!("      {} `{}`\n", prefix, modifier));
+                        let effects: Vec<String> = func.modifier_effects.iter()
+                            .filter(|e| &e.modifier == modifier)
+                            .map(|e| (e, Self::modifier_effect_summary(e)))
+                            .filter(|(_, summary)| !summary.is_empty())
+                            .map(|(e, summary)| format!("{}: {}", Self::modifier_phase_label(e.phase), summary))
+                            .collect();
+                        if effects.is_empty() {
+                            md.push_str(&format!("      {} `{}`\n", prefix, modifier));
+                        } else {
+                            md.push_str(&format!("      {} `{}` — {}\n", prefix, modifier, effects.join(" | ")));
+                        }
                     }
                 }
 
+                if !func.calls_free_functions.is_empty() {
+                    let callees: Vec<String> = func.calls_free_functions.iter()
+                        .map(|f| format!("`{}`", f))
+                        .collect();
+                    md.push_str(&format!("   **Free functions called:** {}\n", callees.join(", ")));
+                }
+
+                if !func.reads_file_constants.is_empty() {
+                    let constants: Vec<String> = func.reads_file_constants.iter()
+                        .map(|c| format!("`{}`", c))
+                        .collect();
+                    md.push_str(&format!("   **File-level constants read:** {}\n", constants.join(", ")));
+                }
+
+                for cast in &func.narrowing_casts {
+                    md.push_str(&format!("   ⚠️ **Narrowing cast:** `{}` ({} → {}, line {})\n",
+                        cast.expression, cast.from, cast.to, cast.line_number));
+                }
+
+                if let Some(assembly) = &func.assembly {
+                    md.push_str(&Self::assembly_summary(assembly));
+                }
+
                 md.push('\n');
             }
         }
@@ -498,7 +863,7 @@ impl OutputFormatter {
 
         // IGNORED RETURN VALUES
         let has_ignored_returns = contract.functions.iter()
-            .any(|f| !f.ignored_returns.is_empty());
+            .any(|f| !f.ignored_returns.is_empty() && !f.name.contains('.'));
 
         if has_ignored_returns {
             md.push_str(&separator);
@@ -514,11 +879,11 @@ impl OutputFormatter {
             use std::collections::BTreeMap;
             let mut by_severity: BTreeMap<String, Vec<(&str, &crate::models::IgnoredReturn)>> = BTreeMap::new();
 
-            for func in &contract.functions {
+            for func in contract.functions.iter().filter(|f| !f.name.contains('.')) {
                 for ignored in &func.ignored_returns {
                     by_severity.entry(ignored.severity.as_str().to_string())
                         .or_default()
-                        .push((&func.name, ignored));
+                        .push((&func.signature, ignored));
                 }
             }
 
@@ -552,6 +917,189 @@ impl OutputFormatter {
             }
         }
 
+        // REENTRANCY
+        if !contract.reentrancy.is_empty() {
+            md.push_str(&separator);
+            md.push('\n');
+            md.push_str("**REENTRANCY**\n");
+            md.push_str(&separator);
+            md.push('\n');
+            md.push('\n');
+            md.push_str("⚠️ **Warning:** State is written or events are emitted after an external call.\n");
+            md.push_str("A callee that re-enters sees the contract before these effects are applied.\n\n");
+
+            for severity in [ReentrancySeverity::High, ReentrancySeverity::Medium, ReentrancySeverity::Low] {
+                let items: Vec<&ReentrancyFinding> = contract.reentrancy.iter()
+                    .filter(|f| f.severity == severity)
+                    .collect();
+                if items.is_empty() {
+                    continue;
+                }
+                md.push_str(&format!("### {} {} Severity\n\n", severity.emoji(), severity.as_str()));
+
+                for (i, finding) in items.iter().enumerate() {
+                    md.push_str(&format!("{}. **In function:** `{}`\n", i + 1, finding.function));
+                    let ether = if finding.sends_value { " *(sends ether)*" } else { "" };
+                    md.push_str(&format!("   - **External call:** `{}` (line {}){}\n", finding.call, finding.call_line, ether));
+                    match &finding.effect {
+                        ReentrancyEffect::StateWrite(var) => {
+                            md.push_str(&format!("   - **Then writes:** `{}` (line {})\n", var, finding.effect_line));
+                        }
+                        ReentrancyEffect::Event(event) => {
+                            md.push_str(&format!("   - **Then emits:** `{}` (line {})\n", event, finding.effect_line));
+                        }
+                    }
+                    if !finding.via.is_empty() {
+                        md.push_str(&format!("   - **Via:** {}\n", finding.via.iter()
+                            .map(|v| format!("`{}`", v))
+                            .collect::<Vec<_>>()
+                            .join(" → ")));
+                    }
+                    md.push('\n');
+                }
+            }
+        }
+
+        // CROSS-FUNCTION REENTRANCY
+        if !contract.cross_reentrancy.is_empty() {
+            md.push_str(&separator);
+            md.push('\n');
+            md.push_str("**CROSS-FUNCTION REENTRANCY**\n");
+            md.push_str(&separator);
+            md.push('\n');
+            md.push('\n');
+            md.push_str("⚠️ **Warning:** These entry points use state another function has not yet updated when its external call runs.\n");
+            md.push_str("They share no reentrancy lock with that function, so a callee can re-enter through them.\n\n");
+
+            for severity in [ReentrancySeverity::High, ReentrancySeverity::Medium, ReentrancySeverity::Low] {
+                let items: Vec<&CrossReentrancyFinding> = contract.cross_reentrancy.iter()
+                    .filter(|f| f.severity == severity)
+                    .collect();
+                if items.is_empty() {
+                    continue;
+                }
+                md.push_str(&format!("### {} {} Severity\n\n", severity.emoji(), severity.as_str()));
+
+                for (i, finding) in items.iter().enumerate() {
+                    let kind = match finding.kind {
+                        CrossReentrancyKind::CrossFunction => "",
+                        CrossReentrancyKind::ReadOnly => " *(read-only)*",
+                    };
+                    let access = match finding.access {
+                        StateAccess::Read => "reads",
+                        StateAccess::Write => "writes",
+                    };
+                    md.push_str(&format!("{}. **Entry point:** `{}`{} {} `{}`\n", i + 1, finding.function, kind, access, finding.variable));
+                    md.push_str(&format!("   - **Stale during:** `{}` in `{}` (line {}), updated at line {}\n",
+                        finding.call, finding.window_function, finding.call_line, finding.write_line));
+                    if !finding.consumers.is_empty() {
+                        md.push_str(&format!("   - **Consumed by:** {}\n", finding.consumers.iter()
+                            .map(|c| format!("`{}`", c))
+                            .collect::<Vec<_>>()
+                            .join(", ")));
+                    }
+                    md.push('\n');
+                }
+            }
+        }
+
+        // UPGRADEABILITY
+        if !contract.initializers.is_empty() || !contract.upgrade_issues.is_empty() {
+            md.push_str(&separator);
+            md.push('\n');
+            md.push_str("**UPGRADEABILITY**\n");
+            md.push_str(&separator);
+            md.push('\n');
+            md.push('\n');
+
+            if !contract.initializers.is_empty() {
+                md.push_str("**Initializers:**\n");
+                for init in &contract.initializers {
+                    let guard = match &init.guard {
+                        Some(g) if g.contains('`') => format!("guarded by {}", g),
+                        Some(g) => format!("guarded by `{}`", g),
+                        None => "⚠️ unguarded".to_string(),
+                    };
+                    md.push_str(&format!("- `{}` (line {}), {}\n", init.function, init.line_number, guard));
+                    if !init.parent_inits.is_empty() {
+                        md.push_str(&format!("  - calls {}\n", init.parent_inits.iter()
+                            .map(|p| format!("`{}`", p))
+                            .collect::<Vec<_>>()
+                            .join(", ")));
+                    }
+                }
+                md.push('\n');
+            }
+
+            if !contract.upgrade_issues.is_empty() {
+                md.push_str("⚠️ **Issues:**\n");
+                for issue in &contract.upgrade_issues {
+                    let line = match issue.line_number {
+                        0 => String::new(),
+                        n => format!(" (line {})", n),
+                    };
+                    md.push_str(&format!("- **{}** `{}`{}: {}\n", issue.kind.label(), issue.subject, line, issue.detail));
+                }
+                md.push('\n');
+            }
+        }
+
+        // LOOPS / DENIAL OF SERVICE
+        if !contract.loops.is_empty() {
+            md.push_str(&separator);
+            md.push('\n');
+            md.push_str("**LOOPS / DENIAL OF SERVICE**\n");
+            md.push_str(&separator);
+            md.push('\n');
+            md.push('\n');
+            md.push_str("⚠️ **Warning:** These loops run once per element of state or input that can grow.\n");
+            md.push_str("Past the block gas limit, or with one reverting callee, the functions reaching them stop working.\n\n");
+
+            for severity in [LoopSeverity::High, LoopSeverity::Medium, LoopSeverity::Low] {
+                let items: Vec<&LoopFinding> = contract.loops.iter()
+                    .filter(|l| l.severity == severity)
+                    .collect();
+                if items.is_empty() {
+                    continue;
+                }
+                md.push_str(&format!("### {} {} Severity\n\n", severity.emoji(), severity.as_str()));
+
+                for (i, finding) in items.iter().enumerate() {
+                    md.push_str(&format!("{}. **In function:** `{}` (line {})\n", i + 1, finding.function, finding.line_number));
+                    let bound = match &finding.bound {
+                        LoopBound::StorageArray(e) => format!("`{}` (storage array)", e),
+                        LoopBound::StateVariable(e) => format!("`{}` (storage)", e),
+                        LoopBound::Parameter(e) => format!("`{}` (caller input)", e),
+                        LoopBound::Fixed(e) => format!("`{}` (fixed)", e),
+                        LoopBound::Other(e) => format!("`{}`", e),
+                    };
+                    md.push_str(&format!("   - **Bound:** {}\n", bound));
+                    for risk in &finding.risks {
+                        let kind = match risk.kind {
+                            LoopRiskKind::ExternalCall => "External call",
+                            LoopRiskKind::ValueTransfer => "Sends ether",
+                            LoopRiskKind::StorageWrite => "Writes",
+                        };
+                        let via = risk.via.as_ref().map(|v| format!(" via `{}`", v)).unwrap_or_default();
+                        md.push_str(&format!("   - **{}:** `{}`{}\n", kind, risk.target, via));
+                    }
+                    if !finding.entry_points.is_empty() {
+                        md.push_str(&format!("   - **Reached from:** {}\n", finding.entry_points.iter()
+                            .map(|e| format!("`{}`", e))
+                            .collect::<Vec<_>>()
+                            .join(", ")));
+                    }
+                    if !finding.growable_by.is_empty() {
+                        md.push_str(&format!("   - **Anyone can grow it via:** {}\n", finding.growable_by.iter()
+                            .map(|e| format!("`{}`", e))
+                            .collect::<Vec<_>>()
+                            .join(", ")));
+                    }
+                }
+                md.push('\n');
+            }
+        }
+
         md.push_str(&double_sep);
         md.push('\n');
         md.push_str("*Generated by MainnetReady - Solidity Enhanced Analyzer*\n");
@@ -559,6 +1107,110 @@ impl OutputFormatter {
         md
     }
 
+    /// Markdown suffix for accesses made by a modifier rather than the function body
+    fn via_modifier_marker(chain: &ModificationChain) -> String {
+        chain.via_modifier.as_ref()
+            .map(|m| format!(" *(via modifier `{}`)*", m))
+            .unwrap_or_default()
+    }
+
+    /// One-line summary of what one side of a modifier does, e.g. "writes `_status`; reads `paused`"
+    fn modifier_effect_summary(effect: &ModifierEffect) -> String {
+        let list = |items: &[String]| items.iter().map(|i| format!("`{}`", i)).collect::<Vec<_>>().join(", ");
+        let external: Vec<String> = effect.external_calls.iter()
+            .map(|c| format!("{}.{}", c.target_variable, c.target_display()))
+            .collect();
+
+        [
+            ("writes", list(&effect.modifies_states)),
+            ("reads", list(&effect.reads_states)),
+            ("calls", list(&effect.calls_functions)),
+            ("external calls", list(&external)),
+        ]
+            .into_iter()
+            .filter(|(_, items)| !items.is_empty())
+            .map(|(label, items)| format!("{} {}", label, items))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Function-report lines describing what inline assembly does
+    fn assembly_summary(assembly: &AssemblyInfo) -> String {
+        let mut md = format!("   **Assembly:** {} block(s){}\n",
+            assembly.blocks,
+            if assembly.memory_safe { ", memory-safe" } else { "" });
+
+        for access in &assembly.storage_accesses {
+            let resolved = access.resolved.as_ref()
+                .map(|r| format!(" → `{}`", r))
+                .unwrap_or_default();
+            md.push_str(&format!("      - `{}` slot `{}`{}\n", access.opcode, access.slot, resolved));
+        }
+        for pointer in &assembly.slot_pointers {
+            let resolved = pointer.resolved.as_ref()
+                .map(|r| format!(" → `{}`", r))
+                .unwrap_or_default();
+            md.push_str(&format!("      - `{}.slot := {}`{}\n", pointer.pointer, pointer.slot, resolved));
+        }
+        for call in &assembly.calls {
+            let value = call.value.as_ref()
+                .map(|v| format!(" (value `{}`)", v))
+                .unwrap_or_default();
+            let target = if call.target.is_empty() {
+                String::new()
+            } else {
+                format!(" → `{}`", call.target)
+            };
+            md.push_str(&format!("      - `{}`{}{}\n", call.opcode, target, value));
+        }
+        for trick in &assembly.memory_tricks {
+            md.push_str(&format!("      - ⚠️  {}\n", trick));
+        }
+        md
+    }
+
+    fn modifier_phase_label(phase: ModifierPhase) -> &'static str {
+        match phase {
+            ModifierPhase::BeforeBody => "before body",
+            ModifierPhase::AfterBody => "after body",
+        }
+    }
+
+    /// Markdown suffix for members merged in from a base contract
+    /// NatSpec tags as report lines, each prefixed with `indent`
+    fn natspec_summary(natspec: &Option<NatSpec>, indent: &str) -> String {
+        let Some(natspec) = natspec else {
+            return String::new();
+        };
+
+        let mut lines = Vec::new();
+        for (label, text) in [("Title", &natspec.title), ("Author", &natspec.author), ("Notice", &natspec.notice), ("Dev", &natspec.dev)] {
+            if let Some(text) = text {
+                lines.push(format!("**{}:** {}", label, text));
+            }
+        }
+        for (name, description) in &natspec.params {
+            lines.push(format!("**@param** `{}`: {}", name, description));
+        }
+        for description in &natspec.returns {
+            lines.push(format!("**@return** {}", description));
+        }
+        if let Some(base) = &natspec.inheritdoc {
+            lines.push(format!("**@inheritdoc** `{}`", base));
+        }
+        for (tag, text) in &natspec.custom {
+            lines.push(format!("**@custom:{}** {}", tag, text));
+        }
+
+        lines.iter().map(|line| format!("{}{}\n", indent, line)).collect()
+    }
+
+    fn inherited_marker(inherited_from: &Option<String>) -> String {
+        inherited_from.as_ref()
+            .map(|base| format!(" *(inherited from `{}`)*", base))
+            .unwrap_or_default()
+    }
+
     fn format_taint_source(source: &crate::dataflow::TaintSource) -> String {
         match source {
             crate::dataflow::TaintSource::FunctionParameter { param_name, .. } => {
@@ -608,6 +1260,10 @@ impl OutputFormatter {
             println!("\n{}", "═".repeat(70).bright_blue());
             println!("{} {}", "📜 Contract:".bold(), contract.name.bright_cyan().bold());
             println!("{} {}", "📁 File:".bold(), contract.file_path.dimmed());
+            println!("{} {}", "🏷️  Kind:".bold(), contract.kind_label());
+            if !contract.linearized_bases.is_empty() {
+                println!("{} {}", "🧬 Inherits:".bold(), contract.linearized_bases.join(" → ").cyan());
+            }
 
             // 1. STATE VARIABLES & THEIR MODIFYING FUNCTIONS
             if !contract.state_variables.is_empty() {
@@ -622,8 +1278,14 @@ impl OutputFormatter {
                     if var.is_immutable {
                         attrs.push("immutable".to_string());
                     }
+                    if let Some(base) = &var.inherited_from {
+                        attrs.push(format!("inherited from {}", base));
+                    }
                     println!("\n  {} {}", "•".bright_blue().bold(), var.name.white().bold());
                     println!("    Type: {}", attrs.join(", ").green());
+                    if let Some(value) = &var.value {
+                        println!("    Value: {}", value.to_string().cyan());
+                    }
 
                     // Display modification chains
                     if !var.modification_chains.is_empty() {
@@ -728,6 +1390,11 @@ impl OutputFormatter {
                     } else {
                         String::new()
                     };
+                    let assembly_mark = if func.assembly.is_some() {
+                        " [assembly]".red().to_string()
+                    } else {
+                        String::new()
+                    };
 
                     println!("\n  {} {}({}){}",
                         "•".bright_blue().bold(),
@@ -736,13 +1403,18 @@ impl OutputFormatter {
                         returns_str.green()
                     );
 
-                    println!("    {} {} | {}{}",
+                    println!("    {} {} | {}{}{}",
                         "Visibility:".dimmed(),
                         func.visibility.magenta(),
                         func.state_mutability.yellow(),
-                        unchecked_mark
+                        unchecked_mark,
+                        assembly_mark
                     );
 
+                    if let Some(base) = &func.inherited_from {
+                        println!("    {} {}", "Inherited from:".dimmed(), base.cyan());
+                    }
+
                     if !func.uses_modifiers.is_empty() {
                         println!("    {} {}",
                             "Modifiers:".dimmed(),
@@ -760,19 +1432,25 @@ impl OutputFormatter {
             let is_last = i == chains.len() - 1;
             let prefix = if is_last { "└─" } else { "├─" };
 
+            let via = chain.via_modifier.as_ref()
+                .map(|m| format!(" via modifier {}", m).yellow().to_string())
+                .unwrap_or_default();
+
             if chain.call_chain.is_empty() {
                 // Direct modification only
-                println!("      {} {} ({})",
+                println!("      {} {} ({}){}",
                     prefix.cyan(),
                     chain.direct_modifier.white(),
-                    chain.direct_modifier_visibility.magenta()
+                    chain.direct_modifier_visibility.magenta(),
+                    via
                 );
             } else {
                 // Build the full chain: DirectModifier ← Caller1 ← Caller2
                 let mut parts = vec![
-                    format!("{} ({})",
+                    format!("{} ({}){}",
                         chain.direct_modifier.white(),
-                        chain.direct_modifier_visibility.magenta()
+                        chain.direct_modifier_visibility.magenta(),
+                        via
                     )
                 ];
 
@@ -791,3 +1469,30 @@ impl OutputFormatter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+
+    #[test]
+    fn overridden_copies_are_not_entry_points() {
+        let project = analyze(r#"
+            interface IToken { function transfer(address to, uint256 amount) external returns (bool); }
+
+            contract X {
+                IToken internal token;
+                function foo() public virtual { token.transfer(msg.sender, 1); }
+            }
+            contract Y is X {
+                function foo() public virtual override { token.transfer(msg.sender, 2); }
+            }
+            contract O is X, Y {
+                function foo() public override(X, Y) { token.transfer(msg.sender, 3); }
+            }
+        "#);
+        let md = OutputFormatter::generate_markdown(contract(&project, "O"));
+        assert!(md.contains("(1 public/external entry points)"), "{}", md);
+        assert_eq!(md.matches("**Ignored call:**").count(), 1, "{}", md);
+    }
+}
@@ -1,16 +1,29 @@
 use anyhow::{Context, Result};
 use solang_parser::{parse, pt};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 
 use crate::models::*;
-use crate::analyzer::StateModificationAnalyzer;
+use crate::abi::{AbiGenerator, TypeResolver};
+use crate::erc7201::Erc7201Verifier;
+use crate::yul::YulAnalyzer;
+
+/// Everything extracted from one .sol file
+pub struct ParsedFile {
+    pub contracts: Vec<ContractInfo>,
+    pub contract_asts: Vec<pt::ContractDefinition>,
+    pub source_unit: SourceUnit,
+    pub free_function_asts: Vec<pt::FunctionDefinition>, // Bodies of `source_unit.functions`, same order
+    pub constant_asts: Vec<pt::VariableDefinition>,      // Declarations of `source_unit.constants`, same order
+}
 
 pub struct SolidityParser;
 
 impl SolidityParser {
     /// Parse file and return both contracts and their ASTs for relationship analysis
-    pub fn parse_file_with_ast(path: &Path) -> Result<(Vec<ContractInfo>, Vec<pt::ContractDefinition>)> {
+    /// Contracts are returned unanalyzed: inheritance has to be resolved project-wide first
+    pub fn parse_file_with_ast(path: &Path) -> Result<ParsedFile> {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read file: {:?}", path))?;
 
@@ -19,20 +32,137 @@ impl SolidityParser {
 
         let mut contracts = Vec::new();
         let mut contract_asts = Vec::new();
+        let mut source_unit = SourceUnit {
+            file_path: path.to_string_lossy().to_string(),
+            functions: Vec::new(),
+            constants: Vec::new(),
+            structs: Vec::new(),
+            enums: Vec::new(),
+            errors: Vec::new(),
+            events: Vec::new(),
+            user_types: Vec::new(),
+            usages: BTreeMap::new(), // Will be filled by analyzer
+            imports: Vec::new(),
+            is_context_only: false,
+            using_directives: Vec::new(),
+            compiler: CompilerInfo {
+                spdx_license: Self::spdx_license(&comments),
+                ..CompilerInfo::default()
+            },
+            line_starts: std::iter::once(0)
+                .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+        };
+        let mut free_function_asts = Vec::new();
+        let mut constant_asts = Vec::new();
 
         for item in &ast.0 {
-            if let pt::SourceUnitPart::ContractDefinition(contract) = item {
-                let mut contract_info = Self::extract_contract_info(contract, path, &content, &comments)?;
+            match item {
+                pt::SourceUnitPart::ContractDefinition(contract) => {
+                    let contract_info = Self::extract_contract_info(contract, path, &content, &comments)?;
+
+                    contracts.push(contract_info);
+                    contract_asts.push((**contract).clone());
+                }
+                pt::SourceUnitPart::FunctionDefinition(f) => {
+                    let mut func = Self::extract_function(f, &content, &comments)?;
+                    func.visibility = "internal".to_string(); // Free functions are always internal
+                    source_unit.functions.push(func);
+                    free_function_asts.push((**f).clone());
+                }
+                pt::SourceUnitPart::VariableDefinition(var) => {
+                    source_unit.constants.push(Self::extract_state_variable(var, &content, &comments)?);
+                    constant_asts.push((**var).clone());
+                }
+                pt::SourceUnitPart::StructDefinition(s) => {
+                    source_unit.structs.push(Self::extract_struct(s, &content, None)?);
+                }
+                pt::SourceUnitPart::EnumDefinition(e) => {
+                    source_unit.enums.push(Self::extract_enum(e, &content)?);
+                }
+                pt::SourceUnitPart::ErrorDefinition(err) => {
+                    source_unit.errors.push(Self::extract_error(err, &content, &comments)?);
+                }
+                pt::SourceUnitPart::EventDefinition(e) => {
+                    source_unit.events.push(Self::extract_event(e, &content, &comments)?);
+                }
+                pt::SourceUnitPart::ImportDirective(import) => {
+                    let (import_path, loc) = match import {
+                        pt::Import::Plain(p, loc)
+                        | pt::Import::GlobalSymbol(p, _, loc)
+                        | pt::Import::Rename(p, _, loc) => (p, loc),
+                    };
+                    if let pt::ImportPath::Filename(literal) = import_path {
+                        source_unit.imports.push(ImportDirective {
+                            path: literal.string.clone(),
+                            line_number: Self::get_line_number(loc, &content),
+                            resolved: None, // Will be filled by import resolver
+                        });
+                    }
+                }
+                pt::SourceUnitPart::Using(using) => {
+                    if let Some(directive) = Self::extract_using(using, &content) {
+                        source_unit.using_directives.push(directive);
+                    }
+                }
+                pt::SourceUnitPart::PragmaDirective(pragma) => {
+                    Self::extract_pragma(pragma, &mut source_unit.compiler);
+                }
+                pt::SourceUnitPart::TypeDefinition(t) => {
+                    source_unit.user_types.push(UserDefinedType {
+                        name: t.name.name.clone(),
+                        underlying: Self::canonical_type(&t.ty),
+                        line_number: Self::get_line_number(&t.loc, &content),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        // Pragmas apply to the whole file, wherever they appear
+        for contract in &mut contracts {
+            contract.compiler = source_unit.compiler.clone();
+        }
 
-                // Analyze state modifications and call chains
-                StateModificationAnalyzer::analyze(&mut contract_info, contract);
+        Ok(ParsedFile { contracts, contract_asts, source_unit, free_function_asts, constant_asts })
+    }
 
-                contracts.push(contract_info);
-                contract_asts.push((**contract).clone());
+    fn extract_pragma(pragma: &pt::PragmaDirective, compiler: &mut CompilerInfo) {
+        match pragma {
+            pt::PragmaDirective::Version(_, name, comparators) if name.name == "solidity" => {
+                let requirement = comparators.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ");
+                // Several `pragma solidity` directives must all hold
+                compiler.solidity = Some(match compiler.solidity.take() {
+                    Some(previous) => format!("{} {}", previous, requirement),
+                    None => requirement,
+                });
             }
+            pt::PragmaDirective::Identifier(_, Some(name), Some(value)) => match name.name.as_str() {
+                "abicoder" => compiler.abicoder = Some(value.name.clone()),
+                "experimental" => compiler.experimental.push(value.name.clone()),
+                _ => {}
+            },
+            pt::PragmaDirective::StringLiteral(_, name, value) if name.name == "experimental" => {
+                compiler.experimental.push(value.string.clone());
+            }
+            _ => {}
         }
+    }
 
-        Ok((contracts, contract_asts))
+    /// License from the `SPDX-License-Identifier:` comment
+    fn spdx_license(comments: &[pt::Comment]) -> Option<String> {
+        comments.iter().find_map(|comment| {
+            let text = match comment {
+                pt::Comment::Line(_, text)
+                | pt::Comment::Block(_, text)
+                | pt::Comment::DocLine(_, text)
+                | pt::Comment::DocBlock(_, text) => text,
+            };
+            let (_, after) = text.split_once("SPDX-License-Identifier:")?;
+            after.split_whitespace().next()
+                .map(|license| license.trim_end_matches("*/").to_string())
+                .filter(|license| !license.is_empty())
+        })
     }
 
     fn extract_contract_info(
@@ -46,6 +176,15 @@ impl SolidityParser {
                 .map(|n| n.name.clone())
                 .unwrap_or_default(),
             file_path: path.to_string_lossy().to_string(),
+            kind: match contract.ty {
+                pt::ContractTy::Interface(_) => ContractKind::Interface,
+                pt::ContractTy::Library(_) => ContractKind::Library,
+                pt::ContractTy::Contract(_) | pt::ContractTy::Abstract(_) => ContractKind::Contract,
+            },
+            is_abstract: matches!(contract.ty, pt::ContractTy::Abstract(_)),
+            base_contracts: contract.base.iter()
+                .filter_map(|b| b.name.identifiers.last().map(|id| id.name.clone()))
+                .collect(),
             state_variables: Vec::new(),
             structs: Vec::new(),
             enums: Vec::new(),
@@ -53,14 +192,31 @@ impl SolidityParser {
             functions: Vec::new(),
             modifiers: Vec::new(),
             errors: Vec::new(),
-            upgradeable_storage: None,
+            upgradeable_storage: Vec::new(), // Will be filled below
             dataflow_analysis: None, // Will be filled by analyzer
+            linearized_bases: Vec::new(), // Will be filled by inheritance resolver
+            unresolved_bases: Vec::new(), // Will be filled by inheritance resolver
+            implementers: Vec::new(), // Will be filled by inheritance resolver
+            is_context_only: false,
+            using_directives: Vec::new(),
+            storage_layout: None, // Will be filled by storage layout
+            proxy: None, // Will be filled by proxy detector
+            diamond_storage: Vec::new(), // Will be filled below
+            compiler: CompilerInfo::default(), // Will be filled from the file's pragmas
+            natspec: Self::extract_natspec(&contract.loc, comments, content),
+            reentrancy: Vec::new(), // Will be filled by ReentrancyDetector
+            cross_reentrancy: Vec::new(), // Will be filled by ReentrancyDetector
+            access_control: Vec::new(), // Will be filled by AccessControlAnalyzer
+            unprotected_writes: Vec::new(), // Will be filled by AccessControlAnalyzer
+            initializers: Vec::new(), // Will be filled by UpgradeabilityChecker
+            upgrade_issues: Vec::new(), // Will be filled by UpgradeabilityChecker
+            loops: Vec::new(), // Will be filled by LoopAnalyzer
         };
 
         for part in &contract.parts {
             match part {
                 pt::ContractPart::VariableDefinition(var) => {
-                    info.state_variables.push(Self::extract_state_variable(var, content)?);
+                    info.state_variables.push(Self::extract_state_variable(var, content, comments)?);
                 }
                 pt::ContractPart::StructDefinition(s) => {
                     // Extract storage location from comments before the struct
@@ -71,17 +227,22 @@ impl SolidityParser {
                     info.enums.push(Self::extract_enum(e, content)?);
                 }
                 pt::ContractPart::EventDefinition(e) => {
-                    info.events.push(Self::extract_event(e, content)?);
+                    info.events.push(Self::extract_event(e, content, comments)?);
                 }
                 pt::ContractPart::ErrorDefinition(err) => {
-                    info.errors.push(Self::extract_error(err, content)?);
+                    info.errors.push(Self::extract_error(err, content, comments)?);
+                }
+                pt::ContractPart::Using(using) => {
+                    if let Some(directive) = Self::extract_using(using, content) {
+                        info.using_directives.push(directive);
+                    }
                 }
                 pt::ContractPart::FunctionDefinition(f) => {
                     // Check if it's a modifier or a function
                     if matches!(f.ty, pt::FunctionTy::Modifier) {
                         info.modifiers.push(Self::extract_modifier(f, content)?);
                     } else {
-                        info.functions.push(Self::extract_function(f, content)?);
+                        info.functions.push(Self::extract_function(f, content, comments)?);
                     }
                 }
                 _ => {}
@@ -92,7 +253,8 @@ impl SolidityParser {
         Self::generate_implicit_getters(&mut info);
 
         // Detect upgradeable storage pattern (ERC-7201)
-        info.upgradeable_storage = Self::detect_upgradeable_storage(&info, contract, content);
+        info.upgradeable_storage = Self::detect_upgradeable_storage(&info, contract);
+        info.diamond_storage = Self::detect_diamond_storage(&info, contract);
 
         Ok(info)
     }
@@ -112,33 +274,66 @@ impl SolidityParser {
             }
 
             // Create implicit getter function
+            let (parameters, value_type) = Self::getter_shape(&var.var_type);
+            let param_types: Vec<String> = parameters.iter().map(|p| p.replace("address payable", "address")).collect();
             let getter = FunctionDef {
                 name: var.name.clone(),
+                signature: format!("{}({})", var.name, param_types.join(",")),
                 visibility: "external".to_string(), // Public variable getters are external
                 state_mutability: "view".to_string(), // Getters are always view
-                parameters: Vec::new(), // Simple getters have no parameters (mappings are handled differently)
-                returns: vec![var.var_type.clone()], // Returns the variable type
+                parameters, // One per mapping key and array index
+                returns: vec![value_type], // Structs are expanded into their members by type resolution
                 line_number: var.line_number, // Use the variable's line number
                 modifies_states: Vec::new(),
                 modifies_state_fields: Vec::new(),
                 reads_states: vec![var.name.clone()], // The getter reads this state variable
                 calls_functions: Vec::new(),
+                calls_free_functions: Vec::new(),
+                reads_file_constants: Vec::new(),
                 external_calls: Vec::new(),
                 storage_params: Vec::new(),
                 uses_modifiers: Vec::new(),
                 modifier_order: Vec::new(),
+                modifier_effects: Vec::new(),
                 emits_events: Vec::new(),
                 uses_errors: Vec::new(),
                 has_unchecked: false,
+                assembly: None,
                 return_value_usage: Vec::new(),
                 ignored_returns: Vec::new(),
+                inherited_from: None,
+                abi_signature: None,
+                selector: None,
+                natspec: var.natspec.clone(), // Docs on a public variable document its getter
+                parameter_types: Vec::new(), // Will be filled by type resolution
+                return_types: Vec::new(),
+                narrowing_casts: Vec::new(),
             };
 
             info.functions.push(getter);
         }
     }
 
-    fn extract_state_variable(var: &pt::VariableDefinition, content: &str) -> Result<StateVariable> {
+    /// Getter arguments of a public variable's type and the type it finally returns:
+    /// `mapping(address => uint256[])` takes `(address, uint256)` and returns `uint256`
+    fn getter_shape(var_type: &str) -> (Vec<String>, String) {
+        let mut parameters = Vec::new();
+        let mut ty = var_type.trim();
+        loop {
+            if let Some((key, value)) = TypeResolver::split_mapping(ty) {
+                parameters.push(key.trim().to_string());
+                ty = value.trim();
+            } else if let Some(open) = ty.strip_suffix(']').and_then(|t| t.rfind('[')) {
+                // `bytes` and `string` are returned whole, other arrays one element at a time
+                parameters.push("uint256".to_string());
+                ty = ty[..open].trim();
+            } else {
+                return (parameters, ty.to_string());
+            }
+        }
+    }
+
+    fn extract_state_variable(var: &pt::VariableDefinition, content: &str, comments: &[pt::Comment]) -> Result<StateVariable> {
         Ok(StateVariable {
             name: var.name.as_ref()
                 .map(|n| n.name.clone())
@@ -154,6 +349,13 @@ impl SolidityParser {
             line_number: Self::get_line_number(&var.loc, content),
             modification_chains: Vec::new(), // Will be filled by analyzer
             read_chains: Vec::new(), // Will be filled by analyzer
+            inherited_from: None,
+            slot: None, // Will be filled by storage layout
+            offset: None,
+            natspec: Self::extract_natspec(&var.loc, comments, content),
+            resolved_type: None, // Will be filled by type resolution
+            value: None,         // Will be filled by ConstantFolder
+            assignments: Vec::new(),
         })
     }
 
@@ -191,7 +393,7 @@ impl SolidityParser {
         })
     }
 
-    fn extract_event(e: &pt::EventDefinition, content: &str) -> Result<EventDef> {
+    fn extract_event(e: &pt::EventDefinition, content: &str, comments: &[pt::Comment]) -> Result<EventDef> {
         let parameters = e.fields.iter().map(|p| EventParam {
             name: p.name.as_ref()
                 .map(|n| n.name.clone())
@@ -207,10 +409,15 @@ impl SolidityParser {
             parameters,
             line_number: Self::get_line_number(&e.loc, content),
             emitted_in: Vec::new(), // Will be filled by analyzer
+            inherited_from: None,
+            is_anonymous: e.anonymous,
+            signature: None, // Will be filled by ABI generator
+            topic0: None,    // Will be filled by ABI generator
+            natspec: Self::extract_natspec(&e.loc, comments, content),
         })
     }
 
-    fn extract_error(err: &pt::ErrorDefinition, content: &str) -> Result<ErrorDef> {
+    fn extract_error(err: &pt::ErrorDefinition, content: &str, comments: &[pt::Comment]) -> Result<ErrorDef> {
         let parameters = err.fields.iter().map(|p| ErrorParam {
             name: p.name.as_ref()
                 .map(|n| n.name.clone())
@@ -226,10 +433,36 @@ impl SolidityParser {
             line_number: Self::get_line_number(&err.loc, content),
             used_in: Vec::new(), // Will be filled by analyzer
             is_inherited: false, // Locally defined errors are not inherited
+            inherited_from: None,
+            declared_at: None,
+            signature: None, // Will be filled by ABI generator
+            selector: None,  // Will be filled by ABI generator
+            natspec: Self::extract_natspec(&err.loc, comments, content),
+        })
+    }
+
+    fn extract_using(using: &pt::Using, content: &str) -> Option<UsingDirective> {
+        let path_to_string = |path: &pt::IdentifierPath| path.identifiers.iter()
+            .map(|id| id.name.clone())
+            .collect::<Vec<_>>()
+            .join(".");
+
+        let (library, functions) = match &using.list {
+            pt::UsingList::Library(path) => (Some(path_to_string(path)), Vec::new()),
+            pt::UsingList::Functions(list) => (None, list.iter().map(|f| path_to_string(&f.path)).collect()),
+            pt::UsingList::Error => return None,
+        };
+
+        Some(UsingDirective {
+            library,
+            functions,
+            target_type: using.ty.as_ref().map(Self::canonical_type),
+            is_global: using.global.is_some(),
+            line_number: Self::get_line_number(&using.loc, content),
         })
     }
 
-    fn extract_function(f: &pt::FunctionDefinition, content: &str) -> Result<FunctionDef> {
+    fn extract_function(f: &pt::FunctionDefinition, content: &str, comments: &[pt::Comment]) -> Result<FunctionDef> {
         let params = f.params.iter()
             .map(|(_, p)| p.as_ref()
                 .map(Self::param_to_string)
@@ -257,9 +490,8 @@ impl SolidityParser {
             .collect();
 
         Ok(FunctionDef {
-            name: f.name.as_ref()
-                .map(|n| n.name.clone())
-                .unwrap_or_else(|| "constructor".to_string()),
+            name: Self::function_name(f),
+            signature: Self::function_signature(f),
             visibility: Self::func_visibility_to_string(&f.attributes),
             state_mutability: Self::func_mutability_to_string(&f.attributes),
             parameters: params,
@@ -269,15 +501,26 @@ impl SolidityParser {
             modifies_state_fields: Vec::new(), // Will be filled by analyzer
             reads_states: Vec::new(),        // Will be filled by analyzer
             calls_functions: Vec::new(),     // Will be filled by analyzer
+            calls_free_functions: Vec::new(), // Will be filled by analyzer
+            reads_file_constants: Vec::new(), // Will be filled by analyzer
             external_calls: Vec::new(),      // Will be filled by analyzer
             storage_params: Vec::new(),      // Will be filled by analyzer
             uses_modifiers: uses_modifiers.clone(), // Extracted here
             modifier_order: uses_modifiers,  // Same as uses_modifiers (order preserved from AST)
+            modifier_effects: Vec::new(),    // Will be filled by analyzer
             emits_events: Vec::new(),        // Will be filled by analyzer
             uses_errors: Vec::new(),         // Will be filled by analyzer
             has_unchecked: false,            // Will be filled by analyzer
+            assembly: None,                  // Will be filled by analyzer
             return_value_usage: Vec::new(),  // Will be filled by analyzer
             ignored_returns: Vec::new(),     // Will be filled by analyzer
+            inherited_from: None,            // Set by inheritance resolver for inherited copies
+            abi_signature: None,             // Will be filled by ABI generator
+            selector: None,                  // Will be filled by ABI generator
+            natspec: Self::extract_natspec(&f.loc, comments, content),
+            parameter_types: Vec::new(),     // Will be filled by type resolution
+            return_types: Vec::new(),        // Will be filled by type resolution
+            narrowing_casts: Vec::new(),     // Will be filled by analyzer
         })
     }
 
@@ -295,9 +538,67 @@ impl SolidityParser {
             parameters: params,
             line_number: Self::get_line_number(&m.loc, content),
             used_in: Vec::new(), // Will be filled by analyzing function modifiers
+            inherited_from: None,
+            effects: Vec::new(), // Will be filled by analyzer
         })
     }
 
+    /// Name of a function definition; constructor, fallback and receive are nameless in the AST
+    pub fn function_name(f: &pt::FunctionDefinition) -> String {
+        match (&f.name, &f.ty) {
+            (Some(name), _) if !name.name.is_empty() => name.name.clone(),
+            (_, pt::FunctionTy::Fallback) => "fallback".to_string(),
+            (_, pt::FunctionTy::Receive) => "receive".to_string(),
+            (_, pt::FunctionTy::Modifier) => String::new(),
+            _ => "constructor".to_string(),
+        }
+    }
+
+    /// Canonical signature used to identify a function: `name(type1,type2)`
+    /// Overloads differ only in their parameter types, so the name alone is not unique.
+    pub fn function_signature(f: &pt::FunctionDefinition) -> String {
+        let param_types: Vec<String> = f.params.iter()
+            .map(|(_, p)| p.as_ref()
+                .map(|p| Self::canonical_type(&p.ty))
+                .unwrap_or_default())
+            .collect();
+        format!("{}({})", Self::function_name(f), param_types.join(","))
+    }
+
+    /// Parameter types of a signature such as `f(uint256,(address,bool)[])`, split at top-level commas
+    pub fn signature_param_types(signature: &str) -> Vec<String> {
+        let params = match signature.split_once('(') {
+            Some((_, rest)) => rest.strip_suffix(')').unwrap_or(rest),
+            None => return Vec::new(),
+        };
+        if params.is_empty() {
+            return Vec::new();
+        }
+
+        let mut result = Vec::new();
+        let mut depth = 0;
+        let mut current = String::new();
+        for c in params.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    result.push(std::mem::take(&mut current));
+                    continue;
+                }
+                _ => {}
+            }
+            current.push(c);
+        }
+        result.push(current);
+        result
+    }
+
+    /// Type as it appears in a canonical signature (`address payable` is just `address`)
+    pub fn canonical_type(ty: &pt::Expression) -> String {
+        Self::type_to_string(ty).replace("address payable", "address")
+    }
+
     // Helper function to get line number from Loc
     fn get_line_number(loc: &pt::Loc, content: &str) -> usize {
         match loc {
@@ -310,7 +611,7 @@ impl SolidityParser {
     }
 
     // Enhanced type to string conversion
-    fn type_to_string(ty: &pt::Expression) -> String {
+    pub fn type_to_string(ty: &pt::Expression) -> String {
         match ty {
             pt::Expression::Type(_, t) => Self::base_type_to_string(t),
             pt::Expression::Variable(ident) => ident.name.clone(),
@@ -343,15 +644,32 @@ impl SolidityParser {
                     Self::type_to_string(value))
             }
             pt::Type::DynamicBytes => "bytes".to_string(),
+            pt::Type::Function { params, attributes, returns } => {
+                let list = |params: &pt::ParameterList| params.iter()
+                    .map(|(_, p)| p.as_ref().map(|p| Self::type_to_string(&p.ty)).unwrap_or_default())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let mut rendered = format!("function({})", list(params));
+                for attr in attributes {
+                    if matches!(attr, pt::FunctionAttribute::Visibility(_) | pt::FunctionAttribute::Mutability(_)) {
+                        rendered.push_str(&format!(" {}", attr));
+                    }
+                }
+                if let Some((returns, _)) = returns.as_ref().filter(|(r, _)| !r.is_empty()) {
+                    rendered.push_str(&format!(" returns ({})", list(returns)));
+                }
+                rendered
+            }
             _ => format!("{:?}", ty),
         }
     }
 
-    fn expr_to_string(expr: &pt::Expression) -> String {
+    pub(crate) fn expr_to_string(expr: &pt::Expression) -> String {
         match expr {
             pt::Expression::NumberLiteral(_, val, _, _) => val.clone(),
             pt::Expression::Variable(ident) => ident.name.clone(),
-            _ => "".to_string(),
+            // Lengths like `2 * N` stay fixed-size; ConstantFolder evaluates them later
+            _ => expr.to_string(),
         }
     }
 
@@ -412,112 +730,369 @@ impl SolidityParser {
 
     /// Extract storage location from NatSpec comments
     /// Looks for: /// @custom:storage-location erc7201:namespace.name
-    fn extract_storage_location(loc: &pt::Loc, comments: &[pt::Comment], _content: &str) -> Option<String> {
-        // Get the position of the struct definition
-        if let pt::Loc::File(_, start, _) = loc {
-            // Look for comments immediately before this position
-            for comment in comments {
-                if let pt::Comment::DocLine(pt::Loc::File(_, _comment_start, comment_end), comment_text) = comment {
-                    // Check if this comment is right before the struct (within ~200 chars)
-                    if comment_end < start && start - comment_end < 200 {
-                        // Look for @custom:storage-location pattern
-                        if let Some(storage_loc) = Self::parse_storage_location_comment(comment_text) {
-                            return Some(storage_loc);
+    fn extract_storage_location(loc: &pt::Loc, comments: &[pt::Comment], content: &str) -> Option<String> {
+        Self::doc_comments(loc, comments, content).iter().rev()
+            .find_map(|text| Self::parse_storage_location_comment(text))
+    }
+
+    /// Doc comments directly above a declaration (only whitespace in between), in source order
+    fn doc_comments<'a>(loc: &pt::Loc, comments: &'a [pt::Comment], content: &str) -> Vec<&'a str> {
+        let pt::Loc::File(_, start, _) = loc else {
+            return Vec::new();
+        };
+
+        let mut docs = Vec::new();
+        let mut cursor = *start;
+        for comment in comments.iter().rev() {
+            let (comment_start, comment_end, text) = match comment {
+                pt::Comment::DocLine(pt::Loc::File(_, s, e), text)
+                | pt::Comment::DocBlock(pt::Loc::File(_, s, e), text) => (*s, *e, text),
+                _ => continue,
+            };
+            if comment_end > cursor {
+                continue;
+            }
+            if !content.get(comment_end..cursor).is_some_and(|gap| gap.trim().is_empty()) {
+                break;
+            }
+            docs.push(text.as_str());
+            cursor = comment_start;
+        }
+        docs.reverse();
+        docs
+    }
+
+    /// NatSpec of the doc comments above a declaration (None if there are none)
+    fn extract_natspec(loc: &pt::Loc, comments: &[pt::Comment], content: &str) -> Option<NatSpec> {
+        let docs = Self::doc_comments(loc, comments, content);
+        if docs.is_empty() {
+            return None;
+        }
+
+        // Split into (tag, text) entries; lines without a tag continue the previous one
+        let mut entries: Vec<(String, String)> = Vec::new();
+        for doc in docs {
+            let body = match doc.strip_prefix("/**") {
+                Some(block) => block.strip_suffix("*/").unwrap_or(block),
+                None => doc.trim_start_matches('/'),
+            };
+            for line in body.lines() {
+                let line = line.trim();
+                let line = line.strip_prefix('*').unwrap_or(line).trim();
+                if line.is_empty() {
+                    continue;
+                }
+                match line.strip_prefix('@') {
+                    Some(tagged) => {
+                        let (tag, text) = tagged.split_once(char::is_whitespace).unwrap_or((tagged, ""));
+                        entries.push((tag.to_string(), text.trim().to_string()));
+                    }
+                    None => match entries.last_mut() {
+                        Some((_, text)) if !text.is_empty() => {
+                            text.push(' ');
+                            text.push_str(line);
                         }
+                        Some((_, text)) => text.push_str(line),
+                        None => entries.push(("notice".to_string(), line.to_string())),
+                    },
+                }
+            }
+        }
+
+        let mut natspec = NatSpec::default();
+        let append = |field: &mut Option<String>, text: String| match field {
+            Some(existing) => {
+                existing.push(' ');
+                existing.push_str(&text);
+            }
+            None => *field = Some(text),
+        };
+        for (tag, text) in entries {
+            match tag.as_str() {
+                "title" => append(&mut natspec.title, text),
+                "author" => append(&mut natspec.author, text),
+                "notice" => append(&mut natspec.notice, text),
+                "dev" => append(&mut natspec.dev, text),
+                "param" => {
+                    let (name, description) = text.split_once(char::is_whitespace).unwrap_or((&text, ""));
+                    natspec.params.push((name.to_string(), description.trim().to_string()));
+                }
+                "return" => natspec.returns.push(text),
+                "inheritdoc" => natspec.inheritdoc = text.split_whitespace().next().map(str::to_string),
+                _ => {
+                    if let Some(custom) = tag.strip_prefix("custom:") {
+                        natspec.custom.insert(custom.to_string(), text);
                     }
                 }
             }
         }
-        None
+        Some(natspec)
     }
 
     /// Parse @custom:storage-location comment to extract namespace
     fn parse_storage_location_comment(comment: &str) -> Option<String> {
         // Pattern: @custom:storage-location erc7201:openzeppelin.storage.ERC20
         if let Some(pos) = comment.find("@custom:storage-location") {
+            // The formula and id are a single token; block comments may continue on the next line
             let after = &comment[pos + "@custom:storage-location".len()..];
-            let trimmed = after.trim();
-            if !trimmed.is_empty() {
-                return Some(trimmed.to_string());
-            }
+            return after.split_whitespace().next().map(str::to_string);
         }
         None
     }
 
-    /// Detect ERC-7201 upgradeable storage pattern
+    /// Find ERC-7201 namespaced storage structs: structs annotated with `@custom:storage-location`
+    /// and structs a storage pointer is pointed at with `$.slot := CONSTANT` in assembly
     fn detect_upgradeable_storage(
         info: &ContractInfo,
         contract: &pt::ContractDefinition,
-        _content: &str,
-    ) -> Option<UpgradeableStorage> {
-        use crate::models::UpgradeableStorage;
-
-        // Look for a struct with storage-location annotation
-        let storage_struct = info.structs.iter().find(|s| s.storage_location.is_some())?;
+    ) -> Vec<UpgradeableStorage> {
+        let slot_constants = Self::slot_constants(contract);
+        let constant_value = |name: &str| slot_constants.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone());
+        let accessors = Self::slot_accessors(info, contract, &slot_constants);
 
-        // Extract namespace from storage location (e.g., "erc7201:openzeppelin.storage.ERC20")
-        let storage_location = storage_struct.storage_location.as_ref()?;
-        let namespace = if let Some(colon_pos) = storage_location.find(':') {
-            storage_location[colon_pos + 1..].to_string()
-        } else {
-            storage_location.clone()
-        };
+        let annotated: Vec<&StructDef> = info.structs.iter().filter(|s| s.storage_location.is_some()).collect();
+        let mut storages = Vec::new();
 
-        // Look for storage location constant (bytes32 constant XXXStorageLocation = ...)
-        let mut storage_location_constant = String::new();
-        let mut storage_slot = String::new();
+        for storage_struct in &annotated {
+            // Namespace from the annotation (e.g., "erc7201:openzeppelin.storage.ERC20")
+            let storage_location = storage_struct.storage_location.clone().unwrap_or_default();
+            let (formula, namespace) = match storage_location.split_once(':') {
+                Some((formula, id)) => (formula.to_string(), id.to_string()),
+                None => (String::new(), storage_location.clone()),
+            };
 
-        for part in &contract.parts {
-            if let pt::ContractPart::VariableDefinition(var) = part {
-                // Check if this is a bytes32 constant
-                let is_bytes32 = matches!(
-                    Self::type_to_string(&var.ty).as_str(),
-                    "bytes32"
-                );
-                let is_constant = var.attrs.iter().any(|a| {
-                    matches!(a, pt::VariableAttribute::Constant(_))
-                });
+            let mut storage_location_constant = String::new();
+            let mut accessor_function = String::new();
 
-                if is_bytes32 && is_constant {
-                    if let Some(name) = &var.name {
-                        let var_name = name.name.clone();
-                        // Check if name matches pattern like "ERC20StorageLocation"
-                        if var_name.contains("StorageLocation") || var_name.contains("Storage") {
-                            storage_location_constant = var_name;
-                            // Extract the slot value from the initializer
-                            if let Some(init) = &var.initializer {
-                                storage_slot = Self::extract_hex_value(init);
-                            }
-                            break;
-                        }
-                    }
-                }
+            // Prefer the accessor that points at this struct
+            if let Some((_, constant, accessor)) = accessors.iter().find(|(s, _, _)| *s == storage_struct.name) {
+                storage_location_constant = constant.clone();
+                accessor_function = accessor.clone();
             }
-        }
 
-        // Look for accessor function (e.g., _getERC20Storage)
-        let accessor_function = info.functions.iter()
-            .find(|f| {
-                f.name.contains("get") &&
-                f.name.contains("Storage") &&
-                f.visibility == "private"
-            })
-            .map(|f| f.name.clone())
-            .unwrap_or_default();
+            // Otherwise a constant holding the namespace's slot or derived from the namespace
+            // string, or, with a single namespace, the naming convention (e.g., "ERC20StorageLocation")
+            if storage_location_constant.is_empty() {
+                let expected = Erc7201Verifier::namespace_slot(&namespace);
+                storage_location_constant = slot_constants.iter()
+                    .find(|(name, value)| (formula == "erc7201" && value.eq_ignore_ascii_case(&expected))
+                        || Self::constant_initializer(contract, name)
+                            .and_then(Self::string_literal_in)
+                            .is_some_and(|id| id == namespace))
+                    .or_else(|| slot_constants.iter()
+                        .filter(|_| annotated.len() == 1)
+                        .find(|(name, _)| name.contains("Storage")))
+                    .map(|(name, _)| name.clone())
+                    .unwrap_or_default();
+            }
+            if accessor_function.is_empty() {
+                let bare_name = storage_struct.name.trim_end_matches("Storage");
+                let candidates: Vec<&FunctionDef> = info.functions.iter()
+                    .filter(|f| f.name.contains("get") && f.name.contains("Storage") && f.visibility == "private")
+                    .collect();
+                accessor_function = candidates.iter()
+                    .find(|f| f.name.contains(bare_name))
+                    .or_else(|| candidates.first().filter(|_| annotated.len() == 1))
+                    .map(|f| f.name.clone())
+                    .unwrap_or_default();
+            }
 
-        // Only return if we found the essential components
-        if !storage_location_constant.is_empty() && !accessor_function.is_empty() {
-            Some(UpgradeableStorage {
+            storages.push(UpgradeableStorage {
                 namespace,
+                formula,
                 storage_struct: storage_struct.name.clone(),
+                storage_slot: constant_value(&storage_location_constant).unwrap_or_default(),
                 storage_location_constant,
-                storage_slot,
                 accessor_function,
                 struct_fields: storage_struct.members.clone(),
+                field_layout: Vec::new(), // Will be filled by StorageLayoutEngine
+                line_number: storage_struct.line_number,
+                expected_slot: None, // Will be filled by Erc7201Verifier
+                issues: Vec::new(),
+            });
+        }
+
+        // Structs reached through `.slot` assignment that lack the annotation; in libraries
+        // these are Diamond storage
+        for (struct_name, constant, accessor) in &accessors {
+            if info.kind == ContractKind::Library || storages.iter().any(|s| &s.storage_struct == struct_name) {
+                continue;
+            }
+            let Some(storage_struct) = info.structs.iter().find(|s| &s.name == struct_name) else {
+                continue;
+            };
+            storages.push(UpgradeableStorage {
+                namespace: String::new(),
+                formula: String::new(),
+                storage_struct: struct_name.clone(),
+                storage_location_constant: constant.clone(),
+                storage_slot: constant_value(constant).unwrap_or_default(),
+                accessor_function: accessor.clone(),
+                struct_fields: storage_struct.members.clone(),
+                field_layout: Vec::new(), // Will be filled by StorageLayoutEngine
                 line_number: storage_struct.line_number,
+                expected_slot: None,
+                issues: vec![format!(
+                    "`{}` is used as namespaced storage through `{}` but has no `@custom:storage-location` annotation",
+                    struct_name, accessor
+                )],
+            });
+        }
+
+        storages
+    }
+
+    /// Find Diamond storage in a library: unannotated structs pinned to a slot constant by an
+    /// accessor such as `diamondStorage()`
+    fn detect_diamond_storage(info: &ContractInfo, contract: &pt::ContractDefinition) -> Vec<DiamondStorage> {
+        if info.kind != ContractKind::Library {
+            return Vec::new();
+        }
+
+        let slot_constants = Self::slot_constants(contract);
+        let mut storages: Vec<DiamondStorage> = Vec::new();
+        for (struct_name, constant, accessor) in Self::slot_accessors(info, contract, &slot_constants) {
+            let Some(storage_struct) = info.structs.iter()
+                .find(|s| s.name == struct_name && s.storage_location.is_none()) else {
+                continue;
+            };
+            if storages.iter().any(|s| s.storage_struct == struct_name) {
+                continue;
+            }
+
+            let slot_id = contract.parts.iter()
+                .find_map(|part| match part {
+                    pt::ContractPart::VariableDefinition(var)
+                        if var.name.as_ref().is_some_and(|n| n.name == constant) => var.initializer.as_ref(),
+                    _ => None,
+                })
+                .and_then(Self::hashed_string);
+            storages.push(DiamondStorage {
+                storage_struct: struct_name,
+                slot: slot_constants.iter()
+                    .find(|(name, _)| *name == constant)
+                    .map(|(_, value)| value.clone())
+                    .unwrap_or_default(),
+                slot_constant: constant,
+                slot_id,
+                accessor_function: accessor,
+                fields: storage_struct.members.clone(),
+                line_number: storage_struct.line_number,
+            });
+        }
+
+        storages
+    }
+
+    /// bytes32 constants that may hold a slot: name -> value (empty if not a literal)
+    fn slot_constants(contract: &pt::ContractDefinition) -> Vec<(String, String)> {
+        contract.parts.iter()
+            .filter_map(|part| match part {
+                pt::ContractPart::VariableDefinition(var)
+                    if Self::type_to_string(&var.ty) == "bytes32"
+                        && var.attrs.iter().any(|a| matches!(a, pt::VariableAttribute::Constant(_))) =>
+                {
+                    let name = var.name.as_ref()?.name.clone();
+                    let slot = var.initializer.as_ref().map(Self::slot_constant_value).unwrap_or_default();
+                    Some((name, slot))
+                }
+                _ => None,
             })
-        } else {
-            None
+            .collect()
+    }
+
+    fn constant_initializer<'c>(contract: &'c pt::ContractDefinition, name: &str) -> Option<&'c pt::Expression> {
+        contract.parts.iter().find_map(|part| match part {
+            pt::ContractPart::VariableDefinition(var) if var.name.as_ref().is_some_and(|n| n.name == name) => {
+                var.initializer.as_ref()
+            }
+            _ => None,
+        })
+    }
+
+    /// Accessors: `function _getS() returns (S storage $) { assembly { $.slot := CONSTANT } }`,
+    /// also through a local (`bytes32 position = CONSTANT;`), as (struct, constant, accessor)
+    fn slot_accessors(
+        info: &ContractInfo,
+        contract: &pt::ContractDefinition,
+        slot_constants: &[(String, String)],
+    ) -> Vec<(String, String, String)> {
+        let mut accessors = Vec::new();
+        for part in &contract.parts {
+            let pt::ContractPart::FunctionDefinition(f) = part else {
+                continue;
+            };
+            let Some(name) = &f.name else {
+                continue;
+            };
+
+            // Locals initialized from a slot constant
+            let mut locals: HashMap<String, String> = HashMap::new();
+            if let Some(pt::Statement::Block { statements, .. }) = &f.body {
+                for stmt in statements {
+                    if let pt::Statement::VariableDefinition(_, decl, Some(pt::Expression::Variable(value))) = stmt {
+                        if let Some(local) = &decl.name {
+                            locals.insert(local.name.clone(), value.name.clone());
+                        }
+                    }
+                }
+            }
+
+            for pointer in YulAnalyzer::slot_pointers(f) {
+                let constant = locals.get(&pointer.slot).cloned().unwrap_or(pointer.slot);
+                if !slot_constants.iter().any(|(n, _)| *n == constant) {
+                    continue;
+                }
+                let pointed_struct = f.returns.iter()
+                    .chain(f.params.iter())
+                    .filter_map(|(_, p)| p.as_ref())
+                    .find(|p| p.name.as_ref().is_some_and(|n| n.name == pointer.pointer))
+                    .map(|p| Self::type_to_string(&p.ty))
+                    .filter(|ty| info.structs.iter().any(|s| &s.name == ty));
+                if let Some(struct_name) = pointed_struct {
+                    accessors.push((struct_name, constant, name.name.clone()));
+                }
+            }
+        }
+        accessors
+    }
+
+    /// The string in `keccak256("id")`, `keccak256(bytes("id"))` or `keccak256(abi.encodePacked("id"))`
+    fn hashed_string(expr: &pt::Expression) -> Option<String> {
+        let pt::Expression::FunctionCall(_, func, args) = expr else {
+            return None;
+        };
+        if !matches!(&**func, pt::Expression::Variable(id) if id.name == "keccak256") || args.len() != 1 {
+            return None;
+        }
+        match &args[0] {
+            pt::Expression::StringLiteral(parts) => Some(parts.iter().map(|p| p.string.as_str()).collect()),
+            pt::Expression::FunctionCall(_, _, inner) if inner.len() == 1 => match &inner[0] {
+                pt::Expression::StringLiteral(parts) => Some(parts.iter().map(|p| p.string.as_str()).collect()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Value of a slot constant that is a literal or a Diamond storage position (`keccak256("id")`);
+    /// ConstantFolder evaluates other expressions, such as the ERC-7201 formula
+    fn slot_constant_value(expr: &pt::Expression) -> String {
+        if let Some(id) = Self::hashed_string(expr) {
+            let hash = AbiGenerator::keccak256(id.as_bytes());
+            return format!("0x{}", hash.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+        }
+        Self::extract_hex_value(expr)
+    }
+
+    pub(crate) fn string_literal_in(expr: &pt::Expression) -> Option<String> {
+        match expr {
+            pt::Expression::StringLiteral(parts) => Some(parts.iter().map(|p| p.string.as_str()).collect()),
+            pt::Expression::FunctionCall(_, _, args) => args.iter().find_map(Self::string_literal_in),
+            pt::Expression::Subtract(_, left, right) => Self::string_literal_in(left).or_else(|| Self::string_literal_in(right)),
+            _ => None,
         }
     }
 
@@ -530,3 +1105,82 @@ impl SolidityParser {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{analyze, analyze_files, contract};
+
+    #[test]
+    fn file_level_definitions_and_their_usages() {
+        let project = analyze_files(&[
+            ("lib/Errors.sol", "error TooHigh(uint256 fee);\n"),
+            ("Exchange.sol", r#"
+                import {TooHigh} from "./lib/Errors.sol";
+
+                uint256 constant MAX_FEE = 1000;
+                struct Order { address maker; uint256 amount; }
+                type Price is uint128;
+
+                function clamp(uint256 fee) pure returns (uint256) {
+                    return fee > MAX_FEE ? MAX_FEE : fee;
+                }
+
+                contract Exchange {
+                    uint256 public fee;
+                    function setFee(uint256 newFee) external {
+                        if (newFee > MAX_FEE) revert TooHigh(newFee);
+                        fee = clamp(newFee);
+                    }
+                }
+            "#),
+        ]);
+        let unit = project.source_units.iter().find(|u| u.file_path.ends_with("Exchange.sol")).unwrap();
+        assert_eq!(unit.functions[0].signature, "clamp(uint256)");
+        assert_eq!(unit.constants[0].name, "MAX_FEE");
+        assert_eq!((unit.structs[0].name.as_str(), unit.user_types[0].name.as_str()), ("Order", "Price"));
+        assert_eq!(unit.usages["clamp(uint256)"], ["Exchange.setFee(uint256)"]);
+        assert_eq!(unit.usages["MAX_FEE"], ["Exchange.setFee(uint256)", "clamp(uint256)"]);
+
+        let exchange = contract(&project, "Exchange");
+        let set_fee = exchange.functions.iter().find(|f| f.name == "setFee").unwrap();
+        assert_eq!(set_fee.calls_free_functions, ["clamp(uint256)"]);
+        assert_eq!(set_fee.reads_file_constants, ["MAX_FEE"]);
+
+        let error = exchange.errors.iter().find(|e| e.name == "TooHigh").unwrap();
+        assert!(error.is_inherited);
+        assert!(error.declared_at.as_deref().is_some_and(|at| at.ends_with("lib/Errors.sol:1")), "{:?}", error.declared_at);
+        assert_eq!(error.signature.as_deref(), Some("TooHigh(uint256)"));
+    }
+
+    #[test]
+    fn public_variables_get_getters_with_key_and_index_parameters() {
+        let project = analyze(r#"
+            contract Vault {
+                struct Position { address owner; uint256[] amounts; mapping(uint256 => bool) flags; string note; }
+                mapping(address => uint256) public balances;
+                mapping(address => mapping(uint256 => uint256[])) public history;
+                address[] public holders;
+                bytes public data;
+                Position public position;
+                mapping(uint256 => Position) public positions;
+            }
+        "#);
+        let vault = contract(&project, "Vault");
+        let getter = |name: &str| {
+            let f = vault.functions.iter().find(|f| f.name == name).unwrap();
+            (f.signature.as_str(), f.returns.clone())
+        };
+
+        assert_eq!(getter("balances"), ("balances(address)", vec!["uint256".to_string()]));
+        assert_eq!(getter("history"), ("history(address,uint256,uint256)", vec!["uint256".to_string()]));
+        assert_eq!(getter("holders"), ("holders(uint256)", vec!["address".to_string()]));
+        assert_eq!(getter("data"), ("data()", vec!["bytes".to_string()]));
+        // Struct getters return the members, leaving out arrays and mappings
+        let members = vec!["address owner".to_string(), "string note".to_string()];
+        assert_eq!(getter("position"), ("position()", members.clone()));
+        assert_eq!(getter("positions"), ("positions(uint256)", members));
+
+        let balances = vault.functions.iter().find(|f| f.name == "balances").unwrap();
+        assert_eq!(balances.selector.as_deref(), Some("0x27e235e3"));
+    }
+}
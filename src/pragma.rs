@@ -0,0 +1,424 @@
+use crate::models::*;
+use anyhow::Result;
+use colored::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+type Version = (u64, u64, u64);
+
+// First versions with checked arithmetic, custom errors, user-defined value types,
+// `using ... global`, PUSH0 by default (shanghai), transient storage opcodes and file-level events
+const CHECKED_ARITHMETIC: Version = (0, 8, 0);
+const CUSTOM_ERRORS: Version = (0, 8, 4);
+const USER_DEFINED_VALUE_TYPES: Version = (0, 8, 8);
+const GLOBAL_USING: Version = (0, 8, 13);
+const PUSH0_DEFAULT: Version = (0, 8, 20);
+const FILE_LEVEL_EVENTS: Version = (0, 8, 22);
+const TRANSIENT_STORAGE: Version = (0, 8, 24);
+
+/// A `pragma solidity` requirement as a union of half-open version intervals `[from, to)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    intervals: Vec<(Version, Option<Version>)>,
+}
+
+impl VersionReq {
+    /// Parse a requirement such as `^0.8.20`, `>=0.7.0 <0.9.0`, `0.7.0 - 0.8.4` or `0.8.7 || ^0.8.20`
+    pub fn parse(requirement: &str) -> Option<VersionReq> {
+        let mut intervals = Vec::new();
+        for alternative in requirement.split("||") {
+            let tokens: Vec<&str> = alternative.split_whitespace().collect();
+            let mut req = VersionReq { intervals: vec![((0, 0, 0), None)] };
+            let mut i = 0;
+            while i < tokens.len() {
+                let comparator = if tokens.get(i + 1) == Some(&"-") {
+                    // `from - to` includes every version matching `to`
+                    let from = Self::parts(tokens[i])?;
+                    let to = Self::parts(tokens.get(i + 2)?)?;
+                    i += 3;
+                    (Self::lowest(&from), Some(Self::after(&to)))
+                } else {
+                    i += 1;
+                    Self::comparator(tokens[i - 1])?
+                };
+                req = req.intersect(&VersionReq { intervals: vec![comparator] });
+            }
+            intervals.extend(req.intervals);
+        }
+        Some(VersionReq { intervals })
+    }
+
+    fn comparator(token: &str) -> Option<(Version, Option<Version>)> {
+        if token == "*" {
+            return Some(((0, 0, 0), None));
+        }
+        let split = token.find(|c: char| c.is_ascii_digit())?;
+        let (op, version) = token.split_at(split);
+        let parts = Self::parts(version)?;
+        let low = Self::lowest(&parts);
+        Some(match op {
+            "" | "=" => (low, Some(Self::after(&parts))),
+            ">=" => (low, None),
+            ">" => (Self::after(&parts), None),
+            "<" => ((0, 0, 0), Some(low)),
+            "<=" => ((0, 0, 0), Some(Self::after(&parts))),
+            "~" => match parts.as_slice() {
+                [major] => (low, Some((major + 1, 0, 0))),
+                [major, minor, ..] => (low, Some((*major, minor + 1, 0))),
+                _ => return None,
+            },
+            "^" => {
+                // The left-most non-zero component must not change
+                let position = parts.iter().position(|p| *p != 0).unwrap_or(parts.len() - 1);
+                let mut upper = parts[..=position].to_vec();
+                upper[position] += 1;
+                (low, Some(Self::lowest(&upper)))
+            }
+            _ => return None,
+        })
+    }
+
+    fn parts(version: &str) -> Option<Vec<u64>> {
+        let parts: Option<Vec<u64>> = version.split('.').map(|p| p.parse().ok()).collect();
+        parts.filter(|p| (1..=3).contains(&p.len()))
+    }
+
+    /// Lowest version matching a possibly partial version (`0.8` -> 0.8.0)
+    fn lowest(parts: &[u64]) -> Version {
+        (parts[0], parts.get(1).copied().unwrap_or(0), parts.get(2).copied().unwrap_or(0))
+    }
+
+    /// First version after every version matching a possibly partial version (`0.8` -> 0.9.0)
+    fn after(parts: &[u64]) -> Version {
+        match parts {
+            [major] => (major + 1, 0, 0),
+            [major, minor] => (*major, minor + 1, 0),
+            _ => (parts[0], parts[1], parts[2] + 1),
+        }
+    }
+
+    pub fn intersect(&self, other: &VersionReq) -> VersionReq {
+        let mut intervals = Vec::new();
+        for (from_a, to_a) in &self.intervals {
+            for (from_b, to_b) in &other.intervals {
+                let from = *from_a.max(from_b);
+                let to = match (to_a, to_b) {
+                    (Some(a), Some(b)) => Some(*a.min(b)),
+                    (Some(a), None) | (None, Some(a)) => Some(*a),
+                    (None, None) => None,
+                };
+                if to.is_none_or(|to| from < to) {
+                    intervals.push((from, to));
+                }
+            }
+        }
+        VersionReq { intervals }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Whether some allowed version is older than `version`
+    pub fn allows_below(&self, version: Version) -> bool {
+        self.intervals.iter().any(|(from, _)| *from < version)
+    }
+
+    /// Whether `version` or some newer version is allowed
+    pub fn allows_from(&self, version: Version) -> bool {
+        self.intervals.iter().any(|(_, to)| to.is_none_or(|to| to > version))
+    }
+
+    /// Pinned to a single release (`0.8.24`, `=0.8.24`)
+    pub fn is_exact(&self) -> bool {
+        matches!(self.intervals.as_slice(), [((major, minor, patch), Some(to))] if *to == (*major, *minor, patch + 1))
+    }
+}
+
+/// Checks `pragma solidity` / `abicoder` / SPDX across the project and against the
+/// language features each file uses
+pub struct PragmaChecker;
+
+impl PragmaChecker {
+    /// Whether arithmetic outside `unchecked` reverts on overflow for every allowed compiler
+    pub fn checked_arithmetic(compiler: &CompilerInfo) -> bool {
+        compiler.solidity.as_deref()
+            .and_then(VersionReq::parse)
+            .is_none_or(|req| !req.allows_below(CHECKED_ARITHMETIC))
+    }
+
+    /// Fill `compiler.issues` of contracts and source units; returns project-level warnings
+    pub fn check(contracts: &mut [ContractInfo], source_units: &mut [SourceUnit]) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let units: Vec<&mut SourceUnit> = source_units.iter_mut().filter(|u| !u.is_context_only).collect();
+
+        let mut requirements: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut floating = Vec::new();
+        let mut without_pragma = Vec::new();
+        let mut without_license = Vec::new();
+        let mut push0 = Vec::new();
+        let mut abicoders: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        // Versions every file accepts; None once a requirement can't be interpreted
+        let mut common = Some(VersionReq { intervals: vec![((0, 0, 0), None)] });
+
+        for unit in units {
+            let file = unit.file_path.clone();
+            if unit.compiler.spdx_license.is_none() {
+                without_license.push(file.clone());
+            }
+
+            let Some(requirement) = unit.compiler.solidity.clone() else {
+                without_pragma.push(file);
+                continue;
+            };
+            requirements.entry(requirement.clone()).or_default().push(file.clone());
+
+            let Some(req) = VersionReq::parse(&requirement) else {
+                unit.compiler.issues.push(format!("`pragma solidity {}` could not be interpreted", requirement));
+                common = None;
+                continue;
+            };
+            if req.is_empty() {
+                unit.compiler.issues.push(format!("`pragma solidity {}` matches no compiler version", requirement));
+            }
+            if !req.is_exact() {
+                floating.push(format!("{} (`{}`)", file, requirement));
+            }
+            if req.allows_from(PUSH0_DEFAULT) {
+                push0.push(file.clone());
+            }
+            common = common.map(|c| c.intersect(&req));
+
+            // ABI coder v2 is the default from 0.8.0
+            let abicoder = match unit.compiler.abicoder.as_deref() {
+                Some("v1") => "v1",
+                Some(_) => "v2",
+                None if unit.compiler.experimental.iter().any(|e| e == "ABIEncoderV2") => "v2",
+                None if req.allows_below(CHECKED_ARITHMETIC) => "v1",
+                None => "v2",
+            };
+            abicoders.entry(abicoder).or_default().push(file.clone());
+
+            Self::check_source_unit(unit, &req, &requirement);
+        }
+
+        for contract in contracts.iter_mut().filter(|c| !c.is_context_only) {
+            let Some(requirement) = contract.compiler.solidity.clone() else {
+                continue;
+            };
+            if let Some(req) = VersionReq::parse(&requirement) {
+                Self::check_contract(contract, &req, &requirement);
+            }
+        }
+
+        if !without_pragma.is_empty() {
+            warnings.push(format!("no `pragma solidity` in: {}", without_pragma.join(", ")));
+        }
+        if requirements.len() > 1 {
+            let listed: Vec<String> = requirements.iter()
+                .map(|(req, files)| format!("`{}` ({} file(s))", req, files.len()))
+                .collect();
+            warnings.push(format!("mixed `pragma solidity` across files: {}", listed.join(", ")));
+        }
+        if common.as_ref().is_some_and(VersionReq::is_empty) && !requirements.is_empty() {
+            warnings.push("no single compiler version satisfies every file's `pragma solidity`".to_string());
+        }
+        if !floating.is_empty() {
+            warnings.push(format!("floating `pragma solidity` in {} file(s): {}", floating.len(), floating.join(", ")));
+        }
+        if abicoders.len() > 1 {
+            let v1 = abicoders.get("v1").cloned().unwrap_or_default();
+            warnings.push(format!(
+                "ABI coder v1 in {} while other files use v2; v1 code can't call functions taking or returning structs or nested arrays",
+                v1.join(", ")
+            ));
+        }
+        if !push0.is_empty() {
+            warnings.push(format!(
+                "{} file(s) allow solc >= 0.8.20, which targets `shanghai` by default and emits PUSH0; pin `evm_version` for chains without it",
+                push0.len()
+            ));
+        }
+        if !without_license.is_empty() {
+            warnings.push(format!("no `SPDX-License-Identifier` in: {}", without_license.join(", ")));
+        }
+
+        warnings
+    }
+
+    fn check_source_unit(unit: &mut SourceUnit, req: &VersionReq, requirement: &str) {
+        let mut features = Vec::new();
+        if !unit.user_types.is_empty() {
+            features.push(("user-defined value types", USER_DEFINED_VALUE_TYPES));
+        }
+        if unit.using_directives.iter().any(|u| u.is_global) {
+            features.push(("`using ... for ... global`", GLOBAL_USING));
+        }
+        if !unit.errors.is_empty() {
+            features.push(("file-level custom errors", CUSTOM_ERRORS));
+        }
+        if !unit.events.is_empty() {
+            features.push(("file-level events", FILE_LEVEL_EVENTS));
+        }
+        Self::push_feature_issues(&mut unit.compiler.issues, &features, req, requirement);
+    }
+
+    fn check_contract(contract: &mut ContractInfo, req: &VersionReq, requirement: &str) {
+        let mut features = Vec::new();
+        if !contract.errors.iter().all(|e| e.is_inherited) {
+            features.push(("custom errors", CUSTOM_ERRORS));
+        }
+        let transient = contract.functions.iter()
+            .filter(|f| f.inherited_from.is_none())
+            .find(|f| f.assembly.as_ref()
+                .is_some_and(|a| a.storage_accesses.iter().any(|s| matches!(s.opcode.as_str(), "tload" | "tstore"))));
+        let transient_feature = transient.map(|f| format!("transient storage (`tload`/`tstore` in `{}`)", f.name));
+        if let Some(feature) = &transient_feature {
+            features.push((feature.as_str(), TRANSIENT_STORAGE));
+        }
+        Self::push_feature_issues(&mut contract.compiler.issues, &features, req, requirement);
+
+        if req.allows_below(CHECKED_ARITHMETIC) {
+            contract.compiler.issues.push(format!(
+                "`pragma solidity {}` allows versions before 0.8.0: arithmetic wraps on overflow unless it goes through SafeMath",
+                requirement
+            ));
+        }
+    }
+
+    fn push_feature_issues(issues: &mut Vec<String>, features: &[(&str, Version)], req: &VersionReq, requirement: &str) {
+        for (feature, (major, minor, patch)) in features {
+            if req.allows_below((*major, *minor, *patch)) {
+                issues.push(format!(
+                    "uses {}, which needs solc >= {}.{}.{}, but `pragma solidity {}` allows older versions",
+                    feature, major, minor, patch, requirement
+                ));
+            }
+        }
+    }
+
+    pub fn print_summary(warnings: &[String], source_units: &[SourceUnit]) {
+        let issues: Vec<(&str, &String)> = source_units.iter()
+            .filter(|u| !u.is_context_only)
+            .flat_map(|u| u.compiler.issues.iter().map(move |i| (u.file_path.as_str(), i)))
+            .collect();
+        if warnings.is_empty() && issues.is_empty() {
+            return;
+        }
+
+        println!();
+        println!("{}", "🧾 Compiler pragmas".bold());
+        for warning in warnings {
+            println!("  {} {}", "⚠️".yellow(), warning);
+        }
+        for (file, issue) in issues {
+            println!("  {} {}: {}", "⚠️".yellow(), file, issue);
+        }
+    }
+
+    /// Write `compiler.md`: pragmas and license of every file, with the project-level warnings
+    pub fn save_report(warnings: &[String], source_units: &[SourceUnit], output_dir: &Path) -> Result<()> {
+        fs::create_dir_all(output_dir)?;
+
+        let mut md = String::new();
+        md.push_str("# Compiler Pragmas\n\n");
+        if warnings.is_empty() {
+            md.push_str("✅ **Pragmas are consistent across files**\n\n");
+        } else {
+            for warning in warnings {
+                md.push_str(&format!("- ⚠️ {}\n", warning));
+            }
+            md.push('\n');
+        }
+
+        md.push_str("| File | SPDX | `pragma solidity` | ABI coder | Issues |\n");
+        md.push_str("|------|------|-------------------|-----------|--------|\n");
+        for unit in source_units.iter().filter(|u| !u.is_context_only) {
+            let compiler = &unit.compiler;
+            md.push_str(&format!("| `{}` | {} | {} | {} | {} |\n",
+                unit.file_path,
+                compiler.spdx_license.as_deref().unwrap_or("-"),
+                compiler.solidity.as_ref().map(|s| format!("`{}`", s)).unwrap_or_else(|| "-".to_string()),
+                compiler.abicoder.as_deref().unwrap_or("default"),
+                compiler.issues.join("; "),
+            ));
+        }
+
+        fs::write(output_dir.join("compiler.md"), md)?;
+        println!("  {} compiler.md", "✓".green());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(requirement: &str) -> VersionReq {
+        VersionReq::parse(requirement).unwrap()
+    }
+
+    fn intervals(requirement: &str) -> Vec<(Version, Option<Version>)> {
+        req(requirement).intervals
+    }
+
+    #[test]
+    fn caret_keeps_the_leftmost_non_zero_component() {
+        assert_eq!(intervals("^0.8.20"), vec![((0, 8, 20), Some((0, 9, 0)))]);
+        assert_eq!(intervals("^1.2.3"), vec![((1, 2, 3), Some((2, 0, 0)))]);
+        assert_eq!(intervals("^0.0.3"), vec![((0, 0, 3), Some((0, 0, 4)))]);
+    }
+
+    #[test]
+    fn tilde_and_partial_versions() {
+        assert_eq!(intervals("~0.8.1"), vec![((0, 8, 1), Some((0, 9, 0)))]);
+        assert_eq!(intervals("0.8"), vec![((0, 8, 0), Some((0, 9, 0)))]);
+        assert_eq!(intervals("<=0.8"), vec![((0, 0, 0), Some((0, 9, 0)))]);
+        assert_eq!(intervals(">0.8.3"), vec![((0, 8, 4), None)]);
+    }
+
+    #[test]
+    fn comparators_in_one_alternative_are_intersected() {
+        assert_eq!(intervals(">=0.7.0 <0.9.0"), vec![((0, 7, 0), Some((0, 9, 0)))]);
+        assert_eq!(intervals("0.7.0 - 0.8.4"), vec![((0, 7, 0), Some((0, 8, 5)))]);
+        assert!(req(">=0.9.0 <0.8.0").is_empty());
+    }
+
+    #[test]
+    fn alternatives_are_unioned() {
+        let union = req("0.8.7 || ^0.8.20");
+        assert_eq!(union.intervals, vec![((0, 8, 7), Some((0, 8, 8))), ((0, 8, 20), Some((0, 9, 0)))]);
+        assert!(!union.is_exact());
+        assert!(union.allows_below((0, 8, 8)));
+        assert!(!union.allows_below((0, 8, 7)));
+    }
+
+    #[test]
+    fn exact_pins_and_bounds() {
+        assert!(req("0.8.24").is_exact());
+        assert!(req("=0.8.24").is_exact());
+        assert!(!req("^0.8.24").is_exact());
+
+        let range = req(">=0.8.0 <0.8.24");
+        assert!(!range.allows_from(TRANSIENT_STORAGE));
+        assert!(range.allows_from((0, 8, 23)));
+        assert!(!range.allows_below(CHECKED_ARITHMETIC));
+        assert!(req("*").allows_from((99, 0, 0)));
+    }
+
+    #[test]
+    fn intersect_drops_disjoint_intervals() {
+        let both = req("^0.8.0").intersect(&req(">=0.8.20 || <0.7.0"));
+        assert_eq!(both.intervals, vec![((0, 8, 20), Some((0, 9, 0)))]);
+        assert!(req("^0.7.0").intersect(&req("^0.8.0")).is_empty());
+    }
+
+    #[test]
+    fn malformed_requirements_are_rejected() {
+        assert_eq!(VersionReq::parse("^abc"), None);
+        assert_eq!(VersionReq::parse("0.8.1.2"), None);
+        assert_eq!(VersionReq::parse("!0.8.0"), None);
+        assert_eq!(VersionReq::parse("0.7.0 -"), None);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::constants::ConstantFolder;
+use crate::dataflow::DataFlowAnalyzer;
+use crate::models::*;
+use crate::parser::SolidityParser;
+use solang_parser::pt;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+// EIP-1967 slots: bytes32(uint256(keccak256("eip1967.proxy.<name>")) - 1)
+const IMPLEMENTATION_SLOT: &str = "360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+const ADMIN_SLOT: &str = "b53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103";
+const BEACON_SLOT: &str = "a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+// Functions that change what a proxy or beacon points at
+const UPGRADE_FUNCTIONS: &[&str] = &["upgradeTo", "upgradeToAndCall", "upgradeBeaconToAndCall", "changeAdmin"];
+
+/// Recognizes proxies, UUPS implementations and beacons, and links proxies to the
+/// implementation they run (from `.analyzerproxies`)
+pub struct ProxyDetector;
+
+#[derive(Default)]
+struct Evidence {
+    implementation_slot: bool,
+    admin: bool,
+    beacon: bool,
+    delegating_fallback: bool,
+    notes: Vec<String>,
+}
+
+impl Evidence {
+    fn note(&mut self, note: String) {
+        if !self.notes.contains(&note) {
+            self.notes.push(note);
+        }
+    }
+}
+
+impl ProxyDetector {
+    /// Read `Proxy = Implementation` pairs from `.analyzerproxies` in the current working directory
+    pub fn load_links() -> Vec<(String, String)> {
+        let links_file = PathBuf::from(".analyzerproxies");
+
+        if !links_file.exists() {
+            return Vec::new();
+        }
+
+        match fs::read_to_string(&links_file) {
+            Ok(content) => {
+                content
+                    .lines()
+                    .map(|line| line.trim())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .filter_map(|line| line.split_once('='))
+                    .map(|(proxy, implementation)| (proxy.trim().to_string(), implementation.trim().to_string()))
+                    .collect()
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Fill `proxy` of every contract, then apply the links; returns warnings for links that
+    /// could not be applied
+    pub fn detect_all(
+        contracts: &mut [ContractInfo],
+        contract_asts: &[pt::ContractDefinition],
+        links: &[(String, String)],
+    ) -> Vec<String> {
+        for (contract, ast) in contracts.iter_mut().zip(contract_asts) {
+            if contract.kind == ContractKind::Contract {
+                contract.proxy = Self::detect(contract, ast);
+            }
+        }
+
+        let mut warnings = Vec::new();
+        for (proxy, implementation) in links {
+            if !contracts.iter().any(|c| &c.name == implementation) {
+                warnings.push(format!(".analyzerproxies: implementation `{}` not found", implementation));
+                continue;
+            }
+            let Some(contract) = contracts.iter_mut().find(|c| &c.name == proxy) else {
+                warnings.push(format!(".analyzerproxies: proxy `{}` not found", proxy));
+                continue;
+            };
+
+            let info = contract.proxy.get_or_insert_with(|| ProxyInfo {
+                kind: ProxyKind::Generic,
+                evidence: vec!["linked in .analyzerproxies".to_string()],
+                upgrade_functions: Vec::new(),
+                implementation: None,
+            });
+            if !info.kind.delegates() {
+                warnings.push(format!(
+                    ".analyzerproxies: `{}` ({}) does not delegate calls; link to `{}` ignored",
+                    proxy,
+                    info.kind.label(),
+                    implementation
+                ));
+                continue;
+            }
+            info.implementation = Some(implementation.clone());
+        }
+
+        warnings
+    }
+
+    fn detect(contract: &ContractInfo, ast: &pt::ContractDefinition) -> Option<ProxyInfo> {
+        let mut evidence = Evidence::default();
+
+        // Slot constants, by their folded value: a literal or the `keccak256("eip1967.proxy.*") - 1` formula
+        for var in &contract.state_variables {
+            let slot = var.value.as_ref()
+                .and_then(ConstantFolder::slot_hex)
+                .and_then(|hex| Self::slot_kind_of_literal(&hex));
+            if let Some(slot) = slot {
+                Self::record_slot(&mut evidence, slot, &format!("`{}`", var.name));
+            }
+        }
+
+        let functions: HashMap<&str, &FunctionDef> = contract.functions.iter()
+            .map(|f| (f.signature.as_str(), f))
+            .collect();
+
+        for func in &contract.functions {
+            if let Some(assembly) = &func.assembly {
+                for access in &assembly.storage_accesses {
+                    if let Some(slot) = Self::slot_kind_of_literal(&access.slot) {
+                        Self::record_slot(&mut evidence, slot, &format!("`{}` in `{}`", access.opcode, func.name));
+                    }
+                }
+            }
+
+            // OpenZeppelin v5 keeps the slots in the ERC1967Utils library
+            for callee in &func.calls_functions {
+                let Some(library_call) = callee.strip_prefix("ERC1967Utils.") else {
+                    continue;
+                };
+                let name = library_call.split('(').next().unwrap_or_default();
+                match name {
+                    "getImplementation" | "upgradeToAndCall" => evidence.implementation_slot = true,
+                    "getAdmin" | "changeAdmin" => evidence.admin = true,
+                    "getBeacon" | "upgradeBeaconToAndCall" => evidence.beacon = true,
+                    _ => continue,
+                }
+                evidence.note(format!("`ERC1967Utils.{}` in `{}`", name, func.name));
+            }
+
+            match func.name.as_str() {
+                "_implementation" => evidence.note("`_implementation()` override".to_string()),
+                "_getBeacon" => {
+                    evidence.beacon = true;
+                    evidence.note("`_getBeacon()` function".to_string());
+                }
+                "_proxyAdmin" | "_getAdmin" => {
+                    evidence.admin = true;
+                    evidence.note(format!("`{}()` function", func.name));
+                }
+                _ => {}
+            }
+        }
+
+        if contract.modifiers.iter().any(|m| m.name == "ifAdmin") {
+            evidence.admin = true;
+            evidence.note("`ifAdmin` modifier".to_string());
+        }
+
+        // Fallback that reaches a delegatecall, directly or through internal helpers
+        let bodies: HashMap<String, &pt::FunctionDefinition> = ast.parts.iter()
+            .filter_map(|part| match part {
+                pt::ContractPart::FunctionDefinition(f) => Some((SolidityParser::function_signature(f), f.as_ref())),
+                _ => None,
+            })
+            .collect();
+        if let Some(delegator) = Self::delegating_function(contract, &functions, &bodies) {
+            evidence.delegating_fallback = true;
+            if delegator == "fallback" {
+                evidence.note("fallback delegatecall".to_string());
+            } else {
+                evidence.note(format!("fallback delegatecall (via `{}`)", delegator));
+            }
+        }
+
+        let has_function = |name: &str| contract.functions.iter().any(|f| f.name == name);
+        let upgrade_functions: Vec<String> = contract.functions.iter()
+            .filter(|f| matches!(f.visibility.as_str(), "public" | "external"))
+            .filter(|f| UPGRADE_FUNCTIONS.contains(&f.name.as_str()))
+            .map(|f| f.signature.clone())
+            .collect();
+        for signature in &upgrade_functions {
+            evidence.note(format!("`{}` function", signature));
+        }
+
+        let kind = if evidence.delegating_fallback {
+            if evidence.beacon {
+                ProxyKind::Beacon
+            } else if evidence.admin {
+                ProxyKind::Transparent
+            } else if evidence.implementation_slot {
+                ProxyKind::Erc1967
+            } else {
+                ProxyKind::Generic
+            }
+        } else if has_function("_authorizeUpgrade") {
+            evidence.note("`_authorizeUpgrade` hook".to_string());
+            ProxyKind::UupsImplementation
+        } else if has_function("upgradeToAndCall") && evidence.implementation_slot {
+            ProxyKind::UupsImplementation
+        } else if has_function("implementation") && has_function("upgradeTo") {
+            evidence.note("`implementation()` getter".to_string());
+            ProxyKind::UpgradeableBeacon
+        } else {
+            return None;
+        };
+
+        Some(ProxyInfo {
+            kind,
+            evidence: evidence.notes,
+            upgrade_functions,
+            implementation: None, // Will be filled from .analyzerproxies
+        })
+    }
+
+    /// First function reachable from the fallback that delegatecalls, if any
+    fn delegating_function(
+        contract: &ContractInfo,
+        functions: &HashMap<&str, &FunctionDef>,
+        bodies: &HashMap<String, &pt::FunctionDefinition>,
+    ) -> Option<String> {
+        let fallback = contract.functions.iter().find(|f| f.name == "fallback")?;
+        let mut visited = HashSet::new();
+        let mut stack = vec![fallback];
+
+        while let Some(func) = stack.pop() {
+            if !visited.insert(func.signature.as_str()) {
+                continue;
+            }
+
+            let in_assembly = func.assembly.as_ref()
+                .is_some_and(|a| a.calls.iter().any(|c| c.opcode == "delegatecall"));
+            let in_solidity = bodies.get(&func.signature)
+                .is_some_and(|body| !DataFlowAnalyzer::delegatecall_targets(body).is_empty());
+            if in_assembly || in_solidity {
+                return Some(func.name.clone());
+            }
+
+            stack.extend(func.calls_functions.iter().filter_map(|callee| functions.get(callee.as_str())));
+        }
+
+        None
+    }
+
+    fn record_slot(evidence: &mut Evidence, slot: &str, source: &str) {
+        match slot {
+            IMPLEMENTATION_SLOT => {
+                evidence.implementation_slot = true;
+                evidence.note(format!("EIP-1967 implementation slot ({})", source));
+            }
+            ADMIN_SLOT => {
+                evidence.admin = true;
+                evidence.note(format!("EIP-1967 admin slot ({})", source));
+            }
+            _ => {
+                evidence.beacon = true;
+                evidence.note(format!("EIP-1967 beacon slot ({})", source));
+            }
+        }
+    }
+
+    fn slot_kind_of_literal(value: &str) -> Option<&'static str> {
+        let value = value.trim().to_lowercase().replace('_', "");
+        let digits = value.strip_prefix("0x").unwrap_or(&value);
+        [IMPLEMENTATION_SLOT, ADMIN_SLOT, BEACON_SLOT].into_iter().find(|slot| *slot == digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+
+    const PROXY: &str = r#"
+        abstract contract Proxy {
+            function _implementation() internal view virtual returns (address);
+            function _delegate(address target) internal {
+                assembly { let result := delegatecall(gas(), target, 0, calldatasize(), 0, 0) }
+            }
+            fallback() external payable { _delegate(_implementation()); }
+        }
+    "#;
+
+    fn kind(source: &str, name: &str) -> Option<ProxyKind> {
+        let project = analyze(&format!("{}{}", PROXY, source));
+        contract(&project, name).proxy.as_ref().map(|p| p.kind)
+    }
+
+    #[test]
+    fn uups_implementation_is_recognized_by_its_upgrade_hook() {
+        let source = r#"
+            contract Vault {
+                bytes32 internal constant IMPLEMENTATION_SLOT = 0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc;
+                function upgradeToAndCall(address implementation, bytes memory data) external payable { _authorizeUpgrade(implementation); }
+                function _authorizeUpgrade(address implementation) internal virtual {}
+            }
+        "#;
+        assert_eq!(kind(source, "Vault"), Some(ProxyKind::UupsImplementation));
+    }
+
+    #[test]
+    fn transparent_proxy_uses_the_admin_slot() {
+        let source = r#"
+            contract AdminProxy is Proxy {
+                bytes32 internal constant IMPLEMENTATION_SLOT = bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1);
+                bytes32 internal constant ADMIN_SLOT = bytes32(uint256(keccak256("eip1967.proxy.admin")) - 1);
+                function _implementation() internal view override returns (address impl) {
+                    bytes32 slot = IMPLEMENTATION_SLOT;
+                    assembly { impl := sload(slot) }
+                }
+            }
+        "#;
+        assert_eq!(kind(source, "AdminProxy"), Some(ProxyKind::Transparent));
+    }
+
+    #[test]
+    fn beacon_proxy_uses_the_beacon_slot() {
+        let source = r#"
+            contract BeaconProxy is Proxy {
+                bytes32 internal constant BEACON_SLOT = 0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50;
+                function _implementation() internal view override returns (address impl) {
+                    bytes32 slot = BEACON_SLOT;
+                    assembly { impl := sload(slot) }
+                }
+            }
+        "#;
+        assert_eq!(kind(source, "BeaconProxy"), Some(ProxyKind::Beacon));
+    }
+
+    #[test]
+    fn namespace_string_alone_is_not_the_implementation_slot() {
+        let source = r#"
+            contract Lookalike is Proxy {
+                bytes32 internal constant IMPLEMENTATION_SLOT = keccak256("eip1967.proxy.implementation");
+                function _implementation() internal view override returns (address impl) {
+                    bytes32 slot = IMPLEMENTATION_SLOT;
+                    assembly { impl := sload(slot) }
+                }
+            }
+        "#;
+        assert_eq!(kind(source, "Lookalike"), Some(ProxyKind::Generic));
+    }
+}
@@ -0,0 +1,1080 @@
+use crate::analyzer::{LibraryScope, StateModificationAnalyzer};
+use crate::models::*;
+use crate::parser::SolidityParser;
+use crate::types::ExpressionTyper;
+use crate::yul::YulAnalyzer;
+use colored::*;
+use solang_parser::pt;
+use std::collections::{HashMap, HashSet};
+
+// Modifier names (lowercased, matched whole) that put a mutex around the function body
+const GUARD_NAMES: &[&str] = &["nonreentrant", "noreentrancy", "reentrancyguard", "lock", "mutex"];
+
+// Low-level calls made by the `Address` helpers of common libraries
+pub(crate) const ADDRESS_CALL_HELPERS: &[&str] = &[
+    "sendValue", "functionCall", "functionCallWithValue", "functionDelegateCall", "safeTransferETH",
+];
+
+// Internal calls followed from an entry point before giving up
+const MAX_CALL_DEPTH: usize = 8;
+
+/// Walks each state-changing entry point in execution order, through modifiers and internal
+/// and library calls, and reports state writes and events that follow an external call
+pub struct ReentrancyDetector;
+
+/// How much the receiver of an external call is under the caller's control
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trust {
+    Fixed,      // `constant`/`immutable` state variable or `this`
+    Configured, // Held in mutable storage, so set by the contract itself
+    Untrusted,  // Parameter, `msg.sender`, local or call result
+}
+
+/// External call already made on the path being walked
+#[derive(Debug, Clone, PartialEq)]
+struct PendingCall {
+    text: String,
+    line: usize,
+    sends_value: bool,
+    gas_limited: bool, // `transfer`/`send` forward 2300 gas, too little to re-enter
+    trust: Trust,
+}
+
+/// Function or modifier body being walked
+struct Frame<'a> {
+    owner: usize, // Index of the contract whose names the body uses (a library for library functions)
+    func: &'a pt::FunctionDefinition,
+    label: String, // Shown in findings, e.g. "_settle" or "modifier whenNotPaused"
+    is_entry: bool,
+    source: Option<&'a SourceUnit>,
+    storage_refs: HashMap<String, String>, // Storage pointers and parameters -> state they alias
+    param_trust: HashMap<String, Trust>,   // Trust of the arguments the caller passed
+}
+
+struct Walker<'a> {
+    index: usize,
+    contract: &'a ContractInfo,
+    all_contracts: &'a [ContractInfo],
+    contract_asts: &'a [pt::ContractDefinition],
+    source_units: &'a [SourceUnit],
+    libraries: LibraryScope<'a>,
+    typers: HashMap<usize, ExpressionTyper<'a>>,
+    state_vars: HashSet<String>,
+    entry: String,
+    frames: Vec<Frame<'a>>,
+    continuations: Vec<(Vec<&'a pt::FunctionDefinition>, &'a pt::FunctionDefinition)>, // What `_` runs
+    pending: Vec<PendingCall>,
+    exits: Vec<PendingCall>, // Calls made on paths that returned from the current frame
+    findings: Vec<ReentrancyFinding>,
+}
+
+impl ReentrancyDetector {
+    pub fn detect(contracts: &mut [ContractInfo], contract_asts: &[pt::ContractDefinition], source_units: &[SourceUnit]) {
+        let snapshot = contracts.to_vec();
+        // Write-after-call windows of every entry point, guarded or not
+        let windows: Vec<Vec<ReentrancyFinding>> = (0..snapshot.len())
+            .map(|i| match snapshot[i].kind == ContractKind::Contract && i < contract_asts.len() {
+                true => Self::check_contract(i, &snapshot, contract_asts, source_units),
+                false => Vec::new(),
+            })
+            .collect();
+        if windows.iter().all(|w| w.is_empty()) {
+            return;
+        }
+
+        // Calls other contracts make into view functions, for read-only reentrancy
+        let external_calls: Vec<ExternalCall> = snapshot.iter().zip(contract_asts)
+            .flat_map(|(contract, ast)| {
+                StateModificationAnalyzer::detect_external_calls(&mut contract.clone(), ast, &snapshot, source_units)
+            })
+            .collect();
+
+        for ((contract, windows), declared) in contracts.iter_mut().zip(windows).zip(&snapshot) {
+            contract.cross_reentrancy = Self::cross_function(declared, &windows, &external_calls);
+            contract.reentrancy = windows.into_iter()
+                .filter(|w| declared.functions.iter()
+                    .find(|f| f.signature == w.function)
+                    .is_none_or(|f| Self::lock_keys(f, declared).is_empty()))
+                .collect();
+        }
+    }
+
+    /// Pair each state variable written after a call with the other entry points using it
+    fn cross_function(contract: &ContractInfo, windows: &[ReentrancyFinding], external_calls: &[ExternalCall]) -> Vec<CrossReentrancyFinding> {
+        let mut findings: Vec<CrossReentrancyFinding> = Vec::new();
+        for window in windows {
+            let ReentrancyEffect::StateWrite(variable) = &window.effect else {
+                continue;
+            };
+            let (Some(var), Some(caller)) = (
+                contract.state_variables.iter().find(|v| &v.name == variable),
+                contract.functions.iter().find(|f| f.signature == window.function),
+            ) else {
+                continue;
+            };
+            let caller_locks = Self::lock_keys(caller, contract);
+
+            for (func, access) in Self::entry_points_using(contract, var) {
+                // Functions restricted to a role aren't open to the callee
+                let restricted = contract.access_control.iter()
+                    .any(|a| a.function == func.signature && !a.guards.is_empty());
+                if func.signature == window.function || restricted || !caller_locks.is_disjoint(&Self::lock_keys(func, contract)) {
+                    continue;
+                }
+                // One finding per function pair and variable, naming the riskiest call of the window
+                let known = findings.iter().position(|f| f.function == func.signature && f.variable == var.name
+                    && f.window_function == window.function);
+                if known.is_some_and(|i| findings[i].severity >= window.severity) {
+                    continue;
+                }
+
+                let read_only = matches!(func.state_mutability.as_str(), "view" | "pure");
+                let consumers: Vec<String> = external_calls.iter()
+                    .filter(|_| read_only)
+                    .filter(|c| c.source_contract != contract.name && c.target_contract.as_ref() == Some(&contract.name))
+                    .filter(|c| match &c.target_signature {
+                        Some(signature) => signature == &func.signature,
+                        None => c.target_function == func.name && c.target_reads_states.contains(&var.name),
+                    })
+                    .map(|c| format!("{}.{}", c.source_contract, c.source_function))
+                    .fold(Vec::new(), |mut acc, consumer| {
+                        if !acc.contains(&consumer) {
+                            acc.push(consumer);
+                        }
+                        acc
+                    });
+                // A view nobody else calls only misleads off-chain readers
+                let severity = match read_only && consumers.is_empty() {
+                    true => ReentrancySeverity::Low,
+                    false => window.severity,
+                };
+
+                let finding = CrossReentrancyFinding {
+                    function: func.signature.clone(),
+                    kind: if read_only { CrossReentrancyKind::ReadOnly } else { CrossReentrancyKind::CrossFunction },
+                    severity,
+                    variable: var.name.clone(),
+                    access,
+                    window_function: window.function.clone(),
+                    call: window.call.clone(),
+                    call_line: window.call_line,
+                    write_line: window.effect_line,
+                    consumers,
+                };
+                match known {
+                    Some(i) => findings[i] = finding,
+                    None => findings.push(finding),
+                }
+            }
+        }
+        findings
+    }
+
+    /// Public/external functions reaching a read or write of `var`, directly or through internal calls
+    fn entry_points_using<'c>(contract: &'c ContractInfo, var: &StateVariable) -> Vec<(&'c FunctionDef, StateAccess)> {
+        let mut entry_points: Vec<(&FunctionDef, StateAccess)> = Vec::new();
+        let chains = var.modification_chains.iter().map(|c| (c, StateAccess::Write))
+            .chain(var.read_chains.iter().map(|c| (c, StateAccess::Read)));
+        for (chain, access) in chains {
+            let reached = std::iter::once(&chain.direct_modifier)
+                .chain(chain.call_chain.iter().map(|c| &c.function_name));
+            for signature in reached {
+                let Some(func) = contract.functions.iter().find(|f| &f.signature == signature) else {
+                    continue;
+                };
+                let is_entry = matches!(func.visibility.as_str(), "public" | "external")
+                    || matches!(func.name.as_str(), "fallback" | "receive");
+                if is_entry && func.name != "constructor" && !func.name.contains('.')
+                    && !entry_points.iter().any(|(f, _)| f.signature == func.signature)
+                {
+                    entry_points.push((func, access));
+                }
+            }
+        }
+        entry_points
+    }
+
+    fn check_contract(
+        index: usize,
+        all_contracts: &[ContractInfo],
+        contract_asts: &[pt::ContractDefinition],
+        source_units: &[SourceUnit],
+    ) -> Vec<ReentrancyFinding> {
+        let contract = &all_contracts[index];
+        let mut walker = Walker {
+            index,
+            contract,
+            all_contracts,
+            contract_asts,
+            source_units,
+            libraries: LibraryScope::new(
+                &contract.name,
+                &contract.file_path,
+                &contract.linearized_bases,
+                all_contracts,
+                source_units,
+            ),
+            typers: HashMap::new(),
+            state_vars: contract.state_variables.iter().map(|v| v.name.clone()).collect(),
+            entry: String::new(),
+            frames: Vec::new(),
+            continuations: Vec::new(),
+            pending: Vec::new(),
+            exits: Vec::new(),
+            findings: Vec::new(),
+        };
+
+        let entry_points = contract.functions.iter()
+            .filter(|f| f.inherited_from.is_none() && !f.name.contains('.'))
+            .filter(|f| matches!(f.visibility.as_str(), "public" | "external") || matches!(f.name.as_str(), "fallback" | "receive"))
+            .filter(|f| !matches!(f.state_mutability.as_str(), "view" | "pure") && f.name != "constructor");
+        for func in entry_points {
+            let Some(body) = walker.function_part(index, &func.signature) else {
+                continue;
+            };
+            let modifiers: Vec<&pt::FunctionDefinition> = func.modifier_order.iter()
+                .filter_map(|name| walker.modifier_part(name))
+                .collect();
+            walker.entry = func.signature.clone();
+            walker.pending.clear();
+            walker.exits.clear();
+            walker.walk_chain(modifiers, body);
+        }
+        walker.findings
+    }
+
+    /// Locks a function holds through `nonReentrant`-style modifiers: ones named like a guard, or
+    /// setting the same state before the body and again after it. A lock is the state the
+    /// modifier uses, or "*" for guards declared outside the project, which are assumed shared.
+    fn lock_keys(func: &FunctionDef, contract: &ContractInfo) -> HashSet<String> {
+        let mut keys = HashSet::new();
+        for name in &func.modifier_order {
+            let effects: Vec<&ModifierEffect> = func.modifier_effects.iter().filter(|e| &e.modifier == name).collect();
+            let phase_writes = |phase: ModifierPhase| -> HashSet<&String> {
+                effects.iter()
+                    .filter(|e| e.phase == phase)
+                    .flat_map(|e| &e.modifies_states)
+                    .collect()
+            };
+            let named = GUARD_NAMES.contains(&name.to_lowercase().as_str());
+            let toggles = contract.modifiers.iter().any(|m| &m.name == name)
+                && phase_writes(ModifierPhase::BeforeBody).intersection(&phase_writes(ModifierPhase::AfterBody)).next().is_some();
+            if !named && !toggles {
+                continue;
+            }
+            let state: Vec<&String> = effects.iter().flat_map(|e| e.modifies_states.iter().chain(&e.reads_states)).collect();
+            if state.is_empty() {
+                keys.insert("*".to_string());
+            } else {
+                keys.extend(state.into_iter().cloned());
+            }
+        }
+        keys
+    }
+
+    pub fn print_summary(contracts: &[ContractInfo]) {
+        let affected: Vec<&ContractInfo> = contracts.iter()
+            .filter(|c| !c.reentrancy.is_empty() || !c.cross_reentrancy.is_empty())
+            .collect();
+        if affected.is_empty() {
+            return;
+        }
+
+        println!();
+        println!("{}", "🔁 Reentrancy (checks-effects-interactions)".bold());
+        for contract in affected {
+            let high = contract.reentrancy.iter().map(|f| f.severity)
+                .chain(contract.cross_reentrancy.iter().map(|f| f.severity))
+                .filter(|s| *s == ReentrancySeverity::High)
+                .count();
+            let read_only = contract.cross_reentrancy.iter().filter(|f| f.kind == CrossReentrancyKind::ReadOnly).count();
+            println!("  {} {}: {} finding(s), {} cross-function, {} read-only, {} high",
+                "⚠️".yellow(),
+                contract.name,
+                contract.reentrancy.len(),
+                contract.cross_reentrancy.len() - read_only,
+                read_only,
+                high
+            );
+        }
+    }
+}
+
+impl<'a> Walker<'a> {
+    fn function_part(&self, owner: usize, signature: &str) -> Option<&'a pt::FunctionDefinition> {
+        self.contract_asts[owner].parts.iter().find_map(|part| match part {
+            pt::ContractPart::FunctionDefinition(f)
+                if !matches!(f.ty, pt::FunctionTy::Modifier) && f.body.is_some()
+                    && SolidityParser::function_signature(f) == signature => Some(f.as_ref()),
+            _ => None,
+        })
+    }
+
+    fn modifier_part(&self, name: &str) -> Option<&'a pt::FunctionDefinition> {
+        self.contract_asts[self.index].parts.iter().find_map(|part| match part {
+            pt::ContractPart::FunctionDefinition(f)
+                if matches!(f.ty, pt::FunctionTy::Modifier) && f.name.as_ref().is_some_and(|n| n.name == name) => Some(f.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// File that declares a member of `owner`, for line numbers
+    fn source_of(&self, owner: usize, inherited_from: Option<&String>) -> Option<&'a SourceUnit> {
+        let file_path = inherited_from
+            .and_then(|base| self.all_contracts.iter().find(|c| &c.name == base))
+            .map_or(self.all_contracts[owner].file_path.as_str(), |c| c.file_path.as_str());
+        self.source_units.iter().find(|u| u.file_path == file_path)
+    }
+
+    fn typer(&mut self, owner: usize) -> &mut ExpressionTyper<'a> {
+        let (all_contracts, source_units) = (self.all_contracts, self.source_units);
+        self.typers.entry(owner)
+            .or_insert_with(|| ExpressionTyper::new(&all_contracts[owner], all_contracts, source_units))
+    }
+
+    /// Modifiers in order, each running the rest of the chain at its `_`, then the body
+    fn walk_chain(&mut self, modifiers: Vec<&'a pt::FunctionDefinition>, body: &'a pt::FunctionDefinition) {
+        let Some((&first, rest)) = modifiers.split_first() else {
+            let declared = self.contract.functions.iter()
+                .find(|f| f.signature == SolidityParser::function_signature(body))
+                .and_then(|f| f.inherited_from.as_ref());
+            let frame = self.frame(self.index, body, String::new(), declared);
+            self.walk_frame(Frame { is_entry: true, ..frame });
+            return;
+        };
+
+        self.continuations.push((rest.to_vec(), body));
+        let name = first.name.as_ref().map(|n| n.name.clone()).unwrap_or_default();
+        let declared = self.contract.modifiers.iter()
+            .find(|m| m.name == name)
+            .and_then(|m| m.inherited_from.as_ref());
+        let frame = self.frame(self.index, first, format!("modifier {}", name), declared);
+        self.walk_frame(frame);
+        self.continuations.pop();
+    }
+
+    fn frame(&self, owner: usize, func: &'a pt::FunctionDefinition, label: String, declared_in: Option<&String>) -> Frame<'a> {
+        Frame {
+            owner,
+            func,
+            label,
+            is_entry: false,
+            source: self.source_of(owner, declared_in),
+            storage_refs: HashMap::new(),
+            param_trust: HashMap::new(),
+        }
+    }
+
+    /// Walk a body; afterwards `pending` holds the calls of every path that leaves it normally
+    fn walk_frame(&mut self, frame: Frame<'a>) {
+        let (owner, func) = (frame.owner, frame.func);
+        self.typer(owner).enter_function(func);
+        self.frames.push(frame);
+        let outer_exits = std::mem::take(&mut self.exits);
+
+        if let Some(body) = &func.body {
+            if self.walk_statement(body) {
+                let fallthrough = std::mem::take(&mut self.pending);
+                self.exits.extend(fallthrough);
+            }
+        }
+        self.pending = Self::merge(std::mem::replace(&mut self.exits, outer_exits), Vec::new());
+
+        self.frames.pop();
+        if let Some(caller) = self.frames.last() {
+            let (owner, func) = (caller.owner, caller.func);
+            self.typer(owner).enter_function(func);
+        }
+    }
+
+    fn merge(mut left: Vec<PendingCall>, right: Vec<PendingCall>) -> Vec<PendingCall> {
+        let mut merged = Vec::new();
+        for call in left.drain(..).chain(right) {
+            if !merged.contains(&call) {
+                merged.push(call);
+            }
+        }
+        merged
+    }
+
+    /// Returns false when the statement never completes normally (`return`, `revert`)
+    fn walk_statement(&mut self, stmt: &'a pt::Statement) -> bool {
+        match stmt {
+            pt::Statement::Block { statements, .. } => statements.iter().all(|s| self.walk_statement(s)),
+            _ if StateModificationAnalyzer::is_placeholder(stmt) => {
+                if let Some((rest, body)) = self.continuations.pop() {
+                    self.walk_chain(rest.clone(), body);
+                    self.continuations.push((rest, body));
+                }
+                true
+            }
+            pt::Statement::Expression(_, expr) => {
+                self.walk_expression(expr);
+                !matches!(expr, pt::Expression::FunctionCall(_, callee, _)
+                    if matches!(callee.as_ref(), pt::Expression::Variable(id) if id.name == "revert"))
+            }
+            pt::Statement::VariableDefinition(_, decl, init) => {
+                if let Some(init) = init {
+                    self.walk_expression(init);
+                    self.track_storage_pointer(decl, init);
+                }
+                true
+            }
+            pt::Statement::If(_, cond, then_branch, else_branch) => {
+                self.walk_expression(cond);
+                let before = self.pending.clone();
+                let then_completes = self.walk_statement(then_branch);
+                let after_then = std::mem::replace(&mut self.pending, before);
+                let else_completes = else_branch.as_ref().is_none_or(|s| self.walk_statement(s));
+                let after_else = std::mem::take(&mut self.pending);
+                self.pending = Self::merge(
+                    if then_completes { after_then } else { Vec::new() },
+                    if else_completes { after_else } else { Vec::new() },
+                );
+                then_completes || else_completes
+            }
+            // Twice, so that calls late in one iteration precede writes early in the next
+            pt::Statement::While(_, cond, body) | pt::Statement::DoWhile(_, body, cond) => {
+                for _ in 0..2 {
+                    self.walk_expression(cond);
+                    self.walk_loop_body(body);
+                }
+                true
+            }
+            pt::Statement::For(_, init, cond, update, body) => {
+                if let Some(init) = init {
+                    self.walk_statement(init);
+                }
+                for _ in 0..2 {
+                    if let Some(cond) = cond {
+                        self.walk_expression(cond);
+                    }
+                    if let Some(body) = body {
+                        self.walk_loop_body(body);
+                    }
+                    if let Some(update) = update {
+                        self.walk_expression(update);
+                    }
+                }
+                true
+            }
+            pt::Statement::Return(_, expr) => {
+                if let Some(expr) = expr {
+                    self.walk_expression(expr);
+                }
+                let returned = std::mem::take(&mut self.pending);
+                self.exits.extend(returned);
+                false
+            }
+            pt::Statement::Revert(_, _, args) => {
+                args.iter().for_each(|arg| self.walk_expression(arg));
+                false
+            }
+            pt::Statement::RevertNamedArgs(_, _, args) => {
+                args.iter().for_each(|arg| self.walk_expression(&arg.expr));
+                false
+            }
+            pt::Statement::Emit(loc, expr) => {
+                let event = match expr {
+                    pt::Expression::FunctionCall(_, callee, args) => {
+                        args.iter().for_each(|arg| self.walk_expression(arg));
+                        callee.to_string()
+                    }
+                    other => other.to_string(),
+                };
+                self.effect(ReentrancyEffect::Event(event), loc);
+                true
+            }
+            pt::Statement::Try(_, expr, returns, catches) => {
+                let (call, success) = StateModificationAnalyzer::try_call(expr, returns);
+                self.walk_expression(call);
+                let before = self.pending.clone();
+                let mut after = Vec::new();
+                let mut completes = false;
+                let bodies = success.into_iter().chain(catches.iter().map(|clause| match clause {
+                    pt::CatchClause::Simple(_, _, body) | pt::CatchClause::Named(_, _, _, body) => body,
+                }));
+                for body in bodies {
+                    self.pending = before.clone();
+                    if self.walk_statement(body) {
+                        completes = true;
+                        after = Self::merge(after, std::mem::take(&mut self.pending));
+                    }
+                }
+                self.pending = if success.is_none() && catches.is_empty() { before } else { after };
+                completes || success.is_none()
+            }
+            pt::Statement::Assembly { loc, block, .. } => {
+                let frame = self.frames.last();
+                let visible: HashSet<String> = if frame.is_some_and(|f| f.owner == self.index) {
+                    self.state_vars.clone()
+                } else {
+                    HashSet::new()
+                };
+                let refs = frame.map(|f| f.storage_refs.clone()).unwrap_or_default();
+                let (writes, _) = YulAnalyzer::state_accesses(block, &visible, &refs);
+                for var in writes {
+                    self.effect(ReentrancyEffect::StateWrite(var), loc);
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+
+    /// A `break` or `continue` only leaves the loop, not the function
+    fn walk_loop_body(&mut self, body: &'a pt::Statement) {
+        let before = self.pending.clone();
+        if !self.walk_statement(body) {
+            self.pending = Self::merge(before, Vec::new());
+        }
+    }
+
+    /// `T storage p = stateVar[...]`, and storage structs returned by accessor functions
+    fn track_storage_pointer(&mut self, decl: &pt::VariableDeclaration, init: &pt::Expression) {
+        let (Some(name), Some(pt::StorageLocation::Storage(_))) = (&decl.name, &decl.storage) else {
+            return;
+        };
+        let target = match init {
+            pt::Expression::FunctionCall(..) => Some(SolidityParser::type_to_string(&decl.ty)),
+            _ => StateModificationAnalyzer::extract_base_variable(init).and_then(|root| self.state_target(&root)),
+        };
+        if let (Some(target), Some(frame)) = (target, self.frames.last_mut()) {
+            frame.storage_refs.insert(name.name.clone(), target);
+        }
+    }
+
+    /// State a name refers to in the current frame
+    fn state_target(&self, name: &str) -> Option<String> {
+        let frame = self.frames.last()?;
+        if let Some(target) = frame.storage_refs.get(name) {
+            return Some(target.clone());
+        }
+        (frame.owner == self.index && self.state_vars.contains(name)).then(|| name.to_string())
+    }
+
+    fn walk_expression(&mut self, expr: &'a pt::Expression) {
+        match expr {
+            pt::Expression::FunctionCall(loc, callee, args) => {
+                let (inner, options) = match callee.as_ref() {
+                    pt::Expression::FunctionCallBlock(_, inner, options) => (inner.as_ref(), Some(options.as_ref())),
+                    other => (other, None),
+                };
+                let mut sends_value = false;
+                if let Some(pt::Statement::Args(_, named)) = options {
+                    for arg in named {
+                        sends_value |= arg.name.name == "value";
+                        self.walk_expression(&arg.expr);
+                    }
+                }
+                if let pt::Expression::MemberAccess(_, base, _) = inner {
+                    self.walk_expression(base);
+                }
+                args.iter().for_each(|arg| self.walk_expression(arg));
+                let args: Vec<&'a pt::Expression> = args.iter().collect();
+                self.call(expr, inner, &args, sends_value, loc);
+            }
+            pt::Expression::NamedFunctionCall(loc, callee, args) => {
+                if let pt::Expression::MemberAccess(_, base, _) = callee.as_ref() {
+                    self.walk_expression(base);
+                }
+                args.iter().for_each(|arg| self.walk_expression(&arg.expr));
+                let args: Vec<&'a pt::Expression> = args.iter().map(|arg| &arg.expr).collect();
+                self.call(expr, callee, &args, false, loc);
+            }
+            // The value is computed before it is stored
+            pt::Expression::Assign(loc, left, right)
+            | pt::Expression::AssignAdd(loc, left, right)
+            | pt::Expression::AssignSubtract(loc, left, right)
+            | pt::Expression::AssignMultiply(loc, left, right)
+            | pt::Expression::AssignDivide(loc, left, right)
+            | pt::Expression::AssignModulo(loc, left, right)
+            | pt::Expression::AssignOr(loc, left, right)
+            | pt::Expression::AssignAnd(loc, left, right)
+            | pt::Expression::AssignXor(loc, left, right)
+            | pt::Expression::AssignShiftLeft(loc, left, right)
+            | pt::Expression::AssignShiftRight(loc, left, right) => {
+                self.walk_expression(right);
+                self.walk_lvalue(left);
+                self.write(left, loc);
+            }
+            pt::Expression::PreIncrement(loc, target)
+            | pt::Expression::PostIncrement(loc, target)
+            | pt::Expression::PreDecrement(loc, target)
+            | pt::Expression::PostDecrement(loc, target)
+            | pt::Expression::Delete(loc, target) => {
+                self.walk_lvalue(target);
+                self.write(target, loc);
+            }
+            pt::Expression::ConditionalOperator(_, cond, if_true, if_false) => {
+                self.walk_expression(cond);
+                let before = self.pending.clone();
+                self.walk_expression(if_true);
+                let after_true = std::mem::replace(&mut self.pending, before);
+                self.walk_expression(if_false);
+                let after_false = std::mem::take(&mut self.pending);
+                self.pending = Self::merge(after_true, after_false);
+            }
+            pt::Expression::ArrayLiteral(_, items) => items.iter().for_each(|item| self.walk_expression(item)),
+            pt::Expression::List(..) | pt::Expression::Type(..) => {}
+            _ => {
+                let (left, right) = expr.components();
+                for operand in [left, right].into_iter().flatten() {
+                    self.walk_expression(operand);
+                }
+            }
+        }
+    }
+
+    /// Index expressions of an assignment target run before the store
+    fn walk_lvalue(&mut self, target: &'a pt::Expression) {
+        match target {
+            pt::Expression::ArraySubscript(_, base, index) => {
+                self.walk_lvalue(base);
+                if let Some(index) = index {
+                    self.walk_expression(index);
+                }
+            }
+            pt::Expression::MemberAccess(_, base, _) => self.walk_lvalue(base),
+            pt::Expression::List(_, items) => {
+                for (_, param) in items {
+                    if let Some(param) = param {
+                        self.walk_lvalue(&param.ty);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn write(&mut self, target: &pt::Expression, loc: &pt::Loc) {
+        if let pt::Expression::List(_, items) = target {
+            for (_, param) in items {
+                if let Some(param) = param {
+                    self.write(&param.ty, loc);
+                }
+            }
+            return;
+        }
+        let Some(root) = StateModificationAnalyzer::extract_base_variable(target) else {
+            return;
+        };
+        let Some(var) = self.state_target(&root) else {
+            return;
+        };
+        // Accessor-returned storage structs are named by their type and first field
+        let var = match (self.state_vars.contains(&var), target) {
+            (false, pt::Expression::MemberAccess(..)) => Self::first_field(target)
+                .map_or(var.clone(), |field| format!("{}.{}", var, field)),
+            _ => var,
+        };
+        self.effect(ReentrancyEffect::StateWrite(var), loc);
+    }
+
+    fn first_field(expr: &pt::Expression) -> Option<String> {
+        match expr {
+            pt::Expression::MemberAccess(_, base, member) => match base.as_ref() {
+                pt::Expression::Variable(_) => Some(member.name.clone()),
+                inner => Self::first_field(inner),
+            },
+            pt::Expression::ArraySubscript(_, base, _) => Self::first_field(base),
+            _ => None,
+        }
+    }
+
+    fn call(&mut self, expr: &pt::Expression, callee: &'a pt::Expression, args: &[&'a pt::Expression], sends_value: bool, loc: &pt::Loc) {
+        let owner = self.frames.last().map_or(self.index, |f| f.owner);
+        match callee {
+            pt::Expression::Variable(ident) => {
+                let part = self.internal_part(owner, &ident.name, args.len());
+                if let Some(part) = part {
+                    self.enter(owner, part, &ident.name, args);
+                }
+            }
+            pt::Expression::MemberAccess(_, base, member) => {
+                if let pt::Expression::Variable(base_name) = base.as_ref() {
+                    match base_name.name.as_str() {
+                        "this" | "abi" | "msg" | "block" | "tx" | "string" | "bytes" | "type" => return,
+                        "super" => {
+                            let part = self.super_part(&member.name, args.len());
+                            if let Some(part) = part {
+                                self.enter(self.index, part, &format!("super.{}", member.name), args);
+                            }
+                            return;
+                        }
+                        name if self.contract.linearized_bases.iter().any(|b| b == name) => {
+                            let qualified = format!("{}.{}", name, member.name);
+                            let part = self.internal_part(self.index, &qualified, args.len())
+                                .or_else(|| self.internal_part(self.index, &member.name, args.len()));
+                            if let Some(part) = part {
+                                self.enter(self.index, part, &qualified, args);
+                            }
+                            return;
+                        }
+                        name => {
+                            if let Some(library) = self.all_contracts.iter().position(|c| c.name == name && c.kind == ContractKind::Library) {
+                                let arg_types = self.argument_types(owner, args);
+                                let callee = self.libraries.resolve_direct(name, &member.name, &arg_types).into_iter().next();
+                                if let Some(part) = callee.and_then(|c| self.function_part(library, &c.function.signature)) {
+                                    self.enter(library, part, &format!("{}.{}", name, member.name), args);
+                                }
+                                return;
+                            }
+                        }
+                    }
+                }
+                self.member_call(expr, base, &member.name, args, sends_value, loc);
+            }
+            _ => {}
+        }
+    }
+
+    /// `x.f(...)` on a value: an external call, a bound library function or an array method
+    fn member_call(&mut self, expr: &pt::Expression, base: &'a pt::Expression, member: &str, args: &[&'a pt::Expression], sends_value: bool, loc: &pt::Loc) {
+        let owner = self.frames.last().map_or(self.index, |f| f.owner);
+        if matches!(member, "push" | "pop") {
+            self.write(base, loc);
+            return;
+        }
+        let Some(receiver) = self.typer(owner).infer(base).and_then(|t| t.receiver_name()) else {
+            return;
+        };
+
+        let arg_types = self.argument_types(owner, args);
+        let bound = self.libraries.resolve_bound(Some(&receiver), member, &arg_types).into_iter().next();
+        if let Some(callee) = bound {
+            let library = callee.library.and_then(|l| self.all_contracts.iter().position(|c| c.name == l));
+            if let Some((library, part)) = library.and_then(|l| Some((l, self.function_part(l, &callee.function.signature)?))) {
+                let mut bound_args = vec![base];
+                bound_args.extend_from_slice(args);
+                self.enter(library, part, &format!("{}.{}", self.all_contracts[library].name, member), &bound_args);
+            }
+            return;
+        }
+
+        let (is_call, sends_value, gas_limited) = if receiver == "address" {
+            match member {
+                "call" | "delegatecall" => (true, sends_value, false),
+                "transfer" | "send" => (true, true, true),
+                _ if ADDRESS_CALL_HELPERS.contains(&member) => (true, member.contains("Value") || member.contains("ETH"), false),
+                _ => (false, false, false),
+            }
+        } else {
+            (!self.is_read_only(&receiver, member), sends_value, false)
+        };
+        if !is_call {
+            return;
+        }
+
+        let trust = self.receiver_trust(base);
+        let call = PendingCall {
+            text: expr.to_string(),
+            line: self.line(loc),
+            sends_value,
+            gas_limited,
+            trust,
+        };
+        if !self.pending.contains(&call) {
+            self.pending.push(call);
+        }
+    }
+
+    /// Whether calling `member` on a `receiver` contract can't change state. Functions the project
+    /// doesn't declare, on interfaces outside it or attached by unloaded libraries, may change state.
+    fn is_read_only(&self, receiver: &str, member: &str) -> bool {
+        let candidates: Vec<&FunctionDef> = self.all_contracts.iter()
+            .find(|c| c.name == receiver)
+            .map(|c| c.functions.iter().filter(|f| f.name == member).collect())
+            .unwrap_or_default();
+        !candidates.is_empty() && candidates.iter().all(|f| matches!(f.state_mutability.as_str(), "view" | "pure"))
+    }
+
+    fn argument_types(&mut self, owner: usize, args: &[&pt::Expression]) -> Vec<Option<String>> {
+        let typer = self.typer(owner);
+        args.iter().map(|arg| typer.argument_type(arg)).collect()
+    }
+
+    fn internal_part(&self, owner: usize, name: &str, arity: usize) -> Option<&'a pt::FunctionDefinition> {
+        self.contract_asts[owner].parts.iter().find_map(|part| match part {
+            pt::ContractPart::FunctionDefinition(f)
+                if !matches!(f.ty, pt::FunctionTy::Modifier | pt::FunctionTy::Constructor)
+                    && f.body.is_some()
+                    && f.params.len() == arity
+                    && SolidityParser::function_name(f) == name => Some(f.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// The next implementation up the linearization, kept in the merged contract as `Base.f`
+    fn super_part(&self, name: &str, arity: usize) -> Option<&'a pt::FunctionDefinition> {
+        let current = self.frames.last()
+            .and_then(|f| f.func.name.as_ref())
+            .and_then(|n| n.name.split_once('.'))
+            .map(|(base, _)| base.to_string());
+        let start = current
+            .and_then(|base| self.contract.linearized_bases.iter().position(|b| *b == base))
+            .map_or(0, |i| i + 1);
+        self.contract.linearized_bases[start.min(self.contract.linearized_bases.len())..].iter()
+            .find_map(|base| self.internal_part(self.index, &format!("{}.{}", base, name), arity))
+    }
+
+    /// Follow an internal or library call, binding storage and trust of the arguments
+    fn enter(&mut self, owner: usize, part: &'a pt::FunctionDefinition, label: &str, args: &[&'a pt::Expression]) {
+        if self.frames.len() >= MAX_CALL_DEPTH || self.frames.iter().any(|f| std::ptr::eq(f.func, part)) {
+            return;
+        }
+        let signature = SolidityParser::function_signature(part);
+        let declared = self.all_contracts[owner].functions.iter()
+            .find(|f| f.signature == signature)
+            .and_then(|f| f.inherited_from.as_ref());
+        let mut frame = self.frame(owner, part, label.to_string(), declared);
+
+        for ((_, param), arg) in part.params.iter().zip(args) {
+            let Some(name) = param.as_ref().and_then(|p| p.name.as_ref()) else {
+                continue;
+            };
+            frame.param_trust.insert(name.name.clone(), self.receiver_trust(arg));
+            let is_storage = param.as_ref().is_some_and(|p| matches!(p.storage, Some(pt::StorageLocation::Storage(_))));
+            let target = StateModificationAnalyzer::extract_base_variable(arg).and_then(|root| self.state_target(&root));
+            if let (true, Some(target)) = (is_storage, target) {
+                frame.storage_refs.insert(name.name.clone(), target);
+            }
+        }
+        self.walk_frame(frame);
+    }
+
+    fn receiver_trust(&self, expr: &pt::Expression) -> Trust {
+        let Some(frame) = self.frames.last() else {
+            return Trust::Untrusted;
+        };
+        match expr {
+            pt::Expression::Parenthesis(_, inner) => self.receiver_trust(inner),
+            // `IERC20(token)`, `payable(x)`, `address(x)`
+            pt::Expression::FunctionCall(_, callee, args) if args.len() == 1 && matches!(callee.as_ref(),
+                pt::Expression::Type(..) | pt::Expression::Variable(_)) =>
+            {
+                let is_cast = match callee.as_ref() {
+                    pt::Expression::Variable(_) => self.typers.get(&frame.owner).is_some_and(|t| t.names_contract(callee)),
+                    _ => true,
+                };
+                if is_cast { self.receiver_trust(&args[0]) } else { Trust::Untrusted }
+            }
+            pt::Expression::Variable(id) if id.name == "this" => Trust::Fixed,
+            pt::Expression::Variable(id) => {
+                if let Some(trust) = frame.param_trust.get(&id.name) {
+                    return *trust;
+                }
+                if frame.storage_refs.contains_key(&id.name) {
+                    return Trust::Configured;
+                }
+                match self.contract.state_variables.iter().find(|v| v.name == id.name && frame.owner == self.index) {
+                    Some(var) if var.is_constant || var.is_immutable => Trust::Fixed,
+                    Some(_) => Trust::Configured,
+                    None => Trust::Untrusted,
+                }
+            }
+            pt::Expression::MemberAccess(_, base, _) if matches!(base.as_ref(), pt::Expression::Variable(id) if id.name == "msg" || id.name == "tx") => {
+                Trust::Untrusted
+            }
+            pt::Expression::MemberAccess(..) | pt::Expression::ArraySubscript(..) => {
+                match StateModificationAnalyzer::extract_base_variable(expr) {
+                    Some(root) if self.state_target(&root).is_some() => Trust::Configured,
+                    Some(root) => frame.param_trust.get(&root).copied().unwrap_or(Trust::Untrusted),
+                    None => Trust::Untrusted,
+                }
+            }
+            _ => Trust::Untrusted,
+        }
+    }
+
+    fn line(&self, loc: &pt::Loc) -> usize {
+        self.frames.last()
+            .and_then(|f| f.source)
+            .map(|u| u.line_at(loc.start()))
+            .unwrap_or_default()
+    }
+
+    /// Record an effect against every external call that may have run before it
+    fn effect(&mut self, effect: ReentrancyEffect, loc: &pt::Loc) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let effect_line = self.line(loc);
+        // Frames entered from the entry point's body, or the modifiers for effects inside them
+        let via: Vec<String> = match self.frames.iter().rposition(|f| f.is_entry) {
+            Some(entry) => self.frames[entry + 1..].iter().map(|f| f.label.clone()).collect(),
+            None => self.frames.iter().map(|f| f.label.clone()).collect(),
+        };
+
+        for call in &self.pending {
+            let severity = match (&effect, call.trust) {
+                (ReentrancyEffect::Event(_), _) => ReentrancySeverity::Low,
+                _ if call.gas_limited => ReentrancySeverity::Low,
+                (_, Trust::Fixed) => ReentrancySeverity::Low,
+                (_, Trust::Configured) => ReentrancySeverity::Medium,
+                (_, Trust::Untrusted) => ReentrancySeverity::High,
+            };
+            let known = self.findings.iter().any(|f| f.function == self.entry
+                && f.call_line == call.line && f.call == call.text && f.effect == effect);
+            if known {
+                continue;
+            }
+            self.findings.push(ReentrancyFinding {
+                function: self.entry.clone(),
+                severity,
+                call: call.text.clone(),
+                call_line: call.line,
+                sends_value: call.sends_value,
+                effect: effect.clone(),
+                effect_line,
+                via: via.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+
+    const BANK: &str = r#"
+        interface IVault { function deposit() external payable; }
+
+        contract Bank {
+            mapping(address => uint256) public balances;
+            uint256 public start;
+            address public vault;
+            address public immutable token;
+            IRewards public immutable rewards;
+
+            modifier checkBlock() { require(block.number > start); _; }
+            modifier onlyTimelock() { require(msg.sender == vault); _; }
+            modifier nonReentrant() { _; }
+
+            function withdrawAfterBlock(uint256 amount) external checkBlock {
+                (bool ok,) = msg.sender.call{value: amount}("");
+                require(ok);
+                balances[msg.sender] -= amount;
+            }
+
+            function withdrawTo(address to, uint256 amount) external onlyTimelock {
+                (bool ok,) = to.call{value: amount}("");
+                require(ok);
+                balances[to] -= amount;
+            }
+
+            function withdraw(uint256 amount) external nonReentrant {
+                (bool ok,) = msg.sender.call{value: amount}("");
+                require(ok);
+                balances[msg.sender] -= amount;
+            }
+
+            function claim() external {
+                rewards.getReward();
+                balances[msg.sender] = 0;
+            }
+
+            function depositToVault() external {
+                IVault(vault).deposit();
+                balances[msg.sender] = 0;
+            }
+
+            function depositFor() external {
+                IVaultFor(token).deposit();
+                balances[msg.sender] = 0;
+            }
+
+            function IVaultFor(address account) internal pure returns (IVault) {
+                return IVault(account);
+            }
+        }
+    "#;
+
+    fn finding<'a>(contract: &'a ContractInfo, function: &str) -> Option<&'a ReentrancyFinding> {
+        contract.reentrancy.iter().find(|f| f.function == function)
+    }
+
+    #[test]
+    fn only_whole_guard_names_lock() {
+        let project = analyze(BANK);
+        let bank = contract(&project, "Bank");
+        assert!(finding(bank, "withdrawAfterBlock(uint256)").is_some(), "{:?}", bank.reentrancy);
+        assert!(finding(bank, "withdrawTo(address,uint256)").is_some(), "{:?}", bank.reentrancy);
+        assert!(finding(bank, "withdraw(uint256)").is_none(), "{:?}", bank.reentrancy);
+    }
+
+    #[test]
+    fn undeclared_interface_functions_may_change_state() {
+        let project = analyze(BANK);
+        let claim = finding(contract(&project, "Bank"), "claim()").expect("getReward() is an external call");
+        assert_eq!(claim.call, "rewards.getReward()");
+    }
+
+    #[test]
+    fn only_conversions_to_contract_types_keep_the_receiver_trust() {
+        let project = analyze(BANK);
+        let bank = contract(&project, "Bank");
+        assert_eq!(finding(bank, "depositToVault()").unwrap().severity, ReentrancySeverity::Medium);
+        // A function named like an interface returns an address the contract doesn't control
+        assert_eq!(finding(bank, "depositFor()").unwrap().severity, ReentrancySeverity::High);
+    }
+
+    const POOL: &str = r#"
+        contract Pool {
+            mapping(address => uint256) balances;
+            address owner;
+            uint256 locked = 1;
+
+            modifier nonReentrant() { require(locked == 1); locked = 2; _; locked = 1; }
+            modifier onlyOwner() { require(msg.sender == owner); _; }
+
+            function withdraw(uint256 amount) external nonReentrant {
+                (bool ok,) = msg.sender.call{value: amount}("");
+                require(ok);
+                balances[msg.sender] -= amount;
+            }
+
+            function transfer(address to, uint256 amount) external {
+                balances[msg.sender] -= amount;
+                balances[to] += amount;
+            }
+
+            function deposit() external payable nonReentrant { balances[msg.sender] += msg.value; }
+
+            function seize(address account) external onlyOwner { balances[account] = 0; }
+
+            function balanceOf(address account) external view returns (uint256) { return balances[account]; }
+        }
+
+        contract Lender {
+            Pool pool;
+            function collateral(address account) external view returns (uint256) { return pool.balanceOf(account); }
+        }
+    "#;
+
+    fn cross<'a>(contract: &'a ContractInfo, function: &str) -> Option<&'a CrossReentrancyFinding> {
+        contract.cross_reentrancy.iter().find(|f| f.function == function)
+    }
+
+    #[test]
+    fn unguarded_siblings_see_state_stale_during_the_call() {
+        let project = analyze(POOL);
+        let pool = contract(&project, "Pool");
+        let transfer = cross(pool, "transfer(address,uint256)").expect("transfer() is not guarded");
+        assert_eq!((transfer.kind, transfer.variable.as_str()), (CrossReentrancyKind::CrossFunction, "balances"));
+        assert_eq!(transfer.window_function, "withdraw(uint256)");
+
+        let view = cross(pool, "balanceOf(address)").expect("balanceOf() reads the stale balance");
+        assert_eq!(view.kind, CrossReentrancyKind::ReadOnly);
+        assert_eq!(view.consumers, ["Lender.collateral(address)"]);
+    }
+
+    #[test]
+    fn siblings_holding_the_same_lock_or_a_role_are_not_reported() {
+        let project = analyze(POOL);
+        let pool = contract(&project, "Pool");
+        assert!(cross(pool, "deposit()").is_none(), "{:?}", pool.cross_reentrancy);
+        assert!(cross(pool, "seize(address)").is_none(), "{:?}", pool.cross_reentrancy);
+    }
+}
@@ -55,6 +55,9 @@ impl StateVarReportGenerator {
         section.push_str(&format!("### State Variable: `{}`\n", state_var.name));
         section.push_str(&format!("**Type:** {}\n", state_var.var_type));
         section.push_str(&format!("**Visibility:** {}\n", state_var.visibility));
+        if let Some(base) = &state_var.inherited_from {
+            section.push_str(&format!("**Inherited from:** {}\n", base));
+        }
         if state_var.is_constant {
             section.push_str("**Constant:** yes\n");
         }
@@ -151,15 +154,15 @@ impl StateVarReportGenerator {
         for func in functions {
             if func.modifies_states.contains(&var_name.to_string())
                 || func.modifies_state_fields.iter().any(|f| f.starts_with(&format!("{}.", var_name))) {
-                direct.push(func.name.clone());
+                direct.push(func.signature.clone());
             }
         }
 
         // Find indirect writers (functions that call direct writers)
         for func in functions {
-            if !direct.contains(&func.name) {
-                if let Some(chain) = Self::find_call_chain_to_writers(&func.name, &direct, call_graph) {
-                    indirect.push((func.name.clone(), chain));
+            if !direct.contains(&func.signature) {
+                if let Some(chain) = Self::find_call_chain_to_writers(&func.signature, &direct, call_graph) {
+                    indirect.push((func.signature.clone(), chain));
                 }
             }
         }
@@ -179,15 +182,15 @@ impl StateVarReportGenerator {
         // Find direct readers
         for func in functions {
             if func.reads_states.contains(&var_name.to_string()) {
-                direct.push(func.name.clone());
+                direct.push(func.signature.clone());
             }
         }
 
         // Find indirect readers (functions that call direct readers)
         for func in functions {
-            if !direct.contains(&func.name) {
-                if let Some(chain) = Self::find_call_chain_to_readers(&func.name, &direct, call_graph) {
-                    indirect.push((func.name.clone(), chain));
+            if !direct.contains(&func.signature) {
+                if let Some(chain) = Self::find_call_chain_to_readers(&func.signature, &direct, call_graph) {
+                    indirect.push((func.signature.clone(), chain));
                 }
             }
         }
@@ -253,7 +256,7 @@ impl StateVarReportGenerator {
         for func in functions {
             // Use the calls_functions field which already tracks internal function calls
             if !func.calls_functions.is_empty() {
-                graph.insert(func.name.clone(), func.calls_functions.clone());
+                graph.insert(func.signature.clone(), func.calls_functions.clone());
             }
         }
 
@@ -264,7 +267,7 @@ impl StateVarReportGenerator {
     fn get_func_visibility(func_name: &str, functions: &[FunctionDef]) -> String {
         functions
             .iter()
-            .find(|f| f.name == func_name)
+            .find(|f| f.signature == func_name)
             .map(|f| f.visibility.clone())
             .unwrap_or_else(|| "unknown".to_string())
     }
@@ -0,0 +1,644 @@
+use crate::models::*;
+use anyhow::Result;
+use colored::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Compares the storage layouts of two versions of a project for upgrade safety
+pub struct StorageDiffer;
+
+/// A variable of a layout, identified by its declaring contract and name
+/// (several bases may each declare a `__gap`)
+type EntryKey = (String, String);
+
+/// A field of an ERC-7201 struct with its position relative to the namespace slot
+struct NamespaceField {
+    name: String,
+    member_type: String, // As written
+    type_id: String,     // Storage type, which tells lengths given by constants apart
+    slot: u64,
+    offset: usize,
+}
+
+impl StorageDiffer {
+    /// Diff every contract that exists in both versions and has a storage layout
+    pub fn compare(old: &[ContractInfo], new: &[ContractInfo]) -> Vec<StorageDiff> {
+        let mut diffs = Vec::new();
+
+        for contract in Self::layout_contracts(new) {
+            let Some(previous) = Self::layout_contracts(old).into_iter().find(|c| c.name == contract.name) else {
+                continue;
+            };
+            let (Some(before), Some(after)) = (&previous.storage_layout, &contract.storage_layout) else {
+                continue;
+            };
+
+            let mut changes = Self::compare_layouts(before, after);
+            changes.extend(Self::compare_structs(before, after));
+            for (old_storage, new_storage) in Self::pair_namespaces(&previous.upgradeable_storage, &contract.upgradeable_storage) {
+                changes.extend(Self::compare_namespaces(old_storage, new_storage));
+            }
+
+            let mut missing_bases = before.missing_bases.clone();
+            for base in &after.missing_bases {
+                if !missing_bases.contains(base) {
+                    missing_bases.push(base.clone());
+                }
+            }
+
+            diffs.push(StorageDiff {
+                contract: contract.name.clone(),
+                changes,
+                missing_bases,
+            });
+        }
+
+        diffs
+    }
+
+    /// Names of contracts with a layout in `contracts` but not in `other`
+    pub fn only_in(contracts: &[ContractInfo], other: &[ContractInfo]) -> Vec<String> {
+        let other = Self::layout_contracts(other);
+        Self::layout_contracts(contracts).into_iter()
+            .filter(|c| !other.iter().any(|o| o.name == c.name))
+            .map(|c| c.name.clone())
+            .collect()
+    }
+
+    fn layout_contracts(contracts: &[ContractInfo]) -> Vec<&ContractInfo> {
+        contracts.iter()
+            .filter(|c| !c.is_context_only && c.storage_layout.is_some())
+            .collect()
+    }
+
+    fn compare_layouts(before: &StorageLayout, after: &StorageLayout) -> Vec<StorageChange> {
+        let mut changes = Vec::new();
+        let old_entries: HashMap<EntryKey, &StorageEntry> = before.storage.iter().map(|e| (Self::key(e), e)).collect();
+        let new_entries: HashMap<EntryKey, &StorageEntry> = after.storage.iter().map(|e| (Self::key(e), e)).collect();
+
+        // Relative order of the variables present in both versions, to tell reordering from shifting
+        let common_order = |layout: &StorageLayout| -> Vec<EntryKey> {
+            layout.storage.iter()
+                .map(Self::key)
+                .filter(|k| old_entries.contains_key(k) && new_entries.contains_key(k) && !Self::is_gap(&k.1))
+                .collect()
+        };
+        let old_order = common_order(before);
+        let new_order = common_order(after);
+
+        let old_gaps: Vec<(u64, u64)> = before.storage.iter()
+            .filter(|e| Self::is_gap(&e.label))
+            .map(|e| Self::span(e, before))
+            .collect();
+        let old_end = before.storage.iter().map(|e| Self::span(e, before).1).max().unwrap_or(0);
+
+        for old in &before.storage {
+            let key = Self::key(old);
+            let Some(new) = new_entries.get(&key) else {
+                continue;
+            };
+
+            if Self::is_gap(&old.label) {
+                let (old_start, old_stop) = Self::span(old, before);
+                let (new_start, new_stop) = Self::span(new, after);
+                if (old_start, old_stop) == (new_start, new_stop) {
+                    continue;
+                }
+                let is_safe = old_stop == new_stop && new_start > old_start;
+                let note = if is_safe {
+                    format!("shrunk by {} slot(s) to make room for new variables", (new_start - old_start) / 32)
+                } else {
+                    format!("gap now ends at slot {} instead of {}; variables after it move", new_stop / 32, old_stop / 32)
+                };
+                changes.push(Self::change(StorageChangeKind::GapResized, old, new, before, after, is_safe, note));
+                continue;
+            }
+
+            let old_type = Self::type_label(old, before);
+            let new_type = Self::type_label(new, after);
+            let moved = (old.slot, old.offset) != (new.slot, new.offset);
+
+            if old_type != new_type {
+                let is_safe = !moved && Self::same_representation(&old_type, &new_type);
+                let note = if is_safe {
+                    "both are stored as a 20-byte address".to_string()
+                } else {
+                    "existing data would be read as a different type".to_string()
+                };
+                changes.push(Self::change(StorageChangeKind::Retyped, old, new, before, after, is_safe, note));
+            } else if moved {
+                let reordered = old_order.iter().position(|k| k == &key) != new_order.iter().position(|k| k == &key);
+                let (kind, note) = if reordered {
+                    (StorageChangeKind::Reordered, "declared in a different order; reads another variable's data")
+                } else {
+                    (StorageChangeKind::Shifted, "moved by an insertion, removal or resize before it")
+                };
+                changes.push(Self::change(kind, old, new, before, after, false, note.to_string()));
+            }
+        }
+
+        // Unmatched variables: renames keep their slot and type
+        let added: Vec<&StorageEntry> = after.storage.iter()
+            .filter(|e| !old_entries.contains_key(&Self::key(e)))
+            .collect();
+        let mut renamed_to = Vec::new();
+
+        for old in before.storage.iter().filter(|e| !new_entries.contains_key(&Self::key(e))) {
+            let rename = added.iter().find(|new| {
+                (new.slot, new.offset) == (old.slot, old.offset)
+                    && Self::type_label(new, after) == Self::type_label(old, before)
+            });
+            match rename {
+                Some(new) => {
+                    renamed_to.push(Self::key(new));
+                    changes.push(Self::change(StorageChangeKind::Renamed, old, new, before, after, true,
+                        format!("`{}` is now `{}`; same slot and type", old.label, new.label)));
+                }
+                None => changes.push(StorageChange {
+                    kind: StorageChangeKind::Removed,
+                    label: old.label.clone(),
+                    before: Some(Self::describe(old, before)),
+                    after: None,
+                    is_safe: false,
+                    note: "its slot is left with stale data, and may be reused by the variables after it".to_string(),
+                }),
+            }
+        }
+
+        for new in added.into_iter().filter(|e| !renamed_to.contains(&Self::key(e))) {
+            let (start, stop) = Self::span(new, after);
+            let in_gap = old_gaps.iter().any(|&(gap_start, gap_stop)| start >= gap_start && stop <= gap_stop);
+            let (kind, is_safe, note) = if start >= old_end {
+                (StorageChangeKind::Appended, true, "placed after all existing variables")
+            } else if in_gap {
+                (StorageChangeKind::Appended, true, "placed in space reserved by a `__gap`")
+            } else {
+                (StorageChangeKind::Inserted, false, "occupies slots used by existing variables")
+            };
+            changes.push(StorageChange {
+                kind,
+                label: new.label.clone(),
+                before: None,
+                after: Some(Self::describe(new, after)),
+                is_safe,
+                note: note.to_string(),
+            });
+        }
+
+        changes
+    }
+
+    /// Structs stored in sequential storage may only gain members at the end, and only where
+    /// nothing is laid out after them: a grown array element shifts every following element
+    fn compare_structs(before: &StorageLayout, after: &StorageLayout) -> Vec<StorageChange> {
+        let mut changes = Vec::new();
+
+        for old_type in before.types.values().filter(|t| !t.members.is_empty()) {
+            let Some((new_id, new_type)) = after.types.iter().find(|(_, t)| t.label == old_type.label && !t.members.is_empty()) else {
+                continue;
+            };
+            let summarize = |ty: &StorageType, layout: &StorageLayout| -> Vec<String> {
+                ty.members.iter().map(|m| format!("{} {}", Self::type_label(m, layout), m.label)).collect()
+            };
+            let old_members = summarize(old_type, before);
+            let new_members = summarize(new_type, after);
+            let same_place = old_type.members.iter().zip(&new_type.members)
+                .all(|(a, b)| (a.slot, a.offset) == (b.slot, b.offset));
+            if old_members == new_members && same_place {
+                continue;
+            }
+
+            let appended = new_members.len() > old_members.len()
+                && new_members.starts_with(&old_members)
+                && same_place;
+            let same_slots = old_type.number_of_bytes.div_ceil(32) == new_type.number_of_bytes.div_ceil(32);
+            let is_safe = appended && (same_slots || Self::can_grow(new_id, after));
+            let note = if is_safe {
+                "members appended; existing members keep their position".to_string()
+            } else if appended {
+                "members appended, but the struct is stored where later data follows it (e.g. as an array element), which now shifts".to_string()
+            } else {
+                "existing members were removed, reordered or retyped".to_string()
+            };
+            changes.push(StorageChange {
+                kind: StorageChangeKind::StructChanged,
+                label: old_type.label.clone(),
+                before: Some(format!("{{ {} }}", old_members.join("; "))),
+                after: Some(format!("{{ {} }}", new_members.join("; "))),
+                is_safe,
+                note,
+            });
+        }
+
+        changes
+    }
+
+    /// Whether a struct can grow without moving other data: every use is as a mapping value,
+    /// the last state variable, or the last member of a struct that can itself grow
+    fn can_grow(type_id: &str, layout: &StorageLayout) -> bool {
+        let last_variable = layout.storage.iter().max_by_key(|e| Self::span(e, layout).1);
+        let as_variable = layout.storage.iter()
+            .filter(|e| e.type_id == type_id)
+            .all(|e| last_variable.is_some_and(|last| std::ptr::eq(e, last)));
+
+        as_variable && layout.types.iter().all(|(id, ty)| {
+            let in_array = ty.base.as_deref() == Some(type_id);
+            let in_struct = ty.members.iter().enumerate()
+                .any(|(i, m)| m.type_id == type_id && (i + 1 < ty.members.len() || !Self::can_grow(id, layout)));
+            !in_array && !in_struct
+        })
+    }
+
+    /// Match namespaced structs of both versions by struct name
+    fn pair_namespaces<'a>(
+        before: &'a [UpgradeableStorage],
+        after: &'a [UpgradeableStorage],
+    ) -> Vec<(Option<&'a UpgradeableStorage>, Option<&'a UpgradeableStorage>)> {
+        let mut pairs: Vec<_> = before.iter()
+            .map(|b| (Some(b), after.iter().find(|a| a.storage_struct == b.storage_struct)))
+            .collect();
+        pairs.extend(after.iter()
+            .filter(|a| !before.iter().any(|b| b.storage_struct == a.storage_struct))
+            .map(|a| (None, Some(a))));
+        pairs
+    }
+
+    /// ERC-7201 namespaced struct fields are compared by name and position within the namespace
+    fn compare_namespaces(before: Option<&UpgradeableStorage>, after: Option<&UpgradeableStorage>) -> Vec<StorageChange> {
+        let (before, after) = match (before, after) {
+            (None, None) => return Vec::new(),
+            (Some(b), None) => {
+                return vec![StorageChange {
+                    kind: StorageChangeKind::NamespaceChanged,
+                    label: b.namespace.clone(),
+                    before: Some(format!("struct {} at {}", b.storage_struct, b.storage_slot)),
+                    after: None,
+                    is_safe: false,
+                    note: "namespaced storage is no longer used; its data is orphaned".to_string(),
+                }];
+            }
+            (None, Some(a)) => {
+                return vec![StorageChange {
+                    kind: StorageChangeKind::NamespaceChanged,
+                    label: a.namespace.clone(),
+                    before: None,
+                    after: Some(format!("struct {} at {}", a.storage_struct, a.storage_slot)),
+                    is_safe: true,
+                    note: "new namespaced storage".to_string(),
+                }];
+            }
+            (Some(b), Some(a)) => (b, a),
+        };
+
+        if before.namespace != after.namespace {
+            return vec![StorageChange {
+                kind: StorageChangeKind::NamespaceChanged,
+                label: after.namespace.clone(),
+                before: Some(format!("{} at {}", before.namespace, before.storage_slot)),
+                after: Some(format!("{} at {}", after.namespace, after.storage_slot)),
+                is_safe: false,
+                note: "a different namespace hashes to a different slot; existing data is orphaned".to_string(),
+            }];
+        }
+
+        let old_fields = Self::namespace_fields(before);
+        let new_fields = Self::namespace_fields(after);
+        let describe = |f: &NamespaceField| format!("slot {}, offset {}: {} {}", f.slot, f.offset, f.member_type, f.name);
+        let old_last = old_fields.iter().map(|f| (f.slot, f.offset)).max();
+        let mut changes = Vec::new();
+        let mut push = |kind, old: Option<&NamespaceField>, new: Option<&NamespaceField>, is_safe, note: String| {
+            let name = new.or(old).map(|f| f.name.as_str()).unwrap_or_default();
+            changes.push(StorageChange {
+                kind,
+                label: format!("{}.{}", after.storage_struct, name),
+                before: old.map(describe),
+                after: new.map(describe),
+                is_safe,
+                note,
+            });
+        };
+
+        // Fields are matched by name; a position alone only identifies a rename
+        let mut renamed_to: Vec<&str> = Vec::new();
+        for old in &old_fields {
+            if let Some(new) = new_fields.iter().find(|n| n.name == old.name) {
+                let moved = (old.slot, old.offset) != (new.slot, new.offset);
+                if old.type_id != new.type_id {
+                    push(StorageChangeKind::Retyped, Some(old), Some(new), false,
+                        "existing data would be read as a different type".to_string());
+                } else if moved {
+                    push(StorageChangeKind::Shifted, Some(old), Some(new), false,
+                        "moved by an insertion, removal or resize before it".to_string());
+                }
+                continue;
+            }
+            let rename = new_fields.iter().find(|n| {
+                (n.slot, n.offset) == (old.slot, old.offset) && n.type_id == old.type_id
+                    && !old_fields.iter().any(|o| o.name == n.name)
+            });
+            match rename {
+                Some(new) => {
+                    renamed_to.push(&new.name);
+                    push(StorageChangeKind::Renamed, Some(old), Some(new), true,
+                        format!("`{}` is now `{}`; same position and type", old.name, new.name));
+                }
+                None => push(StorageChangeKind::Removed, Some(old), None, false,
+                    "its data is left behind, and may be read by the fields after it".to_string()),
+            }
+        }
+
+        for new in new_fields.iter().filter(|n| !old_fields.iter().any(|o| o.name == n.name) && !renamed_to.contains(&n.name.as_str())) {
+            if old_last.is_none_or(|last| (new.slot, new.offset) > last) {
+                push(StorageChangeKind::Appended, None, Some(new), true,
+                    "field appended to the namespaced struct".to_string());
+            } else {
+                push(StorageChangeKind::Inserted, None, Some(new), false,
+                    "occupies space used by existing fields".to_string());
+            }
+        }
+
+        changes
+    }
+
+    /// Fields of a namespaced struct with their position, in declaration order
+    fn namespace_fields(storage: &UpgradeableStorage) -> Vec<NamespaceField> {
+        storage.struct_fields.iter().enumerate()
+            .map(|(i, field)| {
+                let entry = storage.field_layout.iter().find(|e| e.label == field.name);
+                NamespaceField {
+                    name: field.name.clone(),
+                    member_type: field.member_type.clone(),
+                    // Without a layout, the declaration index stands in for the position
+                    type_id: entry.map_or_else(|| field.member_type.clone(), |e| e.type_id.clone()),
+                    slot: entry.map_or(i as u64, |e| e.slot),
+                    offset: entry.map_or(0, |e| e.offset),
+                }
+            })
+            .collect()
+    }
+
+    fn key(entry: &StorageEntry) -> EntryKey {
+        // Paths differ between the two trees; the contract name identifies the declarer
+        let declarer = entry.contract.rsplit(':').next().unwrap_or(&entry.contract);
+        (declarer.to_string(), entry.label.clone())
+    }
+
+    fn is_gap(label: &str) -> bool {
+        label.starts_with("__gap")
+    }
+
+    /// Byte range [start, end) occupied by a variable, counting from slot 0
+    fn span(entry: &StorageEntry, layout: &StorageLayout) -> (u64, u64) {
+        let start = entry.slot * 32 + entry.offset as u64;
+        let bytes = layout.types.get(&entry.type_id).map(|t| t.number_of_bytes).unwrap_or(32);
+        (start, start + bytes)
+    }
+
+    fn type_label(entry: &StorageEntry, layout: &StorageLayout) -> String {
+        layout.types.get(&entry.type_id)
+            .map(|t| t.label.clone())
+            .unwrap_or_else(|| entry.type_id.clone())
+    }
+
+    /// Addresses, payable addresses and contract types share the same encoding
+    fn same_representation(a: &str, b: &str) -> bool {
+        let is_address = |t: &str| t == "address" || t == "address payable" || t.starts_with("contract ");
+        is_address(a) && is_address(b)
+    }
+
+    fn describe(entry: &StorageEntry, layout: &StorageLayout) -> String {
+        format!("slot {}, offset {}: {}", entry.slot, entry.offset, Self::type_label(entry, layout))
+    }
+
+    fn change(
+        kind: StorageChangeKind,
+        old: &StorageEntry,
+        new: &StorageEntry,
+        before: &StorageLayout,
+        after: &StorageLayout,
+        is_safe: bool,
+        note: String,
+    ) -> StorageChange {
+        StorageChange {
+            kind,
+            label: new.label.clone(),
+            before: Some(Self::describe(old, before)),
+            after: Some(Self::describe(new, after)),
+            is_safe,
+            note,
+        }
+    }
+
+    pub fn print_summary(diffs: &[StorageDiff]) {
+        println!();
+        println!("{}", "🧬 Storage layout changes".bold());
+
+        for diff in diffs {
+            let unsafe_count = diff.changes.iter().filter(|c| !c.is_safe).count();
+            if diff.changes.is_empty() {
+                println!("  {} {} {}", "✓".green(), diff.contract.bold(), "unchanged".dimmed());
+            } else if unsafe_count == 0 {
+                println!("  {} {} ({} safe change(s))", "✓".green(), diff.contract.bold(), diff.changes.len());
+            } else {
+                println!("  {} {} ({} unsafe change(s))", "✗".red(), diff.contract.bold(), unsafe_count);
+            }
+
+            for change in &diff.changes {
+                let status = if change.is_safe { "safe".green() } else { "UNSAFE".red().bold() };
+                println!("      {} {} `{}`: {}", status, change.kind.label(), change.label, change.note.dimmed());
+            }
+            if !diff.missing_bases.is_empty() {
+                println!("      {} not compared (outside the project): {}", "⚠️".yellow(), diff.missing_bases.join(", "));
+            }
+        }
+    }
+
+    /// Write `storage_diff.md` and `storage_diff.json`
+    pub fn save_reports(
+        diffs: &[StorageDiff],
+        old_path: &Path,
+        new_path: &Path,
+        only_in_old: &[String],
+        only_in_new: &[String],
+        output_dir: &Path,
+    ) -> Result<()> {
+        fs::create_dir_all(output_dir)?;
+
+        let report = json!({
+            "old": old_path.display().to_string(),
+            "new": new_path.display().to_string(),
+            "safe": diffs.iter().all(StorageDiff::is_safe),
+            "contracts": diffs,
+            "only_in_old": only_in_old,
+            "only_in_new": only_in_new,
+        });
+        fs::write(output_dir.join("storage_diff.json"), serde_json::to_string_pretty(&report)?)?;
+        println!("  {} storage_diff.json", "✓".green());
+
+        let markdown = Self::generate_markdown(diffs, old_path, new_path, only_in_old, only_in_new);
+        fs::write(output_dir.join("storage_diff.md"), markdown)?;
+        println!("  {} storage_diff.md", "✓".green());
+        Ok(())
+    }
+
+    fn generate_markdown(
+        diffs: &[StorageDiff],
+        old_path: &Path,
+        new_path: &Path,
+        only_in_old: &[String],
+        only_in_new: &[String],
+    ) -> String {
+        let mut md = String::new();
+        let separator = "━".repeat(80);
+
+        md.push_str("# Storage Layout Diff\n\n");
+        md.push_str(&format!("**Old:** `{}`\n", old_path.display()));
+        md.push_str(&format!("**New:** `{}`\n\n", new_path.display()));
+
+        let unsafe_total: usize = diffs.iter().map(|d| d.changes.iter().filter(|c| !c.is_safe).count()).sum();
+        if unsafe_total == 0 {
+            md.push_str("✅ **No unsafe storage changes**\n\n");
+        } else {
+            md.push_str(&format!("❌ **{} unsafe storage change(s)**\n\n", unsafe_total));
+        }
+
+        if !only_in_old.is_empty() {
+            md.push_str(&format!("**Only in old:** {}\n", only_in_old.iter().map(|c| format!("`{}`", c)).collect::<Vec<_>>().join(", ")));
+        }
+        if !only_in_new.is_empty() {
+            md.push_str(&format!("**Only in new:** {}\n", only_in_new.iter().map(|c| format!("`{}`", c)).collect::<Vec<_>>().join(", ")));
+        }
+        if !only_in_old.is_empty() || !only_in_new.is_empty() {
+            md.push('\n');
+        }
+
+        for diff in diffs {
+            md.push_str(&separator);
+            md.push('\n');
+            md.push_str(&format!("**CONTRACT: `{}`**\n", diff.contract));
+            md.push_str(&separator);
+            md.push_str("\n\n");
+
+            if !diff.missing_bases.is_empty() {
+                md.push_str(&format!("⚠️ Variables of {} (not in the project) are not compared.\n\n",
+                    diff.missing_bases.iter().map(|b| format!("`{}`", b)).collect::<Vec<_>>().join(", ")));
+            }
+
+            if diff.changes.is_empty() {
+                md.push_str("*No storage changes*\n\n");
+                continue;
+            }
+
+            md.push_str("| Status | Change | Name | Before | After | Note |\n");
+            md.push_str("|--------|--------|------|--------|-------|------|\n");
+            for change in &diff.changes {
+                md.push_str(&format!("| {} | {} | `{}` | {} | {} | {} |\n",
+                    if change.is_safe { "✅ safe" } else { "❌ unsafe" },
+                    change.kind.label(),
+                    change.label,
+                    change.before.as_deref().unwrap_or("—"),
+                    change.after.as_deref().unwrap_or("—"),
+                    change.note
+                ));
+            }
+            md.push('\n');
+        }
+
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::analyze;
+
+    fn namespaced(fields: &str) -> String {
+        format!(r#"
+            contract Main {{
+                /// @custom:storage-location erc7201:example.main
+                struct MainStorage {{ {} }}
+
+                bytes32 private constant MAIN_STORAGE_LOCATION =
+                    keccak256(abi.encode(uint256(keccak256("example.main")) - 1)) & ~bytes32(uint256(0xff));
+
+                function _getMainStorage() private pure returns (MainStorage storage $) {{
+                    assembly {{ $.slot := MAIN_STORAGE_LOCATION }}
+                }}
+            }}
+        "#, fields)
+    }
+
+    fn diff(old: &str, new: &str) -> Vec<StorageChange> {
+        let (old, new) = (analyze(&namespaced(old)), analyze(&namespaced(new)));
+        StorageDiffer::compare(&old.contracts, &new.contracts).into_iter()
+            .flat_map(|d| d.changes)
+            .collect()
+    }
+
+    fn diff_contracts(old: &str, new: &str) -> Vec<StorageChange> {
+        let (old, new) = (analyze(old), analyze(new));
+        StorageDiffer::compare(&old.contracts, &new.contracts).into_iter()
+            .flat_map(|d| d.changes)
+            .collect()
+    }
+
+    fn kinds(changes: &[StorageChange]) -> Vec<(StorageChangeKind, &str, bool)> {
+        changes.iter().map(|c| (c.kind, c.label.as_str(), c.is_safe)).collect()
+    }
+
+    #[test]
+    fn field_inserted_before_existing_ones_is_unsafe() {
+        let changes = diff("uint256 a; uint256 b;", "uint256 x; uint256 a; uint256 b;");
+        assert_eq!(kinds(&changes), [
+            (StorageChangeKind::Shifted, "MainStorage.a", false),
+            (StorageChangeKind::Shifted, "MainStorage.b", false),
+            (StorageChangeKind::Inserted, "MainStorage.x", false),
+        ]);
+    }
+
+    #[test]
+    fn field_appended_or_packed_after_the_last_one_is_safe() {
+        let changes = diff("uint256 a; uint128 b;", "uint256 a; uint128 b; uint128 c; uint256 d;");
+        assert_eq!(kinds(&changes), [
+            (StorageChangeKind::Appended, "MainStorage.c", true),
+            (StorageChangeKind::Appended, "MainStorage.d", true),
+        ]);
+    }
+
+    #[test]
+    fn rename_keeps_position_and_type() {
+        let changes = diff("uint256 a; address b;", "uint256 a; address owner;");
+        assert_eq!(kinds(&changes), [(StorageChangeKind::Renamed, "MainStorage.owner", true)]);
+    }
+
+    #[test]
+    fn removed_and_retyped_fields_are_unsafe() {
+        let changes = diff("uint256 a; uint256 b; uint256 c;", "uint128 a; uint256 c;");
+        assert_eq!(kinds(&changes), [
+            (StorageChangeKind::Retyped, "MainStorage.a", false),
+            (StorageChangeKind::Removed, "MainStorage.b", false),
+            (StorageChangeKind::Shifted, "MainStorage.c", false),
+        ]);
+    }
+
+    #[test]
+    fn member_appended_to_an_array_element_struct_is_unsafe() {
+        let changes = diff_contracts(
+            "contract Main { struct P { uint256 a; } P[] items; uint256 total; }",
+            "contract Main { struct P { uint256 a; uint256 b; } P[] items; uint256 total; }",
+        );
+        assert_eq!(kinds(&changes), [(StorageChangeKind::StructChanged, "struct Main.P", false)]);
+    }
+
+    #[test]
+    fn member_appended_to_a_mapping_value_struct_is_safe() {
+        let changes = diff_contracts(
+            "contract Main { struct P { uint256 a; } mapping(address => P) items; uint256 total; }",
+            "contract Main { struct P { uint256 a; uint256 b; } mapping(address => P) items; uint256 total; }",
+        );
+        assert_eq!(kinds(&changes), [(StorageChangeKind::StructChanged, "struct Main.P", true)]);
+    }
+}
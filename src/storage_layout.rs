@@ -0,0 +1,440 @@
+use crate::abi::TypeResolver;
+use crate::models::*;
+use anyhow::Result;
+use colored::*;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Computes the slot and byte offset of every state variable using solc's packing rules
+pub struct StorageLayoutEngine;
+
+/// How a type occupies storage
+struct Layout {
+    id: String,     // Key into the layout's type table, e.g. "t_uint128"
+    bytes: u64,     // numberOfBytes
+    packable: bool, // Value types share slots; everything else starts (and ends) a slot
+}
+
+/// Lays out the variables of one contract, collecting the types it meets
+struct LayoutBuilder<'a> {
+    resolver: TypeResolver<'a>,
+    contract_id: String, // "path:Name" of the contract being laid out, owner of struct members
+    types: BTreeMap<String, StorageType>,
+    in_progress: HashMap<String, String>, // Id -> label of structs being laid out (a struct can reach itself through a mapping)
+}
+
+impl<'a> LayoutBuilder<'a> {
+    fn new(contract: &'a ContractInfo, all_contracts: &'a [ContractInfo], source_units: &'a [SourceUnit]) -> Self {
+        LayoutBuilder {
+            resolver: TypeResolver::new(contract, all_contracts, source_units),
+            contract_id: StorageLayoutEngine::contract_id(contract),
+            types: BTreeMap::new(),
+            in_progress: HashMap::new(),
+        }
+    }
+
+    /// Assign slots and offsets in declaration order, returning the entries and the number of slots used
+    ///
+    /// Items are packed into the current slot while they fit. Structs, arrays, mappings, strings and
+    /// bytes always start a new slot, and the item following them does too.
+    fn place(&mut self, items: Vec<(String, String, String)>) -> (Vec<StorageEntry>, u64) {
+        let mut entries = Vec::new();
+        let mut slot = 0u64;
+        let mut offset = 0u64;
+
+        for (label, ty, contract) in items {
+            let ty = self.resolver.resolve_type(&ty);
+            let layout = self.layout(&ty);
+            if offset > 0 && (!layout.packable || offset + layout.bytes > 32) {
+                slot += 1;
+                offset = 0;
+            }
+
+            entries.push(StorageEntry {
+                contract,
+                label,
+                offset: offset as usize,
+                slot,
+                type_id: layout.id,
+            });
+
+            if layout.packable {
+                offset += layout.bytes;
+            } else {
+                slot += layout.bytes.div_ceil(32);
+            }
+        }
+
+        let used = slot + u64::from(offset > 0);
+        (entries, used)
+    }
+
+    fn layout(&mut self, ty: &SolType) -> Layout {
+        match ty {
+            SolType::Array { base, length } => {
+                let base = self.layout(base);
+                let base_label = self.label(&base.id);
+
+                let Some(length) = length else {
+                    let id = format!("t_array({})dyn_storage", base.id);
+                    self.register(&id, "dynamic_array", format!("{}[]", base_label), 32, |t| t.base = Some(base.id));
+                    return Layout { id, bytes: 32, packable: false };
+                };
+
+                let length = Self::parse_length(length);
+                let slots = if base.packable && base.bytes <= 16 {
+                    length.div_ceil(32 / base.bytes)
+                } else {
+                    length * base.bytes.div_ceil(32)
+                };
+                let id = format!("t_array({}){}_storage", base.id, length);
+                let bytes = slots * 32;
+                self.register(&id, "inplace", format!("{}[{}]", base_label, length), bytes, |t| t.base = Some(base.id));
+                Layout { id, bytes, packable: false }
+            }
+            SolType::Mapping { key, value } => {
+                let key = self.key_layout(key);
+                let value = self.layout(value);
+                let id = format!("t_mapping({},{})", key.id, value.id);
+                let label = format!("mapping({} => {})", self.label(&key.id), self.label(&value.id));
+                self.register(&id, "mapping", label, 32, |t| {
+                    t.key = Some(key.id);
+                    t.value = Some(value.id);
+                });
+                Layout { id, bytes: 32, packable: false }
+            }
+            SolType::Elementary { name } => self.elementary(name),
+            SolType::Function { external, .. } => {
+                // External function pointers store address + selector
+                let (id, bytes) = if *external { ("t_function_external", 24) } else { ("t_function_internal", 8) };
+                self.register(id, "inplace", "function".to_string(), bytes, |_| {});
+                Layout { id: id.to_string(), bytes, packable: true }
+            }
+            SolType::Struct { name, owner } => self.struct_layout(name, owner),
+            SolType::Enum { name, owner } => {
+                let id = format!("t_enum({})", name);
+                self.register(&id, "inplace", TypeResolver::qualified("enum", owner.clone(), name), 1, |_| {});
+                Layout { id, bytes: 1, packable: true }
+            }
+            SolType::UserDefined { name, underlying } => {
+                let underlying = self.resolver.resolve_type(underlying);
+                let underlying = self.layout(&underlying);
+                let id = format!("t_userDefinedValueType({})", name);
+                self.register(&id, "inplace", name.clone(), underlying.bytes, |_| {});
+                Layout { id, bytes: underlying.bytes, packable: true }
+            }
+            // Contracts and interfaces are stored as addresses; names declared outside the project
+            // are most likely interfaces too
+            SolType::Contract { name } | SolType::Unresolved { name } => {
+                let name = name.rsplit('.').next().unwrap_or(name);
+                let id = format!("t_contract({})", name);
+                self.register(&id, "inplace", format!("contract {}", name), 20, |_| {});
+                Layout { id, bytes: 20, packable: true }
+            }
+        }
+    }
+
+    /// Mapping keys are never stored, so dynamic keys keep their memory location
+    fn key_layout(&mut self, key: &SolType) -> Layout {
+        match key {
+            SolType::Elementary { name } if name == "string" || name == "bytes" => {
+                let id = format!("t_{}_memory_ptr", name);
+                self.register(&id, "bytes", name.clone(), 32, |_| {});
+                Layout { id, bytes: 32, packable: false }
+            }
+            _ => self.layout(key),
+        }
+    }
+
+    fn elementary(&mut self, name: &str) -> Layout {
+        let (id, bytes, encoding) = match name {
+            "bool" => ("t_bool".to_string(), 1, "inplace"),
+            "address" => ("t_address".to_string(), 20, "inplace"),
+            "address payable" => ("t_address_payable".to_string(), 20, "inplace"),
+            "string" => ("t_string_storage".to_string(), 32, "bytes"),
+            "bytes" => ("t_bytes_storage".to_string(), 32, "bytes"),
+            _ => {
+                let bits = |prefix: &str| name.strip_prefix(prefix).and_then(|n| n.parse::<u64>().ok());
+                let bytes = bits("bytes").or_else(|| bits("uint").or_else(|| bits("int")).map(|b| b / 8)).unwrap_or(32);
+                (format!("t_{}", name), bytes, "inplace")
+            }
+        };
+
+        let packable = encoding == "inplace";
+        self.register(&id, encoding, name.to_string(), bytes, |_| {});
+        Layout { id, bytes, packable }
+    }
+
+    fn struct_layout(&mut self, name: &str, owner: &Option<String>) -> Layout {
+        let id = format!("t_struct({})_storage", name);
+        if self.in_progress.contains_key(&id) {
+            return Layout { id, bytes: 32, packable: false };
+        }
+        if let Some(known) = self.types.get(&id) {
+            return Layout { id, bytes: known.number_of_bytes, packable: false };
+        }
+        let Some((_, def)) = self.resolver.find_struct(owner.as_deref(), name) else {
+            return Layout { id, bytes: 32, packable: false };
+        };
+
+        let label = TypeResolver::qualified("struct", owner.clone(), name);
+        self.in_progress.insert(id.clone(), label.clone());
+        let members = def.members.iter()
+            .map(|m| (m.name.clone(), m.member_type.clone(), self.contract_id.clone()))
+            .collect();
+        let (members, slots) = self.place(members);
+        self.in_progress.remove(&id);
+
+        let bytes = slots.max(1) * 32;
+        self.register(&id, "inplace", label, bytes, |t| t.members = members);
+        Layout { id, bytes, packable: false }
+    }
+
+    fn register(&mut self, id: &str, encoding: &str, label: String, bytes: u64, fill: impl FnOnce(&mut StorageType)) {
+        if self.types.contains_key(id) {
+            return;
+        }
+        let mut ty = StorageType {
+            encoding: encoding.to_string(),
+            label,
+            number_of_bytes: bytes,
+            key: None,
+            value: None,
+            base: None,
+            members: Vec::new(),
+        };
+        fill(&mut ty);
+        self.types.insert(id.to_string(), ty);
+    }
+
+    fn label(&self, id: &str) -> String {
+        self.types.get(id)
+            .map(|t| &t.label)
+            .or_else(|| self.in_progress.get(id))
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// Array length as a number; ConstantFolder has already replaced constant expressions, so
+    /// only lengths it couldn't evaluate are left and count as 1
+    fn parse_length(length: &str) -> u64 {
+        match length.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => length.replace('_', "").parse().ok(),
+        }
+        .unwrap_or(1)
+    }
+}
+
+impl StorageLayoutEngine {
+    /// Lay out every deployable or abstract contract; needs inheritance to be resolved so that
+    /// inherited variables come first, most base contract first
+    pub fn compute(contracts: &mut [ContractInfo], source_units: &[SourceUnit]) {
+        let snapshot = contracts.to_vec();
+
+        for (i, contract) in contracts.iter_mut().enumerate() {
+            if contract.kind != ContractKind::Contract {
+                continue;
+            }
+
+            let mut builder = LayoutBuilder::new(&snapshot[i], &snapshot, source_units);
+            let items = contract.state_variables.iter()
+                .filter(|v| Self::in_sequential_storage(v))
+                .map(|v| {
+                    let declarer = v.inherited_from.as_ref()
+                        .and_then(|base| snapshot.iter().find(|c| &c.name == base))
+                        .map(Self::contract_id)
+                        .unwrap_or_else(|| builder.contract_id.clone());
+                    (v.name.clone(), v.var_type.clone(), declarer)
+                })
+                .collect();
+            let (storage, _) = builder.place(items);
+
+            let vars = contract.state_variables.iter_mut().filter(|v| Self::in_sequential_storage(v));
+            for (var, entry) in vars.zip(&storage) {
+                var.slot = Some(entry.slot);
+                var.offset = Some(entry.offset);
+            }
+
+            contract.storage_layout = Some(StorageLayout {
+                storage,
+                types: builder.types,
+                missing_bases: contract.unresolved_bases.clone(),
+            });
+
+            // ERC-7201 structs are laid out from their namespace slot, apart from sequential storage
+            for namespaced in &mut contract.upgradeable_storage {
+                let fields = snapshot[i].structs.iter()
+                    .find(|s| s.name == namespaced.storage_struct)
+                    .map_or(&namespaced.struct_fields, |s| &s.members);
+                let items = fields.iter()
+                    .map(|f| (f.name.clone(), f.member_type.clone(), Self::contract_id(&snapshot[i])))
+                    .collect();
+                let mut builder = LayoutBuilder::new(&snapshot[i], &snapshot, source_units);
+                namespaced.field_layout = builder.place(items).0;
+            }
+        }
+    }
+
+    /// Constants and immutables live in bytecode; fields of an ERC-7201 struct live at its namespace
+    fn in_sequential_storage(var: &StateVariable) -> bool {
+        !var.is_constant && !var.is_immutable && !var.var_type.ends_with("(upgradeable storage)")
+    }
+
+    fn contract_id(contract: &ContractInfo) -> String {
+        format!("{}:{}", contract.file_path, contract.name)
+    }
+
+    /// `forge inspect <Contract> storageLayout` JSON (without AST ids, which we don't track)
+    pub fn to_json(layout: &StorageLayout) -> Value {
+        let types: serde_json::Map<String, Value> = layout.types.iter()
+            .map(|(id, ty)| {
+                let mut entry = json!({
+                    "encoding": ty.encoding,
+                    "label": ty.label,
+                    "numberOfBytes": ty.number_of_bytes.to_string(),
+                });
+                if let Some(key) = &ty.key {
+                    entry["key"] = json!(key);
+                }
+                if let Some(value) = &ty.value {
+                    entry["value"] = json!(value);
+                }
+                if let Some(base) = &ty.base {
+                    entry["base"] = json!(base);
+                }
+                if !ty.members.is_empty() {
+                    entry["members"] = Self::entries_json(&ty.members);
+                }
+                (id.clone(), entry)
+            })
+            .collect();
+
+        json!({
+            "storage": Self::entries_json(&layout.storage),
+            "types": types,
+        })
+    }
+
+    fn entries_json(entries: &[StorageEntry]) -> Value {
+        entries.iter()
+            .map(|e| json!({
+                "contract": e.contract,
+                "label": e.label,
+                "offset": e.offset,
+                "slot": e.slot.to_string(),
+                "type": e.type_id,
+            }))
+            .collect()
+    }
+
+    /// Write `storage_layout.json`: contract name -> forge-style layout
+    pub fn save_layout_file(contracts: &[ContractInfo], output_dir: &Path) -> Result<()> {
+        fs::create_dir_all(output_dir)?;
+
+        let report: serde_json::Map<String, Value> = contracts.iter()
+            .filter(|c| !c.is_context_only)
+            .filter_map(|c| c.storage_layout.as_ref().map(|l| (c.name.clone(), Self::to_json(l))))
+            .collect();
+        fs::write(output_dir.join("storage_layout.json"), serde_json::to_string_pretty(&report)?)?;
+        println!("  {} storage_layout.json", "✓".green());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{analyze, contract};
+
+    /// (name, slot, offset) of each state variable in sequential storage
+    fn slots(source: &str, name: &str) -> Vec<(String, u64, usize)> {
+        let project = analyze(source);
+        contract(&project, name).state_variables.iter()
+            .filter_map(|v| Some((v.name.clone(), v.slot?, v.offset?)))
+            .collect()
+    }
+
+    fn slot_of(slots: &[(String, u64, usize)], name: &str) -> (u64, usize) {
+        slots.iter().find(|(n, ..)| n == name).map(|(_, s, o)| (*s, *o)).unwrap()
+    }
+
+    #[test]
+    fn packs_value_types_that_fit_in_a_slot() {
+        let slots = slots(r#"
+            contract C {
+                uint128 a;
+                uint128 b;
+                uint256 c;
+                address d;
+                bool e;
+                uint16 f;
+                uint256 constant K = 1;
+                address immutable owner = address(1);
+            }
+        "#, "C");
+        assert_eq!(slot_of(&slots, "a"), (0, 0));
+        assert_eq!(slot_of(&slots, "b"), (0, 16));
+        assert_eq!(slot_of(&slots, "c"), (1, 0));
+        assert_eq!(slot_of(&slots, "d"), (2, 0));
+        assert_eq!(slot_of(&slots, "e"), (2, 20));
+        assert_eq!(slot_of(&slots, "f"), (2, 21));
+        assert_eq!(slots.len(), 6, "constants and immutables take no storage");
+    }
+
+    #[test]
+    fn structs_arrays_and_mappings_start_a_new_slot() {
+        let slots = slots(r#"
+            contract C {
+                struct S { uint64 x; uint256 y; }
+                uint8 a;
+                S s;
+                uint8 b;
+                uint8[3] small;
+                uint8 c;
+                uint256[] dynamic;
+                mapping(address => uint256) balances;
+                uint8 d;
+            }
+        "#, "C");
+        assert_eq!(slot_of(&slots, "a"), (0, 0));
+        assert_eq!(slot_of(&slots, "s"), (1, 0));
+        assert_eq!(slot_of(&slots, "b"), (3, 0));
+        assert_eq!(slot_of(&slots, "small"), (4, 0));
+        assert_eq!(slot_of(&slots, "c"), (5, 0));
+        assert_eq!(slot_of(&slots, "dynamic"), (6, 0));
+        assert_eq!(slot_of(&slots, "balances"), (7, 0));
+        assert_eq!(slot_of(&slots, "d"), (8, 0));
+    }
+
+    #[test]
+    fn array_lengths_given_by_constants_are_evaluated() {
+        let slots = slots(r#"
+            uint256 constant WIDTH = 2;
+            contract C {
+                uint256 constant N = 3;
+                uint256[N] byName;
+                uint256[2 * N] byExpression;
+                uint256[WIDTH + N][2] nested;
+                uint256 last;
+            }
+        "#, "C");
+        assert_eq!(slot_of(&slots, "byName"), (0, 0));
+        assert_eq!(slot_of(&slots, "byExpression"), (3, 0));
+        assert_eq!(slot_of(&slots, "nested"), (9, 0));
+        assert_eq!(slot_of(&slots, "last"), (19, 0));
+    }
+
+    #[test]
+    fn inherited_variables_come_first() {
+        let slots = slots(r#"
+            contract Base { uint256 x; }
+            contract Middle is Base { uint128 y; }
+            contract C is Middle { uint128 z; }
+        "#, "C");
+        assert_eq!(slot_of(&slots, "x"), (0, 0));
+        assert_eq!(slot_of(&slots, "y"), (1, 0));
+        assert_eq!(slot_of(&slots, "z"), (1, 16));
+    }
+}
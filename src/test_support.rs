@@ -0,0 +1,43 @@
+use crate::models::ContractInfo;
+use crate::{analyze_project, load_project, AnalyzedProject};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Tests run in parallel, each in its own scratch directory
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// Write `files` (path, contents) to a fresh scratch directory; the caller removes it
+pub(crate) fn scratch_dir(files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "solidity-analyzer-test-{}-{}",
+        std::process::id(),
+        NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+    ));
+    for (path, contents) in files {
+        let file = dir.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, contents).unwrap();
+    }
+    fs::canonicalize(&dir).unwrap()
+}
+
+/// Write `files` (path, source) to a scratch directory and run the full analysis on it
+pub(crate) fn analyze_files(files: &[(&str, &str)]) -> AnalyzedProject {
+    let dir = scratch_dir(files);
+    let project = load_project(&dir).unwrap().expect("no Solidity files written");
+    let analyzed = analyze_project(project);
+    fs::remove_dir_all(&dir).unwrap();
+    analyzed
+}
+
+/// Run the full analysis on a single file
+pub(crate) fn analyze(source: &str) -> AnalyzedProject {
+    analyze_files(&[("Test.sol", source)])
+}
+
+pub(crate) fn contract<'a>(project: &'a AnalyzedProject, name: &str) -> &'a ContractInfo {
+    project.contracts.iter()
+        .find(|c| c.name == name)
+        .unwrap_or_else(|| panic!("contract {} not found", name))
+}
@@ -0,0 +1,462 @@
+use crate::abi::{AbiGenerator, TypeResolver};
+use crate::analyzer::StateModificationAnalyzer;
+use crate::models::*;
+use crate::parser::SolidityParser;
+use solang_parser::pt;
+use std::collections::HashMap;
+
+/// Resolves the declared types of state variables and function signatures into `SolType`s
+pub struct TypeAnnotator;
+
+impl TypeAnnotator {
+    pub fn annotate(contracts: &mut [ContractInfo], source_units: &[SourceUnit]) {
+        let snapshot = contracts.to_vec();
+        for (contract, declared) in contracts.iter_mut().zip(&snapshot) {
+            let resolver = TypeResolver::new(declared, &snapshot, source_units);
+            let resolve_params = |params: &[String]| -> Vec<SolType> {
+                params.iter()
+                    .map(|p| resolver.resolve_type(&AbiGenerator::split_param(p).0))
+                    .collect()
+            };
+
+            for var in &mut contract.state_variables {
+                var.resolved_type = Some(resolver.resolve_type(&var.var_type));
+            }
+            for func in &mut contract.functions {
+                if declared.getter_variable(func).is_some() {
+                    Self::expand_struct_getter(func, &resolver);
+                }
+                func.parameter_types = resolve_params(&func.parameters);
+                func.return_types = resolve_params(&func.returns);
+            }
+        }
+    }
+
+    /// A getter returning a struct returns its members instead, leaving out mappings and
+    /// arrays (`bytes` and `string` are kept)
+    fn expand_struct_getter(getter: &mut FunctionDef, resolver: &TypeResolver) {
+        let [value_type] = getter.returns.as_slice() else {
+            return;
+        };
+        let (qualifier, name) = match value_type.rsplit_once('.') {
+            Some((q, n)) => (Some(q), n),
+            None => (None, value_type.as_str()),
+        };
+        let Some((_, def)) = resolver.find_struct(qualifier, name) else {
+            return;
+        };
+        getter.returns = def.members.iter()
+            .filter(|m| !matches!(resolver.resolve_type(&m.member_type), SolType::Mapping { .. } | SolType::Array { .. }))
+            .map(|m| format!("{} {}", m.member_type, m.name))
+            .collect();
+    }
+}
+
+/// Best-effort static types of expressions inside one contract's functions
+pub struct ExpressionTyper<'a> {
+    resolver: TypeResolver<'a>,
+    contract: &'a ContractInfo,
+    all_contracts: &'a [ContractInfo],
+    locals: HashMap<String, SolType>, // Parameters, returns and locals of the current function
+}
+
+impl<'a> ExpressionTyper<'a> {
+    pub fn new(contract: &'a ContractInfo, all_contracts: &'a [ContractInfo], source_units: &'a [SourceUnit]) -> Self {
+        ExpressionTyper {
+            resolver: TypeResolver::new(contract, all_contracts, source_units),
+            contract,
+            all_contracts,
+            locals: HashMap::new(),
+        }
+    }
+
+    /// Scope the typer to a function (or modifier) body
+    pub fn enter_function(&mut self, func: &pt::FunctionDefinition) {
+        let mut declared = HashMap::new();
+        StateModificationAnalyzer::collect_local_types(func, &mut declared);
+        self.locals = declared.into_iter()
+            .map(|(name, ty)| {
+                let ty = self.resolver.resolve_type(&ty);
+                (name, ty)
+            })
+            .collect();
+    }
+
+    /// Argument type as overload resolution expects it ("#number" for number literals)
+    pub fn argument_type(&self, expr: &pt::Expression) -> Option<String> {
+        match expr {
+            pt::Expression::NumberLiteral(..)
+            | pt::Expression::HexNumberLiteral(..)
+            | pt::Expression::RationalNumberLiteral(..) => Some("#number".to_string()),
+            // Contracts convert implicitly to their bases, which a type name alone can't tell
+            _ => match self.infer(expr)? {
+                SolType::Contract { .. } | SolType::Unresolved { .. } => None,
+                SolType::Elementary { name } if name == "address payable" => Some("address".to_string()),
+                SolType::Struct { name, .. } | SolType::Enum { name, .. } => Some(name),
+                other => Some(other.to_string()),
+            },
+        }
+    }
+
+    /// Type of an expression, when it can be told without full semantic analysis
+    pub fn infer(&self, expr: &pt::Expression) -> Option<SolType> {
+        let elementary = |name: &str| Some(SolType::elementary(name));
+        match expr {
+            pt::Expression::BoolLiteral(..) => elementary("bool"),
+            pt::Expression::StringLiteral(_) => elementary("string"),
+            pt::Expression::HexLiteral(_) => elementary("bytes"),
+            pt::Expression::AddressLiteral(..) => elementary("address"),
+            pt::Expression::Variable(ident) => self.variable(&ident.name),
+            pt::Expression::Parenthesis(_, inner) => self.infer(inner),
+            pt::Expression::MemberAccess(_, base, member) => self.member(base, &member.name),
+            pt::Expression::ArraySubscript(_, base, Some(_)) => self.infer(base)?.element(),
+            pt::Expression::ArraySlice(_, base, ..) => self.infer(base),
+            pt::Expression::FunctionCall(_, callee, args) => self.call(callee, args),
+            pt::Expression::NamedFunctionCall(_, callee, args) => self.call_returns(callee, args.len()),
+            pt::Expression::New(_, inner) => match inner.as_ref() {
+                pt::Expression::FunctionCall(_, ty, _) => self.type_expression(ty),
+                ty => self.type_expression(ty),
+            },
+            pt::Expression::ConditionalOperator(_, _, if_true, if_false) => {
+                self.infer(if_true).or_else(|| self.infer(if_false))
+            }
+
+            pt::Expression::Not(..)
+            | pt::Expression::Less(..)
+            | pt::Expression::More(..)
+            | pt::Expression::LessEqual(..)
+            | pt::Expression::MoreEqual(..)
+            | pt::Expression::Equal(..)
+            | pt::Expression::NotEqual(..)
+            | pt::Expression::And(..)
+            | pt::Expression::Or(..) => elementary("bool"),
+
+            // Shifts and powers keep the type of their left operand
+            pt::Expression::ShiftLeft(_, left, _)
+            | pt::Expression::ShiftRight(_, left, _)
+            | pt::Expression::Power(_, left, _) => self.infer(left),
+
+            // Literal operands take the type of the other side
+            pt::Expression::Add(_, left, right)
+            | pt::Expression::Subtract(_, left, right)
+            | pt::Expression::Multiply(_, left, right)
+            | pt::Expression::Divide(_, left, right)
+            | pt::Expression::Modulo(_, left, right)
+            | pt::Expression::BitwiseAnd(_, left, right)
+            | pt::Expression::BitwiseOr(_, left, right)
+            | pt::Expression::BitwiseXor(_, left, right) => self.infer(left).or_else(|| self.infer(right)),
+
+            pt::Expression::Negate(_, inner)
+            | pt::Expression::BitwiseNot(_, inner)
+            | pt::Expression::PreIncrement(_, inner)
+            | pt::Expression::PreDecrement(_, inner)
+            | pt::Expression::PostIncrement(_, inner)
+            | pt::Expression::PostDecrement(_, inner) => self.infer(inner),
+
+            pt::Expression::Assign(_, left, _)
+            | pt::Expression::AssignAdd(_, left, _)
+            | pt::Expression::AssignSubtract(_, left, _)
+            | pt::Expression::AssignMultiply(_, left, _)
+            | pt::Expression::AssignDivide(_, left, _)
+            | pt::Expression::AssignModulo(_, left, _)
+            | pt::Expression::AssignOr(_, left, _)
+            | pt::Expression::AssignAnd(_, left, _)
+            | pt::Expression::AssignXor(_, left, _)
+            | pt::Expression::AssignShiftLeft(_, left, _)
+            | pt::Expression::AssignShiftRight(_, left, _) => self.infer(left),
+
+            _ => None,
+        }
+    }
+
+    /// A name in value position: locals shadow state variables
+    fn variable(&self, name: &str) -> Option<SolType> {
+        if let Some(ty) = self.locals.get(name) {
+            return Some(ty.clone());
+        }
+        if let Some(var) = self.contract.state_variables.iter().find(|v| v.name == name) {
+            return Some(var.resolved_type.clone().unwrap_or_else(|| self.resolver.resolve_type(&var.var_type)));
+        }
+        match name {
+            "this" => Some(SolType::Contract { name: self.contract.name.clone() }),
+            "now" => Some(SolType::elementary("uint256")),
+            _ => None,
+        }
+    }
+
+    fn member(&self, base: &pt::Expression, member: &str) -> Option<SolType> {
+        let elementary = |name: &str| Some(SolType::elementary(name));
+
+        if let pt::Expression::Variable(ident) = base {
+            if !self.is_value(&ident.name) {
+                let global = match (ident.name.as_str(), member) {
+                    ("msg", "sender") | ("tx", "origin") => elementary("address"),
+                    ("msg", "value") | ("tx", "gasprice") => elementary("uint256"),
+                    ("msg", "data") => elementary("bytes"),
+                    ("msg", "sig") => elementary("bytes4"),
+                    ("block", "coinbase") => elementary("address payable"),
+                    ("block", _) => elementary("uint256"),
+                    _ => None,
+                };
+                if global.is_some() {
+                    return global;
+                }
+            }
+        }
+        // `type(T).max`, `type(I).interfaceId`, ...
+        if let pt::Expression::FunctionCall(_, callee, args) = base {
+            if matches!(callee.as_ref(), pt::Expression::Variable(ident) if ident.name == "type") {
+                return match member {
+                    "min" | "max" => self.type_expression(args.first()?),
+                    "interfaceId" => elementary("bytes4"),
+                    "name" => elementary("string"),
+                    _ => elementary("bytes"), // creationCode, runtimeCode
+                };
+            }
+        }
+        // `Status.Active` or `Vault.Status.Active`
+        if let Some(SolType::Enum { name, owner }) = self.named_type(base) {
+            return Some(SolType::Enum { name, owner });
+        }
+
+        match self.infer(base)? {
+            SolType::Struct { name, owner } => {
+                let (_, def) = self.resolver.find_struct(owner.as_deref(), &name)?;
+                let field = def.members.iter().find(|m| m.name == member)?;
+                Some(self.resolver.resolve_type(&field.member_type))
+            }
+            SolType::Array { .. } if member == "length" => elementary("uint256"),
+            SolType::Elementary { name } if name == "bytes" && member == "length" => elementary("uint256"),
+            ty if ty.is_address() => match member {
+                "balance" => elementary("uint256"),
+                "code" => elementary("bytes"),
+                "codehash" => elementary("bytes32"),
+                _ => None,
+            },
+            SolType::Function { external: true, .. } => match member {
+                "selector" => elementary("bytes4"),
+                "address" => elementary("address"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn call(&self, callee: &pt::Expression, args: &[pt::Expression]) -> Option<SolType> {
+        let elementary = |name: &str| Some(SolType::elementary(name));
+        match callee {
+            // `x.call{value: v}(...)`
+            pt::Expression::FunctionCallBlock(_, inner, _) => self.call(inner, args),
+            pt::Expression::Type(..) | pt::Expression::ArraySubscript(..) => self.type_expression(callee),
+            pt::Expression::Variable(ident) if !self.is_value(&ident.name) => match ident.name.as_str() {
+                "payable" => elementary("address payable"),
+                "keccak256" | "sha256" | "blockhash" | "blobhash" => elementary("bytes32"),
+                "ripemd160" => elementary("bytes20"),
+                "ecrecover" => elementary("address"),
+                "gasleft" | "addmod" | "mulmod" => elementary("uint256"),
+                name => {
+                    // Conversions to contracts and enums, struct constructors
+                    let named = self.named_type(callee);
+                    if named.is_some() {
+                        return named;
+                    }
+                    let functions = self.contract.functions.iter()
+                        .chain(self.resolver.free_functions());
+                    self.first_return(functions.filter(|f| f.name == name), args.len())
+                }
+            },
+            pt::Expression::MemberAccess(_, base, member) => {
+                if let pt::Expression::Variable(ident) = base.as_ref() {
+                    if !self.is_value(&ident.name) {
+                        match (ident.name.as_str(), member.name.as_str()) {
+                            ("abi", "decode") => {
+                                return match args.get(1)? {
+                                    pt::Expression::List(_, items) if items.len() == 1 => {
+                                        self.type_expression(&items[0].1.as_ref()?.ty)
+                                    }
+                                    pt::Expression::List(..) => None,
+                                    ty => self.type_expression(ty),
+                                };
+                            }
+                            ("abi", _) => return elementary("bytes"),
+                            ("string", "concat") => return elementary("string"),
+                            ("bytes", "concat") => return elementary("bytes"),
+                            _ => {}
+                        }
+                    }
+                }
+                if let pt::Expression::Type(_, ty) = base.as_ref() {
+                    return match ty {
+                        pt::Type::String => elementary("string"),
+                        pt::Type::DynamicBytes => elementary("bytes"),
+                        _ => None,
+                    };
+                }
+                // `Price.wrap(x)` and `Price.unwrap(p)`
+                if let Some(SolType::UserDefined { name, underlying }) = self.named_type(base) {
+                    return match member.name.as_str() {
+                        "wrap" => Some(SolType::UserDefined { name, underlying }),
+                        "unwrap" => Some(self.resolver.resolve_type(&underlying)),
+                        _ => None,
+                    };
+                }
+                self.call_returns(callee, args.len())
+            }
+            _ => None,
+        }
+    }
+
+    /// First return value of `f(...)`, `Library.f(...)` or `x.f(...)` where `x` is a contract
+    fn call_returns(&self, callee: &pt::Expression, arg_count: usize) -> Option<SolType> {
+        match callee {
+            pt::Expression::Variable(ident) => {
+                let functions = self.contract.functions.iter().filter(|f| f.name == ident.name);
+                self.first_return(functions, arg_count)
+            }
+            pt::Expression::MemberAccess(_, base, member) => {
+                let contract_name = match base.as_ref() {
+                    pt::Expression::Variable(ident) if ident.name == "super" => self.contract.name.clone(),
+                    pt::Expression::Variable(ident) if !self.is_value(&ident.name) => ident.name.clone(),
+                    _ => match self.infer(base)? {
+                        SolType::Contract { name } => name,
+                        // `arr.push()` returns a reference to the new element
+                        ty @ SolType::Array { length: None, .. } if member.name == "push" && arg_count == 0 => {
+                            return ty.element();
+                        }
+                        _ => return None,
+                    },
+                };
+                let contract = self.all_contracts.iter().find(|c| c.name == contract_name)?;
+                let functions = contract.functions.iter().filter(|f| f.name == member.name);
+                self.first_return(functions, arg_count)
+            }
+            _ => None,
+        }
+    }
+
+    /// First return type shared by every overload taking `arg_count` arguments
+    fn first_return<'f>(&self, functions: impl Iterator<Item = &'f FunctionDef>, arg_count: usize) -> Option<SolType> {
+        let mut returns = functions
+            .filter(|f| f.parameters.len() == arg_count)
+            .map(|f| match f.return_types.first() {
+                Some(ty) => Some(ty.clone()),
+                None => f.returns.first().map(|r| self.resolver.resolve_type(&AbiGenerator::split_param(r).0)),
+            });
+        let first = returns.next()??;
+        returns.all(|ty| ty.as_ref() == Some(&first)).then_some(first)
+    }
+
+    /// A type written as an expression, e.g. the `uint128` of `uint128(x)` or `new Pool[](n)`
+    fn type_expression(&self, expr: &pt::Expression) -> Option<SolType> {
+        match expr {
+            pt::Expression::Type(_, pt::Type::Payable | pt::Type::AddressPayable) => {
+                Some(SolType::elementary("address payable"))
+            }
+            pt::Expression::Type(..) | pt::Expression::ArraySubscript(..) => {
+                Some(self.resolver.resolve_type(&SolidityParser::type_to_string(expr)))
+            }
+            _ => self.named_type(expr),
+        }
+    }
+
+    /// Whether `expr` names a contract, interface or library, as in the conversion `IERC20(token)`
+    pub fn names_contract(&self, expr: &pt::Expression) -> bool {
+        matches!(self.named_type(expr), Some(SolType::Contract { .. }))
+    }
+
+    /// A user-defined type named by `Name` or `Contract.Name`, unless the name is a value in scope
+    fn named_type(&self, expr: &pt::Expression) -> Option<SolType> {
+        let name = match expr {
+            pt::Expression::Variable(ident) if !self.is_value(&ident.name) => ident.name.clone(),
+            pt::Expression::MemberAccess(_, base, member) => match base.as_ref() {
+                pt::Expression::Variable(ident) if !self.is_value(&ident.name) => format!("{}.{}", ident.name, member.name),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        match self.resolver.resolve_type(&name) {
+            SolType::Unresolved { .. } | SolType::Elementary { .. } => None,
+            ty => Some(ty),
+        }
+    }
+
+    fn is_value(&self, name: &str) -> bool {
+        self.locals.contains_key(name) || self.contract.state_variables.iter().any(|v| v.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+
+    const SOURCE: &str = r#"
+        type Price is uint128;
+        interface IOracle { function price() external view returns (Price); }
+        contract Token {}
+        contract Vault {
+            enum Status { Open, Closed }
+            struct Position { address owner; uint256[] amounts; Status status; }
+
+            mapping(address => Position) positions;
+            Token[2] tokens;
+            IOracle oracle;
+            IExternal external_;
+
+            function owner(address account) external view returns (address) { return positions[account].owner; }
+            function count(address account) external view returns (uint256) { return positions[account].amounts.length; }
+            function status() external pure returns (Status) { return Status.Closed; }
+            function first() external view returns (Token) { return tokens[0]; }
+            function price() external view returns (Price) { return oracle.price(); }
+        }
+    "#;
+
+    #[test]
+    fn declared_types_resolve_against_the_project() {
+        let project = analyze(SOURCE);
+        let vault = contract(&project, "Vault");
+        let resolved = |name: &str| vault.state_variables.iter().find(|v| v.name == name).unwrap().resolved_type.clone().unwrap();
+
+        assert_eq!(resolved("positions"), SolType::Mapping {
+            key: Box::new(SolType::elementary("address")),
+            value: Box::new(SolType::Struct { name: "Position".to_string(), owner: Some("Vault".to_string()) }),
+        });
+        assert_eq!(resolved("tokens"), SolType::Array {
+            base: Box::new(SolType::Contract { name: "Token".to_string() }),
+            length: Some("2".to_string()),
+        });
+        assert_eq!(resolved("oracle"), SolType::Contract { name: "IOracle".to_string() });
+        assert_eq!(resolved("external_"), SolType::Unresolved { name: "IExternal".to_string() });
+
+        let price = vault.functions.iter().find(|f| f.name == "price").unwrap();
+        assert_eq!(price.return_types, [SolType::UserDefined { name: "Price".to_string(), underlying: "uint128".to_string() }]);
+    }
+
+    #[test]
+    fn expressions_are_typed_through_members_indices_and_calls() {
+        let project = analyze(SOURCE);
+        let index = project.contracts.iter().position(|c| c.name == "Vault").unwrap();
+        let mut typer = ExpressionTyper::new(&project.contracts[index], &project.contracts, &project.source_units);
+
+        let mut returned = HashMap::new();
+        for part in &project.contract_asts[index].parts {
+            let pt::ContractPart::FunctionDefinition(f) = part else {
+                continue;
+            };
+            let Some(pt::Statement::Block { statements, .. }) = &f.body else {
+                continue;
+            };
+            let [pt::Statement::Return(_, Some(expr))] = statements.as_slice() else {
+                continue;
+            };
+            typer.enter_function(f);
+            returned.insert(f.name.as_ref().unwrap().name.clone(), typer.infer(expr).map(|t| t.to_string()));
+        }
+
+        assert_eq!(returned["owner"].as_deref(), Some("address"));
+        assert_eq!(returned["count"].as_deref(), Some("uint256"));
+        assert_eq!(returned["status"].as_deref(), Some("Vault.Status"));
+        assert_eq!(returned["first"].as_deref(), Some("Token"));
+        assert_eq!(returned["price"].as_deref(), Some("Price"));
+    }
+}
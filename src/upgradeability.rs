@@ -0,0 +1,392 @@
+use crate::access_control::INITIALIZER_MODIFIERS;
+use crate::analyzer::StateModificationAnalyzer;
+use crate::models::*;
+use crate::parser::SolidityParser;
+use colored::*;
+use solang_parser::pt;
+use std::collections::HashSet;
+
+// OpenZeppelin upgradeable bases whose `__X_init` does nothing, so skipping it is harmless
+const EMPTY_INITS: &[&str] = &[
+    "Initializable", "ContextUpgradeable", "ERC165Upgradeable", "UUPSUpgradeable", "MulticallUpgradeable",
+    "ERC1967UpgradeUpgradeable",
+];
+
+// State written by `Initializable` itself (OpenZeppelin 4.x keeps it in sequential storage)
+const INITIALIZABLE_STATE: &[&str] = &["_initialized", "_initializing"];
+
+// `@custom:oz-upgrades-unsafe-allow` values that acknowledge a pattern on purpose
+const ALLOW_TAG: &str = "oz-upgrades-unsafe-allow";
+
+/// Finds initializers and checks the hygiene of contracts deployed behind proxies
+pub struct UpgradeabilityChecker;
+
+impl UpgradeabilityChecker {
+    pub fn check(contracts: &mut [ContractInfo], contract_asts: &[pt::ContractDefinition]) {
+        let snapshot = contracts.to_vec();
+        for ((contract, declared), ast) in contracts.iter_mut().zip(&snapshot).zip(contract_asts) {
+            if contract.kind != ContractKind::Contract {
+                continue;
+            }
+            let upgradeable = Self::is_upgradeable(declared, ast, &snapshot);
+            contract.initializers = declared.functions.iter()
+                .filter(|f| f.inherited_from.is_none() && Self::is_initializer(f))
+                .map(|f| Self::initializer(f, declared, ast))
+                .collect();
+
+            // Without a proxy, a public `initialize` is just a setter
+            if upgradeable {
+                let mut issues = Self::initializer_issues(declared, &contract.initializers);
+                issues.extend(Self::parent_init_issues(declared, &contract.initializers, &snapshot));
+                issues.extend(Self::implementation_issues(declared, ast, &snapshot));
+                contract.upgrade_issues = issues;
+            }
+        }
+    }
+
+    /// Deployed behind a proxy: initializable or UUPS bases, initializer modifiers, a constructor
+    /// calling `_disableInitializers()`, or linked as an implementation. The contract's own name
+    /// says nothing about how it is deployed.
+    fn is_upgradeable(contract: &ContractInfo, ast: &pt::ContractDefinition, all_contracts: &[ContractInfo]) -> bool {
+        if contract.proxy.as_ref().is_some_and(|p| p.kind.delegates()) {
+            return false;
+        }
+        let upgradeable_base = contract.linearized_bases.iter()
+            .chain(&contract.unresolved_bases)
+            .any(|b| b == "Initializable" || b.ends_with("Upgradeable"));
+        upgradeable_base
+            || Self::disables_initializers(ast)
+            || contract.functions.iter().any(|f| f.modifier_order.iter().any(|m| INITIALIZER_MODIFIERS.contains(&m.as_str())))
+            || contract.proxy.as_ref().is_some_and(|p| p.kind == ProxyKind::UupsImplementation)
+            || all_contracts.iter().any(|c| c.proxy.as_ref().and_then(|p| p.implementation.as_ref()) == Some(&contract.name))
+    }
+
+    fn is_initializer(func: &FunctionDef) -> bool {
+        if matches!(func.state_mutability.as_str(), "view" | "pure") {
+            return false;
+        }
+        let public = matches!(func.visibility.as_str(), "public" | "external");
+        func.modifier_order.iter().any(|m| INITIALIZER_MODIFIERS.contains(&m.as_str()))
+            || public && (func.name.starts_with("initialize") || func.name.starts_with("reinitialize")
+                || func.name.strip_prefix("init").is_some_and(|rest| rest.is_empty() || rest.starts_with(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit())))
+            || func.name.starts_with("__") && (func.name.ends_with("_init") || func.name.ends_with("_init_unchained"))
+    }
+
+    fn initializer(func: &FunctionDef, contract: &ContractInfo, ast: &pt::ContractDefinition) -> Initializer {
+        let part = Self::function_part(ast, |f| SolidityParser::function_signature(f) == func.signature);
+        let modifier_guard = part.and_then(|p| p.attributes.iter().find_map(|attr| match attr {
+            pt::FunctionAttribute::BaseOrModifier(_, base) if base.name.identifiers.len() == 1
+                && INITIALIZER_MODIFIERS.contains(&base.name.identifiers[0].name.as_str()) =>
+            {
+                let name = &base.name.identifiers[0].name;
+                Some(match base.args.as_deref() {
+                    Some(args) if !args.is_empty() => format!("{}({})",
+                        name, args.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")),
+                    _ => name.clone(),
+                })
+            }
+            _ => None,
+        }));
+        // A hand-rolled `if (initialized) revert; ... initialized = true;`
+        let flag = func.reads_states.iter()
+            .chain(&func.modifies_states)
+            .find(|v| v.to_lowercase().contains("initializ") && func.modifies_states.contains(v)
+                && (func.reads_states.contains(v) || func.modifier_effects.iter().any(|e| e.reads_states.contains(v))))
+            .map(|v| format!("`{}` flag", v));
+        let access = contract.access_control.iter()
+            .find(|a| a.function == func.signature)
+            .and_then(|a| a.guards.first())
+            .map(|g| g.source.clone());
+
+        Initializer {
+            function: func.signature.clone(),
+            guard: modifier_guard.or(flag).or(access),
+            parent_inits: part.map(|p| Self::parent_inits(p, ast)).unwrap_or_default(),
+            line_number: func.line_number,
+        }
+    }
+
+    fn function_part(ast: &pt::ContractDefinition, matches: impl Fn(&pt::FunctionDefinition) -> bool) -> Option<&pt::FunctionDefinition> {
+        ast.parts.iter().find_map(|part| match part {
+            pt::ContractPart::FunctionDefinition(f) if !matches!(f.ty, pt::FunctionTy::Modifier) && matches(f) => Some(f.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// `__X_init` calls of a function and of the init functions of the project it calls
+    fn parent_inits(part: &pt::FunctionDefinition, ast: &pt::ContractDefinition) -> Vec<String> {
+        let mut found: Vec<String> = Vec::new();
+        let mut queue = vec![part];
+        let mut visited: HashSet<*const pt::FunctionDefinition> = HashSet::new();
+        while let Some(func) = queue.pop() {
+            if !visited.insert(func as *const _) {
+                continue;
+            }
+            let Some(body) = &func.body else {
+                continue;
+            };
+            let mut names = Vec::new();
+            StateModificationAnalyzer::visit_statement_expressions(body, &mut |expr| {
+                if let pt::Expression::FunctionCall(_, callee, _) = expr {
+                    if let pt::Expression::Variable(id) = callee.as_ref() {
+                        if id.name.starts_with("__") && id.name.contains("_init") {
+                            names.push(id.name.clone());
+                        }
+                    }
+                }
+            });
+            for name in names {
+                if let Some(callee) = Self::function_part(ast, |f| f.name.as_ref().is_some_and(|n| n.name == name)) {
+                    queue.push(callee);
+                }
+                if !found.contains(&name) {
+                    found.push(name);
+                }
+            }
+        }
+        found
+    }
+
+    fn initializer_issues(contract: &ContractInfo, initializers: &[Initializer]) -> Vec<UpgradeIssue> {
+        let mut issues = Vec::new();
+        for init in initializers {
+            let Some(func) = contract.functions.iter().find(|f| f.signature == init.function) else {
+                continue;
+            };
+            if !matches!(func.visibility.as_str(), "public" | "external") {
+                continue;
+            }
+            // Behind a proxy, an owner check alone doesn't help: the owner is set by this very call
+            let once = func.modifier_order.iter().any(|m| INITIALIZER_MODIFIERS.contains(&m.as_str()))
+                || init.guard.as_ref().is_some_and(|g| g.ends_with("flag"));
+            let detail = match (once, &init.guard) {
+                (true, _) => continue,
+                (false, None) => "anyone can call it, any number of times".to_string(),
+                (false, Some(guard)) => format!("only `{}` protects it; add `initializer` or `reinitializer(n)`", guard),
+            };
+            issues.push(UpgradeIssue {
+                kind: UpgradeIssueKind::UnguardedInitializer,
+                subject: init.function.clone(),
+                detail,
+                line_number: init.line_number,
+            });
+        }
+        issues
+    }
+
+    /// Upgradeable bases whose `__X_init` no initializer of the contract reaches
+    fn parent_init_issues(contract: &ContractInfo, initializers: &[Initializer], all_contracts: &[ContractInfo]) -> Vec<UpgradeIssue> {
+        let Some(init) = initializers.iter().find(|i| {
+            contract.functions.iter().any(|f| f.signature == i.function
+                && matches!(f.visibility.as_str(), "public" | "external")
+                && f.modifier_order.iter().any(|m| m == "initializer"))
+        }) else {
+            return Vec::new();
+        };
+
+        let mut issues = Vec::new();
+        for base in contract.linearized_bases.iter().filter(|b| !EMPTY_INITS.contains(&b.as_str())) {
+            // Init functions the base declares, or OpenZeppelin's naming for bases outside the project
+            let expected: Vec<String> = match all_contracts.iter().find(|c| &c.name == base) {
+                Some(declared) => declared.functions.iter()
+                    .filter(|f| f.inherited_from.is_none() && f.name.starts_with("__")
+                        && (f.name.ends_with("_init") || f.name.ends_with("_init_unchained")))
+                    .map(|f| f.name.clone())
+                    .collect(),
+                None => match base.strip_suffix("Upgradeable") {
+                    Some(stem) => vec![format!("__{}_init", stem), format!("__{}_init_unchained", stem)],
+                    None => Vec::new(),
+                },
+            };
+            if expected.is_empty() || expected.iter().any(|e| init.parent_inits.contains(e)) {
+                continue;
+            }
+            let shown = expected.iter().find(|e| !e.ends_with("_unchained")).unwrap_or(&expected[0]);
+            issues.push(UpgradeIssue {
+                kind: UpgradeIssueKind::MissingParentInit,
+                subject: init.function.clone(),
+                detail: format!("`{}` is never called, so `{}` stays uninitialized", shown, base),
+                line_number: init.line_number,
+            });
+        }
+        issues
+    }
+
+    /// Whether the constructor calls `_disableInitializers()`
+    fn disables_initializers(ast: &pt::ContractDefinition) -> bool {
+        let Some(body) = Self::function_part(ast, |f| matches!(f.ty, pt::FunctionTy::Constructor)).and_then(|c| c.body.as_ref()) else {
+            return false;
+        };
+        let mut disables = false;
+        StateModificationAnalyzer::visit_statement_expressions(body, &mut |expr| {
+            if let pt::Expression::FunctionCall(_, callee, _) = expr {
+                disables |= matches!(callee.as_ref(), pt::Expression::Variable(id) if id.name == "_disableInitializers");
+            }
+        });
+        disables
+    }
+
+    /// Constructor, immutables and storage gaps
+    fn implementation_issues(contract: &ContractInfo, ast: &pt::ContractDefinition, all_contracts: &[ContractInfo]) -> Vec<UpgradeIssue> {
+        let mut issues = Vec::new();
+        let constructor = contract.functions.iter().find(|f| f.name == "constructor" && f.inherited_from.is_none());
+
+        if contract.is_concrete() {
+            let disables = constructor.is_some_and(|c| c.modifier_order.iter().any(|m| m == "initializer"))
+                || Self::disables_initializers(ast);
+            if !disables {
+                issues.push(UpgradeIssue {
+                    kind: UpgradeIssueKind::MissingDisableInitializers,
+                    subject: constructor.map_or(contract.name.clone(), |c| c.signature.clone()),
+                    detail: "the constructor doesn't call `_disableInitializers()`, so anyone can initialize the implementation".to_string(),
+                    line_number: constructor.map_or(0, |c| c.line_number),
+                });
+            }
+        }
+
+        if let Some(constructor) = constructor {
+            for var in &constructor.modifies_states {
+                let immutable = contract.state_variables.iter().any(|v| &v.name == var && (v.is_immutable || v.is_constant));
+                if immutable || INITIALIZABLE_STATE.contains(&var.as_str()) {
+                    continue;
+                }
+                issues.push(UpgradeIssue {
+                    kind: UpgradeIssueKind::ConstructorWritesState,
+                    subject: var.clone(),
+                    detail: "set in the constructor, which proxies never run; move it to an initializer".to_string(),
+                    line_number: constructor.line_number,
+                });
+            }
+        }
+
+        let own_vars: Vec<&StateVariable> = contract.state_variables.iter().filter(|v| v.inherited_from.is_none()).collect();
+        for var in own_vars.iter().filter(|v| v.is_immutable) {
+            let allowed = var.natspec.as_ref()
+                .and_then(|n| n.custom.get(ALLOW_TAG))
+                .is_some_and(|allow| allow.contains("state-variable-immutable"));
+            if !allowed {
+                issues.push(UpgradeIssue {
+                    kind: UpgradeIssueKind::ImmutableVariable,
+                    subject: var.name.clone(),
+                    detail: format!("every proxy reads the implementation's value; mark it `@custom:{} state-variable-immutable` if intended", ALLOW_TAG),
+                    line_number: var.line_number,
+                });
+            }
+        }
+
+        // Only bases need room to grow, and ERC-7201 namespaces don't share the sequential layout
+        let is_base = contract.is_abstract || all_contracts.iter().any(|c| c.linearized_bases.contains(&contract.name));
+        let sequential: Vec<&&StateVariable> = own_vars.iter().filter(|v| !v.is_constant && !v.is_immutable).collect();
+        if is_base && contract.upgradeable_storage.is_empty() && !sequential.is_empty() && !sequential.iter().any(|v| Self::is_storage_gap(v)) {
+            issues.push(UpgradeIssue {
+                kind: UpgradeIssueKind::MissingStorageGap,
+                subject: contract.name.clone(),
+                detail: format!("{} state variable(s) and no `uint256[n] private __gap`; adding variables later shifts every derived contract", sequential.len()),
+                line_number: sequential[0].line_number,
+            });
+        }
+        issues
+    }
+
+    /// `uint256[50] private __gap`, or `__gap_Vault` and the like when bases name theirs apart
+    fn is_storage_gap(var: &StateVariable) -> bool {
+        var.name.starts_with("__gap") && matches!(&var.resolved_type,
+            Some(SolType::Array { base, length: Some(_) }) if **base == SolType::elementary("uint256"))
+    }
+
+    pub fn print_summary(contracts: &[ContractInfo]) {
+        let affected: Vec<&ContractInfo> = contracts.iter().filter(|c| !c.upgrade_issues.is_empty()).collect();
+        if affected.is_empty() {
+            return;
+        }
+
+        println!();
+        println!("{}", "🧬 Upgradeability".bold());
+        for contract in affected {
+            println!("  {} {}: {} issue(s)", "⚠️".yellow(), contract.name, contract.upgrade_issues.len());
+            for issue in &contract.upgrade_issues {
+                println!("     - {} `{}`: {}", issue.kind.label(), issue.subject, issue.detail);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{analyze, contract};
+
+    fn issues(contract: &ContractInfo, kind: UpgradeIssueKind) -> Vec<&UpgradeIssue> {
+        contract.upgrade_issues.iter().filter(|i| i.kind == kind).collect()
+    }
+
+    #[test]
+    fn public_initialize_is_unguarded_only_behind_a_proxy() {
+        let project = analyze(r#"
+            contract Config {
+                address public owner;
+                function initialize(address newOwner) public { owner = newOwner; }
+            }
+
+            contract Vault is Initializable {
+                address public owner;
+                function initialize(address newOwner) public { owner = newOwner; }
+            }
+        "#);
+        assert!(contract(&project, "Config").upgrade_issues.is_empty());
+        let unguarded = issues(contract(&project, "Vault"), UpgradeIssueKind::UnguardedInitializer);
+        assert_eq!(unguarded.len(), 1);
+        assert_eq!(unguarded[0].detail, "anyone can call it, any number of times");
+    }
+
+    #[test]
+    fn any_fixed_uint256_array_named_gap_reserves_storage() {
+        let project = analyze(r#"
+            abstract contract Named is Initializable {
+                uint256 internal total;
+                uint256[49] private __gap_Named;
+            }
+
+            abstract contract Sized is Initializable {
+                uint256 internal constant GAP = 49;
+                uint256 internal total;
+                uint256[GAP] private __gap;
+            }
+
+            abstract contract Dynamic is Initializable {
+                uint256 internal total;
+                uint256[] private __gap;
+            }
+
+            abstract contract Scalar is Initializable {
+                uint256 internal total;
+                uint256 private __gap;
+            }
+        "#);
+        for name in ["Named", "Sized"] {
+            assert!(issues(contract(&project, name), UpgradeIssueKind::MissingStorageGap).is_empty(), "{}", name);
+        }
+        for name in ["Dynamic", "Scalar"] {
+            assert_eq!(issues(contract(&project, name), UpgradeIssueKind::MissingStorageGap).len(), 1, "{}", name);
+        }
+    }
+
+    #[test]
+    fn upgradeability_is_told_by_structure_not_by_name() {
+        let project = analyze(r#"
+            contract ConfigUpgradeable {
+                address public owner;
+                function initialize(address newOwner) public { owner = newOwner; }
+            }
+
+            contract Registry {
+                address public owner;
+                constructor() { _disableInitializers(); }
+                function initialize(address newOwner) public { owner = newOwner; }
+            }
+        "#);
+        assert!(contract(&project, "ConfigUpgradeable").upgrade_issues.is_empty());
+        let unguarded = issues(contract(&project, "Registry"), UpgradeIssueKind::UnguardedInitializer);
+        assert_eq!(unguarded.len(), 1);
+    }
+}
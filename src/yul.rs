@@ -0,0 +1,418 @@
+use solang_parser::pt;
+use std::collections::{HashMap, HashSet};
+
+use crate::analyzer::StateModificationAnalyzer;
+use crate::models::{AssemblyCall, AssemblyInfo, SlotAccess, SlotPointer};
+
+/// State while walking one assembly block
+struct YulWalker<'a> {
+    slot_names: &'a HashMap<String, String>, // Named slot ("x.slot" or a slot constant) -> what it addresses
+    locals: HashMap<String, Option<String>>, // Yul variable -> what the slot it holds resolves to
+    hashed_slot: Option<String>,             // Slot base last stored to memory, for `keccak256` slot derivation
+    info: AssemblyInfo,
+    call_locs: Vec<pt::Loc>,                 // Location of each entry in `info.calls`
+}
+
+pub struct YulAnalyzer;
+
+impl YulAnalyzer {
+    /// Analyze the inline assembly of a function or modifier body.
+    /// `slot_names` maps named slots, written `x.slot` or as a slot constant,
+    /// to the state variable or namespaced storage struct they address.
+    pub fn analyze(func: &pt::FunctionDefinition, slot_names: &HashMap<String, String>) -> Option<AssemblyInfo> {
+        let mut blocks = Vec::new();
+        if let Some(body) = &func.body {
+            Self::collect_assembly_blocks(body, &mut blocks);
+        }
+        if blocks.is_empty() {
+            return None;
+        }
+
+        let mut walker = YulWalker::new(slot_names);
+        walker.info.blocks = blocks.len();
+        walker.info.memory_safe = blocks.iter().all(|(_, memory_safe)| *memory_safe);
+        for (block, _) in blocks {
+            walker.locals.clear();
+            walker.walk_block(block);
+        }
+        Some(walker.info)
+    }
+
+    /// State variables an assembly block writes (`sstore`) and reads (`sload`)
+    pub fn state_accesses(
+        block: &pt::YulBlock,
+        state_vars: &HashSet<String>,
+        storage_var_mapping: &HashMap<String, String>,
+    ) -> (Vec<String>, Vec<String>) {
+        let mut slot_names: HashMap<String, String> = state_vars.iter()
+            .map(|v| (format!("{}.slot", v), v.clone()))
+            .collect();
+        // Storage pointers (`Info storage info = infos[id]`) address their state variable
+        for (local, var) in storage_var_mapping {
+            if state_vars.contains(var) {
+                slot_names.insert(format!("{}.slot", local), var.clone());
+            }
+        }
+
+        let mut walker = YulWalker::new(&slot_names);
+        walker.walk_block(block);
+
+        let mut writes = Vec::new();
+        let mut reads = Vec::new();
+        for access in &walker.info.storage_accesses {
+            let Some(var) = access.resolved.clone() else {
+                continue;
+            };
+            let target = if access.opcode == "sstore" { &mut writes } else if access.opcode == "sload" { &mut reads } else { continue };
+            if !target.contains(&var) {
+                target.push(var);
+            }
+        }
+        (writes, reads)
+    }
+
+    /// Calls and contract creations in an assembly block, with where each is made
+    pub fn calls(block: &pt::YulBlock) -> Vec<(AssemblyCall, pt::Loc)> {
+        let slot_names = HashMap::new();
+        let mut walker = YulWalker::new(&slot_names);
+        walker.walk_block(block);
+        walker.info.calls.into_iter().zip(walker.call_locs).collect()
+    }
+
+    /// Storage pointers a function re-targets in assembly, e.g. `$.slot := ERC20StorageLocation`
+    pub fn slot_pointers(func: &pt::FunctionDefinition) -> Vec<SlotPointer> {
+        Self::analyze(func, &HashMap::new())
+            .map(|info| info.slot_pointers)
+            .unwrap_or_default()
+    }
+
+    /// Value flows in an assembly block as (source, target) pairs: Yul assignments,
+    /// and `sstore(x.slot, value)` flowing into the state variable `x`
+    pub fn value_flows(block: &pt::YulBlock, state_vars: &HashSet<String>) -> Vec<(String, String)> {
+        let mut flows = Vec::new();
+        Self::collect_value_flows(&block.statements, state_vars, &mut flows);
+        flows
+    }
+
+    fn collect_value_flows(
+        statements: &[pt::YulStatement],
+        state_vars: &HashSet<String>,
+        flows: &mut Vec<(String, String)>,
+    ) {
+        let mut add_flows = |targets: Vec<String>, value: &pt::YulExpression| {
+            let mut sources = Vec::new();
+            Self::collect_identifiers(value, &mut sources);
+            for target in targets {
+                flows.extend(sources.iter().map(|source| (source.clone(), target.clone())));
+            }
+        };
+
+        let mut nested: Vec<&[pt::YulStatement]> = Vec::new();
+        for stmt in statements {
+            match stmt {
+                pt::YulStatement::Assign(_, targets, value) => {
+                    add_flows(targets.iter().map(Self::expression_to_string).collect(), value);
+                }
+                pt::YulStatement::VariableDeclaration(_, names, Some(value)) => {
+                    add_flows(names.iter().map(|name| name.id.name.clone()).collect(), value);
+                }
+                pt::YulStatement::FunctionCall(call) if call.id.name == "sstore" && call.arguments.len() == 2 => {
+                    if let pt::YulExpression::SuffixAccess(_, base, suffix) = &call.arguments[0] {
+                        let var = Self::expression_to_string(base);
+                        if suffix.name == "slot" && state_vars.contains(&var) {
+                            add_flows(vec![var], &call.arguments[1]);
+                        }
+                    }
+                }
+                pt::YulStatement::Block(block) | pt::YulStatement::If(_, _, block) => nested.push(&block.statements),
+                pt::YulStatement::For(for_stmt) => {
+                    nested.push(&for_stmt.init_block.statements);
+                    nested.push(&for_stmt.execution_block.statements);
+                    nested.push(&for_stmt.post_block.statements);
+                }
+                pt::YulStatement::Switch(switch) => {
+                    for case in switch.cases.iter().chain(switch.default.iter()) {
+                        let (pt::YulSwitchOptions::Case(_, _, block) | pt::YulSwitchOptions::Default(_, block)) = case;
+                        nested.push(&block.statements);
+                    }
+                }
+                _ => {}
+            }
+        }
+        for statements in nested {
+            Self::collect_value_flows(statements, state_vars, flows);
+        }
+    }
+
+    fn collect_identifiers(expr: &pt::YulExpression, identifiers: &mut Vec<String>) {
+        match expr {
+            pt::YulExpression::Variable(ident) => identifiers.push(ident.name.clone()),
+            pt::YulExpression::SuffixAccess(_, base, _) => Self::collect_identifiers(base, identifiers),
+            pt::YulExpression::FunctionCall(call) => {
+                for arg in &call.arguments {
+                    Self::collect_identifiers(arg, identifiers);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Find `assembly { ... }` blocks in a statement tree, with whether each is marked memory-safe
+    fn collect_assembly_blocks<'a>(stmt: &'a pt::Statement, blocks: &mut Vec<(&'a pt::YulBlock, bool)>) {
+        match stmt {
+            pt::Statement::Assembly { flags, block, .. } => {
+                let memory_safe = flags.iter().flatten().any(|flag| flag.string == "memory-safe");
+                blocks.push((block, memory_safe));
+            }
+            pt::Statement::Block { statements, .. } => {
+                for s in statements {
+                    Self::collect_assembly_blocks(s, blocks);
+                }
+            }
+            pt::Statement::If(_, _, if_branch, else_branch) => {
+                Self::collect_assembly_blocks(if_branch, blocks);
+                if let Some(else_stmt) = else_branch {
+                    Self::collect_assembly_blocks(else_stmt, blocks);
+                }
+            }
+            pt::Statement::While(_, _, body) | pt::Statement::DoWhile(_, body, _) => {
+                Self::collect_assembly_blocks(body, blocks);
+            }
+            pt::Statement::For(_, init, _, _, body) => {
+                if let Some(init_stmt) = init {
+                    Self::collect_assembly_blocks(init_stmt, blocks);
+                }
+                if let Some(body_stmt) = body {
+                    Self::collect_assembly_blocks(body_stmt, blocks);
+                }
+            }
+            pt::Statement::Try(_, expr, returns, catches) => {
+                if let (_, Some(body)) = StateModificationAnalyzer::try_call(expr, returns) {
+                    Self::collect_assembly_blocks(body, blocks);
+                }
+                for clause in catches {
+                    match clause {
+                        pt::CatchClause::Simple(_, _, body) | pt::CatchClause::Named(_, _, _, body) => {
+                            Self::collect_assembly_blocks(body, blocks);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Render a Yul expression the way it is written
+    pub fn expression_to_string(expr: &pt::YulExpression) -> String {
+        match expr {
+            pt::YulExpression::BoolLiteral(_, value, _) => value.to_string(),
+            pt::YulExpression::NumberLiteral(_, value, exponent, _) if exponent.is_empty() => value.clone(),
+            pt::YulExpression::NumberLiteral(_, value, exponent, _) => format!("{}e{}", value, exponent),
+            pt::YulExpression::HexNumberLiteral(_, value, _) => value.clone(),
+            pt::YulExpression::HexStringLiteral(hex, _) => format!("hex\"{}\"", hex.hex),
+            pt::YulExpression::StringLiteral(string, _) => format!("\"{}\"", string.string),
+            pt::YulExpression::Variable(ident) => ident.name.clone(),
+            pt::YulExpression::SuffixAccess(_, base, suffix) => {
+                format!("{}.{}", Self::expression_to_string(base), suffix.name)
+            }
+            pt::YulExpression::FunctionCall(call) => format!(
+                "{}({})",
+                call.id.name,
+                call.arguments.iter().map(Self::expression_to_string).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+impl<'a> YulWalker<'a> {
+    fn new(slot_names: &'a HashMap<String, String>) -> Self {
+        YulWalker {
+            slot_names,
+            locals: HashMap::new(),
+            hashed_slot: None,
+            info: AssemblyInfo::default(),
+            call_locs: Vec::new(),
+        }
+    }
+
+    fn walk_block(&mut self, block: &pt::YulBlock) {
+        for stmt in &block.statements {
+            self.walk_statement(stmt);
+        }
+    }
+
+    fn walk_statement(&mut self, stmt: &pt::YulStatement) {
+        match stmt {
+            pt::YulStatement::Assign(_, targets, value) => {
+                self.walk_expression(value);
+                let resolved = self.resolve_slot(value);
+                for target in targets {
+                    match target {
+                        // `p.slot := X` points a Solidity storage pointer somewhere else
+                        pt::YulExpression::SuffixAccess(_, base, suffix) if suffix.name == "slot" => {
+                            self.info.slot_pointers.push(SlotPointer {
+                                pointer: YulAnalyzer::expression_to_string(base),
+                                slot: YulAnalyzer::expression_to_string(value),
+                                resolved: resolved.clone(),
+                            });
+                        }
+                        pt::YulExpression::Variable(ident) => {
+                            self.locals.insert(ident.name.clone(), resolved.clone());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            pt::YulStatement::VariableDeclaration(_, names, value) => {
+                if let Some(expr) = value {
+                    self.walk_expression(expr);
+                }
+                let resolved = match (names.len(), value) {
+                    (1, Some(expr)) => self.resolve_slot(expr),
+                    _ => None,
+                };
+                for name in names {
+                    self.locals.insert(name.id.name.clone(), resolved.clone());
+                }
+            }
+            pt::YulStatement::If(_, condition, block) => {
+                self.walk_expression(condition);
+                self.walk_block(block);
+            }
+            pt::YulStatement::For(for_stmt) => {
+                self.walk_block(&for_stmt.init_block);
+                self.walk_expression(&for_stmt.condition);
+                self.walk_block(&for_stmt.execution_block);
+                self.walk_block(&for_stmt.post_block);
+            }
+            pt::YulStatement::Switch(switch) => {
+                self.walk_expression(&switch.condition);
+                for case in switch.cases.iter().chain(switch.default.iter()) {
+                    let (pt::YulSwitchOptions::Case(_, _, block) | pt::YulSwitchOptions::Default(_, block)) = case;
+                    self.walk_block(block);
+                }
+            }
+            pt::YulStatement::Block(block) => self.walk_block(block),
+            pt::YulStatement::FunctionDefinition(def) => {
+                // Yul functions have their own scope
+                let outer = std::mem::take(&mut self.locals);
+                self.walk_block(&def.body);
+                self.locals = outer;
+            }
+            pt::YulStatement::FunctionCall(call) => self.walk_call(call),
+            _ => {}
+        }
+    }
+
+    fn walk_expression(&mut self, expr: &pt::YulExpression) {
+        if let pt::YulExpression::FunctionCall(call) = expr {
+            self.walk_call(call);
+        }
+    }
+
+    fn walk_call(&mut self, call: &pt::YulFunctionCall) {
+        for arg in &call.arguments {
+            self.walk_expression(arg);
+        }
+
+        let args = &call.arguments;
+        let opcode = call.id.name.as_str();
+        match opcode {
+            "sload" | "sstore" | "tload" | "tstore" if !args.is_empty() => {
+                self.info.storage_accesses.push(SlotAccess {
+                    opcode: opcode.to_string(),
+                    slot: YulAnalyzer::expression_to_string(&args[0]),
+                    resolved: self.resolve_slot(&args[0]),
+                });
+            }
+            "call" | "callcode" if args.len() >= 3 => self.push_call(call, Some(&args[1]), Some(&args[2])),
+            "delegatecall" | "staticcall" if args.len() >= 2 => self.push_call(call, Some(&args[1]), None),
+            "create" | "create2" if !args.is_empty() => self.push_call(call, None, Some(&args[0])),
+            "selfdestruct" if !args.is_empty() => self.push_call(call, Some(&args[0]), None),
+            "mstore" if args.len() == 2 => {
+                if matches!(YulAnalyzer::expression_to_string(&args[0]).as_str(), "0x40" | "64") {
+                    self.push_trick("writes free memory pointer");
+                }
+                // Mapping and dynamic array slots are derived by hashing the base slot from memory
+                if let Some(base) = self.resolve_slot(&args[1]) {
+                    self.hashed_slot = Some(base);
+                }
+            }
+            "return" => self.push_trick("return halts execution"),
+            "stop" => self.push_trick("stop halts execution"),
+            _ => {}
+        }
+    }
+
+    fn push_call(&mut self, call: &pt::YulFunctionCall, target: Option<&pt::YulExpression>, value: Option<&pt::YulExpression>) {
+        self.call_locs.push(call.loc);
+        self.info.calls.push(AssemblyCall {
+            opcode: call.id.name.clone(),
+            target: target.map(YulAnalyzer::expression_to_string).unwrap_or_default(),
+            value: value.map(YulAnalyzer::expression_to_string),
+        });
+    }
+
+    fn push_trick(&mut self, trick: &str) {
+        if !self.info.memory_tricks.iter().any(|t| t == trick) {
+            self.info.memory_tricks.push(trick.to_string());
+        }
+    }
+
+    /// What a slot expression addresses, when it can be traced to a named slot
+    fn resolve_slot(&self, expr: &pt::YulExpression) -> Option<String> {
+        match expr {
+            pt::YulExpression::SuffixAccess(_, base, suffix) if suffix.name == "slot" => {
+                self.slot_names.get(&YulAnalyzer::expression_to_string(expr)).cloned()
+                    .filter(|_| matches!(base.as_ref(), pt::YulExpression::Variable(_)))
+            }
+            pt::YulExpression::Variable(ident) => match self.locals.get(&ident.name) {
+                Some(resolved) => resolved.clone(),
+                None => self.slot_names.get(&ident.name).cloned(),
+            },
+            pt::YulExpression::FunctionCall(call) => match call.id.name.as_str() {
+                // Struct members and array elements are offsets from their base slot
+                "add" => call.arguments.iter().find_map(|arg| self.resolve_slot(arg)),
+                "keccak256" => self.hashed_slot.clone(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{analyze, contract};
+
+    #[test]
+    fn slot_of_a_state_variable_maps_to_the_variable() {
+        let project = analyze(r#"
+            contract Raw {
+                address internal owner;
+                uint256 internal counter;
+
+                function bump() external {
+                    assembly ("memory-safe") {
+                        let o := sload(owner.slot)
+                        if iszero(eq(o, caller())) { revert(0, 0) }
+                        sstore(counter.slot, add(sload(counter.slot), 1))
+                    }
+                }
+            }
+        "#);
+        let bump = contract(&project, "Raw").functions.iter().find(|f| f.name == "bump").unwrap();
+        let assembly = bump.assembly.as_ref().unwrap();
+        assert!(assembly.memory_safe);
+        let accesses: Vec<(&str, &str, Option<&str>)> = assembly.storage_accesses.iter()
+            .map(|a| (a.opcode.as_str(), a.slot.as_str(), a.resolved.as_deref()))
+            .collect();
+        assert_eq!(accesses, [
+            ("sload", "owner.slot", Some("owner")),
+            ("sload", "counter.slot", Some("counter")), // Arguments run before the call
+            ("sstore", "counter.slot", Some("counter")),
+        ]);
+        assert_eq!(bump.modifies_states, ["counter"]);
+        assert!(bump.reads_states.contains(&"owner".to_string()));
+    }
+}